@@ -0,0 +1,321 @@
+//! # Abuse Protection
+//!
+//! Guards fork provisioning against being farmed for free compute. Each
+//! client IP may only provision a limited number of sessions within a
+//! sliding window; excess requests get `429 Too Many Requests`.
+//!
+//! The GitHub device-flow endpoints are limited the same way, per client IP
+//! and per device code, so the server can't be used to hammer github.com.
+//!
+//! All other API requests share a per-minute budget: the user's plan limit
+//! with an API token, or a per-IP limit without. Responses report the budget
+//! in `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+//! (seconds until it frees up), so clients can slow down before they're
+//! throttled.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::{Config, PollAuthorizationRequest};
+use domain::models::UserId;
+
+use crate::AppState;
+use crate::auth::resolve_user;
+use crate::client_ip::ClientIp;
+use crate::error::{ApiError, seconds_rounded_up};
+
+/// Window over which per-IP provisioning is counted
+const PROVISIONING_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Window over which auth endpoint requests are counted
+const AUTH_WINDOW: Duration = Duration::from_secs(60);
+
+/// Window over which general API requests are counted
+const API_WINDOW: Duration = Duration::from_secs(60);
+
+/// Largest auth request body buffered to read the device code
+const MAX_AUTH_BODY_BYTES: usize = 16 * 1024;
+
+/// A key's budget after an attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Quota {
+    pub(crate) limit: u32,
+    pub(crate) remaining: u32,
+    /// Until the oldest attempt in the window expires and frees budget
+    pub(crate) reset_after: Duration,
+}
+
+impl Quota {
+    /// Report the budget in `X-RateLimit-*` response headers
+    fn apply(&self, headers: &mut HeaderMap) {
+        headers.insert("x-ratelimit-limit", self.limit.into());
+        headers.insert("x-ratelimit-remaining", self.remaining.into());
+        headers.insert(
+            "x-ratelimit-reset",
+            seconds_rounded_up(self.reset_after).into(),
+        );
+    }
+}
+
+/// Sliding-window request limit per key (client IP, device code, ...)
+pub(crate) struct RateLimiter<K> {
+    max_per_window: u32,
+    window: Duration,
+    attempts: Mutex<HashMap<K, VecDeque<Instant>>>,
+}
+
+/// Per-IP limit on session provisioning
+pub(crate) type ProvisioningLimiter = RateLimiter<IpAddr>;
+
+impl ProvisioningLimiter {
+    pub(crate) fn per_hour(max_per_hour: u32) -> Self {
+        Self::with_window(max_per_hour, PROVISIONING_WINDOW)
+    }
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    fn with_window(max_per_window: u32, window: Duration) -> Self {
+        Self {
+            max_per_window,
+            window,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records an attempt, returning `false` if `key` is over its limit
+    pub(crate) fn try_acquire(&self, key: K) -> bool {
+        self.check(key).is_ok()
+    }
+
+    /// Records an attempt, or returns the exhausted budget of `key`
+    pub(crate) fn check(&self, key: K) -> Result<Quota, Quota> {
+        self.check_limit(key, self.max_per_window)
+    }
+
+    /// Like `check`, with `limit` in place of the limiter's own
+    pub(crate) fn check_limit(&self, key: K, limit: u32) -> Result<Quota, Quota> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        self.drop_expired(&mut attempts, now);
+
+        let times = attempts.entry(key).or_default();
+        let over_limit = times.len() >= limit as usize;
+        if !over_limit {
+            times.push_back(now);
+        }
+
+        // The oldest attempt in the window is the next to expire
+        let oldest = times.front().copied().unwrap_or(now);
+        let quota = Quota {
+            limit,
+            remaining: limit.saturating_sub(times.len() as u32),
+            reset_after: self.window.saturating_sub(now.duration_since(oldest)),
+        };
+        if over_limit { Err(quota) } else { Ok(quota) }
+    }
+
+    /// Forget keys with no attempts left in the window
+    ///
+    /// Checks do this as they go; call it periodically for limiters that
+    /// may sit idle.
+    pub(crate) fn purge_expired(&self) {
+        let mut attempts = self.attempts.lock().unwrap();
+        self.drop_expired(&mut attempts, Instant::now());
+    }
+
+    fn drop_expired(&self, attempts: &mut HashMap<K, VecDeque<Instant>>, now: Instant) {
+        attempts.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= self.window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+    }
+}
+
+/// Limits on the GitHub device-flow endpoints
+pub(crate) struct AuthRateLimiter {
+    per_ip: RateLimiter<IpAddr>,
+    per_device_code: RateLimiter<String>,
+}
+
+impl AuthRateLimiter {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            per_ip: RateLimiter::with_window(config.auth_requests_per_ip_per_minute, AUTH_WINDOW),
+            per_device_code: RateLimiter::with_window(
+                config.auth_polls_per_device_code_per_minute,
+                AUTH_WINDOW,
+            ),
+        }
+    }
+
+    /// Forget clients and device codes that stopped polling
+    pub(crate) fn purge_expired(&self) {
+        self.per_ip.purge_expired();
+        self.per_device_code.purge_expired();
+    }
+}
+
+/// Middleware rejecting auth requests over the per-IP or per-device-code limit
+///
+/// The body is buffered so the device code can be read, then handed on
+/// unchanged. Bodies that don't carry a device code are only limited per IP;
+/// the handler rejects them if they're malformed.
+pub(crate) async fn limit_auth_requests(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let limiter = &state.auth_limiter;
+    limiter
+        .per_ip
+        .check(client_ip)
+        .map_err(|quota| ApiError::RateLimited {
+            retry_after: quota.reset_after,
+        })?;
+
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_AUTH_BODY_BYTES)
+        .await
+        .map_err(|_| ApiError::BadRequest("Request body too large".to_string()))?;
+
+    if let Ok(poll) = serde_json::from_slice::<PollAuthorizationRequest>(&bytes) {
+        limiter
+            .per_device_code
+            .check(poll.device_code)
+            .map_err(|quota| ApiError::RateLimited {
+                retry_after: quota.reset_after,
+            })?;
+    }
+
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
+
+/// Who a general API request counts against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ApiClient {
+    User(UserId),
+    Ip(IpAddr),
+}
+
+/// Per-minute API limit: the configured per-IP limit for clients without
+/// an API token, the plan's limit for users
+pub(crate) type ApiRateLimiter = RateLimiter<ApiClient>;
+
+impl ApiRateLimiter {
+    pub(crate) fn per_minute(config: &Config) -> Self {
+        Self::with_window(config.api_requests_per_ip_per_minute, API_WINDOW)
+    }
+}
+
+/// Middleware enforcing the per-minute API limit and reporting it in headers
+///
+/// The user behind a valid API token is kept in the request's extensions,
+/// so handlers don't authenticate the token a second time. Requests with a
+/// missing or invalid token are limited per IP; the handler rejects them
+/// if it needs a user.
+pub(crate) async fn limit_api_requests(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let checked = match resolve_user(&mut parts, &state).await {
+        Ok(user) => {
+            let tier = match state.subscription_tier(user.id).await {
+                Ok(tier) => tier,
+                Err(e) => return ApiError::from(e).into_response(),
+            };
+            let limit = state.plan_catalog.limits(tier).api_requests_per_minute;
+            let client = ApiClient::User(user.id);
+            parts.extensions.insert(user);
+            state.api_limiter.check_limit(client, limit)
+        }
+        Err(_) => state.api_limiter.check(ApiClient::Ip(client_ip)),
+    };
+
+    let (mut response, quota) = match checked {
+        Ok(quota) => (next.run(Request::from_parts(parts, body)).await, quota),
+        Err(quota) => {
+            let retry_after = quota.reset_after;
+            (ApiError::RateLimited { retry_after }.into_response(), quota)
+        }
+    };
+    quota.apply(response.headers_mut());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_per_ip() {
+        let limiter = ProvisioningLimiter::with_window(2, Duration::from_millis(50));
+        let alice: IpAddr = "10.0.0.1".parse().unwrap();
+        let bob: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(limiter.try_acquire(alice));
+        assert!(limiter.try_acquire(alice));
+        assert!(!limiter.try_acquire(alice));
+
+        // Other IPs have their own budget
+        assert!(limiter.try_acquire(bob));
+
+        // Budget frees up once the window passes
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.try_acquire(alice));
+
+        // Idle keys are forgotten
+        std::thread::sleep(Duration::from_millis(60));
+        limiter.purge_expired();
+        assert!(limiter.attempts.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reports_retry_after() {
+        let limiter = RateLimiter::with_window(1, Duration::from_secs(60));
+
+        assert!(limiter.check("device-code".to_string()).is_ok());
+        let retry_after = limiter
+            .check("device-code".to_string())
+            .unwrap_err()
+            .reset_after;
+        assert!(retry_after > Duration::from_secs(59));
+        assert!(retry_after <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_reports_remaining_quota() {
+        let limiter = ApiRateLimiter::with_window(2, Duration::from_secs(60));
+        let ip = ApiClient::Ip("10.0.0.1".parse().unwrap());
+        let user = ApiClient::User(UserId::new_v4());
+
+        assert_eq!(limiter.check(ip).unwrap().remaining, 1);
+        assert_eq!(limiter.check(ip).unwrap().remaining, 0);
+        let exhausted = limiter.check(ip).unwrap_err();
+        assert_eq!((exhausted.limit, exhausted.remaining), (2, 0));
+
+        // Users get their plan's limit instead of the per-IP one
+        let quota = limiter.check_limit(user, 10).unwrap();
+        assert_eq!((quota.limit, quota.remaining), (10, 9));
+    }
+}
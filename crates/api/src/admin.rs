@@ -0,0 +1,209 @@
+//! # Admin Endpoints
+//!
+//! Operator-only account administration. Requests must carry the configured
+//! `admin_api_token` as a bearer token, or the API token of an active user
+//! with the admin role. Admin routes are disabled when no token is configured
+//! and the caller isn't an admin user.
+
+use axum::{
+    Json, debug_handler,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header::AUTHORIZATION, request::Parts},
+    response::{IntoResponse, Response},
+};
+use common::Pagination;
+use domain::{
+    errors::DomainError,
+    models::{
+        AdminStats, BillingEvent, RetentionReport, User, UserId, UserRole, UserStatus, ZombieReport,
+    },
+};
+use infra::retry::{self, UpstreamRetries};
+use serde::Deserialize;
+
+use crate::{
+    ApiResponse, AppState,
+    auth::resolve_user,
+    error::ApiError,
+    ndjson::{self, Format},
+};
+
+/// Days of signups shown when the client doesn't say
+const DEFAULT_STATS_DAYS: u32 = 30;
+
+/// Longest signup history a client may request
+const MAX_STATS_DAYS: u32 = 365;
+
+/// Proof that the request carries the admin token
+pub(crate) struct AdminAuth;
+
+/// Compare secrets without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let admin_token = state.config().admin_api_token.as_deref();
+        let provided = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let Some(token) = provided else {
+            return Err(match admin_token {
+                Some(_) => DomainError::Unauthorized("Admin token required".to_string()),
+                // Behave as if the routes don't exist
+                None => DomainError::NotFound("Not found".to_string()),
+            }
+            .into());
+        };
+        if admin_token.is_some_and(|admin| constant_time_eq(token.as_bytes(), admin.as_bytes())) {
+            return Ok(AdminAuth);
+        }
+
+        match resolve_user(parts, state).await {
+            Ok(user) if user.role == UserRole::Admin && user.status == UserStatus::Active => {
+                Ok(AdminAuth)
+            }
+            _ if admin_token.is_none() => {
+                Err(DomainError::NotFound("Not found".to_string()).into())
+            }
+            _ => Err(DomainError::Unauthorized("Admin token required".to_string()).into()),
+        }
+    }
+}
+
+/// Suspend a user and stop all of their running sessions
+#[debug_handler]
+pub(crate) async fn suspend_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(user_id): Path<UserId>,
+) -> Result<Json<ApiResponse<User>>, ApiError> {
+    let user = state.user_service.suspend_user(user_id).await?;
+    Ok(Json(ApiResponse::new(user)))
+}
+
+/// Lift a user's suspension
+#[debug_handler]
+pub(crate) async fn unsuspend_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(user_id): Path<UserId>,
+) -> Result<Json<ApiResponse<User>>, ApiError> {
+    let user = state.user_service.unsuspend_user(user_id).await?;
+    Ok(Json(ApiResponse::new(user)))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BillingEventsQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+/// List a user's billing audit log, oldest first
+///
+/// `?format=ndjson` streams the log one event per line.
+#[debug_handler]
+pub(crate) async fn list_billing_events(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(user_id): Path<UserId>,
+    Query(query): Query<BillingEventsQuery>,
+) -> Result<Response, ApiError> {
+    if query.format == Format::Ndjson {
+        let events = state.billing_event_service.clone();
+        return Ok(ndjson::export(move |limit, offset| {
+            let events = events.clone();
+            async move { events.find_events(user_id, limit, offset).await }
+        }));
+    }
+
+    let events: Vec<BillingEvent> = state.billing_event_service.list_events(user_id).await?;
+    let pagination = Pagination::complete(events.len());
+    Ok(Json(ApiResponse::page(events, pagination)).into_response())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StatsQuery {
+    days: Option<u32>,
+}
+
+/// Business KPIs: signups per day, active sessions, quota utilization and churn
+#[debug_handler]
+pub(crate) async fn stats(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<ApiResponse<AdminStats>>, ApiError> {
+    let days = query.days.unwrap_or(DEFAULT_STATS_DAYS);
+    if !(1..=MAX_STATS_DAYS).contains(&days) {
+        return Err(DomainError::InvalidInput(format!(
+            "days must be between 1 and {MAX_STATS_DAYS}"
+        ))
+        .into());
+    }
+
+    let stats = state.stats_service.admin_stats(days).await?;
+    Ok(Json(ApiResponse::new(stats)))
+}
+
+/// How often calls to GitHub, Stripe and Helius have been retried, per host
+///
+/// Counts start from zero when the server starts.
+#[debug_handler(state = AppState)]
+pub(crate) async fn upstream_retries(_admin: AdminAuth) -> Json<ApiResponse<Vec<UpstreamRetries>>> {
+    Json(ApiResponse::new(retry::upstream_retries()))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RetentionQuery {
+    dry_run: Option<bool>,
+}
+
+/// Apply the retention policy now, reporting what each rule purged
+///
+/// Defaults to a dry run that only counts; pass `?dry_run=false` to purge.
+#[debug_handler]
+pub(crate) async fn run_retention(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Query(query): Query<RetentionQuery>,
+) -> Result<Json<ApiResponse<Vec<RetentionReport>>>, ApiError> {
+    let reports = state
+        .retention_service
+        .run(query.dry_run.unwrap_or(true))
+        .await?;
+    Ok(Json(ApiResponse::new(reports)))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ZombieQuery {
+    dry_run: Option<bool>,
+}
+
+/// Find sessions whose client stopped sending heartbeats and mark them failed
+///
+/// Defaults to a dry run that only lists them; pass `?dry_run=false` to
+/// mark them. Unavailable when `zombie_session_minutes` is 0.
+#[debug_handler]
+pub(crate) async fn fail_zombie_sessions(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Query(query): Query<ZombieQuery>,
+) -> Result<Json<ApiResponse<ZombieReport>>, ApiError> {
+    let stale_after = crate::zombie_stale_after(state.config())
+        .ok_or_else(|| DomainError::NotFound("Zombie session checks are disabled".to_string()))?;
+    let report = state
+        .session_service
+        .fail_zombies(stale_after, query.dry_run.unwrap_or(true))
+        .await?;
+    Ok(Json(ApiResponse::new(report)))
+}
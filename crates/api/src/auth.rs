@@ -0,0 +1,77 @@
+//! # Request Authentication
+//!
+//! Axum extractor resolving the ForkForge user behind a request from its
+//! `Authorization: Bearer <api token>` header. Handlers that take an
+//! `AuthenticatedUser` argument are only reached by authenticated, active
+//! users; missing or invalid tokens and deleted accounts get
+//! `401 Unauthorized` and suspended accounts get `403 Forbidden`. On servers
+//! restricted to GitHub organizations or teams, so do GitHub users who have
+//! left them, once their membership is rechecked.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use domain::{
+    errors::DomainError,
+    models::{User, UserId, UserStatus},
+};
+
+use crate::{AppState, error::ApiError};
+
+/// The ForkForge user making the current request
+pub(crate) struct AuthenticatedUser {
+    pub(crate) user_id: UserId,
+}
+
+fn unauthorized(reason: &str) -> ApiError {
+    DomainError::Unauthorized(reason.to_string()).into()
+}
+
+/// Look up the user owning the request's bearer API token
+pub(crate) async fn resolve_user(parts: &mut Parts, state: &AppState) -> Result<User, ApiError> {
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| unauthorized("Authentication required"))?;
+
+    let user_id = state.github_auth_service.authenticate(token).await?;
+
+    state
+        .user_service
+        .get_user(user_id)
+        .await?
+        .ok_or_else(|| unauthorized("Invalid API token"))
+}
+
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        // The rate-limit middleware may already have resolved the token
+        let user = match parts.extensions.get::<User>() {
+            Some(user) => user.clone(),
+            None => resolve_user(parts, state).await?,
+        };
+
+        match user.status {
+            UserStatus::Active => {}
+            UserStatus::Suspended => return Err(ApiError::AccountSuspended),
+            UserStatus::Deleted => return Err(unauthorized("Account has been deleted")),
+        }
+
+        // Single sign-on users are vetted by their identity provider
+        if user.github_user_id.is_some() {
+            state
+                .github_auth_service
+                .ensure_group_access(user.id)
+                .await?;
+        }
+        Ok(AuthenticatedUser { user_id: user.id })
+    }
+}
@@ -0,0 +1,149 @@
+//! HTTP adapter for billing.
+//!
+//! Stripe signs every webhook, so the webhook handler reads the raw body (not
+//! parsed JSON) and hands it to the domain `StripeWebhookService` together
+//! with the `Stripe-Signature` header for verification.
+
+use axum::{
+    Json,
+    body::Bytes,
+    debug_handler,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use common::{BillingRedirect, CheckoutSessionRequest, Pagination, SetBillingCountryRequest};
+use domain::{
+    errors::DomainError,
+    models::{Plan, SubscriptionTier, User},
+    services::billing::{CustomerId, PaymentProcessor},
+};
+
+use crate::{
+    ApiResponse, AppState, auth::AuthenticatedUser, error::ApiError, precondition,
+    validation::ValidJson,
+};
+
+/// Receive a Stripe webhook event
+///
+/// Invalid or missing signatures are rejected with `400 Bad Request`.
+#[debug_handler]
+pub(crate) async fn stripe_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let service = state
+        .webhook_service
+        .as_ref()
+        .ok_or_else(|| DomainError::NotFound("Billing is not configured".to_string()))?;
+
+    let signature = headers
+        .get("Stripe-Signature")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest("Missing Stripe-Signature header".to_string()))?;
+
+    service
+        .process_webhook(&body, signature)
+        .await
+        .map_err(|e| match e {
+            DomainError::Unauthorized(msg) | DomainError::InvalidInput(msg) => {
+                ApiError::BadRequest(msg)
+            }
+            e => e.into(),
+        })?;
+
+    Ok(StatusCode::OK)
+}
+
+/// List plans with their limits and prices in every supported currency
+///
+/// Public so the CLI can show a plan comparison before login.
+#[debug_handler]
+pub(crate) async fn list_plans(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Plan>>>, ApiError> {
+    let plans = state.plan_service.list_plans().await?;
+    let pagination = Pagination::complete(plans.len());
+    Ok(Json(ApiResponse::page(plans, pagination)))
+}
+
+/// Start a Stripe Checkout subscribing the authenticated user to a tier
+///
+/// Returns the checkout page URL for the client to open in a browser.
+#[debug_handler]
+pub(crate) async fn create_checkout_session(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    ValidJson(request): ValidJson<CheckoutSessionRequest>,
+) -> Result<Json<ApiResponse<BillingRedirect>>, ApiError> {
+    let service = state
+        .checkout_service
+        .as_ref()
+        .ok_or_else(|| DomainError::NotFound("Billing is not configured".to_string()))?;
+    let tier: SubscriptionTier = request
+        .tier
+        .trim()
+        .to_ascii_lowercase()
+        .parse()
+        .map_err(DomainError::InvalidInput)?;
+
+    let url = service.checkout_url(user.user_id, tier).await?;
+    Ok(Json(ApiResponse::new(BillingRedirect { url })))
+}
+
+/// Open a Stripe Billing Portal session for the authenticated user
+///
+/// The portal handles plan changes, payment methods, invoices and
+/// cancellation; its URL is returned for the client to open.
+#[debug_handler]
+pub(crate) async fn create_portal_session(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<Json<ApiResponse<BillingRedirect>>, ApiError> {
+    let service = state
+        .checkout_service
+        .as_ref()
+        .ok_or_else(|| DomainError::NotFound("Billing is not configured".to_string()))?;
+
+    let url = service.portal_url(user.user_id).await?;
+    Ok(Json(ApiResponse::new(BillingRedirect { url })))
+}
+
+/// Page Stripe sends users back to when no `billing_return_url` is configured
+pub(crate) async fn billing_return() -> &'static str {
+    "You can close this window and return to your terminal."
+}
+
+/// Set the authenticated user's billing country for tax calculation
+///
+/// The country is also pushed to the user's Stripe customer, if any, so
+/// Stripe Tax applies the right rates. With `If-Match`, the change is
+/// rejected with `409 Conflict` if the user changed since the client read it.
+#[debug_handler]
+pub(crate) async fn set_billing_country(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<SetBillingCountryRequest>,
+) -> Result<(precondition::ETag, Json<ApiResponse<User>>), ApiError> {
+    let expected_version = precondition::if_match(&headers)?;
+    let user = state
+        .user_service
+        .set_billing_country(user.user_id, &request.country, expected_version)
+        .await?;
+
+    if let (Some(stripe), Some(customer_id), Some(country)) = (
+        &state.infra.stripe,
+        &user.stripe_customer_id,
+        &user.billing_country,
+    ) {
+        stripe
+            .update_customer_country(&CustomerId(customer_id.clone()), country)
+            .await?;
+    }
+
+    Ok((
+        precondition::etag(user.updated_at),
+        Json(ApiResponse::new(user)),
+    ))
+}
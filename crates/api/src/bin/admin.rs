@@ -0,0 +1,76 @@
+//! # ForkForge Admin CLI
+//!
+//! Operator tooling for self-hosted ForkForge deployments.
+//!
+//! ## Commands
+//!
+//! - `license show`: Verify and display the installed license
+//! - `license install <key>`: Verify a license key and install it
+
+use common::Config;
+use infra::license::{install_license, load_license};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let args: Vec<&str> = args.iter().skip(1).map(|s| s.as_str()).collect();
+
+    match args.as_slice() {
+        ["license", "show"] => license_show(),
+        ["license", "install", key] => license_install(key),
+        _ => {
+            print_help();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_help() {
+    eprintln!(
+        r#"
+forkforge-admin - Operator tooling for self-hosted ForkForge
+
+USAGE:
+    forkforge-admin <COMMAND>
+
+COMMANDS:
+    license show             Verify and display the installed license
+    license install <KEY>    Verify a license key and install it
+"#
+    );
+}
+
+fn license_show() -> Result<()> {
+    let config = Config::load()?;
+
+    match load_license(&config.license_path)? {
+        Some(license) => {
+            println!("📜 License: {}", config.license_path);
+            println!("   - Licensee: {}", license.licensee);
+            println!("   - Tier:     {:?}", license.tier);
+            println!("   - Expires:  {}", license.expires_at);
+        }
+        None => println!("No license installed at {}", config.license_path),
+    }
+
+    Ok(())
+}
+
+fn license_install(license_key: &str) -> Result<()> {
+    let config = Config::load()?;
+    let license = install_license(&config.license_path, license_key)?;
+
+    println!(
+        "✅ Installed {:?} license for {} (expires {})",
+        license.tier, license.licensee, license.expires_at
+    );
+    Ok(())
+}
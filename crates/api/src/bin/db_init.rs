@@ -10,10 +10,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load configuration
     let config = Config::load()?;
-    println!(
-        "📋 Loaded configuration for profile: {}",
-        std::env::var("FORKFORGE_PROFILE").unwrap_or_else(|_| "default".to_string())
-    );
+    println!("📋 Loaded configuration for profile: {}", Config::profile());
 
     println!("🗄️  Database URL: {}", config.database_url);
 
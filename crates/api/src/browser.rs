@@ -0,0 +1,88 @@
+//! # Browser Clients
+//!
+//! Lets web dashboards call the API from the origins listed in
+//! `cors_allowed_origins`, and sets the security headers browsers expect on
+//! every response. Requests carry API tokens in `Authorization` rather than
+//! cookies, so credentialed CORS requests are never allowed.
+
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue, Method, header},
+    middleware::Next,
+    response::Response,
+};
+use common::{API_VERSION_HEADER, Config};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::request_id::REQUEST_ID_HEADER;
+
+/// How long browsers may cache a preflight response
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Headers set on every response that doesn't set them itself
+///
+/// Browsers ignore HSTS on plain HTTP, so local development is unaffected.
+const SECURITY_HEADERS: [(HeaderName, &str); 4] = [
+    (header::STRICT_TRANSPORT_SECURITY, "max-age=31536000"),
+    (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+    (header::X_FRAME_OPTIONS, "DENY"),
+    (header::REFERRER_POLICY, "no-referrer"),
+];
+
+/// CORS for the configured origins; none are allowed when unset
+pub(crate) fn cors_layer(config: &Config) -> CorsLayer {
+    // Checked by `Config::validate` at startup
+    let origins = config.cors_origins().unwrap_or_default();
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_MATCH,
+            HeaderName::from_static(API_VERSION_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([
+            header::ETAG,
+            header::RETRY_AFTER,
+            header::LINK,
+            HeaderName::from_static("deprecation"),
+            HeaderName::from_static("x-ratelimit-limit"),
+            HeaderName::from_static("x-ratelimit-remaining"),
+            HeaderName::from_static("x-ratelimit-reset"),
+            HeaderName::from_static(API_VERSION_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .max_age(PREFLIGHT_MAX_AGE)
+}
+
+/// Add the [`SECURITY_HEADERS`] to a response
+pub(crate) async fn set_security_headers(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in SECURITY_HEADERS {
+        headers
+            .entry(name)
+            .or_insert(HeaderValue::from_static(value));
+    }
+    response
+}
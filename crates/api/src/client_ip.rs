@@ -0,0 +1,167 @@
+//! # Client IPs
+//!
+//! Behind a load balancer every connection comes from the proxy, so per-IP
+//! limits would lump all clients together. Proxies listed in
+//! `trusted_proxies` are believed about who they forward for: the client IP
+//! is the last address in their `Forwarded` (or, without one,
+//! `X-Forwarded-For`) chain that isn't itself a trusted proxy. Headers from
+//! anyone else are ignored, since clients can send whatever they like.
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, request::Parts},
+};
+use domain::errors::DomainError;
+use ipnet::IpNet;
+
+use crate::AppState;
+use crate::error::ApiError;
+
+/// Reverse proxies whose forwarding headers are believed
+#[derive(Debug, Default)]
+pub(crate) struct TrustedProxies {
+    ranges: Vec<IpNet>,
+}
+
+impl TrustedProxies {
+    pub(crate) fn new(ranges: Vec<IpNet>) -> Self {
+        Self { ranges }
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(&ip))
+    }
+
+    /// IP of the client behind a request received from `peer`
+    pub(crate) fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        // Walk back from the nearest hop; each trusted proxy vouches for the
+        // one before it. An address that can't be parsed ("unknown",
+        // obfuscated identifiers) ends the walk at the proxy that sent it.
+        let mut client = peer;
+        for hop in forwarded_chain(headers).iter().rev() {
+            let Some(ip) = parse_node(hop) else { break };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Forwarded-for addresses, client first, from `Forwarded` or `X-Forwarded-For`
+fn forwarded_chain(headers: &HeaderMap) -> Vec<String> {
+    let values = |name: &str| -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|element| element.trim().to_string())
+            .collect()
+    };
+
+    let forwarded = values("forwarded");
+    if forwarded.is_empty() {
+        return values("x-forwarded-for");
+    }
+
+    // RFC 7239 elements are `;`-separated pairs such as `for=192.0.2.60;proto=https`
+    forwarded
+        .iter()
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(name, _)| name.eq_ignore_ascii_case("for"))
+                .map(|(_, node)| node.trim_matches('"').to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Parse an address with an optional port, IPv6 optionally in brackets
+fn parse_node(node: &str) -> Option<IpAddr> {
+    node.parse::<IpAddr>()
+        .ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            let bracketed = node.strip_prefix('[')?.split(']').next()?;
+            bracketed.parse().ok()
+        })
+}
+
+/// Extractor for the IP of the client behind a request
+///
+/// Use this rather than `ConnectInfo` wherever the client IP matters, so
+/// every limit sees the same address.
+pub(crate) struct ClientIp(pub(crate) IpAddr);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .copied()
+            .ok_or_else(|| DomainError::Internal("Missing connection info".to_string()))?;
+
+        Ok(ClientIp(
+            state.trusted_proxies.client_ip(peer.ip(), &parts.headers),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies() -> TrustedProxies {
+        TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap()])
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_ignores_headers_from_untrusted_peers() {
+        let peer: IpAddr = "203.0.113.9".parse().unwrap();
+        let spoofed = headers("x-forwarded-for", "198.51.100.1");
+        assert_eq!(proxies().client_ip(peer, &spoofed), peer);
+    }
+
+    #[test]
+    fn test_skips_trusted_hops() {
+        let peer: IpAddr = "10.0.0.2".parse().unwrap();
+        let client: IpAddr = "198.51.100.1".parse().unwrap();
+
+        // The leftmost entry is client-supplied and can't be believed
+        let chain = headers("x-forwarded-for", "1.2.3.4, 198.51.100.1, 10.0.0.1");
+        assert_eq!(proxies().client_ip(peer, &chain), client);
+
+        let forwarded = headers(
+            "forwarded",
+            "for=\"[2001:db8::1]:4711\", for=198.51.100.1;proto=https, for=10.0.0.1",
+        );
+        assert_eq!(proxies().client_ip(peer, &forwarded), client);
+
+        let unknown = headers("forwarded", "for=unknown, for=10.0.0.1");
+        assert_eq!(
+            proxies().client_ip(peer, &unknown),
+            "10.0.0.1".parse::<IpAddr>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! # Device-Flow Logins
+//!
+//! A user can take minutes to approve a login on GitHub, longer than most
+//! proxies let a request stay open. Starting a device flow spawns a task
+//! that polls GitHub for the device code and keeps the outcome in memory;
+//! the CLI checks `GET /auth/github/status` every few seconds, which only
+//! reads it.
+//!
+//! A finished login is handed out once, so the tokens can't be fetched
+//! again with a leaked device code. Outcomes nobody collects are dropped by
+//! the token cleanup job.
+//!
+//! Logins in progress are also stored in the database until they finish or
+//! expire. If the server restarts while a user is approving a login, the
+//! CLI's next status check resumes polling GitHub for the stored code.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use domain::models::ProviderToken;
+use domain::services::auth::types::AuthError;
+
+/// How long a finished login waits to be collected
+const UNCOLLECTED_LOGIN_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Outcome of polling GitHub for one device code
+pub(crate) type LoginResult = Result<ProviderToken, AuthError>;
+
+/// What's known about a device code
+#[derive(Debug)]
+pub(crate) enum LoginState {
+    /// Still waiting for the user
+    Pending,
+    /// Polling stopped; this is the only time the outcome is returned
+    Finished(LoginResult),
+}
+
+#[derive(Debug)]
+enum Login {
+    Pending,
+    Finished { result: LoginResult, at: Instant },
+}
+
+/// Device-flow logins in progress, keyed by device code
+#[derive(Debug, Default)]
+pub(crate) struct DeviceFlows {
+    logins: Mutex<HashMap<String, Login>>,
+}
+
+impl DeviceFlows {
+    /// Run `wait` in the background and record its outcome for `device_code`
+    ///
+    /// Does nothing if `device_code` is already tracked, so concurrent
+    /// resumes don't poll GitHub twice.
+    pub(crate) fn start<F>(self: &Arc<Self>, device_code: String, wait: F)
+    where
+        F: Future<Output = LoginResult> + Send + 'static,
+    {
+        match self.logins.lock().unwrap().entry(device_code.clone()) {
+            Entry::Occupied(_) => return,
+            Entry::Vacant(entry) => entry.insert(Login::Pending),
+        };
+
+        let flows = Arc::clone(self);
+        tokio::spawn(async move {
+            let result = wait.await;
+            if let Err(e) = &result {
+                tracing::info!(error = %e, "Device-flow login failed");
+            }
+            flows.logins.lock().unwrap().insert(
+                device_code,
+                Login::Finished {
+                    result,
+                    at: Instant::now(),
+                },
+            );
+        });
+    }
+
+    /// State of the login for `device_code`, or `None` if it's unknown
+    ///
+    /// A finished login is forgotten once its outcome has been returned.
+    pub(crate) fn take(&self, device_code: &str) -> Option<LoginState> {
+        let mut logins = self.logins.lock().unwrap();
+        match logins.get(device_code)? {
+            Login::Pending => Some(LoginState::Pending),
+            Login::Finished { .. } => match logins.remove(device_code) {
+                Some(Login::Finished { result, .. }) => Some(LoginState::Finished(result)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Forget finished logins that were never collected
+    pub(crate) fn purge_expired(&self) {
+        let now = Instant::now();
+        self.logins.lock().unwrap().retain(|_, login| match login {
+            Login::Pending => true,
+            Login::Finished { at, .. } => now.duration_since(*at) < UNCOLLECTED_LOGIN_TTL,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_hands_out_the_outcome_once() {
+        let flows = Arc::new(DeviceFlows::default());
+        let (approve, approved) = oneshot::channel();
+        flows.start("code".to_string(), async move {
+            approved
+                .await
+                .map_err(|_| AuthError::UserAuthenticationTimeout)
+        });
+
+        assert!(matches!(flows.take("code"), Some(LoginState::Pending)));
+        assert!(flows.take("other").is_none());
+
+        approve
+            .send(ProviderToken {
+                access_token: "gho_abc".to_string(),
+                refresh_token: None,
+                expires_at: None,
+                refresh_token_expires_at: None,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        match flows.take("code") {
+            Some(LoginState::Finished(Ok(token))) => assert_eq!(token.access_token, "gho_abc"),
+            other => panic!("expected a finished login, got {other:?}"),
+        }
+        assert!(flows.take("code").is_none());
+    }
+}
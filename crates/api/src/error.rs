@@ -0,0 +1,236 @@
+//! Maps handler failures to HTTP status codes with a JSON error envelope.
+//!
+//! Every error response has the same shape, so clients can branch on a
+//! stable `code` rather than parsing messages:
+//!
+//! ```json
+//! { "error": { "code": "not_found", "message": "Not found: Session ..." } }
+//! ```
+//!
+//! Validation failures also list each invalid field under `fields`, and
+//! every error carries the `request_id` of the request that failed. The
+//! envelope is `common::ApiErrorResponse`.
+
+use std::time::Duration;
+
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use common::{ApiErrorResponse, ErrorBody, FieldError};
+use domain::{errors::DomainError, services::auth::types::AuthError};
+
+use crate::request_id;
+
+/// Errors returned by API handlers and extractors
+pub(crate) enum ApiError {
+    Domain(DomainError),
+    Auth(AuthError),
+    BadRequest(String),
+    AccountSuspended,
+    ProvisioningLimited,
+    /// Too many requests; the client may retry after the given delay
+    RateLimited {
+        retry_after: Duration,
+    },
+    /// Request body fields that failed validation
+    InvalidFields(Vec<FieldError>),
+}
+
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        ApiError::Domain(err)
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(err: AuthError) -> Self {
+        ApiError::Auth(err)
+    }
+}
+
+impl ApiError {
+    /// HTTP status, stable error code and client-facing message
+    pub(crate) fn parts(&self) -> (StatusCode, &'static str, String) {
+        match self {
+            ApiError::Domain(err) => match err {
+                DomainError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found", err.to_string()),
+                DomainError::Unauthorized(_) => {
+                    (StatusCode::UNAUTHORIZED, "unauthorized", err.to_string())
+                }
+                DomainError::InvalidInput(_) => (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "invalid_input",
+                    err.to_string(),
+                ),
+                // Plan limits are lifted by upgrading, hence Payment Required
+                DomainError::QuotaExceeded(_) => (
+                    StatusCode::PAYMENT_REQUIRED,
+                    "quota_exceeded",
+                    err.to_string(),
+                ),
+                DomainError::Conflict(_) => (StatusCode::CONFLICT, "conflict", err.to_string()),
+                DomainError::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden", err.to_string()),
+                DomainError::ExternalService(_) => (
+                    StatusCode::BAD_GATEWAY,
+                    "external_service_error",
+                    err.to_string(),
+                ),
+                // Don't leak internal error details to clients
+                DomainError::Internal(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "internal_error",
+                    "Internal server error".to_string(),
+                ),
+            },
+            ApiError::Auth(err) => {
+                let (status, code) = match err {
+                    AuthError::UserAuthenticationTimeout => {
+                        (StatusCode::REQUEST_TIMEOUT, "authorization_timeout")
+                    }
+                    AuthError::UserDeniedAuthentication => {
+                        (StatusCode::UNAUTHORIZED, "authorization_denied")
+                    }
+                    AuthError::ServerConfigurationError { .. }
+                    | AuthError::InternalServerError { .. } => {
+                        (StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
+                    }
+                };
+                (status, code, err.message())
+            }
+            ApiError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, "bad_request", message.clone())
+            }
+            ApiError::AccountSuspended => (
+                StatusCode::FORBIDDEN,
+                "account_suspended",
+                "Account is suspended".to_string(),
+            ),
+            ApiError::ProvisioningLimited => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                "Session provisioning limit reached, try again later".to_string(),
+            ),
+            ApiError::RateLimited { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                "Too many requests, try again later".to_string(),
+            ),
+            ApiError::InvalidFields(fields) => {
+                let problems: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.field, f.message))
+                    .collect();
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "invalid_input",
+                    problems.join("; "),
+                )
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, code, message) = self.parts();
+        let fields = match &self {
+            ApiError::InvalidFields(fields) => fields.clone(),
+            _ => Vec::new(),
+        };
+        let body = ApiErrorResponse {
+            error: ErrorBody {
+                code: code.to_string(),
+                message,
+                fields,
+                request_id: request_id::current(),
+            },
+        };
+        let mut response = (status, Json(body)).into_response();
+
+        if let ApiError::RateLimited { retry_after } = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                seconds_rounded_up(retry_after).max(1).into(),
+            );
+        }
+
+        response
+    }
+}
+
+/// Whole seconds, rounded up so clients don't retry too early
+pub(crate) fn seconds_rounded_up(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_error_mapping() {
+        let cases = [
+            (DomainError::NotFound("x".into()), 404, "not_found"),
+            (DomainError::Unauthorized("x".into()), 401, "unauthorized"),
+            (DomainError::InvalidInput("x".into()), 422, "invalid_input"),
+            (
+                DomainError::QuotaExceeded("x".into()),
+                402,
+                "quota_exceeded",
+            ),
+            (DomainError::Conflict("x".into()), 409, "conflict"),
+            (DomainError::Forbidden("x".into()), 403, "forbidden"),
+            (
+                DomainError::ExternalService("x".into()),
+                502,
+                "external_service_error",
+            ),
+            (DomainError::Internal("x".into()), 500, "internal_error"),
+        ];
+
+        for (err, status, code) in cases {
+            let (actual_status, actual_code, _) = ApiError::from(err).parts();
+            assert_eq!(actual_status.as_u16(), status);
+            assert_eq!(actual_code, code);
+        }
+
+        // Internal details stay server-side
+        let (_, _, message) = ApiError::from(DomainError::Internal("db password".into())).parts();
+        assert_eq!(message, "Internal server error");
+    }
+
+    #[test]
+    fn test_rate_limited_sets_retry_after() {
+        let response = ApiError::RateLimited {
+            retry_after: Duration::from_millis(2500),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+    }
+
+    #[test]
+    fn test_invalid_fields_message() {
+        let err = ApiError::InvalidFields(vec![
+            FieldError {
+                field: "name".to_string(),
+                message: "must not be empty".to_string(),
+            },
+            FieldError {
+                field: "accounts[0]".to_string(),
+                message: "must be a base58 public key".to_string(),
+            },
+        ]);
+
+        let (status, code, message) = err.parts();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(code, "invalid_input");
+        assert_eq!(
+            message,
+            "name: must not be empty; accounts[0]: must be a base58 public key"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Utc};
 /// HTTP adapter for GitHub device flow initiation.
 ///
 /// These HTTP endpoint handlers demonstrates the adapter pattern - translating between:
@@ -16,7 +17,7 @@
 /// 1. Extracts the domain service from Axum state
 /// 2. Calls the domain method (no parameters needed for device flow)
 /// 3. Maps the domain response to HTTP JSON response
-/// 4. Converts domain errors to HTTP status codes
+/// 4. Converts domain errors to HTTP responses via `ApiError`
 ///
 /// # Benefits
 ///
@@ -24,52 +25,90 @@
 /// - **Testability**: Domain logic testable without spinning up HTTP server
 /// - **Single Responsibility**: HTTP concerns stay in API layer only
 use common::{
+    ApiTokenRequest, ApiTokenResponse, AuthorizationStatus, AuthorizationStatusResponse,
     CheckUserAuthorisedResponse, DeviceCodeResponse, GitHubUser, PollAuthorizationRequest,
 };
-use domain::services::auth::types::AuthError;
+use domain::{errors::DomainError, models::ProviderToken, services::auth::types::AuthError};
 
-use axum::{Json, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{Json, debug_handler, extract::State};
 
-use crate::AppState;
+use crate::{
+    AppState,
+    auth::AuthenticatedUser,
+    device_flows::LoginState,
+    error::ApiError,
+    validation::{ValidJson, ValidQuery},
+};
+
+/// How often the legacy long poll checks whether the login finished
+const LEGACY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
 
-// Wrapper to implement IntoResponse for domain error types
-pub(crate) struct ApiError(AuthError);
+/// GitHub's token response shape, with lifetimes relative to now
+fn token_response(token: ProviderToken) -> CheckUserAuthorisedResponse {
+    let seconds_left =
+        |at: Option<DateTime<Utc>>| at.map(|at| (at - Utc::now()).num_seconds().max(0));
 
-impl From<AuthError> for ApiError {
-    fn from(err: AuthError) -> Self {
-        ApiError(err)
+    CheckUserAuthorisedResponse {
+        access_token: token.access_token,
+        _token_type: "bearer".to_string(),
+        _scope: "user".to_string(),
+        refresh_token: token.refresh_token,
+        expires_in: seconds_left(token.expires_at),
+        refresh_token_expires_in: seconds_left(token.refresh_token_expires_at),
     }
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> axum::response::Response {
-        let status = match &self.0 {
-            AuthError::UserAuthenticationTimeout => StatusCode::REQUEST_TIMEOUT,
-            AuthError::UserDeniedAuthentication => StatusCode::UNAUTHORIZED,
-            AuthError::ServerConfigurationError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
-            AuthError::InternalServerError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-
-        (
-            status,
-            Json(serde_json::json!({ "error": self.0.message() })),
-        )
-            .into_response()
+/// Device code nobody started a login for, or whose outcome was already taken
+fn unknown_device_code() -> ApiError {
+    DomainError::NotFound("Unknown device code, start a new login".to_string()).into()
+}
+
+/// Poll GitHub for `device_code` in the background
+fn start_polling(state: &AppState, device_code: String) {
+    let auth_service = state.github_auth_service.clone();
+    let polled_code = device_code.clone();
+    state.device_flows.start(device_code, async move {
+        auth_service.wait_for_authorization(&polled_code).await
+    });
+}
+
+/// State of the login for `device_code`
+///
+/// Logins this process doesn't know about, e.g. ones started before a
+/// restart, are resumed from the database while their code is valid.
+async fn login_state(state: &AppState, device_code: &str) -> Result<LoginState, ApiError> {
+    if let Some(login) = state.device_flows.take(device_code) {
+        if matches!(login, LoginState::Finished(_))
+            && let Err(e) = state
+                .github_auth_service
+                .finish_device_flow(device_code)
+                .await
+        {
+            // Left for the cleanup job; the outcome can't be taken twice anyway
+            tracing::warn!(error = %e, "Failed to forget a finished device flow");
+        }
+        return Ok(login);
     }
+
+    let flow = state
+        .github_auth_service
+        .resumable_device_flow(device_code)
+        .await?
+        .ok_or_else(unknown_device_code)?;
+    tracing::info!("Resuming a device-flow login started before a restart");
+    start_polling(state, flow.device_code);
+    Ok(LoginState::Pending)
 }
 
 /// Step 1: Initiate device flow
 /// This takes no parameters and returns a device code that maps to the user's auth attempt.
-///
+/// GitHub is polled for the code in the background from here on.
 #[debug_handler]
 pub(crate) async fn github_create_user_device_session(
     State(state): State<AppState>,
-) -> Result<Json<DeviceCodeResponse>, StatusCode> {
-    let domain_response = state
-        .github_auth_service
-        .request_device_code()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<Json<DeviceCodeResponse>, ApiError> {
+    let domain_response = state.github_auth_service.request_device_code().await?;
+    start_polling(&state, domain_response.device_code.clone());
 
     // Convert domain response to common response type
     let response = DeviceCodeResponse {
@@ -83,49 +122,155 @@ pub(crate) async fn github_create_user_device_session(
     Ok(Json(response))
 }
 
-/// Step 2: Poll for user authorization
-/// Takes device code (code that maps from the oauth app to the user's auth attempt)
-/// and polls for the user's authorization status. (If they've authorized on web)
+/// Step 2: Check whether the user has authorized the device
+/// Returns immediately; clients call this every `interval` seconds until the
+/// status is no longer `pending`. The tokens are only returned once.
 #[debug_handler]
-pub(crate) async fn check_user_authorised(
+pub(crate) async fn authorization_status(
     State(state): State<AppState>,
-    Json(poll_request): Json<PollAuthorizationRequest>,
-) -> Result<Json<CheckUserAuthorisedResponse>, ApiError> {
-    let access_token = state
-        .github_auth_service
-        .wait_for_authorization(&poll_request.device_code)
-        .await?;
-
-    // Create response with the access token
-    let response = CheckUserAuthorisedResponse {
-        access_token,
-        _token_type: "bearer".to_string(),
-        _scope: "user".to_string(),
+    ValidQuery(query): ValidQuery<PollAuthorizationRequest>,
+) -> Result<Json<AuthorizationStatusResponse>, ApiError> {
+    let status = |status| AuthorizationStatusResponse {
+        status,
+        token: None,
     };
 
-    println!("Authentication successful, Token: {response:?}");
+    let response = match login_state(&state, &query.device_code).await? {
+        LoginState::Pending => status(AuthorizationStatus::Pending),
+        LoginState::Finished(Ok(token)) => {
+            tracing::info!("GitHub device authorization completed");
+            AuthorizationStatusResponse {
+                status: AuthorizationStatus::Complete,
+                token: Some(token_response(token)),
+            }
+        }
+        LoginState::Finished(Err(AuthError::UserDeniedAuthentication)) => {
+            status(AuthorizationStatus::Denied)
+        }
+        LoginState::Finished(Err(AuthError::UserAuthenticationTimeout)) => {
+            status(AuthorizationStatus::Expired)
+        }
+        LoginState::Finished(Err(e)) => return Err(e.into()),
+    };
     Ok(Json(response))
 }
 
+/// Step 2 for older CLIs: wait until the user has authorized the device
+/// Holds the request open for up to 15 minutes, so it's only served when
+/// `legacy_auth_long_poll` is set.
+#[debug_handler]
+pub(crate) async fn check_user_authorised(
+    State(state): State<AppState>,
+    ValidJson(poll_request): ValidJson<PollAuthorizationRequest>,
+) -> Result<Json<CheckUserAuthorisedResponse>, ApiError> {
+    loop {
+        match login_state(&state, &poll_request.device_code).await? {
+            LoginState::Pending => tokio::time::sleep(LEGACY_POLL_INTERVAL).await,
+            LoginState::Finished(result) => {
+                let token = result?;
+                tracing::info!("GitHub device authorization completed");
+                return Ok(Json(token_response(token)));
+            }
+        }
+    }
+}
+
 /// Step 3: Get user details
 /// Takes the access token and fetches the user's details from GitHub.
 #[debug_handler]
 pub async fn github_login(
     State(state): State<AppState>,
-    Json(access_token): Json<String>,
-) -> Result<Json<GitHubUser>, StatusCode> {
+    ValidJson(access_token): ValidJson<String>,
+) -> Result<Json<GitHubUser>, ApiError> {
     let domain_user = state
         .github_auth_service
         // TODO: Remove this get_user call for `authorize()`
         .get_user(&access_token)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
 
     // Convert domain user to common user type
     let user = GitHubUser {
-        id: domain_user.id,
-        login: domain_user.login,
+        id: domain_user.provider_id.parse().map_err(|_| {
+            DomainError::ExternalService(format!(
+                "Unexpected GitHub user ID: {}",
+                domain_user.provider_id
+            ))
+        })?,
+        login: domain_user.username,
+        email: domain_user.email,
+        name: domain_user.display_name,
     };
 
     Ok(Json(user))
 }
+
+/// Step 4: Exchange a GitHub access token for a ForkForge API token
+/// Creates the ForkForge user on first login, and stores the GitHub tokens
+/// so expiring ones can be refreshed.
+#[debug_handler]
+pub(crate) async fn issue_api_token(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<ApiTokenRequest>,
+) -> Result<Json<ApiTokenResponse>, ApiError> {
+    let github_user = state
+        .github_auth_service
+        .get_user(&request.access_token)
+        .await?;
+    // Before creating the account, so outsiders never get one
+    state
+        .github_auth_service
+        .check_group_access(&request.access_token, &github_user)
+        .await?;
+
+    let user = state
+        .user_service
+        .find_or_create_github_user(&github_user)
+        .await?;
+    // Not fatal: checkout creates the customer if Stripe is down now
+    if let Some(checkout) = &state.checkout_service
+        && user.stripe_customer_id.is_none()
+        && let Err(e) = checkout.ensure_customer(user.id).await
+    {
+        tracing::warn!(user_id = %user.id, error = %e, "Failed to create Stripe customer");
+    }
+    let now = Utc::now();
+    let expires_at = |seconds: Option<i64>| seconds.map(|seconds| now + Duration::seconds(seconds));
+    state
+        .github_auth_service
+        .save_provider_token(
+            user.id,
+            &ProviderToken {
+                access_token: request.access_token,
+                refresh_token: request.refresh_token,
+                expires_at: expires_at(request.expires_in),
+                refresh_token_expires_at: expires_at(request.refresh_token_expires_in),
+            },
+        )
+        .await?;
+
+    let api_token = state
+        .github_auth_service
+        .create_api_token(user.id, Some("CLI".to_string()))
+        .await?;
+
+    Ok(Json(ApiTokenResponse {
+        token: api_token.token,
+    }))
+}
+
+/// Refresh the authenticated user's stored GitHub tokens
+///
+/// Only GitHub apps with expiring user tokens issue refresh tokens; users of
+/// other apps get `401` and must log in again.
+#[debug_handler]
+pub(crate) async fn refresh_github_token(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<Json<CheckUserAuthorisedResponse>, ApiError> {
+    let token = state
+        .github_auth_service
+        .refresh_provider_token(user.user_id)
+        .await?;
+
+    Ok(Json(token_response(token)))
+}
@@ -0,0 +1,84 @@
+//! # Background Jobs
+//!
+//! Periodic housekeeping that runs alongside the HTTP server: data
+//! retention, and purging expired API tokens and idle rate-limit state.
+//! Each job runs once at startup and then on its interval. On shutdown,
+//! jobs stop at their next tick; a run in progress is allowed to finish.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Interval tasks started by `main` and stopped on shutdown
+pub struct Jobs {
+    shutdown: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Jobs {
+    pub(crate) fn new() -> Self {
+        Self {
+            shutdown: watch::channel(false).0,
+            handles: Vec::new(),
+        }
+    }
+
+    /// Run `job` now and then every `period` until shutdown
+    pub(crate) fn every<F, Fut>(&mut self, name: &'static str, period: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut shutdown = self.shutdown.subscribe();
+        self.handles.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => job().await,
+                    _ = shutdown.changed() => break,
+                }
+            }
+            tracing::debug!(job = name, "Background job stopped");
+        }));
+    }
+
+    /// Stop every job, waiting for runs in progress to finish
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                tracing::error!(error = %e, "Background job panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_runs_until_shutdown() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let mut jobs = Jobs::new();
+        let counter = runs.clone();
+        jobs.every("count", Duration::from_millis(10), move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(35)).await;
+        jobs.shutdown().await;
+        let after_shutdown = runs.load(Ordering::SeqCst);
+        assert!(after_shutdown >= 2);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), after_shutdown);
+    }
+}
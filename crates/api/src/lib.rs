@@ -0,0 +1,575 @@
+//! # ForkForge API
+//!
+//! The HTTP API for ForkForge/Chainbox, built with Axum. It provides REST
+//! endpoints for authentication, session management, and billing. The `api`
+//! binary serves `router`; integration tests in `tests/` serve it too.
+//!
+//! ## Architecture
+//!
+//! The server uses the `ServerInfra` façade from the infra crate to access all
+//! infrastructure services (database, external APIs, etc.) while keeping the
+//! HTTP layer focused on request/response handling.
+//!
+//! ## Endpoints
+//!
+//! - Authentication: GitHub OAuth device flow
+//! - Sessions: Fork session management and region placement
+//! - Snapshots: Time-travel snapshot creation
+//! - Billing: Stripe webhook handling, plans and prices
+//! - Admin: Account suspension and billing audit log
+//! - Version: Build and configuration summary for debugging deployments
+//!
+//! All of them are served under `/v1`; see `versioning`.
+
+mod abuse;
+mod admin;
+mod auth;
+mod billing;
+mod browser;
+mod client_ip;
+mod device_flows;
+mod error;
+mod github;
+mod jobs;
+mod ndjson;
+mod precondition;
+mod request_id;
+mod scim;
+mod sessions;
+mod sso;
+mod users;
+mod validation;
+mod versioning;
+
+use axum::{
+    Json, Router,
+    extract::State,
+    middleware,
+    routing::{get, post, put},
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::Level;
+
+use common::{API_PREFIX, ApiResponse, BuildInfo, CliVersionResponse, Config};
+use domain::{
+    errors::DomainError,
+    models::{AccessGroup, License, Region, RegionCatalog, SubscriptionTier, UserId},
+    services::{
+        auth::github::AuthService,
+        billing::{
+            checkout::CheckoutService,
+            events::BillingEventService,
+            plans::{PlanCatalog, PlanService},
+            subscriptions::SubscriptionService,
+            webhooks::StripeWebhookService,
+        },
+        quota::QuotaService,
+        resolver::Resolver,
+        retention::{RetentionPolicy, RetentionService},
+        sessions::SessionService,
+        snapshots::SnapshotService,
+        stats::StatsService,
+        users::{SignupRules, UserService},
+    },
+};
+use github::github_create_user_device_session;
+use infra::{DbRepo, GitHubDeviceFlowProvider, OidcClient, ServerInfra, StripeSdk};
+
+use crate::abuse::{
+    ApiRateLimiter, AuthRateLimiter, ProvisioningLimiter, limit_api_requests, limit_auth_requests,
+};
+use crate::browser::{cors_layer, set_security_headers};
+use crate::client_ip::TrustedProxies;
+use crate::device_flows::DeviceFlows;
+use crate::github::{
+    authorization_status, check_user_authorised, github_login, issue_api_token,
+    refresh_github_token,
+};
+pub use crate::jobs::Jobs;
+use crate::versioning::{deprecate_unversioned, negotiate_version};
+
+/// Application state shared across all request handlers
+///
+/// Contains configuration and service instances needed by handlers.
+/// Cloned for each request due to Axum's state management.
+#[derive(Clone)]
+pub struct AppState {
+    config: Config,
+    infra: Arc<ServerInfra>,
+    github_auth_service: Arc<AuthService<GitHubDeviceFlowProvider, DbRepo>>,
+    session_service: Arc<SessionService<DbRepo>>,
+    quota_service: Arc<QuotaService<DbRepo>>,
+    resolver: Arc<Resolver<DbRepo>>,
+    snapshot_service: Arc<SnapshotService<DbRepo>>,
+    user_service: Arc<UserService<DbRepo, DbRepo>>,
+    billing_event_service: Arc<BillingEventService<DbRepo>>,
+    subscription_service: Arc<SubscriptionService<DbRepo>>,
+    stats_service: Arc<StatsService<DbRepo>>,
+    retention_service: Arc<RetentionService<DbRepo>>,
+    plan_service: Arc<PlanService<StripeSdk>>,
+    plan_catalog: Arc<PlanCatalog>,
+    regions: Arc<RegionCatalog>,
+    /// Present only when Stripe is configured
+    webhook_service: Option<Arc<StripeWebhookService<StripeSdk, DbRepo, DbRepo>>>,
+    /// Present only when Stripe is configured
+    checkout_service: Option<Arc<CheckoutService<StripeSdk, DbRepo>>>,
+    /// Present only when single sign-on is configured
+    oidc_client: Option<Arc<OidcClient>>,
+    license: Option<License>,
+    provisioning_limiter: Arc<ProvisioningLimiter>,
+    auth_limiter: Arc<AuthRateLimiter>,
+    api_limiter: Arc<ApiRateLimiter>,
+    trusted_proxies: Arc<TrustedProxies>,
+    device_flows: Arc<DeviceFlows>,
+    runtime: Arc<RuntimeInfo>,
+}
+
+impl AppState {
+    /// Create the domain services over `infra`
+    ///
+    /// Logs a summary of the build and configuration, as served at
+    /// `GET /version`.
+    pub async fn new(
+        config: &Config,
+        infra: Arc<ServerInfra>,
+        license: Option<License>,
+    ) -> Result<Self, DomainError> {
+        let runtime = runtime_info(config, &infra, license.as_ref()).await;
+        tracing::info!(
+            version = runtime.version,
+            git_sha = %runtime.build.short_sha(),
+            built_at = %runtime.build.built_at,
+            profile = %runtime.profile,
+            subsystems = ?runtime.subsystems,
+            database = %runtime.database,
+            migration_version = ?runtime.migration_version,
+            "Starting ForkForge API"
+        );
+
+        let mut device_flow_provider = GitHubDeviceFlowProvider::new(
+            config.github_client_id.clone().ok_or_else(|| {
+                DomainError::Internal("GitHub client ID not configured".to_string())
+            })?,
+            config.github_client_secret.clone(),
+            &config.github_base_url,
+            config.github_scopes.clone(),
+            infra.http.clone(),
+        );
+        if let Some(oauth_url) = &config.github_oauth_url {
+            device_flow_provider = device_flow_provider.with_oauth_url(oauth_url);
+        }
+        if let Some(api_url) = &config.github_api_url {
+            device_flow_provider = device_flow_provider.with_api_url(api_url);
+        }
+        // Checked by `Config::validate` at startup
+        let required_groups = config
+            .github_groups()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|group| AccessGroup::parse(group))
+            .collect();
+        let github_auth_service = Arc::new(
+            AuthService::new(device_flow_provider, infra.db.clone()).with_required_groups(
+                required_groups,
+                chrono::Duration::minutes(i64::from(config.github_group_recheck_minutes)),
+            ),
+        );
+
+        // Tier limits and features are read once; restart to pick up changes
+        let plan_catalog = Arc::new(PlanCatalog::load(&infra.db).await?);
+        let webhook_service = infra.stripe.clone().map(|stripe| {
+            Arc::new(StripeWebhookService::new(
+                stripe,
+                infra.db.clone(),
+                SubscriptionService::new(infra.db.clone()),
+            ))
+        });
+        let billing_return_url = config
+            .billing_return_url
+            .clone()
+            .unwrap_or_else(|| format!("{}{API_PREFIX}/billing/return", config.api_base_url));
+        let checkout_service = infra.stripe.clone().map(|stripe| {
+            Arc::new(CheckoutService::new(
+                stripe,
+                infra.db.clone(),
+                billing_return_url,
+            ))
+        });
+
+        let oidc_client = config.oidc_issuer.as_deref().map(|issuer| {
+            Arc::new(OidcClient::new(
+                issuer,
+                config.oidc_client_id.clone().unwrap_or_default(),
+                config.oidc_client_secret.clone(),
+                infra.http.clone(),
+            ))
+        });
+
+        Ok(Self {
+            config: config.clone(),
+            github_auth_service,
+            session_service: Arc::new(SessionService::new(infra.db.clone())),
+            quota_service: Arc::new(QuotaService::new(plan_catalog.clone(), infra.db.clone())),
+            resolver: Arc::new(Resolver::new(infra.db.clone())),
+            snapshot_service: Arc::new(SnapshotService::new(infra.db.clone())),
+            user_service: Arc::new(
+                UserService::new(infra.db.clone(), infra.db.clone())
+                    .with_signup_rules(signup_rules(config)),
+            ),
+            billing_event_service: Arc::new(BillingEventService::new(infra.db.clone())),
+            subscription_service: Arc::new(SubscriptionService::new(infra.db.clone())),
+            stats_service: Arc::new(StatsService::new(infra.db.clone())),
+            retention_service: Arc::new(RetentionService::new(
+                infra.db.clone(),
+                retention_policy(config),
+            )),
+            plan_service: Arc::new(PlanService::new(plan_catalog.clone(), infra.stripe.clone())),
+            plan_catalog,
+            regions: Arc::new(region_catalog(config)),
+            webhook_service,
+            checkout_service,
+            oidc_client,
+            license,
+            provisioning_limiter: Arc::new(ProvisioningLimiter::per_hour(
+                config.sessions_per_ip_per_hour,
+            )),
+            auth_limiter: Arc::new(AuthRateLimiter::new(config)),
+            api_limiter: Arc::new(ApiRateLimiter::per_minute(config)),
+            // Checked by `Config::validate` before the server starts
+            trusted_proxies: Arc::new(TrustedProxies::new(
+                config.trusted_proxy_ranges().unwrap_or_default(),
+            )),
+            device_flows: Arc::new(DeviceFlows::default()),
+            runtime: Arc::new(runtime),
+            infra,
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl AppState {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Whether a valid self-hosted license unlocks Pro features
+    fn pro_features_enabled(&self) -> bool {
+        self.license.as_ref().is_some_and(License::unlocks_pro)
+    }
+
+    /// Tier whose limits apply to a user's requests
+    ///
+    /// A self-hosted license unlocks Pro for everyone; otherwise it's the
+    /// user's own subscription.
+    async fn subscription_tier(&self, user_id: UserId) -> Result<SubscriptionTier, DomainError> {
+        if self.pro_features_enabled() {
+            return Ok(SubscriptionTier::Pro);
+        }
+        self.subscription_service.effective_tier(user_id).await
+    }
+}
+
+async fn health() -> Json<ApiResponse<&'static str>> {
+    Json(ApiResponse::new("Ok"))
+}
+
+/// Oldest CLI version this server supports, checked by the CLI before API calls
+async fn cli_version(State(state): State<AppState>) -> Json<ApiResponse<CliVersionResponse>> {
+    Json(ApiResponse::new(CliVersionResponse {
+        min_version: state.config.min_cli_version.clone(),
+    }))
+}
+
+/// What this server is and how it's configured
+///
+/// Logged at startup and served at `GET /version`.
+#[derive(Debug, Clone, Serialize)]
+struct RuntimeInfo {
+    version: &'static str,
+    #[serde(flatten)]
+    build: BuildInfo,
+    /// Configuration profile from `FORKFORGE_PROFILE`
+    profile: String,
+    /// Optional subsystems that are enabled, e.g. `stripe`
+    subsystems: Vec<&'static str>,
+    /// Database driver, from the scheme of the database URL
+    database: String,
+    /// Latest applied migration, e.g. `20250218000001`
+    migration_version: Option<i64>,
+}
+
+async fn runtime_info(
+    config: &Config,
+    infra: &ServerInfra,
+    license: Option<&License>,
+) -> RuntimeInfo {
+    let subsystems = [
+        ("stripe", infra.stripe.is_some()),
+        ("helius", infra.helius.is_some()),
+        ("pro_license", license.is_some_and(License::unlocks_pro)),
+        ("regions", !config.regions.trim().is_empty()),
+        ("zombie_sessions", zombie_stale_after(config).is_some()),
+        ("legacy_auth_long_poll", config.legacy_auth_long_poll),
+    ];
+    let migration_version = infra.db.migration_version().await.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Failed to read migration version");
+        None
+    });
+    RuntimeInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        build: BuildInfo::current(),
+        profile: Config::profile(),
+        subsystems: subsystems
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect(),
+        database: config
+            .database_url
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        migration_version,
+    }
+}
+
+/// Build and configuration of this server, for debugging deployments
+async fn version(State(state): State<AppState>) -> Json<ApiResponse<RuntimeInfo>> {
+    Json(ApiResponse::new(state.runtime.as_ref().clone()))
+}
+
+/// Retention periods from configuration, where 0 days disables a rule
+fn retention_policy(config: &Config) -> RetentionPolicy {
+    let days = |days: u32| (days > 0).then_some(days);
+    RetentionPolicy {
+        unused_auth_token_days: days(config.retention_auth_token_days),
+        deleted_user_days: days(config.retention_deleted_user_days),
+        billing_event_days: days(config.retention_billing_event_days),
+    }
+}
+
+fn signup_rules(config: &Config) -> SignupRules {
+    SignupRules {
+        min_account_age: (config.min_github_account_age_days > 0)
+            .then(|| chrono::Duration::days(config.min_github_account_age_days.into())),
+        blocked_email_domains: config.blocked_domains(),
+    }
+}
+
+/// Regions from configuration; empty when none are configured
+fn region_catalog(config: &Config) -> RegionCatalog {
+    // Checked by `Config::validate` before the server starts
+    let regions = config
+        .region_targets()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, gateway_url)| Region { name, gateway_url })
+        .collect();
+    RegionCatalog::new(regions, config.default_region.clone())
+        .expect("Invalid region configuration")
+}
+
+/// Time between checks for zombie sessions
+const ZOMBIE_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long a session may go without a heartbeat, or `None` if never failed
+fn zombie_stale_after(config: &Config) -> Option<chrono::Duration> {
+    (config.zombie_session_minutes > 0)
+        .then(|| chrono::Duration::minutes(i64::from(config.zombie_session_minutes)))
+}
+
+/// Start the retention, token cleanup and zombie session jobs on their
+/// configured intervals
+pub fn start_jobs(state: &AppState) -> Jobs {
+    let config = &state.config;
+    let mut jobs = Jobs::new();
+
+    let retention = state.retention_service.clone();
+    let retention_interval = Duration::from_secs(u64::from(config.retention_interval_hours) * 3600);
+    jobs.every("retention", retention_interval, move || {
+        let retention = retention.clone();
+        async move {
+            match retention.run(false).await {
+                Ok(reports) => {
+                    for report in reports {
+                        tracing::info!(
+                            rule = report.rule.as_str(),
+                            affected = report.affected,
+                            cutoff = %report.cutoff,
+                            "Applied retention rule"
+                        );
+                    }
+                }
+                Err(e) => tracing::error!(error = %e, "Retention run failed"),
+            }
+        }
+    });
+
+    if let Some(stale_after) = zombie_stale_after(config) {
+        let sessions = state.session_service.clone();
+        jobs.every("zombie_sessions", ZOMBIE_CHECK_INTERVAL, move || {
+            let sessions = sessions.clone();
+            async move {
+                match sessions.fail_zombies(stale_after, false).await {
+                    Ok(report) if report.zombies.is_empty() => {}
+                    Ok(report) => tracing::warn!(
+                        zombies = report.zombies.len(),
+                        failed = report.failed,
+                        cutoff = %report.cutoff,
+                        "Marked sessions without heartbeats failed"
+                    ),
+                    Err(e) => tracing::error!(error = %e, "Checking for zombie sessions failed"),
+                }
+            }
+        });
+    }
+
+    let state = state.clone();
+    let cleanup_interval =
+        Duration::from_secs(u64::from(config.token_cleanup_interval_minutes) * 60);
+    jobs.every("token_cleanup", cleanup_interval, move || {
+        let state = state.clone();
+        async move {
+            match state.github_auth_service.purge_expired_tokens().await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!(deleted, "Purged expired API tokens"),
+                Err(e) => tracing::error!(error = %e, "Purging expired API tokens failed"),
+            }
+            match state.github_auth_service.purge_expired_device_flows().await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!(deleted, "Purged expired device flows"),
+                Err(e) => tracing::error!(error = %e, "Purging expired device flows failed"),
+            }
+            // Device codes nobody polls any more would otherwise linger
+            state.device_flows.purge_expired();
+            state.auth_limiter.purge_expired();
+            state.api_limiter.purge_expired();
+            state.provisioning_limiter.purge_expired();
+        }
+    });
+
+    jobs
+}
+
+/// Routes of the API, with rate limiting and request tracing
+pub fn router(state: AppState) -> Router {
+    // Device-flow and single sign-on endpoints start logins, so they're
+    // rate limited
+    let mut device_flow = Router::new()
+        .route(
+            "/auth/github/device-code",
+            post(github_create_user_device_session),
+        )
+        .route("/auth/github/status", get(authorization_status))
+        .route("/auth/oidc/config", get(sso::oidc_config))
+        .route("/auth/oidc/token", post(sso::issue_oidc_token));
+    if state.config.legacy_auth_long_poll {
+        device_flow = device_flow.route(
+            "/auth/github/wait-for-authorization",
+            post(check_user_authorised),
+        );
+    }
+    let device_flow = device_flow.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        limit_auth_requests,
+    ));
+
+    let api = Router::new()
+        // Authentication
+        .merge(device_flow)
+        .route("/auth/github-login", get(github_login))
+        .route("/auth/token", post(issue_api_token))
+        .route("/auth/refresh", post(refresh_github_token))
+        .route("/cli/version", get(cli_version))
+        .route("/version", get(version))
+        .route(
+            "/sessions",
+            post(sessions::create_session).get(sessions::list_sessions),
+        )
+        .route("/regions", get(sessions::list_regions))
+        .route("/sessions:batchStop", post(sessions::batch_stop_sessions))
+        .route(
+            "/sessions/{id}",
+            get(sessions::get_session).patch(sessions::update_session),
+        )
+        .route("/sessions/{id}/stop", post(sessions::stop_session))
+        .route(
+            "/sessions/{id}/collaborators",
+            post(sessions::add_collaborator),
+        )
+        .route("/sessions/{id}/usage", post(sessions::record_usage))
+        .route("/sessions/{id}/snapshots", get(sessions::list_snapshots))
+        .route(
+            "/snapshots:batchDelete",
+            post(sessions::batch_delete_snapshots),
+        )
+        .route("/snapshots/{id}", post(sessions::create_snapshot))
+        .route("/snapshots/{id}/restore", post(sessions::restore_snapshot))
+        .route("/me", get(users::get_profile).patch(users::update_profile))
+        .route("/billing/plans", get(billing::list_plans))
+        .route("/billing/country", put(billing::set_billing_country))
+        .route(
+            "/billing/checkout-session",
+            post(billing::create_checkout_session),
+        )
+        .route("/billing/portal", post(billing::create_portal_session))
+        .route("/billing/return", get(billing::billing_return))
+        // Administration
+        .route("/admin/users/{id}/suspend", post(admin::suspend_user))
+        .route("/admin/users/{id}/unsuspend", post(admin::unsuspend_user))
+        .route(
+            "/admin/users/{id}/billing-events",
+            get(admin::list_billing_events),
+        )
+        .route("/admin/stats", get(admin::stats))
+        .route("/admin/upstream-retries", get(admin::upstream_retries))
+        .route("/admin/retention/run", post(admin::run_retention))
+        .route("/admin/sessions/zombies", post(admin::fail_zombie_sessions))
+        // Directory sync (SCIM 2.0 subset)
+        .route(
+            "/scim/v2/ServiceProviderConfig",
+            get(scim::service_provider_config),
+        )
+        .route(
+            "/scim/v2/Users",
+            get(scim::list_users).post(scim::create_user),
+        )
+        .route(
+            "/scim/v2/Users/{id}",
+            get(scim::get_user)
+                .put(scim::replace_user)
+                .patch(scim::patch_user)
+                .delete(scim::delete_user),
+        )
+        .route("/scim/v2/Bulk", post(scim::bulk))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            limit_api_requests,
+        ))
+        // Not rate limited: Stripe retries webhooks it can't deliver
+        .route("/billing/webhook", post(billing::stripe_webhook));
+
+    Router::new()
+        .nest(API_PREFIX, api.clone())
+        // The unversioned paths from before /v1, until clients have moved
+        .merge(api.layer(middleware::from_fn(deprecate_unversioned)))
+        // Not rate limited or versioned: load balancers poll health checks
+        .route("/health", get(health))
+        .layer(middleware::from_fn(negotiate_version))
+        .layer(middleware::from_fn(set_security_headers))
+        // Outside the routes, so preflight requests are answered for any path
+        .layer(cors_layer(&state.config))
+        // One span per request carrying method, path and request ID; the
+        // response event adds status and latency
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_id::make_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        // Outermost, so every log line and response has the ID
+        .layer(middleware::from_fn(request_id::assign_request_id))
+        .with_state(state)
+}
@@ -0,0 +1,110 @@
+//! # NDJSON Exports
+//!
+//! List endpoints accept `?format=ndjson` to export every matching row as
+//! newline-delimited JSON (`application/x-ndjson`), one object per line,
+//! instead of a page wrapped in `{"data": [...]}`. Rows are read from the
+//! database a page at a time and written out as each page arrives, so an
+//! export never holds the whole result set in memory.
+//!
+//! A database error part-way through aborts the response body, so clients
+//! see a truncated transfer rather than a silently short export.
+
+use std::future::Future;
+
+use axum::{
+    body::{Body, Bytes},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use domain::errors::DomainError;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+
+/// Rows read from the database per page while exporting
+pub(crate) const EXPORT_PAGE_SIZE: u32 = 500;
+
+/// Response format requested with `?format=`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    /// One page in the usual `{"data": [...]}` envelope
+    #[default]
+    Json,
+    /// Every row, one JSON object per line
+    Ndjson,
+}
+
+/// Stream every row `fetch_page(limit, offset)` returns as NDJSON
+///
+/// Pages are fetched until one comes back short.
+pub(crate) fn export<T, F, Fut>(fetch_page: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: Fn(u32, u32) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Vec<T>, DomainError>> + Send + 'static,
+{
+    let pages = stream::unfold(
+        (fetch_page, Some(0)),
+        |(fetch_page, offset): (F, Option<u32>)| async move {
+            let offset = offset?;
+            let rows = match fetch_page(EXPORT_PAGE_SIZE, offset).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::error!(error = %e, "Export failed part-way");
+                    return Some((
+                        Err(std::io::Error::other(e.to_string())),
+                        (fetch_page, None),
+                    ));
+                }
+            };
+            if rows.is_empty() {
+                return None;
+            }
+
+            let next = (rows.len() == EXPORT_PAGE_SIZE as usize).then(|| offset + EXPORT_PAGE_SIZE);
+            Some((Ok(lines(&rows)), (fetch_page, next)))
+        },
+    );
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(pages),
+    )
+        .into_response()
+}
+
+/// One JSON object per row, each followed by a newline
+fn lines<T: Serialize>(rows: &[T]) -> Bytes {
+    let mut buf = Vec::new();
+    for row in rows {
+        // Serializing plain data into memory can't fail
+        serde_json::to_writer(&mut buf, row).expect("row serializes to JSON");
+        buf.push(b'\n');
+    }
+    Bytes::from(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[tokio::test]
+    async fn test_exports_every_page() {
+        let total = EXPORT_PAGE_SIZE + 2;
+        let response = export(move |limit, offset| async move {
+            Ok((offset..total.min(offset + limit)).collect::<Vec<u32>>())
+        });
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let rows: Vec<&str> = body.lines().collect();
+        assert_eq!(rows.len(), total as usize);
+        assert_eq!(rows[0], "0");
+        assert_eq!(rows.last(), Some(&"501"));
+    }
+}
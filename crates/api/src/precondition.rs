@@ -0,0 +1,70 @@
+//! # Conditional Updates
+//!
+//! Sessions and users are versioned by their `updated_at` timestamp, sent to
+//! clients as an `ETag`. A client that echoes it back in `If-Match` only
+//! updates the record if nobody else has since; stale writes get
+//! `409 Conflict` instead of silently overwriting the newer state.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
+use chrono::{DateTime, Utc};
+
+use crate::error::ApiError;
+
+/// `ETag` response header, returned alongside a handler's JSON body
+pub(crate) type ETag = [(HeaderName, HeaderValue); 1];
+
+/// Strong entity tag for a record last updated at `updated_at`
+pub(crate) fn etag(updated_at: DateTime<Utc>) -> ETag {
+    let version = updated_at.timestamp_nanos_opt().unwrap_or_default();
+    let value =
+        HeaderValue::from_str(&format!("\"{version}\"")).expect("digits are a valid header value");
+    [(header::ETAG, value)]
+}
+
+/// Version the client expects from its `If-Match` header
+///
+/// `None` when the header is absent or `*`, i.e. any version may be updated.
+pub(crate) fn if_match(headers: &HeaderMap) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    let invalid = || ApiError::BadRequest("If-Match must be a single ETag or *".to_string());
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    let version = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .and_then(|version| version.parse::<i64>().ok())
+        .ok_or_else(invalid)?;
+    Ok(Some(DateTime::from_timestamp_nanos(version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(if_match: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_str(if_match).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_if_match_round_trips_etag() {
+        let updated_at = Utc::now();
+        let [(_, etag)] = etag(updated_at);
+
+        let expected = if_match(&headers(etag.to_str().unwrap())).ok();
+        assert_eq!(expected, Some(Some(updated_at)));
+
+        assert_eq!(if_match(&HeaderMap::new()).ok(), Some(None));
+        assert_eq!(if_match(&headers("*")).ok(), Some(None));
+        for invalid in ["123", "W/\"123\"", "\"1\", \"2\"", "\"abc\""] {
+            assert!(if_match(&headers(invalid)).is_err(), "{invalid}");
+        }
+    }
+}
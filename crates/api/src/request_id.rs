@@ -0,0 +1,86 @@
+//! # Request IDs
+//!
+//! Every request gets an ID tying the server's logs for it to the response
+//! the client saw. Callers may pass their own in `X-Request-Id`, as a proxy
+//! in front of the API would; otherwise a UUIDv7 is generated. The ID is
+//! echoed in the response header, recorded on the request's trace span and
+//! included in error bodies, so users can quote it in bug reports.
+
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use tracing::Span;
+use uuid::Uuid;
+
+/// Header carrying the request ID, both ways
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest caller-supplied ID kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled, if called while handling one
+pub(crate) fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Keep the caller's request ID or assign one, and make it available to
+/// everything handling the request
+pub(crate) async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::now_v7().to_string());
+    // Valid IDs are visible ASCII, so always a valid header value
+    let header = HeaderValue::from_str(&id).expect("request ID is a valid header value");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header.clone());
+
+    let mut response = REQUEST_ID.scope(id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+/// One span per request, carrying its method, path and ID
+pub(crate) fn make_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
+
+/// Whether a caller-supplied ID is safe to log and echo back
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caller_ids_are_checked() {
+        assert!(is_valid("0192f0c4-7a3e-7b1c-9d2e-4f5a6b7c8d9e"));
+        assert!(is_valid("lb:abc_123.4"));
+        assert!(!is_valid(""));
+        assert!(!is_valid("has space"));
+        assert!(!is_valid("line\nbreak"));
+        assert!(!is_valid(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
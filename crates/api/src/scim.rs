@@ -0,0 +1,746 @@
+//! # SCIM Provisioning
+//!
+//! A minimal subset of SCIM 2.0 (RFC 7643, RFC 7644) so enterprise identity
+//! providers (Okta, Microsoft Entra ID, ...) can sync their directory into a
+//! private deployment. Only the `User` resource is served, with `userName`
+//! (the email address), `displayName`, `active` and `roles` (`member` or
+//! `admin`). Requests are authenticated like the other admin endpoints.
+//!
+//! Provisioned users claim their account on their first GitHub login or
+//! single sign-on with the same verified email. Deactivating a user suspends
+//! them; deleting one deprovisions them for good.
+//!
+//! Served:
+//!
+//! - `GET /scim/v2/ServiceProviderConfig`
+//! - `GET /scim/v2/Users`, filtered by `userName eq "..."` at most
+//! - `POST /scim/v2/Users`
+//! - `GET`, `PUT`, `PATCH` and `DELETE /scim/v2/Users/{id}`
+//! - `POST /scim/v2/Bulk`, for any of the above writes
+//!
+//! Every failure, including a body that isn't valid JSON, is reported in
+//! SCIM's error format.
+
+use axum::{
+    Json, debug_handler,
+    extract::{FromRequest, Path, Query, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use common::API_PREFIX;
+use domain::{
+    errors::DomainError,
+    models::{DirectoryChanges, User, UserId, UserRole, UserStatus},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{AppState, admin::AdminAuth, error::ApiError};
+
+const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const BULK_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:BulkResponse";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const SERVICE_PROVIDER_CONFIG_SCHEMA: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+
+/// Content type of every SCIM response
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+/// Users per page when the client doesn't say
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Most users returned in one page
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Most operations in one bulk request
+const MAX_BULK_OPERATIONS: usize = 1000;
+
+/// A SCIM response body, sent as `application/scim+json`
+struct Scim<T>(StatusCode, T);
+
+impl<T: Serialize> IntoResponse for Scim<T> {
+    fn into_response(self) -> Response {
+        let mut response = (self.0, Json(self.1)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(SCIM_CONTENT_TYPE),
+        );
+        response
+    }
+}
+
+/// A failure, reported in SCIM's error format
+pub(crate) struct ScimError {
+    error: ApiError,
+    /// SCIM error type, when one more specific than the status applies
+    scim_type: Option<&'static str>,
+}
+
+impl<E: Into<ApiError>> From<E> for ScimError {
+    fn from(err: E) -> Self {
+        ScimError {
+            error: err.into(),
+            scim_type: None,
+        }
+    }
+}
+
+impl ScimError {
+    fn body(&self) -> (StatusCode, Value) {
+        let (status, _, detail) = self.error.parts();
+        let mut body = json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": status.as_u16().to_string(),
+            "detail": detail,
+        });
+        let scim_type = self
+            .scim_type
+            .or((status == StatusCode::CONFLICT).then_some("uniqueness"));
+        if let Some(scim_type) = scim_type {
+            body["scimType"] = json!(scim_type);
+        }
+        (status, body)
+    }
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> Response {
+        let (status, body) = self.body();
+        Scim(status, body).into_response()
+    }
+}
+
+fn bad_request(detail: impl Into<String>) -> ScimError {
+    ScimError::from(ApiError::BadRequest(detail.into()))
+}
+
+/// A body that couldn't be parsed into the expected resource
+fn invalid_syntax(detail: impl Into<String>) -> ScimError {
+    ScimError {
+        scim_type: Some("invalidSyntax"),
+        ..bad_request(detail)
+    }
+}
+
+/// JSON body extractor that reports unreadable bodies in SCIM's error format
+pub(crate) struct ScimJson<T>(T);
+
+impl<T, S> FromRequest<S> for ScimJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ScimError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| invalid_syntax(rejection.body_text()))?;
+        Ok(ScimJson(body))
+    }
+}
+
+/// One value of a multi-valued attribute, e.g. an email address or role
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MultiValue {
+    value: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    primary: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Name {
+    formatted: Option<String>,
+    given_name: Option<String>,
+    family_name: Option<String>,
+}
+
+/// A `User` resource as sent by the identity provider
+///
+/// Attributes other than these are accepted and ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UserRequest {
+    user_name: Option<String>,
+    #[serde(default)]
+    emails: Vec<MultiValue>,
+    display_name: Option<String>,
+    name: Option<Name>,
+    active: Option<bool>,
+    roles: Option<Vec<MultiValue>>,
+}
+
+impl UserRequest {
+    /// The email address: `userName` when it is one, else the primary email
+    fn email(&self) -> Option<String> {
+        self.user_name
+            .clone()
+            .filter(|name| name.contains('@'))
+            .or_else(|| {
+                self.emails
+                    .iter()
+                    .find(|email| email.primary)
+                    .or(self.emails.first())
+                    .map(|email| email.value.clone())
+            })
+    }
+
+    fn display_name(&self) -> Option<String> {
+        self.display_name.clone().or_else(|| {
+            let name = self.name.as_ref()?;
+            name.formatted.clone().or_else(|| {
+                let parts: Vec<&str> = [&name.given_name, &name.family_name]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join(" "))
+            })
+        })
+    }
+
+    /// The changes setting every attribute present, and with `replace`,
+    /// resetting the others as PUT requires
+    fn changes(&self, replace: bool) -> Result<DirectoryChanges, ScimError> {
+        let display_name = self.display_name();
+        let roles = match (&self.roles, replace) {
+            (Some(roles), _) => Some(role(roles)?),
+            (None, true) => Some(UserRole::Member),
+            (None, false) => None,
+        };
+        Ok(DirectoryChanges {
+            primary_email: self.email(),
+            display_name: (replace || display_name.is_some()).then_some(display_name),
+            role: roles,
+            active: self.active.or(replace.then_some(true)),
+        })
+    }
+}
+
+/// The role named by a `roles` attribute; none means `member`
+fn role(roles: &[MultiValue]) -> Result<UserRole, ScimError> {
+    let mut role = UserRole::Member;
+    for value in roles {
+        match value.value.parse() {
+            Ok(UserRole::Admin) => role = UserRole::Admin,
+            Ok(UserRole::Member) => {}
+            Err(_) => {
+                return Err(bad_request(format!(
+                    "Unknown role {:?}; roles are \"member\" and \"admin\"",
+                    value.value
+                )));
+            }
+        }
+    }
+    Ok(role)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Meta {
+    resource_type: &'static str,
+    created: String,
+    last_modified: String,
+    location: String,
+}
+
+/// A `User` resource as returned to the identity provider
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserResource {
+    schemas: [&'static str; 1],
+    id: String,
+    user_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    emails: Vec<MultiValue>,
+    active: bool,
+    roles: Vec<MultiValue>,
+    meta: Meta,
+}
+
+/// Where a user's resource is served
+fn location(state: &AppState, id: UserId) -> String {
+    format!(
+        "{}{API_PREFIX}/scim/v2/Users/{id}",
+        state.config.api_base_url
+    )
+}
+
+fn resource(state: &AppState, user: User) -> UserResource {
+    UserResource {
+        schemas: [USER_SCHEMA],
+        id: user.id.to_string(),
+        user_name: user.primary_email.clone(),
+        display_name: user.display_name,
+        emails: vec![MultiValue {
+            value: user.primary_email,
+            primary: true,
+        }],
+        active: user.status == UserStatus::Active,
+        roles: vec![MultiValue {
+            value: user.role.as_str().to_string(),
+            primary: true,
+        }],
+        meta: Meta {
+            resource_type: "User",
+            created: user.created_at.to_rfc3339(),
+            last_modified: user.updated_at.to_rfc3339(),
+            location: location(state, user.id),
+        },
+    }
+}
+
+/// What this server supports, for identity providers that ask
+#[debug_handler]
+pub(crate) async fn service_provider_config(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+) -> impl IntoResponse {
+    let unsupported = json!({"supported": false});
+    Scim(
+        StatusCode::OK,
+        json!({
+            "schemas": [SERVICE_PROVIDER_CONFIG_SCHEMA],
+            "patch": {"supported": true},
+            "bulk": {
+                "supported": true,
+                "maxOperations": MAX_BULK_OPERATIONS,
+                "maxPayloadSize": 2 * 1024 * 1024,
+            },
+            "filter": {"supported": true, "maxResults": MAX_PAGE_SIZE},
+            "changePassword": unsupported,
+            "sort": unsupported,
+            "etag": unsupported,
+            "authenticationSchemes": [{
+                "type": "oauthbearertoken",
+                "name": "Bearer token",
+                "description": "The admin API token, or an admin user's API token",
+            }],
+            "meta": {
+                "resourceType": "ServiceProviderConfig",
+                "location": format!(
+                    "{}{API_PREFIX}/scim/v2/ServiceProviderConfig",
+                    state.config.api_base_url
+                ),
+            },
+        }),
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListQuery {
+    filter: Option<String>,
+    start_index: Option<u32>,
+    count: Option<u32>,
+}
+
+/// The email in a `userName eq "..."` filter, the only one supported
+fn user_name_filter(filter: &str) -> Result<String, ScimError> {
+    let invalid = || {
+        bad_request(format!(
+            "Unsupported filter {filter:?}; only userName eq \"...\" is supported"
+        ))
+    };
+    let mut words = filter.trim().splitn(3, ' ');
+    let (Some(attribute), Some(operator), Some(value)) = (words.next(), words.next(), words.next())
+    else {
+        return Err(invalid());
+    };
+    if !attribute.eq_ignore_ascii_case("userName") || !operator.eq_ignore_ascii_case("eq") {
+        return Err(invalid());
+    }
+    value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .map(str::to_string)
+        .ok_or_else(invalid)
+}
+
+/// List users, oldest first, or find one by `userName`
+#[debug_handler]
+pub(crate) async fn list_users(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Query(query): Query<ListQuery>,
+) -> Result<Response, ScimError> {
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let (users, total) = match &query.filter {
+        Some(filter) => {
+            let email = user_name_filter(filter)?;
+            let users: Vec<User> = state
+                .user_service
+                .find_user_by_email(&email)
+                .await?
+                .into_iter()
+                .collect();
+            let total = users.len() as u64;
+            (users, total)
+        }
+        None => {
+            state
+                .user_service
+                .list_users(count, start_index - 1)
+                .await?
+        }
+    };
+
+    let resources: Vec<UserResource> = users
+        .into_iter()
+        .take(count as usize)
+        .map(|user| resource(&state, user))
+        .collect();
+    Ok(Scim(
+        StatusCode::OK,
+        json!({
+            "schemas": [LIST_RESPONSE_SCHEMA],
+            "totalResults": total,
+            "startIndex": start_index,
+            "itemsPerPage": resources.len(),
+            "Resources": resources,
+        }),
+    )
+    .into_response())
+}
+
+/// Users that are deleted are gone as far as the directory is concerned
+async fn find_user(state: &AppState, id: UserId) -> Result<User, ScimError> {
+    state
+        .user_service
+        .get_user(id)
+        .await?
+        .filter(|user| user.status != UserStatus::Deleted)
+        .ok_or_else(|| DomainError::NotFound(format!("User {id}")).into())
+}
+
+#[debug_handler]
+pub(crate) async fn get_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(id): Path<UserId>,
+) -> Result<Response, ScimError> {
+    let user = find_user(&state, id).await?;
+    Ok(Scim(StatusCode::OK, resource(&state, user)).into_response())
+}
+
+async fn create(state: &AppState, request: UserRequest) -> Result<User, ScimError> {
+    let email = request
+        .email()
+        .ok_or_else(|| bad_request("userName or emails must hold the user's email address"))?;
+    let changes = DirectoryChanges {
+        primary_email: None,
+        ..request.changes(true)?
+    };
+    Ok(state.user_service.provision_user(&email, changes).await?)
+}
+
+/// Provision a user ahead of their first login
+#[debug_handler]
+pub(crate) async fn create_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    ScimJson(request): ScimJson<UserRequest>,
+) -> Result<Response, ScimError> {
+    let user = create(&state, request).await?;
+    let location = location(&state, user.id);
+    let mut response = Scim(StatusCode::CREATED, resource(&state, user)).into_response();
+    if let Ok(location) = HeaderValue::from_str(&location) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    Ok(response)
+}
+
+async fn replace(state: &AppState, id: UserId, request: UserRequest) -> Result<User, ScimError> {
+    find_user(state, id).await?;
+    Ok(state
+        .user_service
+        .apply_directory_changes(id, request.changes(true)?)
+        .await?)
+}
+
+/// Replace a user's attributes; those left out are reset
+#[debug_handler]
+pub(crate) async fn replace_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(id): Path<UserId>,
+    ScimJson(request): ScimJson<UserRequest>,
+) -> Result<Response, ScimError> {
+    let user = replace(&state, id, request).await?;
+    Ok(Scim(StatusCode::OK, resource(&state, user)).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PatchRequest {
+    #[serde(rename = "Operations")]
+    operations: Vec<PatchOperation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchOperation {
+    op: String,
+    path: Option<String>,
+    value: Option<Value>,
+}
+
+/// The changes a list of `add`, `replace` and `remove` operations make
+///
+/// Operations without a `path` carry an object of attributes to set.
+fn patch_changes(operations: Vec<PatchOperation>) -> Result<DirectoryChanges, ScimError> {
+    let mut changes = DirectoryChanges::default();
+    for operation in operations {
+        let op = operation.op.to_ascii_lowercase();
+        let value = operation.value.unwrap_or(Value::Null);
+        let attributes = match (op.as_str(), operation.path) {
+            ("remove", Some(path)) if path.eq_ignore_ascii_case("displayName") => {
+                changes.display_name = Some(None);
+                continue;
+            }
+            ("remove", Some(path)) if path.eq_ignore_ascii_case("roles") => {
+                changes.role = Some(UserRole::Member);
+                continue;
+            }
+            ("add" | "replace", Some(path)) => json!({ path: value }),
+            ("add" | "replace", None) => value,
+            _ => return Err(bad_request(format!("Unsupported patch operation {op:?}"))),
+        };
+        let Value::Object(attributes) = attributes else {
+            return Err(bad_request("Patch value must be an object of attributes"));
+        };
+
+        for (name, value) in attributes {
+            let invalid = || bad_request(format!("Invalid value for {name}: {value}"));
+            match name.to_ascii_lowercase().as_str() {
+                "active" => {
+                    // Some providers send booleans as strings
+                    changes.active = Some(match &value {
+                        Value::Bool(active) => *active,
+                        Value::String(active) => active.eq_ignore_ascii_case("true"),
+                        _ => return Err(invalid()),
+                    });
+                }
+                "displayname" => {
+                    changes.display_name = Some(match &value {
+                        Value::String(name) => Some(name.clone()),
+                        Value::Null => None,
+                        _ => return Err(invalid()),
+                    });
+                }
+                "username" => {
+                    let Value::String(email) = &value else {
+                        return Err(invalid());
+                    };
+                    changes.primary_email = Some(email.clone());
+                }
+                "roles" => {
+                    let roles: Vec<MultiValue> =
+                        serde_json::from_value(value.clone()).map_err(|_| invalid())?;
+                    changes.role = Some(role(&roles)?);
+                }
+                // Left to the identity provider
+                _ => {}
+            }
+        }
+    }
+    Ok(changes)
+}
+
+async fn patch(state: &AppState, id: UserId, request: PatchRequest) -> Result<User, ScimError> {
+    find_user(state, id).await?;
+    let changes = patch_changes(request.operations)?;
+    Ok(state
+        .user_service
+        .apply_directory_changes(id, changes)
+        .await?)
+}
+
+/// Change some of a user's attributes, e.g. `active` to deactivate them
+#[debug_handler]
+pub(crate) async fn patch_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(id): Path<UserId>,
+    ScimJson(request): ScimJson<PatchRequest>,
+) -> Result<Response, ScimError> {
+    let user = patch(&state, id, request).await?;
+    Ok(Scim(StatusCode::OK, resource(&state, user)).into_response())
+}
+
+/// Deprovision a user who left the directory
+#[debug_handler]
+pub(crate) async fn delete_user(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(id): Path<UserId>,
+) -> Result<StatusCode, ScimError> {
+    state.user_service.deprovision_user(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BulkRequest {
+    /// Stop after this many operations failed
+    fail_on_errors: Option<usize>,
+    #[serde(rename = "Operations")]
+    operations: Vec<BulkOperation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkOperation {
+    method: String,
+    bulk_id: Option<String>,
+    path: String,
+    #[serde(default)]
+    data: Value,
+}
+
+/// Run one operation of a bulk request, returning the user it wrote
+async fn run_bulk_operation(
+    state: &AppState,
+    operation: &BulkOperation,
+) -> Result<(StatusCode, Option<User>), ScimError> {
+    let id = match operation.path.trim_end_matches('/') {
+        "/Users" => None,
+        path => Some(
+            path.strip_prefix("/Users/")
+                .and_then(|id| id.parse::<UserId>().ok())
+                .ok_or_else(|| bad_request(format!("Unsupported path {:?}", operation.path)))?,
+        ),
+    };
+    let data = || operation.data.clone();
+    let invalid_data = |e: serde_json::Error| invalid_syntax(format!("Invalid data: {e}"));
+
+    match (operation.method.to_ascii_uppercase().as_str(), id) {
+        ("POST", None) => {
+            let request = serde_json::from_value(data()).map_err(invalid_data)?;
+            Ok((StatusCode::CREATED, Some(create(state, request).await?)))
+        }
+        ("PUT", Some(id)) => {
+            let request = serde_json::from_value(data()).map_err(invalid_data)?;
+            Ok((StatusCode::OK, Some(replace(state, id, request).await?)))
+        }
+        ("PATCH", Some(id)) => {
+            let request = serde_json::from_value(data()).map_err(invalid_data)?;
+            Ok((StatusCode::OK, Some(patch(state, id, request).await?)))
+        }
+        ("DELETE", Some(id)) => {
+            state.user_service.deprovision_user(id).await?;
+            Ok((StatusCode::NO_CONTENT, None))
+        }
+        (method, _) => Err(bad_request(format!(
+            "Unsupported operation {method} {}",
+            operation.path
+        ))),
+    }
+}
+
+/// Provision, update and deprovision many users in one request
+///
+/// Operations run in order, each on its own; one failing doesn't undo the
+/// others. Each gets its own status in the response.
+#[debug_handler]
+pub(crate) async fn bulk(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    ScimJson(request): ScimJson<BulkRequest>,
+) -> Result<Response, ScimError> {
+    if request.operations.len() > MAX_BULK_OPERATIONS {
+        return Err(DomainError::InvalidInput(format!(
+            "At most {MAX_BULK_OPERATIONS} operations are allowed per bulk request"
+        ))
+        .into());
+    }
+
+    let mut results = Vec::with_capacity(request.operations.len());
+    let mut errors = 0;
+    for operation in &request.operations {
+        if request.fail_on_errors.is_some_and(|limit| errors >= limit) {
+            break;
+        }
+
+        let mut result = json!({"method": operation.method.to_ascii_uppercase()});
+        if let Some(bulk_id) = &operation.bulk_id {
+            result["bulkId"] = json!(bulk_id);
+        }
+        match run_bulk_operation(&state, operation).await {
+            Ok((status, user)) => {
+                result["status"] = json!(status.as_u16().to_string());
+                if let Some(user) = user {
+                    result["location"] = json!(location(&state, user.id));
+                }
+            }
+            Err(error) => {
+                errors += 1;
+                let (status, body) = error.body();
+                result["status"] = json!(status.as_u16().to_string());
+                result["response"] = body;
+            }
+        }
+        results.push(result);
+    }
+
+    Ok(Scim(
+        StatusCode::OK,
+        json!({
+            "schemas": [BULK_RESPONSE_SCHEMA],
+            "Operations": results,
+        }),
+    )
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_name_filter() {
+        assert_eq!(
+            user_name_filter("userName eq \"ada@corp.example\"")
+                .ok()
+                .unwrap(),
+            "ada@corp.example"
+        );
+        assert_eq!(
+            user_name_filter("username EQ \"ada@corp.example\"")
+                .ok()
+                .unwrap(),
+            "ada@corp.example"
+        );
+        assert!(user_name_filter("emails co \"corp\"").is_err());
+        assert!(user_name_filter("userName eq ada@corp.example").is_err());
+    }
+
+    #[test]
+    fn test_patch_changes() {
+        // Okta deactivates with a path-less replace, Entra ID with a path
+        let operations: PatchRequest = serde_json::from_value(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {"op": "replace", "value": {"active": false}},
+                {"op": "Replace", "path": "displayName", "value": "Ada"},
+                {"op": "add", "path": "roles", "value": [{"value": "admin"}]},
+            ],
+        }))
+        .unwrap();
+        let changes = patch_changes(operations.operations).ok().unwrap();
+        assert_eq!(changes.active, Some(false));
+        assert_eq!(changes.display_name, Some(Some("Ada".to_string())));
+        assert_eq!(changes.role, Some(UserRole::Admin));
+        assert_eq!(changes.primary_email, None);
+
+        let unknown_role: PatchRequest = serde_json::from_value(json!({
+            "Operations": [{"op": "replace", "path": "roles", "value": [{"value": "owner"}]}],
+        }))
+        .unwrap();
+        assert!(patch_changes(unknown_role.operations).is_err());
+    }
+}
@@ -1,90 +1,38 @@
 //! # ForkForge API Server
 //!
-//! This is the main HTTP API server for ForkForge/Chainbox, built with Axum.
-//! It provides REST endpoints for authentication, session management, and billing.
-//!
-//! ## Architecture
-//!
-//! The server uses the `ServerInfra` façade from the infra crate to access all
-//! infrastructure services (database, external APIs, etc.) while keeping the
-//! HTTP layer focused on request/response handling.
-//!
-//! ## Endpoints
-//!
-//! - Authentication: GitHub OAuth device flow
-//! - Sessions: Fork session management
-//! - Snapshots: Time-travel snapshot creation
-//! - Billing: Stripe webhook handling
-
-mod github;
+//! Loads configuration, connects to the database and third-party services,
+//! and serves the `api` router until Ctrl-C or SIGTERM.
 
-use axum::{
-    Json, Router,
-    extract::Path,
-    routing::{get, post},
-};
-use serde::Serialize;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
 
-use common::Config;
-use domain::{
-    repositories::{AuthRepository, UserRepository},
-    services::auth::github::AuthService,
-};
-use github::github_create_user_device_session;
-use infra::{GitHubDeviceFlowProvider, ServerInfra};
+use api::{AppState, router, start_jobs};
+use common::{Config, DeploymentMode};
+use infra::ServerInfra;
 
-use crate::github::{check_user_authorised, github_login};
-
-/// Application state shared across all request handlers
-///
-/// Contains configuration and service instances needed by handlers.
-/// Cloned for each request due to Axum's state management.
-// TODO: Add some sort of rate limiting to the requests to github.com
-#[derive(Clone)]
-pub(crate) struct AppState {
-    config: Config,
-    #[allow(dead_code)]
-    infra: Arc<ServerInfra>,
-    github_auth_service: Arc<AuthService<GitHubDeviceFlowProvider, AuthRepository>>,
-}
+/// Resolves on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-#[allow(dead_code)]
-impl AppState {
-    fn config(&self) -> &Config {
-        &self.config
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
     }
-}
-
-// TODO: We're gonna start validating incoming requests
-#[derive(Serialize)]
-struct ApiResponse<T> {
-    data: T,
-}
-
-async fn health() -> Json<ApiResponse<&'static str>> {
-    Json(ApiResponse { data: "Ok" })
-}
-
-async fn new_session() -> Json<ApiResponse<&'static str>> {
-    // TODO: Use domain::services::sessions::create_session
-    Json(ApiResponse {
-        data: "Starting session stub",
-    })
-}
-
-async fn new_snapshot(Path(_id): Path<String>) -> Json<ApiResponse<&'static str>> {
-    // TODO: Use domain::services::snapshots::create_snapshot
-    Json(ApiResponse {
-        data: "Starting snapshot stub",
-    })
-}
-
-async fn stripe_webhook() -> Json<ApiResponse<&'static str>> {
-    // TODO: Use domain::services::billing::webhooks::process_stripe_webhook
-    Json(ApiResponse {
-        data: "Starting webhook stub",
-    })
+    tracing::info!("Shutting down");
 }
 
 /// Main entry point for the API server
@@ -94,15 +42,47 @@ async fn stripe_webhook() -> Json<ApiResponse<&'static str>> {
 ///
 /// ## Initialization Order
 ///
-/// 1. Load configuration from config.toml and environment
-/// 2. Initialize infrastructure (database, HTTP clients, Stripe)
-/// 3. Create domain services with dependency injection
-/// 4. Configure HTTP routes
-/// 5. Start server on configured host:port
+/// 1. Initialize logging (filtered by `RUST_LOG`, default `info`)
+/// 2. Load configuration from config.toml and environment, and validate it
+/// 3. Validate the self-hosted license, if installed
+/// 4. Initialize infrastructure (database, HTTP clients, Stripe)
+/// 5. Create domain services with dependency injection
+/// 6. Configure HTTP routes
+/// 7. Start background jobs and the server on configured host:port
+/// 8. On Ctrl-C or SIGTERM, finish in-flight requests and stop the jobs
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
     // Load configuration
     let config = Config::load().expect("Failed to load configuration");
+    if let Err(problems) = config.validate(DeploymentMode::Server) {
+        for problem in &problems {
+            tracing::error!("Invalid configuration: {problem}");
+        }
+        std::process::exit(1);
+    }
+
+    // Validate self-hosted license offline; Pro features stay locked without one
+    let license = match infra::license::load_license(&config.license_path) {
+        Ok(license) => license,
+        Err(e) => {
+            tracing::warn!(path = %config.license_path, error = %e, "Ignoring license");
+            None
+        }
+    };
+    if let Some(license) = &license {
+        tracing::info!(
+            licensee = %license.licensee,
+            tier = ?license.tier,
+            expires_at = %license.expires_at,
+            "License loaded"
+        );
+    }
 
     // Initialize infrastructure
     let infra = Arc::new(
@@ -111,46 +91,24 @@ async fn main() {
             .expect("Failed to initialize infrastructure"),
     );
 
-    // Create GitHub device flow provider and auth service
-    let device_flow_provider = GitHubDeviceFlowProvider::new(
-        config
-            .github_client_id
-            .clone()
-            .expect("GitHub client ID not configured"),
-        infra.http.clone(),
-    );
-
-    let github_auth_service = Arc::new(AuthService::new(
-        device_flow_provider,
-        todo!("Add the reposity instance"),
-    ));
-
-    let state = AppState {
-        config: config.clone(),
-        infra,
-        github_auth_service,
-    };
-
-    let app = Router::new()
-        // Authentication
-        .route(
-            "/auth/github/device-code",
-            post(github_create_user_device_session),
-        )
-        .route(
-            "/auth/github/wait-for-authorization",
-            post(check_user_authorised),
-        )
-        .route("/auth/github-login", get(github_login))
-        .route("/health", get(health))
-        .route("/sessions", post(new_session))
-        .route("/snapshots/{id}", post(new_snapshot))
-        .route("/billing/webhook", post(stripe_webhook))
-        .with_state(state);
+    let state = AppState::new(&config, infra, license)
+        .await
+        .expect("Failed to initialize services");
+    let jobs = start_jobs(&state);
+    let app = router(state);
 
     let addr = format!("{}:{}", config.api_host, config.api_port);
-    println!("Server listening on... {addr}");
+    tracing::info!(%addr, "Server listening");
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Client addresses feed per-IP abuse protection
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+
+    jobs.shutdown().await;
 }
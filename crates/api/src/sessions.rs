@@ -0,0 +1,533 @@
+//! HTTP adapter for fork session management.
+//!
+//! Validates incoming requests before handing them to the domain
+//! `SessionService`. Session and snapshot paths accept anything the
+//! `Resolver` understands: a full ID, a session slug or a unique ID prefix.
+//! Sessions are returned with an `ETag` for conditional updates.
+
+use axum::{
+    Json, debug_handler,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use common::{
+    BatchDeleteSnapshotsRequest, BatchItemError, BatchItemResult, BatchStopSessionsRequest,
+    CreateSessionRequest, CreateSnapshotRequest, Pagination, RestoreSnapshotRequest,
+    SessionUsageRequest, UpdateSessionRequest,
+};
+use domain::{
+    errors::DomainError,
+    models::{
+        CollaboratorAccess, ForkSession, Region, SessionCollaborator, SessionStatus,
+        SessionSummary, SessionUsage, Snapshot, SnapshotFilter, SnapshotId, UserId, UserStatus,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    ApiResponse, AppState,
+    auth::AuthenticatedUser,
+    client_ip::ClientIp,
+    error::ApiError,
+    ndjson::{self, Format},
+    precondition,
+    validation::ValidJson,
+};
+
+/// Sessions returned per page when the client doesn't say
+const DEFAULT_LIST_LIMIT: u32 = 20;
+
+/// Largest page of sessions a client may request
+const MAX_LIST_LIMIT: u32 = 100;
+
+/// Most items a single batch request may act on
+const MAX_BATCH_ITEMS: usize = 100;
+
+/// Session response carrying the session's `ETag`
+type VersionedSession = (precondition::ETag, Json<ApiResponse<ForkSession>>);
+
+fn versioned(session: ForkSession) -> VersionedSession {
+    (
+        precondition::etag(session.updated_at),
+        Json(ApiResponse::new(session)),
+    )
+}
+
+/// Create a fork session owned by the authenticated user
+///
+/// Provisioning is limited per client IP to stop sessions being farmed
+/// for free compute, and per user by their plan's concurrent session limit.
+/// The IP's allowance is only spent once every other check has passed, so
+/// rejected requests don't use it up for everyone behind the same address.
+/// Listed accounts, programs and mints are captured from mainnet before the
+/// session is created. The session is placed in the requested region, else
+/// the user's preferred one, else the server's default.
+#[debug_handler]
+pub(crate) async fn create_session(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    user: AuthenticatedUser,
+    ValidJson(request): ValidJson<CreateSessionRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<ForkSession>>), ApiError> {
+    // An empty name means the session is named after its generated slug
+    let name = request.name.trim().to_string();
+
+    let tier = state.subscription_tier(user.user_id).await?;
+    state
+        .quota_service
+        .check_session_quota(user.user_id, tier)
+        .await?;
+
+    let preferred_region = if request.region.is_none() && !state.regions.regions().is_empty() {
+        state
+            .user_service
+            .get_user(user.user_id)
+            .await?
+            .and_then(|user| user.preferred_region)
+    } else {
+        None
+    };
+    let region = state
+        .regions
+        .place(request.region.as_deref(), preferred_region.as_deref())?
+        .map(|region| region.name.clone());
+
+    let pubkeys = request.pubkeys();
+    let helius = match (pubkeys.is_empty(), &state.infra.helius) {
+        (true, _) => None,
+        (false, Some(helius)) => Some(helius),
+        (false, None) => {
+            return Err(
+                DomainError::NotFound("Account cloning is not configured".to_string()).into(),
+            );
+        }
+    };
+
+    if !state.provisioning_limiter.try_acquire(client_ip) {
+        return Err(ApiError::ProvisioningLimited);
+    }
+
+    let session = match helius {
+        None => {
+            state
+                .session_service
+                .create_session(user.user_id, name, request.fork_slot, region)
+                .await?
+        }
+        Some(helius) => {
+            state
+                .session_service
+                .create_session_with_accounts(
+                    helius,
+                    user.user_id,
+                    name,
+                    request.fork_slot,
+                    region,
+                    &pubkeys,
+                )
+                .await?
+        }
+    };
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::new(session))))
+}
+
+/// Regions sessions can be placed in, with the gateway serving each
+#[debug_handler]
+pub(crate) async fn list_regions(State(state): State<AppState>) -> Json<ApiResponse<Vec<Region>>> {
+    let regions = state.regions.regions().to_vec();
+    let pagination = Pagination::complete(regions.len());
+    Json(ApiResponse::page(regions, pagination))
+}
+
+/// Query parameters for listing sessions
+#[derive(Deserialize)]
+pub(crate) struct ListSessionsQuery {
+    status: Option<SessionStatus>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`
+    cursor: Option<String>,
+    #[serde(default)]
+    format: Format,
+}
+
+/// List the authenticated user's sessions, newest first
+///
+/// Supports `?status=running&limit=20&cursor=...`, or `?format=ndjson` to
+/// export every matching session instead of a page. Each page reports the
+/// total and the cursor of the next page; `offset` still works in place of
+/// the cursor.
+#[debug_handler]
+pub(crate) async fn list_sessions(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ListSessionsQuery>,
+) -> Result<Response, ApiError> {
+    if query.format == Format::Ndjson {
+        let sessions = state.session_service.clone();
+        let (user_id, status) = (user.user_id, query.status);
+        return Ok(ndjson::export(move |limit, offset| {
+            let sessions = sessions.clone();
+            async move { sessions.find_sessions(user_id, status, limit, offset).await }
+        }));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+        return Err(DomainError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_LIST_LIMIT}"
+        ))
+        .into());
+    }
+
+    let offset = match &query.cursor {
+        Some(cursor) => Pagination::parse_cursor(cursor)
+            .ok_or_else(|| DomainError::InvalidInput(format!("Invalid cursor {cursor:?}")))?,
+        None => query.offset.unwrap_or(0),
+    };
+
+    let sessions: Vec<SessionSummary> = state
+        .session_service
+        .find_sessions(user.user_id, query.status, limit, offset)
+        .await?;
+    let total = state
+        .session_service
+        .count_sessions(user.user_id, query.status)
+        .await?;
+
+    let pagination = Pagination::offset(offset, limit, sessions.len(), Some(total));
+    Ok(Json(ApiResponse::page(sessions, pagination)).into_response())
+}
+
+/// Get a session the user owns or collaborates on
+#[debug_handler]
+pub(crate) async fn get_session(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+) -> Result<VersionedSession, ApiError> {
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    let session = state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Read)
+        .await?;
+
+    Ok(versioned(session))
+}
+
+/// Rename a session; requires ownership or write access
+///
+/// With `If-Match`, the rename is rejected with `409 Conflict` if the
+/// session changed since the client fetched it.
+#[debug_handler]
+pub(crate) async fn update_session(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<UpdateSessionRequest>,
+) -> Result<VersionedSession, ApiError> {
+    let expected_version = precondition::if_match(&headers)?;
+    let name = request.name.trim();
+
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+        .await?;
+    let session = state
+        .session_service
+        .rename_session(id, name.to_string(), expected_version)
+        .await?;
+
+    Ok(versioned(session))
+}
+
+/// Stop a session; requires ownership or write access
+#[debug_handler]
+pub(crate) async fn stop_session(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<ForkSession>>, ApiError> {
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+        .await?;
+    let session = state.session_service.stop_session(id).await?;
+
+    Ok(Json(ApiResponse::new(session)))
+}
+
+/// Request to share a session with another user
+#[derive(Debug, Deserialize)]
+pub(crate) struct AddCollaboratorRequest {
+    user_id: UserId,
+    access: CollaboratorAccess,
+}
+
+/// Grant another user read or write access to a session; owner only
+///
+/// Granting again replaces the user's previous access. Ownership is checked
+/// before the collaborator is looked up, so only owners learn whether a
+/// user ID exists.
+#[debug_handler]
+pub(crate) async fn add_collaborator(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+    ValidJson(request): ValidJson<AddCollaboratorRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<SessionCollaborator>>), ApiError> {
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    state
+        .session_service
+        .authorize_owner(id, user.user_id)
+        .await?;
+    let exists = state
+        .user_service
+        .get_user(request.user_id)
+        .await?
+        .is_some_and(|collaborator| collaborator.status != UserStatus::Deleted);
+    if !exists {
+        return Err(DomainError::NotFound(format!("User {}", request.user_id)).into());
+    }
+
+    let collaborator = state
+        .session_service
+        .add_collaborator(user.user_id, id, request.user_id, request.access)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::new(collaborator))))
+}
+
+/// Record a usage heartbeat; requires ownership or write access
+///
+/// Sessions that outrun their owner's plan duration are stopped here.
+#[debug_handler]
+pub(crate) async fn record_usage(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+    ValidJson(request): ValidJson<SessionUsageRequest>,
+) -> Result<Json<ApiResponse<SessionUsage>>, ApiError> {
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    let session = state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+        .await?;
+    let tier = state.subscription_tier(session.user_id).await?;
+    let usage = state
+        .quota_service
+        .record_usage(id, request.elapsed_seconds, request.peak_memory_bytes, tier)
+        .await?;
+
+    Ok(Json(ApiResponse::new(usage)))
+}
+
+/// Save a snapshot of a session; requires ownership or write access
+///
+/// Limited by the session owner's plan, since snapshots count against the
+/// owner's session rather than the collaborator taking them.
+#[debug_handler]
+pub(crate) async fn create_snapshot(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+    ValidJson(request): ValidJson<CreateSnapshotRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Snapshot>>), ApiError> {
+    let name = request.name.trim();
+
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    let session = state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+        .await?;
+    let tier = state.subscription_tier(session.user_id).await?;
+    state.quota_service.check_snapshot_quota(id, tier).await?;
+
+    let snapshot = state
+        .snapshot_service
+        .create_snapshot(id, user.user_id, name.to_string(), request.description)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::new(snapshot))))
+}
+
+/// List snapshots of a session, newest first; requires read access
+#[debug_handler]
+pub(crate) async fn list_snapshots(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<Vec<Snapshot>>>, ApiError> {
+    let id = state.resolver.resolve_session(user.user_id, &key).await?;
+    state
+        .session_service
+        .authorize_access(id, user.user_id, CollaboratorAccess::Read)
+        .await?;
+    let snapshots = state.snapshot_service.list_snapshots(id).await?;
+
+    let pagination = Pagination::complete(snapshots.len());
+    Ok(Json(ApiResponse::page(snapshots, pagination)))
+}
+
+/// Restore a snapshot into a new or existing session
+///
+/// The caller needs read access to the snapshot's session, and write access
+/// to the target session. A new session is owned by the caller and counts
+/// against their concurrent session limit.
+#[debug_handler]
+pub(crate) async fn restore_snapshot(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(key): Path<String>,
+    ValidJson(request): ValidJson<RestoreSnapshotRequest>,
+) -> Result<Json<ApiResponse<ForkSession>>, ApiError> {
+    let snapshot_id = state.resolver.resolve_snapshot(user.user_id, &key).await?;
+    let snapshot = state
+        .snapshot_service
+        .get_snapshot(snapshot_id)
+        .await?
+        .ok_or_else(|| DomainError::NotFound(format!("Snapshot {snapshot_id}")))?;
+    state
+        .session_service
+        .authorize_access(snapshot.session_id, user.user_id, CollaboratorAccess::Read)
+        .await?;
+
+    let target = match request.session {
+        Some(key) => {
+            let id = state.resolver.resolve_session(user.user_id, &key).await?;
+            state
+                .session_service
+                .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+                .await?;
+            Some(id)
+        }
+        None => {
+            let tier = state.subscription_tier(user.user_id).await?;
+            state
+                .quota_service
+                .check_session_quota(user.user_id, tier)
+                .await?;
+            None
+        }
+    };
+
+    let session = state
+        .snapshot_service
+        .restore_snapshot(snapshot_id, user.user_id, target)
+        .await?;
+
+    Ok(Json(ApiResponse::new(session)))
+}
+
+/// Result entry for one batch item
+fn batch_result(id: String, result: Result<(), ApiError>) -> BatchItemResult {
+    match result {
+        Ok(()) => BatchItemResult {
+            id,
+            ok: true,
+            error: None,
+        },
+        Err(err) => {
+            let (_, code, message) = err.parts();
+            BatchItemResult {
+                id,
+                ok: false,
+                error: Some(BatchItemError {
+                    code: code.to_string(),
+                    message,
+                }),
+            }
+        }
+    }
+}
+
+/// Stop several sessions, reporting the outcome of each
+///
+/// Each session needs ownership or write access; one failing doesn't stop
+/// the rest.
+#[debug_handler]
+pub(crate) async fn batch_stop_sessions(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    ValidJson(request): ValidJson<BatchStopSessionsRequest>,
+) -> Result<Json<ApiResponse<Vec<BatchItemResult>>>, ApiError> {
+    if request.ids.len() > MAX_BATCH_ITEMS {
+        return Err(DomainError::InvalidInput(format!(
+            "At most {MAX_BATCH_ITEMS} sessions can be stopped at once"
+        ))
+        .into());
+    }
+
+    let mut results = Vec::with_capacity(request.ids.len());
+    for key in request.ids {
+        let result = async {
+            let id = state.resolver.resolve_session(user.user_id, &key).await?;
+            state
+                .session_service
+                .authorize_access(id, user.user_id, CollaboratorAccess::Write)
+                .await?;
+            state.session_service.stop_session(id).await?;
+            Ok(())
+        }
+        .await;
+        results.push(batch_result(key, result));
+    }
+
+    Ok(Json(ApiResponse::new(results)))
+}
+
+/// Delete several snapshots, by ID and/or server-side filter
+///
+/// A snapshot may be deleted by whoever took it, or by anyone with write
+/// access to its session. Each snapshot reports its own outcome.
+#[debug_handler]
+pub(crate) async fn batch_delete_snapshots(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    ValidJson(request): ValidJson<BatchDeleteSnapshotsRequest>,
+) -> Result<Json<ApiResponse<Vec<BatchItemResult>>>, ApiError> {
+    let mut keys = request.ids;
+    if let Some(filter) = request.filter {
+        let filter: SnapshotFilter = filter.parse().map_err(DomainError::InvalidInput)?;
+        let matching = state
+            .snapshot_service
+            .find_matching(user.user_id, filter, MAX_BATCH_ITEMS as u32 + 1)
+            .await?;
+        keys.extend(matching.iter().map(SnapshotId::to_string));
+    }
+    if keys.len() > MAX_BATCH_ITEMS {
+        return Err(DomainError::InvalidInput(format!(
+            "At most {MAX_BATCH_ITEMS} snapshots can be deleted at once; narrow the filter"
+        ))
+        .into());
+    }
+
+    let mut results = Vec::with_capacity(keys.len());
+    for key in keys {
+        let result = async {
+            let id = state.resolver.resolve_snapshot(user.user_id, &key).await?;
+            let snapshot = state
+                .snapshot_service
+                .get_snapshot(id)
+                .await?
+                .ok_or_else(|| DomainError::NotFound(format!("Snapshot {key}")))?;
+            if snapshot.user_id != user.user_id {
+                state
+                    .session_service
+                    .authorize_access(snapshot.session_id, user.user_id, CollaboratorAccess::Write)
+                    .await?;
+            }
+            state.snapshot_service.delete_snapshot(id).await?;
+            Ok(())
+        }
+        .await;
+        results.push(batch_result(key, result));
+    }
+
+    Ok(Json(ApiResponse::new(results)))
+}
@@ -0,0 +1,77 @@
+//! # Single Sign-On
+//!
+//! OpenID Connect sign-in, for servers with `oidc_issuer` set. The CLI runs
+//! the authorization code flow with PKCE in the user's browser and trades
+//! the code it gets back for a ForkForge API token here. The server redeems
+//! the code, so the issuer's tokens never reach the CLI.
+//!
+//! Users are matched by their subject at the issuer; see
+//! `UserService::find_or_create_oidc_user` for how first sign-ins are
+//! linked to existing accounts.
+
+use axum::{Json, debug_handler, extract::State};
+use common::{ApiTokenResponse, OidcConfigResponse, OidcTokenRequest};
+use domain::{errors::DomainError, models::UserStatus, services::auth::oidc::OidcProvider};
+use infra::OidcClient;
+
+use crate::{AppState, error::ApiError, validation::ValidJson};
+
+/// The configured OIDC client, or `404` when single sign-on is off
+fn oidc_client(state: &AppState) -> Result<&OidcClient, ApiError> {
+    state.oidc_client.as_deref().ok_or_else(|| {
+        DomainError::NotFound("Single sign-on is not configured on this server".to_string()).into()
+    })
+}
+
+/// Where to send the user's browser to sign in, and with which client
+#[debug_handler]
+pub(crate) async fn oidc_config(
+    State(state): State<AppState>,
+) -> Result<Json<OidcConfigResponse>, ApiError> {
+    let authorization_endpoint = oidc_client(&state)?.authorization_endpoint().await?;
+
+    Ok(Json(OidcConfigResponse {
+        authorization_endpoint,
+        client_id: state.config.oidc_client_id.clone().unwrap_or_default(),
+        scopes: state.config.oidc_scopes.clone(),
+    }))
+}
+
+/// Exchange an authorization code for a ForkForge API token
+///
+/// Creates the ForkForge user on first sign-in. Blank fields are rejected
+/// before anything is sent to the issuer.
+#[debug_handler]
+pub(crate) async fn issue_oidc_token(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<OidcTokenRequest>,
+) -> Result<Json<ApiTokenResponse>, ApiError> {
+    let claims = oidc_client(&state)?
+        .exchange_code(&request.code, &request.code_verifier, &request.redirect_uri)
+        .await?;
+    let user = state.user_service.find_or_create_oidc_user(&claims).await?;
+    match user.status {
+        UserStatus::Active => {}
+        UserStatus::Suspended => return Err(ApiError::AccountSuspended),
+        UserStatus::Deleted => {
+            return Err(DomainError::Unauthorized("Account has been deleted".to_string()).into());
+        }
+    }
+
+    // Not fatal: checkout creates the customer if Stripe is down now
+    if let Some(checkout) = &state.checkout_service
+        && user.stripe_customer_id.is_none()
+        && let Err(e) = checkout.ensure_customer(user.id).await
+    {
+        tracing::warn!(user_id = %user.id, error = %e, "Failed to create Stripe customer");
+    }
+
+    let api_token = state
+        .github_auth_service
+        .create_api_token(user.id, Some("CLI (SSO)".to_string()))
+        .await?;
+
+    Ok(Json(ApiTokenResponse {
+        token: api_token.token,
+    }))
+}
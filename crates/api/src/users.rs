@@ -0,0 +1,66 @@
+//! HTTP adapter for the signed-in user's own account.
+
+use axum::{Json, debug_handler, extract::State};
+use common::UpdateProfileRequest;
+use domain::{
+    errors::DomainError,
+    models::{ProfileChanges, User},
+};
+
+use crate::{
+    ApiResponse, AppState, auth::AuthenticatedUser, error::ApiError, precondition,
+    validation::ValidJson,
+};
+
+/// The signed-in user's account and profile settings
+#[debug_handler]
+pub(crate) async fn get_profile(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<(precondition::ETag, Json<ApiResponse<User>>), ApiError> {
+    let user = state
+        .user_service
+        .get_user(user.user_id)
+        .await?
+        .ok_or_else(|| DomainError::NotFound(format!("User {}", user.user_id)))?;
+
+    Ok((
+        precondition::etag(user.updated_at),
+        Json(ApiResponse::new(user)),
+    ))
+}
+
+/// Change the user's profile settings
+///
+/// Only the fields in the body are changed, so this never conflicts with a
+/// concurrent change to another field. A preferred region must be one the
+/// server offers, if it offers any.
+#[debug_handler]
+pub(crate) async fn update_profile(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    ValidJson(request): ValidJson<UpdateProfileRequest>,
+) -> Result<(precondition::ETag, Json<ApiResponse<User>>), ApiError> {
+    if let Some(region) = &request.preferred_region {
+        let region = region.trim().to_lowercase();
+        if !region.is_empty() && !state.regions.regions().is_empty() {
+            state.regions.place(Some(&region), None)?;
+        }
+    }
+
+    let changes = ProfileChanges {
+        primary_email: request.email,
+        display_name: request.display_name,
+        contact_email: request.contact_email,
+        preferred_region: request.preferred_region,
+    };
+    let user = state
+        .user_service
+        .update_profile(user.user_id, changes)
+        .await?;
+
+    Ok((
+        precondition::etag(user.updated_at),
+        Json(ApiResponse::new(user)),
+    ))
+}
@@ -0,0 +1,399 @@
+//! # Request Validation
+//!
+//! `ValidJson<T>` reads a request body like `Json<T>`, then checks it with
+//! `T`'s `Validate` impl before the handler runs. Bad bodies are rejected
+//! with `422 Unprocessable Entity`, listing every invalid field.
+//! `ValidQuery<T>` does the same for query strings:
+//!
+//! ```json
+//! {"error": {"code": "invalid_input", "message": "name: must not be empty",
+//!            "fields": [{"field": "name", "message": "must not be empty"}]}}
+//! ```
+
+use axum::{
+    Json,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
+};
+use common::{
+    ApiTokenRequest, BatchDeleteSnapshotsRequest, BatchStopSessionsRequest, CheckoutSessionRequest,
+    CreateSessionRequest, CreateSnapshotRequest, FieldError, OidcTokenRequest,
+    PollAuthorizationRequest, RestoreSnapshotRequest, SessionUsageRequest,
+    SetBillingCountryRequest, UpdateProfileRequest, UpdateSessionRequest,
+};
+use domain::models::{MAX_DESCRIPTION_LEN, MAX_NAME_LEN, MAX_REGION_LEN};
+use serde::de::DeserializeOwned;
+
+use crate::error::ApiError;
+use crate::sessions::AddCollaboratorRequest;
+
+/// Most accounts a session may copy from mainnet when it is created
+const MAX_FORK_ACCOUNTS: usize = 100;
+
+/// Longest device code accepted; GitHub's are 40 characters
+const MAX_DEVICE_CODE_LEN: usize = 128;
+
+/// Lengths a PKCE code verifier may have (RFC 7636, section 4.1)
+const CODE_VERIFIER_LEN: std::ops::RangeInclusive<usize> = 43..=128;
+
+/// Problems found in a request body, in field order
+#[derive(Debug, Default)]
+pub(crate) struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Check a name, which is trimmed before use
+    fn name(&mut self, field: &str, name: &str, required: bool) {
+        let name = name.trim();
+        if required && name.is_empty() {
+            self.add(field, "must not be empty");
+        } else if name.chars().count() > MAX_NAME_LEN {
+            self.add(field, format!("must be at most {MAX_NAME_LEN} characters"));
+        } else if name.chars().any(char::is_control) {
+            self.add(field, "must not contain control characters");
+        }
+    }
+
+    fn pubkeys(&mut self, field: &str, pubkeys: &[String]) {
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            let is_pubkey = bs58::decode(pubkey)
+                .into_vec()
+                .is_ok_and(|bytes| bytes.len() == 32);
+            if !is_pubkey {
+                self.add(format!("{field}[{i}]"), "must be a base58 public key");
+            }
+        }
+    }
+
+    /// Check a region name is well-formed; whether it exists is up to the server
+    fn region(&mut self, field: &str, region: &str) {
+        if region.len() > MAX_REGION_LEN
+            || !region
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            self.add(
+                field,
+                format!("must be up to {MAX_REGION_LEN} letters, digits and dashes"),
+            );
+        }
+    }
+
+    fn into_result(self) -> Result<(), Vec<FieldError>> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.0)
+        }
+    }
+}
+
+/// Field-level checks on a request body
+pub(crate) trait Validate {
+    /// Record a problem for each invalid field
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+impl Validate for CreateSessionRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        // Empty names are fine; the session is named after its slug
+        errors.name("name", &self.name, false);
+        errors.pubkeys("accounts", &self.accounts);
+        errors.pubkeys("programs", &self.programs);
+        errors.pubkeys("mints", &self.mints);
+        if let Some(region) = &self.region {
+            errors.region("region", region);
+        }
+        if self.pubkeys().len() > MAX_FORK_ACCOUNTS {
+            errors.add(
+                "accounts",
+                format!("at most {MAX_FORK_ACCOUNTS} accounts can be copied into a session"),
+            );
+        }
+    }
+}
+
+impl Validate for UpdateSessionRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name, true);
+    }
+}
+
+impl Validate for CreateSnapshotRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name, true);
+        if self
+            .description
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LEN)
+        {
+            errors.add(
+                "description",
+                format!("must be at most {MAX_DESCRIPTION_LEN} characters"),
+            );
+        }
+    }
+}
+
+impl Validate for PollAuthorizationRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        let code = &self.device_code;
+        if code.is_empty() {
+            errors.add("device_code", "must not be empty");
+        } else if code.len() > MAX_DEVICE_CODE_LEN
+            || !code
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            errors.add("device_code", "is not a device code");
+        }
+    }
+}
+
+impl Validate for OidcTokenRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.code.trim().is_empty() {
+            errors.add("code", "must not be empty");
+        }
+
+        let verifier = &self.code_verifier;
+        if verifier.is_empty() {
+            errors.add("code_verifier", "must not be empty");
+        } else if !CODE_VERIFIER_LEN.contains(&verifier.len())
+            || !verifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'))
+        {
+            errors.add("code_verifier", "is not a PKCE code verifier");
+        }
+
+        if self.redirect_uri.trim().is_empty() {
+            errors.add("redirect_uri", "must not be empty");
+        }
+    }
+}
+
+impl Validate for UpdateProfileRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self
+            .email
+            .as_ref()
+            .is_some_and(|email| email.trim().is_empty())
+        {
+            errors.add("email", "must not be empty");
+        }
+        // Empty display names and regions are fine; they clear them
+        if let Some(display_name) = &self.display_name {
+            errors.name("display_name", display_name, false);
+        }
+        if let Some(region) = &self.preferred_region {
+            errors.region("preferred_region", region.trim());
+        }
+    }
+}
+
+impl Validate for RestoreSnapshotRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self
+            .session
+            .as_ref()
+            .is_some_and(|session| session.trim().is_empty())
+        {
+            errors.add("session", "must not be empty");
+        }
+    }
+}
+
+impl Validate for ApiTokenRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.access_token.trim().is_empty() {
+            errors.add("access_token", "must not be empty");
+        }
+    }
+}
+
+/// Bodies whose fields are fully checked by parsing, or by their handler
+macro_rules! no_field_rules {
+    ($($body:ty),* $(,)?) => {
+        $(impl Validate for $body {
+            fn validate(&self, _errors: &mut FieldErrors) {}
+        })*
+    };
+}
+
+no_field_rules!(
+    AddCollaboratorRequest,
+    SessionUsageRequest,
+    BatchStopSessionsRequest,
+    BatchDeleteSnapshotsRequest,
+    CheckoutSessionRequest,
+    SetBillingCountryRequest,
+    // A GitHub access token, sent as a bare JSON string
+    String,
+);
+
+/// JSON body extractor that rejects invalid bodies with `422`
+pub(crate) struct ValidJson<T>(pub(crate) T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+
+        let mut errors = FieldErrors::default();
+        body.validate(&mut errors);
+        errors.into_result().map_err(ApiError::InvalidFields)?;
+        Ok(ValidJson(body))
+    }
+}
+
+/// Query string extractor that rejects invalid queries with `422`
+pub(crate) struct ValidQuery<T>(pub(crate) T);
+
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+
+        let mut errors = FieldErrors::default();
+        query.validate(&mut errors);
+        errors.into_result().map_err(ApiError::InvalidFields)?;
+        Ok(ValidQuery(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(body: &impl Validate) -> Vec<FieldError> {
+        let mut errors = FieldErrors::default();
+        body.validate(&mut errors);
+        errors.0
+    }
+
+    fn fields(body: &impl Validate) -> Vec<String> {
+        errors(body).into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn test_session_requests() {
+        let request = CreateSessionRequest {
+            name: " panic-2245 ".to_string(),
+            fork_slot: None,
+            accounts: vec!["9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM".to_string()],
+            programs: vec!["not-a-pubkey".to_string()],
+            mints: Vec::new(),
+            region: Some("eu west".to_string()),
+        };
+        assert_eq!(fields(&request), ["programs[0]", "region"]);
+
+        let rename = UpdateSessionRequest {
+            name: "x".repeat(MAX_NAME_LEN + 1),
+        };
+        assert_eq!(fields(&rename), ["name"]);
+        let rename = UpdateSessionRequest {
+            name: "  ".to_string(),
+        };
+        assert_eq!(errors(&rename)[0].message, "must not be empty");
+    }
+
+    #[test]
+    fn test_snapshot_and_device_code_requests() {
+        let snapshot = CreateSnapshotRequest {
+            name: "before\nliquidation".to_string(),
+            description: Some("x".repeat(MAX_DESCRIPTION_LEN + 1)),
+        };
+        assert_eq!(fields(&snapshot), ["name", "description"]);
+
+        let poll = PollAuthorizationRequest {
+            device_code: "3584d83530557fdd1f46af8289938c8ef79f9dc5".to_string(),
+        };
+        assert!(errors(&poll).is_empty());
+        let poll = PollAuthorizationRequest {
+            device_code: "'; DROP TABLE users; --".to_string(),
+        };
+        assert_eq!(fields(&poll), ["device_code"]);
+    }
+
+    #[test]
+    fn test_profile_requests() {
+        let clear = UpdateProfileRequest {
+            display_name: Some(String::new()),
+            preferred_region: Some(String::new()),
+            ..UpdateProfileRequest::default()
+        };
+        assert!(errors(&clear).is_empty());
+
+        let profile = UpdateProfileRequest {
+            email: Some(" ".to_string()),
+            display_name: Some("x".repeat(MAX_NAME_LEN + 1)),
+            contact_email: None,
+            preferred_region: Some("eu west".to_string()),
+        };
+        assert_eq!(
+            fields(&profile),
+            ["email", "display_name", "preferred_region"]
+        );
+    }
+
+    #[test]
+    fn test_restore_and_token_requests() {
+        let restore = RestoreSnapshotRequest { session: None };
+        assert!(errors(&restore).is_empty());
+        let restore = RestoreSnapshotRequest {
+            session: Some(" ".to_string()),
+        };
+        assert_eq!(fields(&restore), ["session"]);
+
+        let token = ApiTokenRequest {
+            access_token: String::new(),
+            refresh_token: None,
+            expires_in: None,
+            refresh_token_expires_in: None,
+        };
+        assert_eq!(fields(&token), ["access_token"]);
+    }
+
+    #[test]
+    fn test_oidc_token_requests() {
+        let request = OidcTokenRequest {
+            code: "SplxlOBeZQQYbYS6WxSbIA".to_string(),
+            code_verifier: "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string(),
+            redirect_uri: "http://127.0.0.1:8400/callback".to_string(),
+        };
+        assert!(errors(&request).is_empty());
+
+        let blank = OidcTokenRequest {
+            code: " ".to_string(),
+            code_verifier: String::new(),
+            redirect_uri: String::new(),
+        };
+        assert_eq!(fields(&blank), ["code", "code_verifier", "redirect_uri"]);
+        let short = OidcTokenRequest {
+            code_verifier: "too-short".to_string(),
+            ..request
+        };
+        assert_eq!(errors(&short)[0].message, "is not a PKCE code verifier");
+    }
+}
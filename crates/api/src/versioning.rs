@@ -0,0 +1,67 @@
+//! # API Versioning
+//!
+//! Every API route is served under `/v1`. Clients may also name the
+//! version they were written against in the `ForkForge-API-Version`
+//! header; a version this server doesn't serve is rejected with
+//! `400 Bad Request` rather than answered in a shape the client doesn't
+//! expect. Every response carries the version that served it.
+//!
+//! `/health` stays unversioned, as load balancers are configured with it.
+//!
+//! The unversioned paths from before `/v1` are still served as aliases,
+//! with a `Deprecation` header and a `Link` to the `/v1` path that replaces
+//! them (RFC 9745).
+
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::{API_PREFIX, API_VERSION, API_VERSION_HEADER};
+
+use crate::error::ApiError;
+
+/// When the unversioned paths were deprecated, as an RFC 9745 date
+/// (2026-10-16)
+const UNVERSIONED_DEPRECATED_AT: &str = "@1792108800";
+
+/// Reject requests for versions other than [`API_VERSION`], and label
+/// responses with the version that served them
+pub(crate) async fn negotiate_version(request: Request, next: Next) -> Response {
+    if let Some(requested) = request.headers().get(API_VERSION_HEADER) {
+        let requested = requested.to_str().unwrap_or_default().trim();
+        if requested != API_VERSION {
+            return ApiError::BadRequest(format!(
+                "API version {requested:?} is not supported; this server serves version {API_VERSION}"
+            ))
+            .into_response();
+        }
+    }
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(API_VERSION_HEADER, HeaderValue::from_static(API_VERSION));
+    response
+}
+
+/// Mark a response to an unversioned path as deprecated, pointing at the
+/// `/v1` path that replaces it
+pub(crate) async fn deprecate_unversioned(request: Request, next: Next) -> Response {
+    let successor = format!(
+        "<{API_PREFIX}{}>; rel=\"successor-version\"",
+        request.uri().path()
+    );
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(
+        "deprecation",
+        HeaderValue::from_static(UNVERSIONED_DEPRECATED_AT),
+    );
+    if let Ok(successor) = HeaderValue::from_str(&successor) {
+        headers.insert("link", successor);
+    }
+    response
+}
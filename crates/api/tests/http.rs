@@ -0,0 +1,1136 @@
+//! End-to-end tests of the API over HTTP
+//!
+//! Each test serves the real router on a local port, backed by a fresh
+//! SQLite database in a temporary directory. GitHub, Stripe and the OIDC
+//! issuer are replaced by fakes served the same way, so every request goes through the real
+//! adapters, middleware and handlers.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use api::{AppState, router};
+use axum::{
+    Form, Json, Router,
+    extract::Path,
+    http::HeaderMap,
+    routing::{get, post},
+};
+use common::{API_PREFIX, Config};
+use hmac::{Hmac, Mac};
+use infra::ServerInfra;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use tempfile::TempDir;
+
+const WEBHOOK_SECRET: &str = "whsec_test";
+const ADMIN_TOKEN: &str = "admin-test-token";
+const PRO_PRODUCT: &str = "prod_pro";
+const PRO_PRICE: &str = "price_pro";
+const DASHBOARD_ORIGIN: &str = "https://app.forkforge.dev";
+const SSO_CODE: &str = "sso-code";
+const SSO_VERIFIER: &str = "sso-verifier-0123456789abcdef0123456789abcdef";
+
+/// Serve `app` on a free local port, returning its base URL
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    format!("http://{addr}")
+}
+
+/// GitHub, where every device code is approved on the first poll
+///
+/// The `gho_private` token belongs to a user who keeps their email private.
+fn fake_github() -> Router {
+    Router::new()
+        .route(
+            "/login/device/code",
+            post(|| async {
+                Json(json!({
+                    "device_code": "3584d83530557fdd1f46af8289938c8ef79f9dc5",
+                    "user_code": "WDJB-MJHT",
+                    "verification_uri": "https://github.com/login/device",
+                    "expires_in": 900,
+                    "interval": 5,
+                }))
+            }),
+        )
+        .route(
+            "/login/oauth/access_token",
+            post(|| async {
+                Json(json!({
+                    "access_token": "gho_test",
+                    "token_type": "bearer",
+                    "scope": "user",
+                }))
+            }),
+        )
+        .route(
+            "/api/v3/user",
+            get(|headers: HeaderMap| async move {
+                if headers["authorization"] == "Bearer gho_private" {
+                    return Json(json!({
+                        "id": 108109,
+                        "login": "hubot",
+                        "email": null,
+                        "name": "Hubot",
+                    }));
+                }
+                Json(json!({
+                    "id": 583231,
+                    "login": "octocat",
+                    "email": "octocat@github.com",
+                    "name": "The Octocat",
+                }))
+            }),
+        )
+        .route(
+            "/api/v3/user/emails",
+            get(|| async {
+                Json(json!([
+                    {"email": "old@example.com", "primary": false, "verified": true},
+                    {"email": "hubot@example.com", "primary": true, "verified": true},
+                ]))
+            }),
+        )
+}
+
+/// OpenID Connect issuer, redeeming [`SSO_CODE`] for [`SSO_VERIFIER`]
+fn fake_issuer() -> Router {
+    Router::new()
+        .route(
+            "/.well-known/openid-configuration",
+            get(|headers: HeaderMap| async move {
+                let issuer = format!("http://{}", headers["host"].to_str().unwrap());
+                Json(json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{issuer}/authorize"),
+                    "token_endpoint": format!("{issuer}/token"),
+                    "userinfo_endpoint": format!("{issuer}/userinfo"),
+                }))
+            }),
+        )
+        .route(
+            "/token",
+            post(|Form(form): Form<HashMap<String, String>>| async move {
+                if form["grant_type"] != "authorization_code"
+                    || form["code"] != SSO_CODE
+                    || form["code_verifier"] != SSO_VERIFIER
+                {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({"error": "invalid_grant"})),
+                    );
+                }
+                (
+                    StatusCode::OK,
+                    Json(json!({"access_token": "oidc_test", "token_type": "Bearer"})),
+                )
+            }),
+        )
+        .route(
+            "/userinfo",
+            get(|| async {
+                Json(json!({
+                    "sub": "00u1",
+                    "email": "grace@corp.example",
+                    "email_verified": true,
+                    "name": "Grace Hopper",
+                }))
+            }),
+        )
+}
+
+/// Stripe, selling the Pro tier at a single price
+fn fake_stripe() -> Router {
+    Router::new()
+        .route(
+            "/v1/customers",
+            post(|| async { Json(json!({"id": "cus_test"})) }),
+        )
+        .route(
+            "/v1/products/{id}",
+            get(|Path(id): Path<String>| async move {
+                Json(json!({"id": id, "default_price": PRO_PRICE}))
+            }),
+        )
+        .route(
+            "/v1/prices/{id}",
+            get(|Path(id): Path<String>| async move {
+                Json(json!({
+                    "id": id,
+                    "product": PRO_PRODUCT,
+                    "currency": "usd",
+                    "unit_amount": 2900,
+                    "recurring": {"interval": "month"},
+                }))
+            }),
+        )
+        .route(
+            "/v1/checkout/sessions",
+            post(|Form(form): Form<HashMap<String, String>>| async move {
+                // Checkout must collect tax with Stripe Tax
+                if form.get("automatic_tax[enabled]").map(String::as_str) != Some("true")
+                    || form.get("customer_update[address]").map(String::as_str) != Some("auto")
+                {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({"error": {"message": "automatic_tax not enabled"}})),
+                    );
+                }
+                (
+                    StatusCode::OK,
+                    Json(json!({"id": "cs_test", "url": "https://checkout.stripe.com/c/cs_test"})),
+                )
+            }),
+        )
+}
+
+/// The API under test, with its own database and fakes
+struct TestApp {
+    url: String,
+    http: reqwest::Client,
+    _dir: TempDir,
+}
+
+impl TestApp {
+    async fn start() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            database_url: format!("sqlite:{}", dir.path().join("forkforge.db").display()),
+            github_client_id: Some("Iv1.test".to_string()),
+            github_base_url: serve(fake_github()).await,
+            stripe_secret_key: Some("sk_test".to_string()),
+            stripe_webhook_secret: WEBHOOK_SECRET.to_string(),
+            stripe_product_id_pro_tier: Some(PRO_PRODUCT.to_string()),
+            stripe_api_url: format!("{}/v1", serve(fake_stripe()).await),
+            admin_api_token: Some(ADMIN_TOKEN.to_string()),
+            cors_allowed_origins: DASHBOARD_ORIGIN.to_string(),
+            oidc_issuer: Some(serve(fake_issuer()).await),
+            oidc_client_id: Some("forkforge-cli".to_string()),
+            ..Config::default()
+        };
+
+        let infra = ServerInfra::new(&config).await.unwrap();
+        infra.db.run_migrations().await.unwrap();
+        let state = AppState::new(&config, Arc::new(infra), None).await.unwrap();
+
+        Self {
+            url: serve(router(state)).await,
+            http: reqwest::Client::new(),
+            _dir: dir,
+        }
+    }
+
+    /// URL of a path of the current API version
+    fn url(&self, path: &str) -> String {
+        format!("{}{API_PREFIX}{path}", self.url)
+    }
+
+    /// Exchange a GitHub access token for an API token
+    async fn api_token(&self, access_token: &str) -> String {
+        let response = self
+            .http
+            .post(self.url("/auth/token"))
+            .json(&json!({"access_token": access_token}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        body["token"].as_str().unwrap().to_string()
+    }
+
+    /// Send a webhook signed like Stripe signs them
+    async fn webhook(&self, event: &Value) -> reqwest::Response {
+        let payload = event.to_string();
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_SECRET.as_bytes()).unwrap();
+        mac.update(format!("{timestamp}.{payload}").as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        self.http
+            .post(self.url("/billing/webhook"))
+            .header("Stripe-Signature", format!("t={timestamp},v1={signature}"))
+            .body(payload)
+            .send()
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_device_login_issues_api_token() {
+    let app = TestApp::start().await;
+
+    let response = app
+        .http
+        .post(app.url("/auth/github/device-code"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let device: Value = response.json().await.unwrap();
+    assert_eq!(device["user_code"], "WDJB-MJHT");
+    let device_code = device["device_code"].as_str().unwrap();
+
+    // The server first polls GitHub after 5 seconds; polls of our own are
+    // rate limited per device code, so keep them sparse
+    let mut status = json!({"status": "pending"});
+    for _ in 0..5 {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        status = app
+            .http
+            .get(app.url("/auth/github/status"))
+            .query(&[("device_code", device_code)])
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if status["status"] != "pending" {
+            break;
+        }
+    }
+    assert_eq!(status["status"], "complete", "{status}");
+    let access_token = status["token"]["access_token"].as_str().unwrap();
+    assert_eq!(access_token, "gho_test");
+
+    let token = app.api_token(access_token).await;
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile["data"]["primary_email"], "octocat@github.com");
+    assert_eq!(profile["data"]["display_name"], "The Octocat");
+    assert_eq!(profile["data"]["github_user_id"], 583231);
+
+    // Logging in again signs into the same account
+    let again = app.api_token(access_token).await;
+    assert_ne!(again, token);
+    let profile_again: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&again)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile_again["data"]["id"], profile["data"]["id"]);
+}
+
+#[tokio::test]
+async fn test_login_with_private_email() {
+    let app = TestApp::start().await;
+
+    let token = app.api_token("gho_private").await;
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile["data"]["primary_email"], "hubot@example.com");
+    assert_eq!(profile["data"]["display_name"], "Hubot");
+}
+
+#[tokio::test]
+async fn test_single_sign_on() {
+    let app = TestApp::start().await;
+
+    let config: Value = app
+        .http
+        .get(app.url("/auth/oidc/config"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(
+        config["authorization_endpoint"]
+            .as_str()
+            .unwrap()
+            .ends_with("/authorize")
+    );
+    assert_eq!(config["client_id"], "forkforge-cli");
+    assert_eq!(config["scopes"], "openid email profile");
+
+    let sign_in = |code: &str| {
+        app.http.post(app.url("/auth/oidc/token")).json(&json!({
+            "code": code,
+            "code_verifier": SSO_VERIFIER,
+            "redirect_uri": "http://127.0.0.1:8400/callback",
+        }))
+    };
+    let rejected = sign_in("stale-code").send().await.unwrap();
+    assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+    let blank = sign_in(" ").send().await.unwrap();
+    assert_eq!(blank.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = blank.json().await.unwrap();
+    assert_eq!(body["error"]["fields"][0]["field"], "code");
+
+    let response = sign_in(SSO_CODE).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(body["token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile["data"]["primary_email"], "grace@corp.example");
+    assert_eq!(profile["data"]["display_name"], "Grace Hopper");
+    assert_eq!(profile["data"]["github_user_id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_scim_provisioning() {
+    let app = TestApp::start().await;
+    let users_url = app.url("/scim/v2/Users");
+
+    let response = app.http.get(&users_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let grace = json!({
+        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+        "userName": "grace@corp.example",
+        "name": {"givenName": "Grace", "familyName": "Hopper"},
+        "roles": [{"value": "admin"}],
+        "active": true,
+    });
+    let response = app
+        .http
+        .post(&users_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&grace)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["content-type"], "application/scim+json");
+    let created: Value = response.json().await.unwrap();
+    let grace_id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(created["displayName"], "Grace Hopper");
+    assert_eq!(created["roles"][0]["value"], "admin");
+    assert!(
+        created["meta"]["location"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("{API_PREFIX}/scim/v2/Users/{grace_id}"))
+    );
+
+    let response = app
+        .http
+        .post(&users_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&grace)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "409");
+    assert_eq!(body["scimType"], "uniqueness");
+
+    // Unreadable bodies get a SCIM error too
+    let response = app
+        .http
+        .post(&users_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .header("content-type", "application/scim+json")
+        .body(r#"{"userName": "#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["content-type"], "application/scim+json");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(
+        body["schemas"][0],
+        "urn:ietf:params:scim:api:messages:2.0:Error"
+    );
+    assert_eq!(body["scimType"], "invalidSyntax");
+    let response = app
+        .http
+        .post(&users_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&json!({"userName": "ada@corp.example", "active": "yes"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["scimType"], "invalidSyntax");
+
+    let found: Value = app
+        .http
+        .get(&users_url)
+        .query(&[("filter", "userName eq \"grace@corp.example\"")])
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(found["totalResults"], 1);
+    assert_eq!(found["Resources"][0]["id"], grace_id.as_str());
+
+    // Signing in claims the provisioned account, whose role lets it
+    // administer the server; members can't
+    let response = app
+        .http
+        .post(app.url("/auth/oidc/token"))
+        .json(&json!({
+            "code": SSO_CODE,
+            "code_verifier": SSO_VERIFIER,
+            "redirect_uri": "http://127.0.0.1:8400/callback",
+        }))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+    let grace_token = body["token"].as_str().unwrap().to_string();
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&grace_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile["data"]["id"], grace_id.as_str());
+    let response = app
+        .http
+        .get(app.url("/scim/v2/ServiceProviderConfig"))
+        .bearer_auth(&grace_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let member_token = app.api_token("gho_test").await;
+    let response = app
+        .http
+        .get(app.url("/scim/v2/ServiceProviderConfig"))
+        .bearer_auth(&member_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response: Value = app
+        .http
+        .post(app.url("/scim/v2/Bulk"))
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkRequest"],
+            "Operations": [
+                {
+                    "method": "POST",
+                    "bulkId": "ada",
+                    "path": "/Users",
+                    "data": {"userName": "ada@corp.example", "displayName": "Ada"},
+                },
+                {
+                    "method": "PATCH",
+                    "path": format!("/Users/{grace_id}"),
+                    "data": {
+                        "Operations": [{"op": "replace", "path": "active", "value": false}],
+                    },
+                },
+                {
+                    "method": "DELETE",
+                    "path": format!("/Users/{}", uuid::Uuid::now_v7()),
+                },
+            ],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let results = response["Operations"].as_array().unwrap();
+    assert_eq!(results[0]["bulkId"], "ada");
+    assert_eq!(results[0]["status"], "201");
+    assert_eq!(results[1]["status"], "200");
+    assert_eq!(results[2]["status"], "404");
+    let ada_location = results[0]["location"].as_str().unwrap();
+    let ada_id = ada_location.rsplit('/').next().unwrap();
+
+    let deactivated: Value = app
+        .http
+        .get(app.url(&format!("/scim/v2/Users/{grace_id}")))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(deactivated["active"], false);
+    let response = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&grace_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let ada_url = app.url(&format!("/scim/v2/Users/{ada_id}"));
+    let response = app
+        .http
+        .delete(&ada_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .http
+        .get(&ada_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let listed: Value = app
+        .http
+        .get(&users_url)
+        .query(&[("count", "10")])
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    // Grace and the member; Ada is gone
+    assert_eq!(listed["totalResults"], 2);
+    assert_eq!(listed["startIndex"], 1);
+}
+
+#[tokio::test]
+async fn test_create_and_list_sessions() {
+    let app = TestApp::start().await;
+
+    let response = app
+        .http
+        .post(app.url("/sessions"))
+        .json(&json!({"name": "liquidation-replay"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let token = app.api_token("gho_test").await;
+    let response = app
+        .http
+        .post(app.url("/sessions"))
+        .bearer_auth(&token)
+        .json(&json!({"name": "liquidation-replay"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["data"]["name"], "liquidation-replay");
+
+    let sessions: Value = app
+        .http
+        .get(app.url("/sessions"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(sessions["data"].as_array().unwrap().len(), 1);
+    assert_eq!(sessions["data"][0]["id"], created["data"]["id"]);
+
+    let response = app
+        .http
+        .get(app.url(&format!(
+            "/sessions/{}",
+            created["data"]["id"].as_str().unwrap()
+        )))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_malformed_bodies_get_the_error_envelope() {
+    let app = TestApp::start().await;
+    let token = app.api_token("gho_test").await;
+    let created: Value = app
+        .http
+        .post(app.url("/sessions"))
+        .bearer_auth(&token)
+        .json(&json!({"name": "liquidation-replay"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let session_id = created["data"]["id"].as_str().unwrap();
+
+    let requests = [
+        (
+            format!("/sessions/{session_id}/usage"),
+            json!({"elapsed_seconds": "ten"}),
+        ),
+        ("/sessions:batchStop".to_string(), json!({"ids": "all"})),
+        ("/snapshots:batchDelete".to_string(), json!({"ids": 7})),
+        ("/billing/checkout-session".to_string(), json!({})),
+    ];
+    for (path, body) in requests {
+        let response = app
+            .http
+            .post(app.url(&path))
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{path}");
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "bad_request", "{path}");
+    }
+
+    let response = app
+        .http
+        .put(app.url("/billing/country"))
+        .bearer_auth(&token)
+        .body("DE")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "bad_request");
+
+    let response = app
+        .http
+        .post(app.url("/auth/token"))
+        .json(&json!({"access_token": " "}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["fields"][0]["field"], "access_token");
+}
+
+#[tokio::test]
+async fn test_rejected_sessions_keep_the_ip_allowance() {
+    let app = TestApp::start().await;
+    let token = app.api_token("gho_test").await;
+    let create = || {
+        app.http
+            .post(app.url("/sessions"))
+            .bearer_auth(&token)
+            .json(&json!({}))
+    };
+
+    let created: Value = create().send().await.unwrap().json().await.unwrap();
+    // Entry allows one session at a time; going over it many more times
+    // than the hourly IP allowance never uses that allowance up
+    for _ in 0..12 {
+        let response = create().send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "quota_exceeded");
+    }
+
+    let response = app
+        .http
+        .post(app.url(&format!(
+            "/sessions/{}/stop",
+            created["data"]["id"].as_str().unwrap()
+        )))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = create().send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_session_collaborators() {
+    let app = TestApp::start().await;
+    let owner = app.api_token("gho_test").await;
+    let reader = app.api_token("gho_private").await;
+
+    let created: Value = app
+        .http
+        .post(app.url("/sessions"))
+        .bearer_auth(&owner)
+        .json(&json!({"name": "shared-replay"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let session_url = app.url(&format!(
+        "/sessions/{}",
+        created["data"]["id"].as_str().unwrap()
+    ));
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&reader)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let grant = json!({"user_id": profile["data"]["id"], "access": "read"});
+
+    let response = app
+        .http
+        .get(&session_url)
+        .bearer_auth(&reader)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    // Only the owner shares a session
+    let response = app
+        .http
+        .post(format!("{session_url}/collaborators"))
+        .bearer_auth(&reader)
+        .json(&grant)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    // Non-owners can't probe whether a user ID exists
+    let response = app
+        .http
+        .post(format!("{session_url}/collaborators"))
+        .bearer_auth(&reader)
+        .json(&json!({"user_id": "4f1e1f44-7a39-4c2e-9a57-0f3d4c1b2a10", "access": "read"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Malformed grants get the JSON error envelope
+    let response = app
+        .http
+        .post(format!("{session_url}/collaborators"))
+        .bearer_auth(&owner)
+        .json(&json!({"user_id": profile["data"]["id"], "access": "admin"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "bad_request");
+
+    let response = app
+        .http
+        .post(format!("{session_url}/collaborators"))
+        .bearer_auth(&owner)
+        .json(&grant)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["access"], "read");
+
+    let response = app
+        .http
+        .get(&session_url)
+        .bearer_auth(&reader)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let session: Value = response.json().await.unwrap();
+    assert_eq!(session["data"]["name"], "shared-replay");
+
+    // Read access doesn't extend to renaming
+    let response = app
+        .http
+        .patch(&session_url)
+        .bearer_auth(&reader)
+        .json(&json!({"name": "mine-now"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_stripe_webhook_activates_subscription() {
+    let app = TestApp::start().await;
+    let token = app.api_token("gho_test").await;
+
+    // Logging in created the Stripe customer the webhook refers to
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(profile["data"]["stripe_customer_id"], "cus_test");
+
+    let checkout: Value = app
+        .http
+        .post(app.url("/billing/checkout-session"))
+        .bearer_auth(&token)
+        .json(&json!({"tier": "pro"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        checkout["data"]["url"],
+        "https://checkout.stripe.com/c/cs_test"
+    );
+
+    let event = json!({
+        "id": "evt_test",
+        "type": "customer.subscription.created",
+        "created": chrono::Utc::now().timestamp(),
+        "data": {"object": {
+            "id": "sub_test",
+            "customer": "cus_test",
+            "status": "active",
+            "items": {"data": [{"price": {"id": PRO_PRICE}}]},
+        }},
+    });
+
+    let unsigned = app
+        .http
+        .post(app.url("/billing/webhook"))
+        .header("Stripe-Signature", "t=0,v1=00")
+        .body(event.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unsigned.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(app.webhook(&event).await.status(), StatusCode::OK);
+    // Stripe redelivers events; a replay changes nothing
+    assert_eq!(app.webhook(&event).await.status(), StatusCode::OK);
+
+    // Subscribed users change plans through the portal instead
+    let response = app
+        .http
+        .post(app.url("/billing/checkout-session"))
+        .bearer_auth(&token)
+        .json(&json!({"tier": "pro"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let profile: Value = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let events: Value = app
+        .http
+        .get(app.url(&format!(
+            "/admin/users/{}/billing-events",
+            profile["data"]["id"].as_str().unwrap()
+        )))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let events = events["data"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["kind"], "tier_changed");
+    assert_eq!(events[0]["stripe_event_id"], "evt_test");
+}
+
+#[tokio::test]
+async fn test_unversioned_paths_are_deprecated_aliases() {
+    let app = TestApp::start().await;
+    let token = app.api_token("gho_test").await;
+
+    let current = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(current.status(), StatusCode::OK);
+    assert_eq!(current.headers()["forkforge-api-version"], "1");
+    assert!(current.headers().get("deprecation").is_none());
+
+    let legacy = app
+        .http
+        .get(format!("{}/me", app.url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(legacy.status(), StatusCode::OK);
+    assert!(legacy.headers().contains_key("deprecation"));
+    assert_eq!(
+        legacy.headers()["link"],
+        "</v1/me>; rel=\"successor-version\""
+    );
+
+    // Health checks stay where load balancers expect them
+    let health = app
+        .http
+        .get(format!("{}/health", app.url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(health.status(), StatusCode::OK);
+    assert!(health.headers().get("deprecation").is_none());
+
+    let response = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth(&token)
+        .header("ForkForge-API-Version", "2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_browser_clients() {
+    let app = TestApp::start().await;
+
+    let preflight = |origin: &'static str| {
+        app.http
+            .request(reqwest::Method::OPTIONS, app.url("/sessions"))
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .header(
+                "Access-Control-Request-Headers",
+                "authorization,content-type",
+            )
+            .send()
+    };
+    let allowed = preflight(DASHBOARD_ORIGIN).await.unwrap();
+    assert_eq!(allowed.status(), StatusCode::OK);
+    assert_eq!(
+        allowed.headers()["access-control-allow-origin"],
+        DASHBOARD_ORIGIN
+    );
+    let other = preflight("https://evil.example").await.unwrap();
+    assert!(other.headers().get("access-control-allow-origin").is_none());
+
+    let health = app
+        .http
+        .get(format!("{}/health", app.url))
+        .header("Origin", DASHBOARD_ORIGIN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        health.headers()["access-control-allow-origin"],
+        DASHBOARD_ORIGIN
+    );
+    assert_eq!(health.headers()["x-content-type-options"], "nosniff");
+    assert!(health.headers().contains_key("strict-transport-security"));
+}
+
+#[tokio::test]
+async fn test_request_ids() {
+    let app = TestApp::start().await;
+
+    let failed = app
+        .http
+        .get(app.url("/me"))
+        .bearer_auth("not-a-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(failed.status(), StatusCode::UNAUTHORIZED);
+    let request_id = failed.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body: Value = failed.json().await.unwrap();
+    assert_eq!(body["error"]["request_id"], request_id);
+
+    // IDs from a proxy in front of the API are kept
+    let forwarded = app
+        .http
+        .get(format!("{}/health", app.url))
+        .header("X-Request-Id", "lb-4f5a6b7c")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forwarded.headers()["x-request-id"], "lb-4f5a6b7c");
+
+    let replaced = app
+        .http
+        .get(format!("{}/health", app.url))
+        .header("X-Request-Id", "not <safe> to log")
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(replaced.headers()["x-request-id"], "not <safe> to log");
+}
@@ -0,0 +1,319 @@
+//! # ForkForge Smoke Test
+//!
+//! Walks the happy path against a deployed API, for use as a post-deploy
+//! gate and a staging canary: check health, sign in with a seeded API
+//! token, create a session, snapshot it, restore the snapshot into it,
+//! then delete the snapshot and stop the session. Exits non-zero if any
+//! step fails.
+//!
+//! Whatever the run created is cleaned up even when a later step fails.
+//!
+//! ```text
+//! FORKFORGE_SMOKETEST_TOKEN=ff_... forkforge-smoketest https://api.forkforge.dev
+//! ```
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use common::{
+    API_PREFIX, ApiResponse, BatchDeleteSnapshotsRequest, BatchItemResult, CreateSessionRequest,
+    CreateSnapshotRequest, RestoreSnapshotRequest,
+};
+use domain::errors::DomainError;
+use domain::models::{ForkSession, Snapshot, User};
+use infra::OutboundConfig;
+use serde::de::DeserializeOwned;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// API token of the account the smoke test signs in as
+const TOKEN_VAR: &str = "FORKFORGE_SMOKETEST_TOKEN";
+
+/// Proxy for requests to the API, as for the CLI
+const PROXY_VAR: &str = "FORKFORGE_HTTP_PROXY";
+
+/// PEM bundle of extra root certificates, as for the CLI
+const CA_CERTS_VAR: &str = "FORKFORGE_EXTRA_CA_CERTS";
+
+/// Longest any one request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let api_url = match args.as_slice() {
+        [api_url] if !api_url.starts_with('-') => api_url.trim_end_matches('/').to_string(),
+        _ => {
+            print_help();
+            std::process::exit(2);
+        }
+    };
+    let Ok(token) = std::env::var(TOKEN_VAR) else {
+        eprintln!("Error: {TOKEN_VAR} must be set to an API token");
+        std::process::exit(2);
+    };
+
+    let outbound = OutboundConfig {
+        proxy: std::env::var(PROXY_VAR).ok(),
+        extra_ca_certs: std::env::var(CA_CERTS_VAR).ok(),
+    };
+    let client = match outbound.client_builder().and_then(|builder| {
+        builder
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("forkforge-smoketest/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| DomainError::Internal(e.to_string()))
+    }) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut smoketest = Smoketest {
+        api: Api {
+            client,
+            api_url,
+            token,
+        },
+        session: None,
+        snapshot: None,
+        failed: false,
+    };
+    smoketest.run().await;
+    smoketest.clean_up().await;
+
+    if smoketest.failed {
+        eprintln!("Smoke test failed");
+        std::process::exit(1);
+    }
+    println!("Smoke test passed");
+}
+
+fn print_help() {
+    eprintln!(
+        r#"
+forkforge-smoketest - Check a ForkForge deployment end to end
+
+USAGE:
+    {TOKEN_VAR}=<API_TOKEN> forkforge-smoketest <API_URL>
+
+Creates a session and a snapshot as the token's user, restores the
+snapshot, and cleans up after itself. Exits 1 if any step fails.
+"#
+    );
+}
+
+/// One run against a deployment, remembering what it created
+struct Smoketest {
+    api: Api,
+    /// Session created by this run and not yet stopped
+    session: Option<ForkSession>,
+    /// Snapshot created by this run and not yet deleted
+    snapshot: Option<Snapshot>,
+    failed: bool,
+}
+
+impl Smoketest {
+    /// Run the happy path up to the first failing step
+    async fn run(&mut self) {
+        let api = &self.api;
+        if step(&mut self.failed, "health", api.health())
+            .await
+            .is_none()
+        {
+            return;
+        }
+        let Some(user) = step(&mut self.failed, "sign in", api.profile()).await else {
+            return;
+        };
+        println!("  as {}", user.primary_email);
+
+        let Some(session) = step(&mut self.failed, "create session", api.create_session()).await
+        else {
+            return;
+        };
+        println!("  {}", session.slug);
+        let session_id = session.id.to_string();
+        self.session = Some(session);
+
+        let Some(snapshot) = step(
+            &mut self.failed,
+            "create snapshot",
+            api.create_snapshot(&session_id),
+        )
+        .await
+        else {
+            return;
+        };
+        let snapshot_id = snapshot.id.to_string();
+        self.snapshot = Some(snapshot);
+
+        step(
+            &mut self.failed,
+            "restore snapshot",
+            api.restore(&snapshot_id, &session_id),
+        )
+        .await;
+    }
+
+    /// Delete the snapshot and stop the session this run created, if any
+    async fn clean_up(&mut self) {
+        let api = &self.api;
+        if let Some(snapshot) = self.snapshot.take() {
+            let id = snapshot.id.to_string();
+            step(
+                &mut self.failed,
+                "delete snapshot",
+                api.delete_snapshot(&id),
+            )
+            .await;
+        }
+        if let Some(session) = self.session.take() {
+            let id = session.id.to_string();
+            step(&mut self.failed, "stop session", api.stop_session(&id)).await;
+        }
+    }
+}
+
+/// Run one step, printing how it went and how long it took
+async fn step<T>(
+    failed: &mut bool,
+    name: &str,
+    step: impl Future<Output = Result<T>>,
+) -> Option<T> {
+    let started = Instant::now();
+    let result = step.await;
+    let elapsed = started.elapsed().as_millis();
+    match result {
+        Ok(value) => {
+            println!("✓ {name} ({elapsed} ms)");
+            Some(value)
+        }
+        Err(e) => {
+            println!("✗ {name} ({elapsed} ms): {e}");
+            *failed = true;
+            None
+        }
+    }
+}
+
+/// The deployment's API, signed in with the seeded token
+struct Api {
+    client: reqwest::Client,
+    api_url: String,
+    token: String,
+}
+
+impl Api {
+    /// URL of a path of the API version this build speaks
+    fn url(&self, path: &str) -> String {
+        format!("{}{API_PREFIX}{path}", self.api_url)
+    }
+
+    async fn health(&self) -> Result<()> {
+        let health_url = format!("{}/health", self.api_url);
+        let response = self.client.get(health_url).send().await?;
+        if !response.status().is_success() {
+            return Err(format!("{} returned {}", response.url(), response.status()).into());
+        }
+        Ok(())
+    }
+
+    async fn profile(&self) -> Result<User> {
+        let request = self.client.get(self.url("/me")).bearer_auth(&self.token);
+        read_data(request).await
+    }
+
+    /// Create an empty session, named so leftovers are easy to spot
+    async fn create_session(&self) -> Result<ForkSession> {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let request = self
+            .client
+            .post(self.url("/sessions"))
+            .bearer_auth(&self.token)
+            .json(&CreateSessionRequest {
+                name: format!("smoketest-{started}"),
+                fork_slot: None,
+                accounts: Vec::new(),
+                programs: Vec::new(),
+                mints: Vec::new(),
+                region: None,
+            });
+        read_data(request).await
+    }
+
+    async fn create_snapshot(&self, session_id: &str) -> Result<Snapshot> {
+        let request = self
+            .client
+            .post(self.url(&format!("/snapshots/{session_id}")))
+            .bearer_auth(&self.token)
+            .json(&CreateSnapshotRequest {
+                name: "smoketest".to_string(),
+                description: Some("Created by forkforge-smoketest".to_string()),
+            });
+        read_data(request).await
+    }
+
+    async fn restore(&self, snapshot_id: &str, session_id: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(self.url(&format!("/snapshots/{snapshot_id}/restore")))
+            .bearer_auth(&self.token)
+            .json(&RestoreSnapshotRequest {
+                session: Some(session_id.to_string()),
+            });
+        let session: ForkSession = read_data(request).await?;
+        if session.id.to_string() != session_id {
+            return Err(format!("restored into {} instead of {session_id}", session.id).into());
+        }
+        Ok(())
+    }
+
+    async fn delete_snapshot(&self, snapshot_id: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(self.url("/snapshots:batchDelete"))
+            .bearer_auth(&self.token)
+            .json(&BatchDeleteSnapshotsRequest {
+                ids: vec![snapshot_id.to_string()],
+                filter: None,
+            });
+        let results: Vec<BatchItemResult> = read_data(request).await?;
+        match results.into_iter().find(|result| !result.ok) {
+            Some(failed) => Err(format!(
+                "{} not deleted: {}",
+                failed.id,
+                failed.error.map(|e| e.message).unwrap_or_default()
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    async fn stop_session(&self, session_id: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(self.url(&format!("/sessions/{session_id}/stop")))
+            .bearer_auth(&self.token);
+        let _: ForkSession = read_data(request).await?;
+        Ok(())
+    }
+}
+
+/// Send a request and unwrap the data of a successful response
+async fn read_data<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T> {
+    let response = request.send().await?;
+    let status = response.status();
+    let url = response.url().clone();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("{url} returned {status}: {body}").into());
+    }
+    let response: ApiResponse<T> = serde_json::from_str(&body)
+        .map_err(|e| format!("Unexpected response from {url}: {e}\nBody: {body}"))?;
+    Ok(response.data)
+}
@@ -6,30 +6,74 @@
 //! ## Architecture
 //!
 //! The CLI communicates with the ForkForge API server for authentication and
-//! session management. It uses the infra crate's `HttpClient` for OAuth
-//! operations and API communication.
+//! session management. It uses the infra crate's `ClientInfra`: its
+//! `HttpClient` for OAuth operations and its `ForkForgeApiClient` for API
+//! communication.
 //!
 //! ## Commands
 //!
-//! - `login`: Authenticate via GitHub OAuth device flow
-//! - `up`: Launch a forked Solana validator (coming soon)
+//! - `login`: Authenticate via GitHub OAuth device flow; `--resume` picks up
+//!   an interrupted login, and `--sso` signs in with the server's OpenID
+//!   Connect provider instead
+//! - `logout`: Remove stored credentials
+//! - `whoami`: Show the logged-in account and its profile settings
+//! - `upgrade`: Compare plans, limits and prices
+//! - `create`: Create a session with the accounts in `forkforge.toml`
+//! - `ls`: List your fork sessions
+//! - `export`: Write every session to a file as newline-delimited JSON
+//! - `snapshot create|list|delete|restore`: Manage session snapshots, with
+//!   `--json` output for scripts
+//! - `up`: Launch a forked Solana validator; `--local` runs it without an
+//!   account, never contacting the API
+//! - `ps`: List validators started by `up`
+//! - `down`: Stop a validator started by `up`
+//! - `<name>`: Any other command runs the `forkforge-<name>` plugin on PATH
 
 use clap::{Parser, Subcommand};
-use common::{CheckUserAuthorisedResponse, DeviceCodeResponse, PollAuthorizationRequest};
+use colored::*;
+use common::{
+    ApiTokenRequest, ApiTokenResponse, AuthorizationStatus, AuthorizationStatusResponse, BuildInfo,
+    CheckUserAuthorisedResponse, DeviceCodeResponse,
+};
 use domain::services::auth::types::GitHubUser;
 use domain::services::http_service::HttpService;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
 
 mod client_config;
+mod credentials;
+mod errors;
+mod export;
 mod github;
-mod infrastructure;
+mod pending_login;
+mod pipeline;
+mod plugins;
+mod profile;
+mod project;
+mod runtime;
+mod sessions;
+mod snapshots;
+mod sso;
+mod upgrade;
+mod usage;
+mod validator;
 
-use client_config::ClientConfig;
-use infrastructure::http_client::HttpClient;
+use client_config::{ClientConfig, ClientContext};
+use errors::CliError;
+use pipeline::{Auth, Requirements};
+use project::ProjectConfig;
+use usage::UsageReporter;
 
 /// ForkForge CLI - Fast Solana mainnet forking for local development
 #[derive(Parser)]
-#[command(name="forkforge", version, about, long_about = None)]
+#[command(name="forkforge", about, long_about = None, disable_version_flag = true)]
 struct Cli {
+    /// Print version
+    #[arg(short = 'V', long)]
+    version: bool,
+    /// With --version, also print the git commit and build time
+    #[arg(long, requires = "version")]
+    verbose: bool,
     /// Command to execute
     #[command(subcommand)]
     command: Option<Commands>,
@@ -39,28 +83,272 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Authenticate with GitHub to access ForkForge services
-    Login,
+    Login {
+        /// Continue an interrupted login with its code, if still valid
+        #[arg(long, conflicts_with = "sso")]
+        resume: bool,
+        /// Sign in with the identity provider the server is configured
+        /// with, in the browser, instead of GitHub
+        #[arg(long)]
+        sso: bool,
+    },
+    /// Remove the API token saved by `login`
+    Logout,
+    /// Show the account you're logged in as (needs an account)
+    Whoami,
     /// Launch a forked Solana validator with configured accounts
-    Up,
+    Up {
+        /// Account or program to clone from the fork source (repeatable)
+        #[arg(long = "clone", value_name = "PUBKEY")]
+        clone_accounts: Vec<String>,
+        /// Report how long each startup stage took
+        #[arg(long)]
+        profile_startup: bool,
+        /// ForkForge session (ID, slug or ID prefix) to report usage for while the validator runs
+        #[arg(long, value_name = "SESSION")]
+        session: Option<String>,
+        /// RPC endpoint to fork from, instead of FORKFORGE_FORK_RPC_URL
+        #[arg(long, value_name = "URL")]
+        rpc_url: Option<String>,
+        /// Run without a ForkForge account, never contacting the API
+        #[arg(long, conflicts_with = "session")]
+        local: bool,
+    },
+    /// List validators started by `up` and the ports they listen on
+    Ps,
+    /// Stop a validator started by `up`, marking its session stopped
+    Down {
+        /// Session shown by `ps`
+        session: String,
+    },
+    /// Create a session with the accounts listed in forkforge.toml (needs an account)
+    Create {
+        /// Session name; a slug like brave-otter-42 is generated when omitted
+        name: Option<String>,
+        /// Region to run the session in; your preferred region when omitted
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// List your fork sessions (needs an account)
+    Ls {
+        /// Only show sessions with this status (pending, running, stopped, failed)
+        #[arg(long)]
+        status: Option<String>,
+        /// Maximum number of sessions to show
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Export all your sessions to a file as newline-delimited JSON (needs an account)
+    Export {
+        /// File to write
+        #[arg(long, short, default_value = "sessions.ndjson")]
+        output: std::path::PathBuf,
+        /// Only export sessions with this status (pending, running, stopped, failed)
+        #[arg(long)]
+        status: Option<String>,
+    },
+    /// Restore a snapshot; kept for scripts, see `snapshot restore`
+    #[command(hide = true)]
+    Restore {
+        /// Snapshot to restore, by ID or unique ID prefix
+        snapshot: String,
+        /// Session (ID, slug or ID prefix) to restore into instead of creating one
+        #[arg(long, value_name = "SESSION")]
+        into: Option<String>,
+    },
+    /// Manage snapshots (needs an account)
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// Compare plans, or subscribe to one in the browser
+    Upgrade {
+        /// Show prices in this currency (e.g. EUR) when available
+        #[arg(long)]
+        currency: Option<String>,
+        /// Open checkout for this plan (entry, lite or pro)
+        #[arg(long, conflicts_with = "manage")]
+        tier: Option<String>,
+        /// Open the billing portal to change plan, card or cancel
+        #[arg(long)]
+        manage: bool,
+        /// Set your billing country for tax (e.g. DE), before any checkout
+        #[arg(long, value_name = "CODE", conflicts_with = "manage")]
+        country: Option<String>,
+    },
+    /// Run an external `forkforge-<name>` plugin
+    #[command(external_subcommand)]
+    Plugin(Vec<String>),
 }
 
-async fn up(_config: ClientConfig) -> Result<(), Box<dyn std::error::Error>> {
-    todo!("Implement Up command!");
+/// `forkforge snapshot` subcommands
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// Save a snapshot of a session
+    Create {
+        /// Session (ID, slug or ID prefix) to snapshot
+        session: String,
+        /// Snapshot name
+        #[arg(long)]
+        name: String,
+        /// What the snapshot captures
+        #[arg(long)]
+        description: Option<String>,
+        /// Print the snapshot as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List a session's snapshots, newest first
+    List {
+        /// Session (ID, slug or ID prefix)
+        session: String,
+        /// Print the snapshots as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete snapshots by ID prefix, or all matching a filter
+    #[command(alias = "rm")]
+    Delete {
+        /// Snapshots to delete, by ID or unique ID prefix
+        snapshots: Vec<String>,
+        /// Also delete snapshots matching this filter, e.g. older-than:30d
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print the result for each snapshot as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Restore a snapshot into a new session, or into an existing one
+    Restore {
+        /// Snapshot to restore, by ID or unique ID prefix
+        snapshot: String,
+        /// Session (ID, slug or ID prefix) to restore into instead of creating one
+        #[arg(long, value_name = "SESSION")]
+        into: Option<String>,
+        /// Print the restored session as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Commands {
+    /// Steps the pipeline runs before this command's handler
+    fn requirements(&self) -> Requirements {
+        let (auth, api) = match self {
+            Commands::Login { .. } => (Auth::None, true),
+            Commands::Logout => (Auth::None, false),
+            Commands::Up { session, .. } if session.is_some() => (Auth::Required, true),
+            // Local mode never reads the stored token, so it can't reach the API
+            Commands::Up { local: true, .. } | Commands::Ps => (Auth::None, false),
+            // Logged-in users get usage left over from offline runs resent
+            Commands::Up { .. } => (Auth::Optional, false),
+            // Stopping the local validator works offline; the API is best effort
+            Commands::Down { .. } => (Auth::Optional, false),
+            Commands::Whoami
+            | Commands::Create { .. }
+            | Commands::Ls { .. }
+            | Commands::Export { .. }
+            | Commands::Restore { .. }
+            | Commands::Snapshot { .. } => (Auth::Required, true),
+            Commands::Upgrade {
+                tier,
+                manage,
+                country,
+                ..
+            } if tier.is_some() || *manage || country.is_some() => (Auth::Required, true),
+            Commands::Upgrade { .. } => (Auth::None, true),
+            // Plugins get the token in their handshake and may not use the API
+            Commands::Plugin(_) => (Auth::Optional, false),
+        };
+        Requirements { auth, api }
+    }
+}
+
+/// Launch a local validator forked from the configured RPC endpoint
+///
+/// Accounts listed in the project's `forkforge.toml` are cloned along with
+/// any passed on the command line. With a session ID or slug, usage
+/// heartbeats are reported for that session, and any left unsent by an
+/// earlier offline run are flushed first; without one, a logged-in user's
+/// unsent heartbeats are flushed in the background. In local mode the
+/// stored token isn't loaded and nothing is sent to the API, and what an
+/// account would add is listed instead.
+async fn up(
+    ctx: ClientContext,
+    clone_accounts: Vec<String>,
+    profile_startup: bool,
+    session: Option<String>,
+    rpc_url: Option<String>,
+    local: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if local {
+        println!(
+            "{} running without a ForkForge account; nothing is sent to the API",
+            "Local mode:".bright_yellow().bold()
+        );
+        println!(
+            "  Usage reporting, sessions, snapshots and hosted regions need an account: {}",
+            "forkforge login".bright_white()
+        );
+    }
+
+    let mut clone_accounts = clone_accounts;
+    for pubkey in ProjectConfig::load()?
+        .session_request(String::new())
+        .pubkeys()
+    {
+        if !clone_accounts.contains(&pubkey) {
+            clone_accounts.push(pubkey);
+        }
+    }
+
+    let session_name = session.clone();
+    let usage = match session {
+        Some(session) => {
+            usage::flush_pending(&ctx).await;
+            Some(UsageReporter::new(ctx.clone(), session))
+        }
+        None => {
+            if !local && ctx.config.api_token.is_some() {
+                // Not awaited, so an unreachable API never delays the validator
+                let ctx = ctx.clone();
+                tokio::spawn(async move { usage::flush_pending(&ctx).await });
+            }
+            None
+        }
+    };
+
+    let reservation = runtime::reserve(session_name.as_deref())?;
+    let name = reservation.name.clone();
+    let result = validator::run(validator::ValidatorConfig {
+        binary: ctx.config.validator_binary,
+        fork_rpc_url: rpc_url.unwrap_or(ctx.config.fork_rpc_url),
+        runtime: reservation,
+        clone_accounts,
+        profile_startup,
+        usage,
+    })
+    .await;
+
+    if let Err(e) = runtime::release(&name) {
+        tracing::warn!(error = %e, "Failed to remove validator from the runtime registry");
+    }
+    result
 }
 
 /// Retrieve device code from GitHub through our API
 async fn get_device_code(
-    config: &ClientConfig,
+    ctx: &ClientContext,
 ) -> Result<DeviceCodeResponse, Box<dyn std::error::Error>> {
-    let device_code_url = format!("{}/auth/github/device-code", config.api_base_url);
+    let api = &ctx.infra().api;
+    let device_code_url = api.url("/auth/github/device-code");
 
-    let device_response = config
-        .http_client
-        .post(&device_code_url)
+    let device_response = api
+        .post("/auth/github/device-code")
         .json(&serde_json::json!({}))
         .send()
         .await
-        .map_err(|e| format!("Failed to get device code from {device_code_url}: {e}"))?;
+        .map_err(|e| CliError::request_failed("Starting login", &device_code_url, &e))?;
 
     let status = device_response.status();
     let body = device_response
@@ -69,7 +357,7 @@ async fn get_device_code(
         .map_err(|e| format!("Failed to read device code response: {e}"))?;
 
     if !status.is_success() {
-        return Err(format!("Device code API error ({status}): {body}").into());
+        return Err(CliError::api("Starting login", status, &body).into());
     }
 
     let device_auth_data: DeviceCodeResponse = serde_json::from_str(&body)
@@ -78,95 +366,325 @@ async fn get_device_code(
     Ok(device_auth_data)
 }
 
-/// Poll for user authorization with GitHub
+/// Poll the API until the user authorizes the device with GitHub
+///
+/// Checks every `interval` seconds, as GitHub asked when the device code
+/// was issued.
 async fn poll_for_authorization(
-    config: &ClientConfig,
-    device_code: String,
+    ctx: &ClientContext,
+    device_auth_data: &DeviceCodeResponse,
 ) -> Result<CheckUserAuthorisedResponse, Box<dyn std::error::Error>> {
-    let poll_url = format!("{}/auth/github/wait-for-authorization", config.api_base_url);
-    let poll_response = config
-        .long_poll_client
-        .post(&poll_url)
-        .json(&PollAuthorizationRequest { device_code })
+    let api = &ctx.infra().api;
+    let status_url = api.url("/auth/github/status");
+    let interval = Duration::from_secs(u64::from(device_auth_data._interval.max(1)));
+
+    loop {
+        let request = api
+            .get("/auth/github/status")
+            .query(&[("device_code", &device_auth_data.device_code)]);
+        let status_response = api.send_idempotent(request).await.map_err(|e| {
+            CliError::request_failed("Waiting for GitHub authorization", &status_url, &e)
+        })?;
+
+        let status = status_response.status();
+        let body = status_response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read response body: {e}"))?;
+
+        if !status.is_success() {
+            return Err(CliError::api("Waiting for GitHub authorization", status, &body).into());
+        }
+
+        let auth_status: AuthorizationStatusResponse = serde_json::from_str(&body)
+            .map_err(|e| format!("Failed to parse auth status JSON: {e}\nBody: {body}"))?;
+
+        match auth_status.status {
+            AuthorizationStatus::Pending => tokio::time::sleep(interval).await,
+            AuthorizationStatus::Complete => {
+                return auth_status
+                    .token
+                    .ok_or_else(|| "Authorization completed without a token".into());
+            }
+            AuthorizationStatus::Denied => {
+                return Err(CliError::new("GitHub login was denied")
+                    .fix("Run `forkforge login` again and approve the request")
+                    .into());
+            }
+            AuthorizationStatus::Expired => {
+                return Err(CliError::new("GitHub login timed out")
+                    .cause("The code wasn't entered before it expired")
+                    .fix("Run `forkforge login` again")
+                    .into());
+            }
+        }
+    }
+}
+
+/// Exchange GitHub tokens for a ForkForge API token
+async fn request_api_token(
+    ctx: &ClientContext,
+    auth_response: CheckUserAuthorisedResponse,
+) -> Result<ApiTokenResponse, Box<dyn std::error::Error>> {
+    let token_url = format!("{}/auth/token", ctx.config.api_url());
+    let token_response = ctx
+        .http_client()
+        .post(&token_url)
+        .json(&ApiTokenRequest {
+            access_token: auth_response.access_token,
+            refresh_token: auth_response.refresh_token,
+            expires_in: auth_response.expires_in,
+            refresh_token_expires_in: auth_response.refresh_token_expires_in,
+        })
         .send()
         .await
-        .map_err(|e| format!("Failed to poll authorization at {poll_url}: {e}"))?;
+        .map_err(|e| CliError::request_failed("Requesting an API token", &token_url, &e))?;
 
-    let status = poll_response.status();
-    let body = poll_response
+    let status = token_response.status();
+    let body = token_response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response body: {e}"))?;
+        .map_err(|e| format!("Failed to read API token response: {e}"))?;
 
     if !status.is_success() {
-        return Err(format!("API error ({status}): {body}").into());
+        return Err(CliError::api("Requesting an API token", status, &body).into());
     }
 
-    let auth_response: CheckUserAuthorisedResponse = serde_json::from_str(&body)
-        .map_err(|e| format!("Failed to parse auth response JSON: {e}\nBody: {body}"))?;
+    let api_token: ApiTokenResponse = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse API token JSON: {e}\nBody: {body}"))?;
 
-    Ok(auth_response)
+    Ok(api_token)
 }
 
 /// Handle the GitHub OAuth login flow
 ///
 /// Implements the complete GitHub device flow authentication:
-/// 1. Request device code from API server
+/// 1. Request device code from API server, or with `resume`, reuse the one
+///    an interrupted login left behind
 /// 2. Display verification URL and code to user
 /// 3. Poll for authorization completion
 /// 4. Retrieve user information
+/// 5. Exchange the GitHub token for a ForkForge API token
+/// 6. Store the API token for later commands
 ///
 /// Uses the infra crate's HttpClient for HTTP operations,
 /// demonstrating proper use of dependency injection.
-async fn handle_login(config: ClientConfig) -> Result<(), Box<dyn std::error::Error>> {
-    // Create domain services with dependency injection
-    let http_adapter = HttpClient::with_default_client();
-    let api_service = HttpService::new(config.api_base_url.clone(), http_adapter);
+async fn handle_login(ctx: ClientContext, resume: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Create domain services with dependency injection, sharing the
+    // context's connection pool
+    let http_adapter = ctx.infra().http.clone();
+    let api_service = HttpService::new(ctx.config.api_url(), http_adapter);
 
     // Step 1: Get device and user verification codes
-    let device_auth_data = get_device_code(&config).await?;
+    let device_auth_data = if resume {
+        pending_login::load()?.ok_or_else(|| {
+            CliError::new("No login to resume")
+                .cause("No login was interrupted, or its code has expired")
+                .fix("forkforge login")
+        })?
+    } else {
+        let device_auth_data = get_device_code(&ctx).await?;
+        // Not fatal: only `login --resume` needs it
+        if let Err(e) = pending_login::save(&device_auth_data) {
+            tracing::warn!(error = %e, "Failed to remember the pending login");
+        }
+        device_auth_data
+    };
 
     // Step 2: Prompt user to verify
     github::prompt_user_to_verify(&device_auth_data).await;
 
     // Step 3: Poll for user authorization
-    let auth_response = poll_for_authorization(&config, device_auth_data.device_code).await?;
+    let auth_response = poll_for_authorization(&ctx, &device_auth_data).await?;
+    // The code can't be used again; a failed login keeps it until it
+    // expires, in case the API was only unreachable
+    if let Err(e) = pending_login::clear() {
+        tracing::warn!(error = %e, "Failed to forget the pending login");
+    }
 
     // Step 4: Get user info using domain service
     let user: GitHubUser = github::get_user_info(&auth_response.access_token, &api_service).await?;
 
-    // Step 5: Write or update the user's entry in the database.
-    // TODO: Later, add a new endpoint to securley generate an API token for the user.
-    // We will link this with the TUI (or website) later so that the user can manage their keys.
-
     // TODO: Replace this with something more fancy like loading bars or something.
     println!(
         "Logging in to user {}... who has ID {}",
         user.login, user.id
     );
 
+    // Step 5: Get a ForkForge API token (creates the account on first login)
+    let api_token = request_api_token(&ctx, auth_response).await?;
+
+    // Step 6: Store the token so later commands are authenticated
+    let store = credentials::save(&api_token.token)?;
+    println!("Logged in. API token saved to {store}.");
+
+    Ok(())
+}
+
+/// Remove stored credentials
+fn handle_logout() -> Result<(), Box<dyn std::error::Error>> {
+    if credentials::clear()? {
+        println!("Logged out.");
+    } else {
+        println!("Not logged in.");
+    }
+
     Ok(())
 }
 
+/// `forkforge --version [--verbose]`
+fn print_version(verbose: bool) {
+    let version = env!("CARGO_PKG_VERSION");
+    println!("forkforge {version}");
+    if verbose {
+        let build = BuildInfo::current();
+        println!("commit: {}", build.short_sha());
+        println!("built:  {}", build.built_at);
+    }
+}
+
+/// Send diagnostics to stderr so they never mix with command output
+///
+/// Only warnings are shown by default; set `RUST_LOG=debug` to see more.
+fn init_logging() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+}
+
 /// CLI entry point
 ///
 /// Parses command-line arguments and routes to appropriate command handlers.
-/// Loads configuration from environment variables (no config file access for
-/// security reasons - CLI doesn't have access to server secrets).
+/// Failures are rendered with a probable cause and fix before exiting
+/// non-zero.
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
+    init_logging();
     let cli: Cli = Cli::parse();
-    let config = ClientConfig::load()?;
+    if cli.version {
+        print_version(cli.verbose);
+        return;
+    }
+
+    if let Err(e) = run(cli).await {
+        errors::render(e.as_ref());
+        std::process::exit(1);
+    }
+}
+
+/// Run the parsed command
+///
+/// Loads configuration from environment variables (no config file access for
+/// security reasons - CLI doesn't have access to server secrets).
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = ClientContext::new(ClientConfig::load()?);
+    let Some(command) = cli.command else {
+        panic!("Incorrect Command!");
+    };
+    let ctx = pipeline::prepare(ctx, command.requirements()).await?;
 
-    match cli.command {
-        Some(Commands::Up) => {
-            up(config).await?;
+    match command {
+        Commands::Up {
+            clone_accounts,
+            profile_startup,
+            session,
+            rpc_url,
+            local,
+        } => {
+            up(
+                ctx,
+                clone_accounts,
+                profile_startup,
+                session,
+                rpc_url,
+                local,
+            )
+            .await?;
+        }
+        Commands::Ps => {
+            runtime::list()?;
+        }
+        Commands::Down { session } => {
+            let stopped = runtime::stop(&session).await?;
+            if stopped.is_forkforge_session
+                && let Err(e) = sessions::stop(&ctx, &stopped.name).await
+            {
+                tracing::warn!(error = %e, "Validator stopped, but the session wasn't marked stopped");
+            }
+        }
+        Commands::Login { sso: true, .. } => {
+            let api_token = sso::login(&ctx).await?;
+            let store = credentials::save(&api_token.token)?;
+            println!("Logged in. API token saved to {store}.");
+        }
+        Commands::Login { resume, .. } => {
+            handle_login(ctx, resume).await?;
+        }
+        Commands::Logout => {
+            handle_logout()?;
+        }
+        Commands::Whoami => {
+            profile::whoami(&ctx).await?;
+        }
+        Commands::Create { name, region } => {
+            sessions::create(&ctx, name, region).await?;
+        }
+        Commands::Ls { status, limit } => {
+            sessions::list(&ctx, status.as_deref(), limit).await?;
+        }
+        Commands::Export { output, status } => {
+            export::sessions(&ctx, status.as_deref(), &output).await?;
+        }
+        Commands::Restore { snapshot, into } => {
+            snapshots::restore(&ctx, &snapshot, into, false).await?;
         }
-        Some(Commands::Login) => {
-            handle_login(config).await?;
+        Commands::Snapshot { command } => match command {
+            SnapshotCommands::Create {
+                session,
+                name,
+                description,
+                json,
+            } => snapshots::create(&ctx, &session, name, description, json).await?,
+            SnapshotCommands::List { session, json } => {
+                snapshots::list(&ctx, &session, json).await?;
+            }
+            SnapshotCommands::Delete {
+                snapshots,
+                filter,
+                json,
+            } => snapshots::remove(&ctx, snapshots, filter, json).await?,
+            SnapshotCommands::Restore {
+                snapshot,
+                into,
+                json,
+            } => snapshots::restore(&ctx, &snapshot, into, json).await?,
+        },
+        Commands::Upgrade {
+            currency,
+            tier,
+            manage,
+            country,
+        } => {
+            if let Some(country) = country {
+                upgrade::set_country(&ctx, &country).await?;
+                if tier.is_none() {
+                    return Ok(());
+                }
+            }
+            if manage {
+                upgrade::open_portal(&ctx).await?;
+            } else if let Some(tier) = tier {
+                upgrade::open_checkout(&ctx, &tier).await?;
+            } else {
+                upgrade::run(&ctx, currency.as_deref()).await?;
+            }
         }
-        _ => {
-            panic!("Incorrect Command!");
+        Commands::Plugin(args) => {
+            plugins::run_plugin(&ctx, &args)?;
         }
     }
 
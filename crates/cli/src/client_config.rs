@@ -1,4 +1,8 @@
+use common::{API_PREFIX, BuildInfo};
+use infra::{ClientInfra, OutboundConfig};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 /// Minimal configuration for the CLI client - contains NO secrets
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -9,11 +13,26 @@ pub struct ClientConfig {
     #[serde(default = "default_api_timeout_seconds")]
     pub api_timeout_seconds: u64,
 
-    #[serde(skip)]
-    pub http_client: reqwest::Client,
+    /// RPC endpoint the local validator forks from
+    #[serde(default = "default_fork_rpc_url")]
+    pub fork_rpc_url: String,
 
-    #[serde(skip)]
-    pub long_poll_client: reqwest::Client,
+    /// Validator binary used by `up`
+    #[serde(default = "default_validator_binary")]
+    pub validator_binary: String,
+
+    /// ForkForge API token for authenticated requests
+    #[serde(default)]
+    pub api_token: Option<String>,
+
+    /// Proxy for requests to the API and GitHub; `HTTPS_PROXY` and
+    /// `HTTP_PROXY` are used when unset
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+
+    /// PEM bundle of root certificates trusted on top of the system's
+    #[serde(default)]
+    pub extra_ca_certs: Option<String>,
 }
 
 fn default_api_base_url() -> String {
@@ -24,16 +43,24 @@ fn default_api_timeout_seconds() -> u64 {
     30
 }
 
+fn default_fork_rpc_url() -> String {
+    "https://api.mainnet-beta.solana.com".to_string()
+}
+
+fn default_validator_binary() -> String {
+    "solana-test-validator".to_string()
+}
+
 impl Default for ClientConfig {
     fn default() -> Self {
         Self {
             api_base_url: default_api_base_url(),
             api_timeout_seconds: default_api_timeout_seconds(),
-            http_client: reqwest::Client::new(),
-            long_poll_client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(900))
-                .build()
-                .expect("Failed to build long poll client"),
+            fork_rpc_url: default_fork_rpc_url(),
+            validator_binary: default_validator_binary(),
+            api_token: None,
+            http_proxy: None,
+            extra_ca_certs: None,
         }
     }
 }
@@ -48,16 +75,120 @@ impl ClientConfig {
             config.api_base_url = url;
         }
 
+        if let Ok(url) = std::env::var("FORKFORGE_FORK_RPC_URL") {
+            config.fork_rpc_url = url;
+        }
+
+        if let Ok(binary) = std::env::var("FORKFORGE_VALIDATOR_BINARY") {
+            config.validator_binary = binary;
+        }
+
+        if let Ok(token) = std::env::var("FORKFORGE_API_TOKEN") {
+            config.api_token = Some(token);
+        }
+
+        if let Ok(proxy) = std::env::var("FORKFORGE_HTTP_PROXY") {
+            config.http_proxy = Some(proxy);
+        }
+
+        if let Ok(path) = std::env::var("FORKFORGE_EXTRA_CA_CERTS") {
+            config.extra_ca_certs = Some(path);
+        }
+
         if let Ok(timeout) = std::env::var("FORKFORGE_API_TIMEOUT_SECONDS") {
             if let Ok(seconds) = timeout.parse::<u64>() {
                 config.api_timeout_seconds = seconds;
-                // Rebuild clients with new timeout
-                config.http_client = reqwest::Client::builder()
-                    .timeout(std::time::Duration::from_secs(seconds))
-                    .build()?;
             }
         }
 
         Ok(config)
     }
+
+    /// Fall back to the token saved by `login` when `FORKFORGE_API_TOKEN`
+    /// isn't set
+    pub fn with_stored_credentials(mut self) -> Self {
+        if self.api_token.is_none() {
+            self.api_token = crate::credentials::load();
+        }
+        self
+    }
+
+    /// Proxy and root certificates for requests to remote services
+    pub fn outbound(&self) -> OutboundConfig {
+        OutboundConfig {
+            proxy: self.http_proxy.clone(),
+            extra_ca_certs: self.extra_ca_certs.clone(),
+        }
+    }
+
+    /// Base URL of the API version this CLI speaks, e.g.
+    /// `http://localhost:3000/v1`
+    pub fn api_url(&self) -> String {
+        format!("{}{API_PREFIX}", self.api_base_url)
+    }
+}
+
+/// Configuration plus the infrastructure built from it, shared by every command
+///
+/// Clients are built on first use and shared by clones, so commands that
+/// make several requests reuse pooled connections instead of paying for a
+/// TLS handshake each time.
+#[derive(Clone)]
+pub struct ClientContext {
+    pub config: ClientConfig,
+    clients: Arc<Clients>,
+}
+
+#[derive(Default)]
+struct Clients {
+    infra: OnceLock<ClientInfra>,
+}
+
+/// Sent with API requests, including usage heartbeats, so the server can
+/// tell which build made them, e.g. `forkforge/0.1.0 (3f2a9c1d4e5b)`
+fn user_agent() -> String {
+    format!(
+        "forkforge/{}",
+        BuildInfo::current().describe(env!("CARGO_PKG_VERSION"))
+    )
+}
+
+impl ClientContext {
+    pub fn new(config: ClientConfig) -> Self {
+        Self {
+            config,
+            clients: Arc::default(),
+        }
+    }
+
+    /// Client-safe infrastructure, including the authenticated API client
+    pub fn infra(&self) -> &ClientInfra {
+        self.clients.infra.get_or_init(|| {
+            ClientInfra::new(
+                &self.config.api_url(),
+                self.config.api_token.clone(),
+                Duration::from_secs(self.config.api_timeout_seconds),
+                &user_agent(),
+                &self.config.outbound(),
+            )
+            .expect("Failed to build HTTP client")
+        })
+    }
+
+    /// Client for API requests, with the configured timeout
+    pub fn http_client(&self) -> &reqwest::Client {
+        self.infra().api.http_client()
+    }
+
+    /// Fall back to the token saved by `login` when `FORKFORGE_API_TOKEN`
+    /// isn't set
+    pub fn with_stored_credentials(mut self) -> Self {
+        let config = self.config.clone().with_stored_credentials();
+        if config.api_token != self.config.api_token {
+            // The API client carries the token, so it has to be rebuilt
+            self.clients = Arc::default();
+        }
+        self.config = config;
+        self
+    }
 }
@@ -0,0 +1,145 @@
+//! # Credential Storage
+//!
+//! Persists the ForkForge API token between runs so `login` only has to be
+//! done once. The token goes into the OS keychain when one is available,
+//! falling back to `~/.config/forkforge/credentials.toml` (readable only by
+//! the owner) on headless machines without a keychain service.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Keychain service name the token is stored under
+const KEYRING_SERVICE: &str = "forkforge";
+
+/// Keychain account name the token is stored under
+const KEYRING_USER: &str = "api-token";
+
+/// Where a token ended up after `save`
+pub enum CredentialStore {
+    Keychain,
+    File(PathBuf),
+}
+
+impl fmt::Display for CredentialStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialStore::Keychain => write!(f, "the system keychain"),
+            CredentialStore::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// On-disk format of the fallback credentials file
+#[derive(Default, Deserialize, Serialize)]
+struct CredentialsFile {
+    api_token: Option<String>,
+}
+
+fn keyring_entry() -> Option<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).ok()
+}
+
+/// Path of the fallback credentials file
+fn credentials_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(home
+        .join(".config")
+        .join("forkforge")
+        .join("credentials.toml"))
+}
+
+fn read_file() -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let path = credentials_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let file: CredentialsFile = toml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+    Ok(file.api_token)
+}
+
+fn write_file(token: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = credentials_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let contents = toml::to_string(&CredentialsFile {
+        api_token: Some(token.to_string()),
+    })?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&path)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Load the stored API token, if any
+///
+/// Storage errors are treated as "not logged in" rather than failing the
+/// command; the server will reject the request with a clear message.
+pub fn load() -> Option<String> {
+    if let Some(token) = keyring_entry().and_then(|entry| entry.get_password().ok()) {
+        return Some(token);
+    }
+
+    read_file().ok().flatten()
+}
+
+/// Store the API token, preferring the OS keychain
+pub fn save(token: &str) -> Result<CredentialStore, Box<dyn std::error::Error>> {
+    if let Some(entry) = keyring_entry()
+        && entry.set_password(token).is_ok()
+    {
+        // Don't leave an older token behind in the fallback file
+        if let Ok(path) = credentials_path()
+            && path.exists()
+        {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(CredentialStore::Keychain);
+    }
+
+    Ok(CredentialStore::File(write_file(token)?))
+}
+
+/// Remove stored credentials from both the keychain and the fallback file
+///
+/// Returns whether anything was removed.
+pub fn clear() -> Result<bool, Box<dyn std::error::Error>> {
+    let mut removed = false;
+
+    if let Some(entry) = keyring_entry() {
+        match entry.delete_credential() {
+            Ok(()) => removed = true,
+            Err(keyring::Error::NoEntry) => {}
+            // No usable keychain on this machine, so nothing was stored there
+            Err(keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)) => {}
+            Err(e) => return Err(format!("Failed to remove token from keychain: {e}").into()),
+        }
+    }
+
+    let path = credentials_path()?;
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+        removed = true;
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,158 @@
+//! # Error Rendering
+//!
+//! Failures are shown as a short block saying what happened, the probable
+//! cause and how to fix it, rather than a raw status code and response body:
+//!
+//! ```text
+//! error: Listing sessions failed: Unauthorized: Invalid API token
+//!   cause: Your API token is missing, expired or revoked
+//!   fix:   forkforge login
+//!   request ID: 0192f0c4-7a3e-7b1c-9d2e-4f5a6b7c8d9e
+//! ```
+//!
+//! API failures are mapped from the stable `code` in the server's error
+//! envelope, and show the server's request ID for bug reports; local
+//! failures build a [`CliError`] directly.
+
+use colored::*;
+use common::{ApiErrorResponse, BuildInfo};
+use std::fmt;
+
+/// A failure with enough context for the user to act on it
+#[derive(Debug)]
+pub struct CliError {
+    what: String,
+    cause: Option<String>,
+    fix: Option<String>,
+    request_id: Option<String>,
+}
+
+impl CliError {
+    pub fn new(what: impl Into<String>) -> Self {
+        Self {
+            what: what.into(),
+            cause: None,
+            fix: None,
+            request_id: None,
+        }
+    }
+
+    /// Why this probably happened
+    pub fn cause(mut self, cause: impl Into<String>) -> Self {
+        self.cause = Some(cause.into());
+        self
+    }
+
+    /// What to run or do about it
+    pub fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    /// No API token in the environment or credential store
+    pub fn not_logged_in() -> Self {
+        Self::new("Not logged in")
+            .cause("No API token in FORKFORGE_API_TOKEN or the credential store")
+            .fix("forkforge login")
+    }
+
+    /// A request that never got a response
+    pub fn request_failed(action: &str, url: &str, err: &reqwest::Error) -> Self {
+        let error = Self::new(format!("{action} failed"));
+        if err.is_timeout() {
+            error
+                .cause(format!("{url} did not respond in time"))
+                .fix("Retry, or raise FORKFORGE_API_TIMEOUT_SECONDS")
+        } else if err.is_connect() {
+            error
+                .cause(format!("Could not connect to {url}"))
+                .fix("Check your connection, or point FORKFORGE_API_BASE_URL at the right server")
+        } else {
+            error.cause(err.to_string())
+        }
+    }
+
+    /// A non-success response from the ForkForge API
+    pub fn api(action: &str, status: reqwest::StatusCode, body: &str) -> Self {
+        let Ok(ApiErrorResponse { error }) = serde_json::from_str(body) else {
+            let error = Self::new(format!("{action} failed ({status})"));
+            return if body.trim().is_empty() {
+                error
+            } else {
+                error.cause(body.trim())
+            };
+        };
+
+        let what = Self {
+            request_id: error.request_id,
+            ..Self::new(format!("{action} failed: {}", error.message))
+        };
+        match error.code.as_str() {
+            "unauthorized" => what
+                .cause("Your API token is missing, expired or revoked")
+                .fix("forkforge login"),
+            "account_suspended" => what
+                .cause("Your account has been suspended")
+                .fix("Contact ForkForge support to restore access"),
+            "forbidden" => what
+                .cause("Your account isn't allowed to do this on this server")
+                .fix("Ask the server's administrator for access"),
+            "quota_exceeded" => what
+                .cause("Your plan's limit has been reached")
+                .fix("forkforge upgrade"),
+            "rate_limited" => what
+                .cause("Too many requests in a short time")
+                .fix("Wait a minute, then try again"),
+            "not_found" => what
+                .cause("It may have been deleted or belong to another account")
+                .fix("forkforge ls"),
+            "invalid_input" | "bad_request" => what
+                .cause("The server rejected the request's arguments")
+                .fix("forkforge help"),
+            "authorization_timeout" => what
+                .cause("The GitHub code expired before it was approved")
+                .fix("forkforge login"),
+            "authorization_denied" => what
+                .cause("Access was denied on GitHub")
+                .fix("forkforge login"),
+            "external_service_error" => what
+                .cause("A service ForkForge depends on (GitHub, Stripe or the RPC) is unavailable")
+                .fix("Try again in a few minutes"),
+            "internal_error" => what
+                .cause("The ForkForge server hit an unexpected error")
+                .fix(format!(
+                    "Try again; if it keeps failing, rerun with RUST_LOG=debug and report it \
+                     along with the request ID and your version, forkforge {}",
+                    BuildInfo::current().describe(env!("CARGO_PKG_VERSION"))
+                )),
+            _ => what,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.what)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Print an error to stderr, with cause and fix when known
+pub fn render(err: &(dyn std::error::Error + 'static)) {
+    let Some(err) = err.downcast_ref::<CliError>() else {
+        eprintln!("{} {err}", "error:".bright_red().bold());
+        return;
+    };
+
+    eprintln!("{} {}", "error:".bright_red().bold(), err.what);
+    if let Some(cause) = &err.cause {
+        eprintln!("  {} {cause}", "cause:".yellow());
+    }
+    if let Some(fix) = &err.fix {
+        eprintln!("  {}   {}", "fix:".green(), fix.bright_white());
+    }
+    if let Some(request_id) = &err.request_id {
+        eprintln!("  {} {request_id}", "request ID:".dimmed());
+    }
+}
@@ -0,0 +1,182 @@
+//! # Session Export
+//!
+//! `forkforge export` downloads every session as newline-delimited JSON,
+//! one session per line, into a file. The server streams the export and it
+//! is written to disk as it arrives, so it works for any number of
+//! sessions. A running count is shown on the terminal while it downloads.
+
+use colored::*;
+use infra::retry::send_idempotent;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+
+/// Export the user's sessions, optionally only those in `status`, to `output`
+pub async fn sessions(
+    ctx: &ClientContext,
+    status: Option<&str>,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let sessions_url = format!("{}/sessions", ctx.config.api_url());
+    let mut query = vec![("format", "ndjson")];
+    if let Some(status) = status {
+        query.push(("status", status));
+    }
+
+    let request = ctx
+        .http_client()
+        .get(&sessions_url)
+        .bearer_auth(api_token)
+        .query(&query);
+    let mut response = send_idempotent(request)
+        .await
+        .map_err(|e| CliError::request_failed("Exporting sessions", &sessions_url, &e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::api("Exporting sessions", status, &body).into());
+    }
+
+    let cant_write = |e: std::io::Error| {
+        CliError::new(format!("Can't write {}", output.display())).cause(e.to_string())
+    };
+    let mut file = BufWriter::new(File::create(output).map_err(cant_write)?);
+    let show_progress = std::io::stderr().is_terminal();
+    let mut rows = 0;
+
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                // Don't leave a truncated export looking like a complete one
+                drop(file);
+                let _ = std::fs::remove_file(output);
+                return Err(CliError::new("Export interrupted")
+                    .cause(e.to_string())
+                    .fix("Run the export again")
+                    .into());
+            }
+        };
+        file.write_all(&chunk).map_err(cant_write)?;
+
+        rows += chunk.iter().filter(|&&byte| byte == b'\n').count();
+        if show_progress {
+            eprint!("\r  {rows} sessions");
+        }
+    }
+    file.flush().map_err(cant_write)?;
+    if show_progress {
+        eprintln!();
+    }
+
+    println!(
+        "{} Exported {rows} sessions to {}",
+        "✓".bright_green(),
+        output.display().to_string().bright_white()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_config::ClientConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    /// Serve one request with `response`, sending back the request head
+    async fn serve_once(response: &'static str) -> (ClientContext, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (head_tx, head_rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0; 1024];
+            while !head.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                head.extend_from_slice(&buf[..n]);
+            }
+            let _ = head_tx.send(String::from_utf8_lossy(&head).into_owned());
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let config = ClientConfig {
+            api_base_url: format!("http://{addr}"),
+            api_token: Some("ff_test".to_string()),
+            ..ClientConfig::default()
+        };
+        (ClientContext::new(config), head_rx)
+    }
+
+    fn output_path(test: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("forkforge-{test}-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_ndjson() {
+        let (ctx, head) = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\n\
+             Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+             b\r\n{\"id\":\"a\"}\n\r\nb\r\n{\"id\":\"b\"}\n\r\n0\r\n\r\n",
+        )
+        .await;
+        let output = output_path("export");
+
+        sessions(&ctx, Some("running"), &output).await.unwrap();
+
+        let head = head.await.unwrap();
+        assert!(head.contains("?format=ndjson&status=running "));
+        assert!(
+            head.to_ascii_lowercase()
+                .contains("authorization: bearer ff_test")
+        );
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "{\"id\":\"a\"}\n{\"id\":\"b\"}\n"
+        );
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_error_leaves_no_file() {
+        let (ctx, _head) =
+            serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await;
+        let output = output_path("export-error");
+
+        assert!(sessions(&ctx, None, &output).await.is_err());
+        assert!(!output.exists());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_export_is_deleted() {
+        // The connection closes before the final chunk
+        let (ctx, _head) = serve_once(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+             b\r\n{\"id\":\"a\"}\n\r\n",
+        )
+        .await;
+        let output = output_path("export-interrupted");
+
+        let err = sessions(&ctx, None, &output).await.unwrap_err();
+        assert_eq!(err.to_string(), "Export interrupted");
+        assert!(!output.exists());
+    }
+}
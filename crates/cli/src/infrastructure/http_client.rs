@@ -1,2 +0,0 @@
-// Re-export from infra crate
-pub use infra::HttpClient;
@@ -1 +0,0 @@
-pub mod http_client;
@@ -0,0 +1,102 @@
+//! # Pending Login
+//!
+//! Remembers the device code of a login in progress in
+//! `~/.config/forkforge/pending_login.json` (readable only by the owner),
+//! so `forkforge login --resume` can pick it up after the CLI was
+//! interrupted instead of starting over with a new code. Removed once the
+//! login finishes, whatever the outcome.
+
+use common::DeviceCodeResponse;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// On-disk format of the pending login file
+#[derive(Deserialize, Serialize)]
+struct PendingLogin {
+    device: DeviceCodeResponse,
+    /// Unix time the device code expires at
+    expires_at: u64,
+}
+
+/// Path of the pending login file
+fn pending_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(home
+        .join(".config")
+        .join("forkforge")
+        .join("pending_login.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Remember a login that was just started
+pub fn save(device: &DeviceCodeResponse) -> Result<(), Box<dyn std::error::Error>> {
+    let path = pending_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let contents = serde_json::to_string_pretty(&PendingLogin {
+        device: device.clone(),
+        expires_at: now_secs() + u64::from(device._expires_in),
+    })?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&path)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())?;
+
+    Ok(())
+}
+
+/// The login left pending by an earlier run, if its code is still valid
+///
+/// Its `expires_in` is the time left. An expired login is removed.
+pub fn load() -> Result<Option<DeviceCodeResponse>, Box<dyn std::error::Error>> {
+    let path = pending_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let pending: PendingLogin = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+    let Some(left) = pending
+        .expires_at
+        .checked_sub(now_secs())
+        .filter(|&left| left > 0)
+    else {
+        clear()?;
+        return Ok(None);
+    };
+    Ok(Some(DeviceCodeResponse {
+        _expires_in: u32::try_from(left).unwrap_or(u32::MAX),
+        ..pending.device
+    }))
+}
+
+/// Forget the pending login, if any
+pub fn clear() -> Result<(), Box<dyn std::error::Error>> {
+    let path = pending_path()?;
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
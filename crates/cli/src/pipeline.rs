@@ -0,0 +1,99 @@
+//! # Command Pipeline
+//!
+//! Cross-cutting steps run before any command handler, so handlers don't
+//! each re-implement them:
+//!
+//! 1. Load the stored API token for commands that use it, failing early
+//!    with a login hint when a command needs one and there isn't any
+//! 2. Check that the server still supports this CLI version
+//!
+//! API tokens are long-lived and the server has no notice feed yet, so
+//! there is no token refresh or notice step.
+
+use common::{ApiResponse, CliVersionResponse};
+use infra::retry::send_idempotent;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+
+/// Whether a command uses the stored API token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auth {
+    /// Never sends the token
+    None,
+    /// Sends the token when there is one
+    Optional,
+    /// Fails without a token
+    Required,
+}
+
+/// What a command needs before its handler runs
+#[derive(Debug, Clone, Copy)]
+pub struct Requirements {
+    pub auth: Auth,
+    /// Talks to the ForkForge API, so the server must support this version
+    pub api: bool,
+}
+
+/// Run the pre-command steps, returning the context the handler should use
+pub async fn prepare(
+    ctx: ClientContext,
+    requirements: Requirements,
+) -> Result<ClientContext, Box<dyn std::error::Error>> {
+    let ctx = match requirements.auth {
+        Auth::None => ctx,
+        Auth::Optional => ctx.with_stored_credentials(),
+        Auth::Required => {
+            let ctx = ctx.with_stored_credentials();
+            if ctx.config.api_token.is_none() {
+                return Err(CliError::not_logged_in().into());
+            }
+            ctx
+        }
+    };
+
+    if requirements.api {
+        check_version(&ctx).await?;
+    }
+
+    Ok(ctx)
+}
+
+/// Fail if the server no longer supports this CLI version
+///
+/// Any problem reaching the server is left for the command itself to
+/// report, so this check never hides the real error.
+async fn check_version(ctx: &ClientContext) -> Result<(), Box<dyn std::error::Error>> {
+    let version_url = format!("{}/cli/version", ctx.config.api_url());
+    let min_version = match send_idempotent(ctx.http_client().get(&version_url)).await {
+        Ok(response) if response.status().is_success() => response
+            .json::<ApiResponse<CliVersionResponse>>()
+            .await
+            .ok()
+            .and_then(|response| response.data.min_version),
+        Ok(response) => {
+            tracing::debug!(status = %response.status(), "Skipping CLI version check");
+            None
+        }
+        Err(e) => {
+            tracing::debug!(error = %e, "Skipping CLI version check");
+            None
+        }
+    };
+
+    let version = env!("CARGO_PKG_VERSION");
+    if let Some(min_version) = min_version
+        && common::cli::is_older_than(version, &min_version)
+    {
+        return Err(
+            CliError::new(format!("forkforge {version} is no longer supported"))
+                .cause(format!(
+                    "The server requires version {min_version} or newer"
+                ))
+                .fix("Update forkforge to the latest release")
+                .into(),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! # CLI Plugins
+//!
+//! Unknown subcommands are forwarded to `forkforge-<name>` executables found on
+//! `PATH` (e.g. `forkforge anchor` runs `forkforge-anchor`), so third parties can
+//! extend the CLI without forking it.
+//!
+//! ## Context Handshake
+//!
+//! Before the plugin runs, the CLI writes a single JSON line to its stdin
+//! describing the current context (API base URL, API token, and the
+//! validators started by `up` with their ports). Plugins that don't need it
+//! can simply ignore stdin.
+//!
+//! `session` is the validator a plugin should act on: the one named by
+//! `FORKFORGE_SESSION`, else the only one running. It is `null` when that
+//! is ambiguous, and plugins can pick from `sessions` themselves.
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::client_config::ClientContext;
+use crate::runtime::{self, RunningSession};
+
+/// Version of the JSON context handshake sent to plugins
+const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Context handed to plugins over stdin
+#[derive(Serialize)]
+struct PluginContext<'a> {
+    protocol_version: u32,
+    cli_version: &'a str,
+    api_base_url: &'a str,
+    api_token: Option<&'a str>,
+    session: Option<PluginSession>,
+    sessions: Vec<PluginSession>,
+}
+
+/// A validator started by `up`, as plugins see it
+#[derive(Debug, Clone, Serialize)]
+struct PluginSession {
+    /// ForkForge session ID or slug, or `local-<n>`
+    name: String,
+    is_forkforge_session: bool,
+    rpc_url: String,
+    ws_url: String,
+    rpc_port: u16,
+}
+
+impl From<&RunningSession> for PluginSession {
+    fn from(session: &RunningSession) -> Self {
+        Self {
+            name: session.name.clone(),
+            is_forkforge_session: session.is_forkforge_session,
+            rpc_url: format!("http://127.0.0.1:{}", session.ports.rpc),
+            ws_url: format!("ws://127.0.0.1:{}", session.ports.ws),
+            rpc_port: session.ports.rpc,
+        }
+    }
+}
+
+/// The session a plugin should act on, if there's no doubt which
+fn active_session(sessions: &[PluginSession], selected: Option<&str>) -> Option<PluginSession> {
+    match selected {
+        Some(name) => sessions.iter().find(|s| s.name == name).cloned(),
+        None if sessions.len() == 1 => sessions.first().cloned(),
+        None => None,
+    }
+}
+
+/// Whether `name` can only name a file in a PATH directory, rather than
+/// reach outside it
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// Find a `forkforge-<name>` executable on PATH
+fn find_plugin(name: &str) -> Option<PathBuf> {
+    if !is_valid_name(name) {
+        return None;
+    }
+    let binary = format!("forkforge-{name}{}", std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&binary))
+        .find(|path| path.is_file())
+}
+
+/// Run an external plugin with the remaining arguments
+///
+/// Exits the process with the plugin's status code if it fails, so scripts
+/// see the same exit code they would calling the plugin directly.
+pub fn run_plugin(ctx: &ClientContext, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (name, plugin_args) = args.split_first().ok_or("No command given")?;
+    if !is_valid_name(name) {
+        return Err(format!("Unknown command '{name}'").into());
+    }
+    let path = find_plugin(name)
+        .ok_or_else(|| format!("Unknown command '{name}' (no forkforge-{name} plugin on PATH)"))?;
+
+    tracing::debug!(plugin = %path.display(), "Running plugin");
+    let mut child = Command::new(path)
+        .args(plugin_args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    // A broken registry shouldn't stop plugins that don't use sessions
+    let sessions: Vec<PluginSession> = runtime::running()
+        .inspect_err(|e| tracing::warn!(error = %e, "Failed to read running validators"))
+        .unwrap_or_default()
+        .iter()
+        .map(PluginSession::from)
+        .collect();
+    let selected = std::env::var("FORKFORGE_SESSION").ok();
+    let context = serde_json::to_string(&PluginContext {
+        protocol_version: PLUGIN_PROTOCOL_VERSION,
+        cli_version: env!("CARGO_PKG_VERSION"),
+        api_base_url: &ctx.config.api_base_url,
+        api_token: ctx.config.api_token.as_deref(),
+        session: active_session(&sessions, selected.as_deref()),
+        sessions,
+    })?;
+
+    // Plugins may exit without reading the handshake, so a closed pipe is fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{context}");
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, rpc_port: u16) -> PluginSession {
+        PluginSession {
+            name: name.to_string(),
+            is_forkforge_session: false,
+            rpc_url: format!("http://127.0.0.1:{rpc_port}"),
+            ws_url: format!("ws://127.0.0.1:{}", rpc_port + 1),
+            rpc_port,
+        }
+    }
+
+    #[test]
+    fn test_plugin_names_stay_on_path() {
+        assert!(is_valid_name("anchor"));
+        assert!(is_valid_name("anchor-v2"));
+        for name in ["", "../bin/sh", "a/b", "a\\b", "..", "x..y"] {
+            assert!(!is_valid_name(name), "{name:?}");
+            assert!(find_plugin(name).is_none());
+        }
+    }
+
+    #[test]
+    fn test_active_session() {
+        let sessions = vec![session("local-0", 8899), session("brave-otter-42", 8909)];
+        assert_eq!(
+            active_session(&sessions, Some("brave-otter-42")).map(|s| s.rpc_port),
+            Some(8909)
+        );
+        assert!(active_session(&sessions, Some("local-7")).is_none());
+        // With several running, plugins have to be told which
+        assert!(active_session(&sessions, None).is_none());
+        assert_eq!(
+            active_session(&sessions[..1], None).map(|s| s.name),
+            Some("local-0".to_string())
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! # Profile Commands
+//!
+//! `forkforge whoami` shows the account the CLI is logged in as, with the
+//! profile settings managed through `PATCH /me`.
+
+use colored::*;
+use common::ApiResponse;
+use domain::models::User;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+
+/// Show the logged-in account
+pub async fn whoami(ctx: &ClientContext) -> Result<(), Box<dyn std::error::Error>> {
+    let api = &ctx.infra().api;
+    let me_url = api.url("/me");
+    let response = api
+        .send_idempotent(api.get("/me"))
+        .await
+        .map_err(|e| CliError::request_failed("Fetching your account", &me_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read account response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Fetching your account", status, &body).into());
+    }
+
+    let user = serde_json::from_str::<ApiResponse<User>>(&body)
+        .map_err(|e| format!("Failed to parse account JSON: {e}\nBody: {body}"))?
+        .data;
+
+    match &user.display_name {
+        Some(name) => println!("{} <{}>", name.bright_white().bold(), user.primary_email),
+        None => println!("{}", user.primary_email.bright_white().bold()),
+    }
+    let unset = || "not set".dimmed().to_string();
+    println!(
+        "  {:<16} {}",
+        "Contact email",
+        user.contact_email.clone().unwrap_or_else(unset)
+    );
+    println!(
+        "  {:<16} {}",
+        "Region",
+        user.preferred_region.clone().unwrap_or_else(unset)
+    );
+    if let Some(github_id) = user.github_user_id {
+        println!("  {:<16} {github_id}", "GitHub ID");
+    }
+    println!("  {:<16} {}", "Status", user.status.as_str());
+    println!(
+        "  {:<16} {}",
+        "Member since",
+        user.created_at.format("%Y-%m-%d")
+    );
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! # Project Configuration
+//!
+//! A `forkforge.toml` in the project directory, or any directory above it,
+//! lists what forks of the project need from mainnet:
+//!
+//! ```toml
+//! accounts = ["9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"]
+//! programs = ["whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"]
+//! mints = ["EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"]
+//! ```
+//!
+//! `up` clones everything listed into the local validator, and `create`
+//! sends the lists with the session request so the server captures them.
+
+use common::CreateSessionRequest;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::errors::CliError;
+
+/// Name of the project configuration file
+pub const PROJECT_FILE: &str = "forkforge.toml";
+
+/// Accounts, programs and token mints to clone into a project's forks
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub programs: Vec<String>,
+    #[serde(default)]
+    pub mints: Vec<String>,
+}
+
+impl ProjectConfig {
+    /// Load the nearest `forkforge.toml`, or an empty config if there is none
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let cwd = std::env::current_dir()?;
+        match find_project_file(&cwd) {
+            Some(path) => Ok(Self::from_file(&path)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, CliError> {
+        let invalid = || CliError::new(format!("Invalid {}", path.display()));
+
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid().cause(e.to_string()))?;
+        let config: Self = toml::from_str(&contents).map_err(|e| {
+            invalid()
+                .cause(e.message().to_string())
+                .fix("List pubkeys under `accounts`, `programs` or `mints`")
+        })?;
+
+        for (field, pubkeys) in [
+            ("accounts", &config.accounts),
+            ("programs", &config.programs),
+            ("mints", &config.mints),
+        ] {
+            if let Some(pubkey) = pubkeys.iter().find(|pubkey| !is_pubkey(pubkey)) {
+                return Err(invalid()
+                    .cause(format!(
+                        "`{pubkey}` in `{field}` is not a base58 public key"
+                    ))
+                    .fix("Fix or remove the entry"));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Session request that captures everything listed
+    pub fn session_request(&self, name: String) -> CreateSessionRequest {
+        CreateSessionRequest {
+            name,
+            fork_slot: None,
+            accounts: self.accounts.clone(),
+            programs: self.programs.clone(),
+            mints: self.mints.clone(),
+            region: None,
+        }
+    }
+}
+
+/// Search `dir` and its ancestors for the project file
+fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Whether `value` decodes to a 32-byte Solana public key
+fn is_pubkey(value: &str) -> bool {
+    bs58::decode(value)
+        .into_vec()
+        .is_ok_and(|bytes| bytes.len() == 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty scratch directory unique to one test
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("forkforge-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_is_pubkey() {
+        assert!(is_pubkey("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"));
+        assert!(is_pubkey("11111111111111111111111111111111"));
+        // Not base58
+        assert!(!is_pubkey("0WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"));
+        // Decodes, but not to 32 bytes
+        assert!(!is_pubkey("9WzDXwBbmkg8ZTbNMqUxvQ"));
+        assert!(!is_pubkey(""));
+    }
+
+    #[test]
+    fn test_from_file_rejects_invalid_pubkeys() {
+        let dir = scratch_dir("project-config");
+        let path = dir.join(PROJECT_FILE);
+
+        std::fs::write(
+            &path,
+            "accounts = [\"9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM\"]\n\
+             mints = [\"not-a-mint\"]\n",
+        )
+        .unwrap();
+        let err = ProjectConfig::from_file(&path).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid {}", path.display()));
+        assert!(format!("{err:?}").contains("`not-a-mint` in `mints` is not a base58 public key"));
+
+        std::fs::write(
+            &path,
+            "programs = [\"whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc\"]\n",
+        )
+        .unwrap();
+        let config = ProjectConfig::from_file(&path).unwrap();
+        assert_eq!(config.programs.len(), 1);
+        assert!(config.accounts.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_project_file_searches_ancestors() {
+        let dir = scratch_dir("project-search");
+        let nested = dir.join("programs").join("amm");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(
+            find_project_file(&nested).filter(|path| path.starts_with(&dir)),
+            None
+        );
+
+        std::fs::write(dir.join(PROJECT_FILE), "").unwrap();
+        assert_eq!(find_project_file(&nested), Some(dir.join(PROJECT_FILE)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
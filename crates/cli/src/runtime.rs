@@ -0,0 +1,423 @@
+//! # Running Validators
+//!
+//! Every `up` registers its validator in `~/.config/forkforge/runtime.json`
+//! with its own RPC, WebSocket and faucet ports, so several sessions can run
+//! side by side. `forkforge ps` lists them and `forkforge down <session>`
+//! stops one and deletes its ledger.
+//!
+//! The registry is only read and written while holding an exclusive lock on
+//! `runtime.lock`, so concurrent `up`s never pick the same ports. Entries
+//! whose processes have died, e.g. after a crash, are dropped whenever the
+//! registry is read.
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::CliError;
+
+/// RPC port of the first validator, `solana-test-validator`'s default
+const FIRST_RPC_PORT: u16 = 8899;
+
+/// Faucet port of the first validator, `solana-test-validator`'s default
+const FIRST_FAUCET_PORT: u16 = 9900;
+
+/// Gap between the ports of consecutive validators
+const PORT_STRIDE: u16 = 10;
+
+/// Most validators that can run at once
+const MAX_RUNNING: u16 = 20;
+
+/// How long `down` lets a validator shut down before killing it
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Ports one validator listens on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ports {
+    pub rpc: u16,
+    /// Always `rpc + 1`; `solana-test-validator` doesn't let it be chosen
+    pub ws: u16,
+    pub faucet: u16,
+}
+
+impl Ports {
+    fn for_slot(slot: u16) -> Self {
+        let rpc = FIRST_RPC_PORT + slot * PORT_STRIDE;
+        Self {
+            rpc,
+            ws: rpc + 1,
+            faucet: FIRST_FAUCET_PORT + slot * PORT_STRIDE,
+        }
+    }
+
+    /// Whether nothing else on this machine is listening on these ports
+    fn are_free(&self) -> bool {
+        [self.rpc, self.ws, self.faucet]
+            .iter()
+            .all(|&port| TcpListener::bind(("127.0.0.1", port)).is_ok())
+    }
+}
+
+/// A validator started by `up`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningSession {
+    /// Session passed to `up --session`, or `local-<n>` without one
+    pub name: String,
+    /// Whether `name` is a ForkForge session rather than a local-only run
+    #[serde(default)]
+    pub is_forkforge_session: bool,
+    /// PID of the `forkforge up` managing the validator
+    pub pid: u32,
+    /// PID of the validator, once it has been launched
+    pub validator_pid: Option<u32>,
+    pub ports: Ports,
+    pub ledger_dir: PathBuf,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+}
+
+impl RunningSession {
+    fn is_alive(&self) -> bool {
+        process_is_alive(self.pid) || self.validator_pid.is_some_and(process_is_alive)
+    }
+}
+
+/// Directory holding the registry and its lock
+fn runtime_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(home.join(".config").join("forkforge"))
+}
+
+/// Run `change` on the registry while holding its lock, then save it
+fn with_registry<T>(
+    change: impl FnOnce(&mut Vec<RunningSession>) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let dir = runtime_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let lock_path = dir.join("runtime.lock");
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {e}", lock_path.display()))?;
+    // Released when `lock` is dropped
+    File::lock(&lock).map_err(|e| format!("Failed to lock {}: {e}", lock_path.display()))?;
+
+    let path = dir.join("runtime.json");
+    let mut sessions: Vec<RunningSession> = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read {}: {e}", path.display()).into()),
+    };
+    sessions.retain(RunningSession::is_alive);
+
+    let result = change(&mut sessions)?;
+    std::fs::write(&path, serde_json::to_string_pretty(&sessions)?)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(result)
+}
+
+/// Claim free ports and a ledger directory for a new validator
+///
+/// The entry belongs to this process until `release` is called.
+pub fn reserve(session: Option<&str>) -> Result<RunningSession, Box<dyn std::error::Error>> {
+    with_registry(|sessions| {
+        let entry = next_entry(sessions, session, Ports::are_free)?;
+        sessions.push(entry.clone());
+        Ok(entry)
+    })
+}
+
+/// Entry for a new validator in the first slot that isn't registered and
+/// whose ports `is_free` accepts
+fn next_entry(
+    sessions: &[RunningSession],
+    session: Option<&str>,
+    is_free: impl Fn(&Ports) -> bool,
+) -> Result<RunningSession, CliError> {
+    if let Some(running) = session.and_then(|name| sessions.iter().find(|s| s.name == name)) {
+        return Err(
+            CliError::new(format!("{} is already running", running.name))
+                .cause(format!("Its RPC is on port {}", running.ports.rpc))
+                .fix(format!("forkforge down {}", running.name)),
+        );
+    }
+
+    let slot = (0..MAX_RUNNING)
+        .find(|&slot| {
+            let ports = Ports::for_slot(slot);
+            !sessions.iter().any(|s| s.ports == ports) && is_free(&ports)
+        })
+        .ok_or_else(|| {
+            CliError::new("No free validator ports")
+                .cause(format!(
+                    "{} validators are running, or other processes hold their ports",
+                    sessions.len()
+                ))
+                .fix("Stop one with `forkforge down <session>`; `forkforge ps` lists them")
+        })?;
+
+    Ok(RunningSession {
+        name: session
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("local-{slot}")),
+        is_forkforge_session: session.is_some(),
+        pid: std::process::id(),
+        validator_pid: None,
+        ports: Ports::for_slot(slot),
+        ledger_dir: std::env::temp_dir()
+            .join("forkforge-ledger")
+            .join(slot.to_string()),
+        started_at: unix_now(),
+    })
+}
+
+/// Record the PID of the validator launched for `name`
+pub fn set_validator_pid(name: &str, pid: u32) -> Result<(), Box<dyn std::error::Error>> {
+    with_registry(|sessions| {
+        if let Some(entry) = sessions.iter_mut().find(|s| s.name == name) {
+            entry.validator_pid = Some(pid);
+        }
+        Ok(())
+    })
+}
+
+/// Remove `name` from the registry once its validator has stopped
+pub fn release(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    with_registry(|sessions| {
+        sessions.retain(|s| s.name != name);
+        Ok(())
+    })
+}
+
+/// Validators currently running, oldest first
+pub fn running() -> Result<Vec<RunningSession>, Box<dyn std::error::Error>> {
+    with_registry(|sessions| Ok(sessions.clone()))
+}
+
+/// `forkforge ps`: list running validators
+pub fn list() -> Result<(), Box<dyn std::error::Error>> {
+    let sessions = running()?;
+    if sessions.is_empty() {
+        println!("No validators running.");
+        return Ok(());
+    }
+
+    let now = unix_now();
+    println!(
+        "{:<24} {:>8} {:>6} {:>6} {:>6} {:>9}",
+        "SESSION".bold(),
+        "PID".bold(),
+        "RPC".bold(),
+        "WS".bold(),
+        "FAUCET".bold(),
+        "UPTIME".bold()
+    );
+    for session in sessions {
+        println!(
+            "{:<24} {:>8} {:>6} {:>6} {:>6} {:>9}",
+            session.name,
+            session.validator_pid.unwrap_or(session.pid),
+            session.ports.rpc,
+            session.ports.ws,
+            session.ports.faucet,
+            format_uptime(now.saturating_sub(session.started_at))
+        );
+    }
+    Ok(())
+}
+
+/// `forkforge down <session>`: stop a running validator and delete its ledger
+///
+/// The `up` running it is sent SIGTERM, which it handles like Ctrl-C, so it
+/// reports final usage and stops the validator. Anything still running
+/// after `STOP_GRACE_PERIOD` is killed. Returns the stopped entry.
+pub async fn stop(name: &str) -> Result<RunningSession, Box<dyn std::error::Error>> {
+    let session = with_registry(|sessions| Ok(sessions.iter().find(|s| s.name == name).cloned()))?
+        .ok_or_else(|| {
+            CliError::new(format!("{name} isn't running"))
+                .fix("forkforge ps lists running validators")
+        })?;
+
+    // A validator whose `up` crashed has nobody to stop it, so ask it directly
+    match session.validator_pid {
+        Some(pid) if !process_is_alive(session.pid) => terminate(pid),
+        _ => terminate(session.pid),
+    }
+    let deadline = tokio::time::Instant::now() + STOP_GRACE_PERIOD;
+    while session.is_alive() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    for pid in [Some(session.pid), session.validator_pid]
+        .into_iter()
+        .flatten()
+        .filter(|&pid| process_is_alive(pid))
+    {
+        tracing::warn!(pid, "Didn't stop within the grace period, killing it");
+        kill(pid);
+    }
+
+    release(name)?;
+    match std::fs::remove_dir_all(&session.ledger_dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!(
+            error = %e,
+            path = %session.ledger_dir.display(),
+            "Failed to delete ledger"
+        ),
+    }
+    println!("{} Stopped {}", "✓".bright_green(), name.bright_white());
+    Ok(session)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn format_uptime(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        _ => format!("{}h{}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Run a process-management command, ignoring its output
+fn run_quietly(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_alive(pid: u32) -> bool {
+    std::path::Path::new(&format!("/proc/{pid}")).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_is_alive(pid: u32) -> bool {
+    run_quietly("kill", &["-0", &pid.to_string()])
+}
+
+#[cfg(windows)]
+fn process_is_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+/// Ask a process to shut down
+#[cfg(unix)]
+fn terminate(pid: u32) {
+    run_quietly("kill", &["-TERM", &pid.to_string()]);
+}
+
+/// Windows has no SIGTERM to send another console, so the tree is ended
+#[cfg(windows)]
+fn terminate(pid: u32) {
+    run_quietly("taskkill", &["/PID", &pid.to_string(), "/T", "/F"]);
+}
+
+#[cfg(unix)]
+fn kill(pid: u32) {
+    run_quietly("kill", &["-KILL", &pid.to_string()]);
+}
+
+#[cfg(windows)]
+fn kill(pid: u32) {
+    run_quietly("taskkill", &["/PID", &pid.to_string(), "/F"]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserved(sessions: &mut Vec<RunningSession>, session: Option<&str>) -> RunningSession {
+        let entry = next_entry(sessions, session, |_| true).unwrap();
+        sessions.push(entry.clone());
+        entry
+    }
+
+    #[test]
+    fn test_ports_for_slot() {
+        assert_eq!(
+            Ports::for_slot(0),
+            Ports {
+                rpc: 8899,
+                ws: 8900,
+                faucet: 9900
+            }
+        );
+        assert_eq!(
+            Ports::for_slot(3),
+            Ports {
+                rpc: 8929,
+                ws: 8930,
+                faucet: 9930
+            }
+        );
+
+        // Neighbouring slots never share a port
+        let last = Ports::for_slot(MAX_RUNNING - 1);
+        assert!(Ports::for_slot(1).rpc > Ports::for_slot(0).ws);
+        assert!(last.ws < FIRST_FAUCET_PORT);
+    }
+
+    #[test]
+    fn test_next_entry_skips_taken_slots() {
+        let mut sessions = Vec::new();
+        let first = reserved(&mut sessions, None);
+        let second = reserved(&mut sessions, Some("alpha"));
+        assert_eq!(
+            (first.name.as_str(), first.ports),
+            ("local-0", Ports::for_slot(0))
+        );
+        assert_eq!(second.ports, Ports::for_slot(1));
+        assert!(second.is_forkforge_session);
+        assert_ne!(first.ledger_dir, second.ledger_dir);
+
+        // A slot freed by `down` is reused
+        sessions.remove(0);
+        assert_eq!(reserved(&mut sessions, None).ports, Ports::for_slot(0));
+
+        // Ports held by another process are skipped
+        let entry = next_entry(&sessions, None, |ports| ports.rpc != Ports::for_slot(2).rpc);
+        assert_eq!(entry.unwrap().ports, Ports::for_slot(3));
+
+        let none_free = next_entry(&sessions, None, |_| false).unwrap_err();
+        assert!(none_free.to_string().contains("No free validator ports"));
+    }
+
+    #[test]
+    fn test_next_entry_rejects_running_session_name() {
+        let mut sessions = Vec::new();
+        reserved(&mut sessions, Some("alpha"));
+
+        let err = next_entry(&sessions, Some("alpha"), |_| true).unwrap_err();
+        assert!(err.to_string().contains("alpha is already running"));
+        assert!(next_entry(&sessions, Some("beta"), |_| true).is_ok());
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(0), "0s");
+        assert_eq!(format_uptime(59), "59s");
+        assert_eq!(format_uptime(60), "1m");
+        assert_eq!(format_uptime(3599), "59m");
+        assert_eq!(format_uptime(3600), "1h0m");
+        assert_eq!(format_uptime(5 * 3600 + 7 * 60 + 30), "5h7m");
+    }
+}
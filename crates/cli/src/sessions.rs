@@ -0,0 +1,196 @@
+//! # Session Commands
+//!
+//! `forkforge create` starts a session with the accounts in the project's
+//! `forkforge.toml`, and `forkforge ls` lists the user's fork sessions.
+//! `forkforge down` marks the session it stops as stopped.
+
+use colored::*;
+use common::{ApiResponse, CreateSessionRequest};
+use domain::models::{ForkSession, SessionStatus, SessionSummary};
+use infra::retry::send_idempotent;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+use crate::project::ProjectConfig;
+
+/// Fetch the first page of the user's sessions, newest first
+async fn fetch_sessions(
+    ctx: &ClientContext,
+    status: Option<&str>,
+    limit: u32,
+) -> Result<ApiResponse<Vec<SessionSummary>>, Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let sessions_url = format!("{}/sessions", ctx.config.api_url());
+    let mut query = vec![("limit", limit.to_string())];
+    if let Some(status) = status {
+        query.push(("status", status.to_string()));
+    }
+
+    let request = ctx
+        .http_client()
+        .get(&sessions_url)
+        .bearer_auth(api_token)
+        .query(&query);
+    let response = send_idempotent(request)
+        .await
+        .map_err(|e| CliError::request_failed("Listing sessions", &sessions_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read sessions response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Listing sessions", status, &body).into());
+    }
+
+    let sessions = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse sessions JSON: {e}\nBody: {body}"))?;
+
+    Ok(sessions)
+}
+
+fn colored_status(status: SessionStatus) -> ColoredString {
+    let label = format!("{:<8}", status.as_str());
+    match status {
+        SessionStatus::Running => label.green(),
+        SessionStatus::Pending => label.yellow(),
+        SessionStatus::Stopped => label.dimmed(),
+        SessionStatus::Failed => label.red(),
+    }
+}
+
+/// Print a table of the user's sessions
+pub async fn list(
+    ctx: &ClientContext,
+    status: Option<&str>,
+    limit: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ApiResponse {
+        data: sessions,
+        pagination,
+    } = fetch_sessions(ctx, status, limit).await?;
+    if sessions.is_empty() {
+        println!("No sessions.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<24} {:<24} {:<8} {:<16} {:>9}",
+        "SLUG", "NAME", "STATUS", "CREATED", "SNAPSHOTS"
+    );
+    for summary in &sessions {
+        let session = &summary.session;
+        println!(
+            "{:<24} {:<24} {} {:<16} {:>9}",
+            session.slug,
+            session.name,
+            colored_status(session.status),
+            session.created_at.format("%Y-%m-%d %H:%M"),
+            summary.snapshot_count
+        );
+    }
+    if let Some(total) = pagination.and_then(|p| p.total)
+        && total > sessions.len() as u64
+    {
+        println!(
+            "{}",
+            format!(
+                "Showing {} of {total}; raise --limit to see more",
+                sessions.len()
+            )
+            .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Create a session capturing everything listed in the project's `forkforge.toml`
+pub async fn create(
+    ctx: &ClientContext,
+    name: Option<String>,
+    region: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let request = CreateSessionRequest {
+        region,
+        ..ProjectConfig::load()?.session_request(name.unwrap_or_default())
+    };
+    let sessions_url = format!("{}/sessions", ctx.config.api_url());
+    // Not retried: a repeated create would start a second session
+    let response = ctx
+        .http_client()
+        .post(&sessions_url)
+        .bearer_auth(api_token)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed("Creating session", &sessions_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read session response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Creating session", status, &body).into());
+    }
+
+    let created: ApiResponse<ForkSession> = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse session JSON: {e}\nBody: {body}"))?;
+
+    print_ready("Created session", &created.data);
+    Ok(())
+}
+
+/// Mark a session stopped once its local validator has been shut down
+pub async fn stop(ctx: &ClientContext, session: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let stop_url = format!("{}/sessions/{session}/stop", ctx.config.api_url());
+    // Stopping is safe to repeat, so retries are fine
+    let request = ctx.http_client().post(&stop_url).bearer_auth(api_token);
+    let response = send_idempotent(request)
+        .await
+        .map_err(|e| CliError::request_failed("Stopping session", &stop_url, &e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::api("Stopping session", status, &body).into());
+    }
+    Ok(())
+}
+
+/// Confirm a session is ready and show how to run it
+pub fn print_ready(action: &str, session: &ForkSession) {
+    println!(
+        "{} {action} {}",
+        "✓".bright_green(),
+        session.slug.bright_white().bold()
+    );
+    if let Some(region) = &session.region {
+        println!("  Region: {}", region.bright_white());
+    }
+    println!(
+        "  Run it with: {}",
+        format!("forkforge up --session {}", session.slug).bright_white()
+    );
+}
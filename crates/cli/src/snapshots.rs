@@ -0,0 +1,235 @@
+//! # Snapshot Commands
+//!
+//! `forkforge snapshot` saves, lists, deletes and restores snapshots of fork
+//! sessions. Sessions can be named by ID, slug or ID prefix, and snapshots
+//! by ID or ID prefix. Deletion is one batch request: snapshots can also be
+//! selected on the server with a filter such as `older-than:30d`, and each
+//! one reports whether it was deleted.
+//!
+//! Every verb takes `--json` to print the API's data instead of a table,
+//! for scripting.
+
+use colored::*;
+use common::{
+    ApiResponse, BatchDeleteSnapshotsRequest, BatchItemResult, CreateSnapshotRequest,
+    RestoreSnapshotRequest,
+};
+use domain::models::{ForkSession, Snapshot};
+use infra::retry::send_idempotent;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+use crate::sessions::print_ready;
+
+/// Read a response body, failing with the API's error for `action`
+async fn read_data<T: DeserializeOwned>(
+    action: &str,
+    response: reqwest::Response,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("{action}: failed to read response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api(action, status, &body).into());
+    }
+
+    let response: ApiResponse<T> = serde_json::from_str(&body)
+        .map_err(|e| format!("{action}: failed to parse JSON: {e}\nBody: {body}"))?;
+    Ok(response.data)
+}
+
+fn print_json(data: &impl Serialize) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(data)?);
+    Ok(())
+}
+
+/// Enough of an ID to name it as a prefix
+///
+/// IDs start with their creation time in milliseconds, so shorter prefixes
+/// are shared by snapshots made around the same time. The 18 characters
+/// shown end with 12 random bits.
+fn short_id(snapshot: &Snapshot) -> String {
+    snapshot.id.to_string()[..18].to_string()
+}
+
+/// Save a snapshot of `session`
+pub async fn create(
+    ctx: &ClientContext,
+    session: &str,
+    name: String,
+    description: Option<String>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let snapshot_url = format!("{}/snapshots/{session}", ctx.config.api_url());
+    // Not retried: a repeated create would save a second snapshot
+    let response = ctx
+        .http_client()
+        .post(&snapshot_url)
+        .bearer_auth(api_token)
+        .json(&CreateSnapshotRequest { name, description })
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed("Creating snapshot", &snapshot_url, &e))?;
+    let snapshot: Snapshot = read_data("Creating snapshot", response).await?;
+
+    if json {
+        return print_json(&snapshot);
+    }
+
+    println!(
+        "{} Created snapshot {} ({})",
+        "✓".bright_green(),
+        snapshot.name.bright_white().bold(),
+        short_id(&snapshot)
+    );
+    println!(
+        "  Restore it with: {}",
+        format!("forkforge snapshot restore {}", short_id(&snapshot)).bright_white()
+    );
+    Ok(())
+}
+
+/// Print a table of a session's snapshots
+pub async fn list(
+    ctx: &ClientContext,
+    session: &str,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let snapshots_url = format!("{}/sessions/{session}/snapshots", ctx.config.api_url());
+    let request = ctx.http_client().get(&snapshots_url).bearer_auth(api_token);
+    let response = send_idempotent(request)
+        .await
+        .map_err(|e| CliError::request_failed("Listing snapshots", &snapshots_url, &e))?;
+    let snapshots: Vec<Snapshot> = read_data("Listing snapshots", response).await?;
+
+    if json {
+        return print_json(&snapshots);
+    }
+    if snapshots.is_empty() {
+        println!("No snapshots.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<8} {:<24} {:>12} {:<16}",
+        "ID", "NAME", "SLOT", "CREATED"
+    );
+    for snapshot in &snapshots {
+        let slot = snapshot
+            .fork_slot
+            .map_or_else(|| "latest".to_string(), |slot| slot.to_string());
+        println!(
+            "{:<8} {:<24} {:>12} {:<16}",
+            short_id(snapshot),
+            snapshot.name,
+            slot,
+            snapshot.created_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    Ok(())
+}
+
+/// Delete the given snapshots and those matching `filter`
+pub async fn remove(
+    ctx: &ClientContext,
+    ids: Vec<String>,
+    filter: Option<String>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if ids.is_empty() && filter.is_none() {
+        return Err(CliError::new("No snapshots to delete")
+            .fix("forkforge snapshot delete <SNAPSHOT>... or --filter older-than:30d")
+            .into());
+    }
+
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let batch_url = format!("{}/snapshots:batchDelete", ctx.config.api_url());
+    let response = ctx
+        .http_client()
+        .post(&batch_url)
+        .bearer_auth(api_token)
+        .json(&BatchDeleteSnapshotsRequest { ids, filter })
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed("Deleting snapshots", &batch_url, &e))?;
+    let results: Vec<BatchItemResult> = read_data("Deleting snapshots", response).await?;
+
+    let failed = results.iter().filter(|result| !result.ok).count();
+    if json {
+        print_json(&results)?;
+    } else if results.is_empty() {
+        println!("No snapshots matched.");
+    } else {
+        for result in &results {
+            match &result.error {
+                None => println!("{} Deleted {}", "✓".bright_green(), result.id),
+                Some(error) => eprintln!("{} {}: {}", "✗".bright_red(), result.id, error.message),
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(CliError::new(format!(
+            "{failed} of {} snapshots could not be deleted",
+            results.len()
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Restore a snapshot into `session`, or into a new session when omitted
+pub async fn restore(
+    ctx: &ClientContext,
+    snapshot: &str,
+    session: Option<String>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let restore_url = format!("{}/snapshots/{snapshot}/restore", ctx.config.api_url());
+    // Not retried: a repeated restore would create a second session
+    let response = ctx
+        .http_client()
+        .post(&restore_url)
+        .bearer_auth(api_token)
+        .json(&RestoreSnapshotRequest { session })
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed("Restoring snapshot", &restore_url, &e))?;
+    let restored: ForkSession = read_data("Restoring snapshot", response).await?;
+
+    if json {
+        return print_json(&restored);
+    }
+
+    print_ready("Restored snapshot into", &restored);
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! # Single Sign-On
+//!
+//! `forkforge login --sso` signs in with the identity provider the server
+//! is configured with, using the OpenID Connect authorization code flow
+//! with PKCE. The provider redirects the browser back to a one-shot
+//! listener on `127.0.0.1`, and the code it brings is traded for a
+//! ForkForge API token through the API.
+
+use colored::*;
+use common::{ApiTokenResponse, OidcConfigResponse, OidcTokenRequest};
+use infra::oidc::{pkce_challenge, pkce_verifier};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+
+/// How long the user has to finish signing in
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Path the provider redirects back to
+const CALLBACK_PATH: &str = "/callback";
+
+/// Page shown in the browser once it's back; the terminal tells the outcome
+const RETURNED_PAGE: &str = "<!doctype html><title>ForkForge</title>\
+    <p>You can close this tab and return to the terminal.</p>";
+
+/// Query of the authorization request (RFC 6749 section 4.1.1, RFC 7636)
+#[derive(Serialize)]
+struct AuthorizationRequest<'a> {
+    response_type: &'static str,
+    client_id: &'a str,
+    redirect_uri: &'a str,
+    scope: &'a str,
+    state: &'a str,
+    code_challenge: &'a str,
+    code_challenge_method: &'static str,
+}
+
+/// Sign in through the browser, returning a new API token
+pub async fn login(ctx: &ClientContext) -> Result<ApiTokenResponse, Box<dyn std::error::Error>> {
+    let config = oidc_config(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to listen for the sign-in redirect: {e}"))?;
+    let redirect_uri = format!(
+        "http://127.0.0.1:{}{CALLBACK_PATH}",
+        listener.local_addr()?.port()
+    );
+
+    let verifier = pkce_verifier();
+    // Any unguessable value works; it ties the redirect to this sign-in
+    let state = pkce_verifier();
+    let query = serde_urlencoded::to_string(AuthorizationRequest {
+        response_type: "code",
+        client_id: &config.client_id,
+        redirect_uri: &redirect_uri,
+        scope: &config.scopes,
+        state: &state,
+        code_challenge: &pkce_challenge(&verifier),
+        code_challenge_method: "S256",
+    })?;
+    let separator = if config.authorization_endpoint.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    let authorization_url = format!("{}{separator}{query}", config.authorization_endpoint);
+
+    println!("\n{}", "Single Sign-On".bright_white().bold());
+    println!("{}", "━━━━━━━━━━━━━━".bright_cyan());
+    println!(
+        "{} {}",
+        "→".bright_yellow(),
+        authorization_url.bright_blue()
+    );
+    if let Err(e) = open::that(&authorization_url) {
+        eprintln!("{} Failed to open browser: {}", "✗".bright_red(), e);
+        println!("{}", "Please open the URL above manually.".yellow());
+    } else {
+        println!(
+            "{} {}",
+            "✓".bright_green(),
+            "Sign in in your browser; waiting for it to return...".green()
+        );
+    }
+
+    let code = tokio::time::timeout(SIGN_IN_TIMEOUT, wait_for_code(&listener, &state))
+        .await
+        .map_err(|_| {
+            CliError::new("Single sign-on timed out")
+                .cause("The browser didn't return within 5 minutes")
+                .fix("forkforge login --sso")
+        })??;
+
+    request_api_token(
+        ctx,
+        &OidcTokenRequest {
+            code,
+            code_verifier: verifier,
+            redirect_uri,
+        },
+    )
+    .await
+}
+
+/// How the server's single sign-on is set up
+async fn oidc_config(
+    ctx: &ClientContext,
+) -> Result<OidcConfigResponse, Box<dyn std::error::Error>> {
+    let api = &ctx.infra().api;
+    let config_url = api.url("/auth/oidc/config");
+    let response = api
+        .send_idempotent(api.get("/auth/oidc/config"))
+        .await
+        .map_err(|e| CliError::request_failed("Starting single sign-on", &config_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read single sign-on settings: {e}"))?;
+
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(CliError::new("Single sign-on is not available")
+            .cause("This server isn't configured for single sign-on")
+            .fix("forkforge login")
+            .into());
+    }
+    if !status.is_success() {
+        return Err(CliError::api("Starting single sign-on", status, &body).into());
+    }
+
+    Ok(serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse single sign-on settings: {e}\nBody: {body}"))?)
+}
+
+/// Answer browser requests until the provider redirects back with a code
+/// for this sign-in
+async fn wait_for_code(
+    listener: &TcpListener,
+    state: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        let mut buf = vec![0; 8192];
+        let len = socket.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..len]);
+
+        // e.g. "GET /callback?code=...&state=... HTTP/1.1"
+        let target = request.split_whitespace().nth(1).unwrap_or_default();
+        let Some(query) = target
+            .strip_prefix(CALLBACK_PATH)
+            .map(|rest| rest.trim_start_matches('?'))
+        else {
+            // Browsers also ask for /favicon.ico
+            socket
+                .write_all(
+                    b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                )
+                .await?;
+            continue;
+        };
+        let params: HashMap<String, String> = serde_urlencoded::from_str(query).unwrap_or_default();
+        if params.get("state").map(String::as_str) != Some(state) {
+            socket
+                .write_all(
+                    b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                )
+                .await?;
+            continue;
+        }
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html; charset=utf-8\r\n\
+             content-length: {}\r\nconnection: close\r\n\r\n{RETURNED_PAGE}",
+            RETURNED_PAGE.len()
+        );
+        socket.write_all(response.as_bytes()).await?;
+
+        if let Some(error) = params.get("error") {
+            let mut failure = CliError::new(format!("Single sign-on failed: {error}"));
+            if let Some(description) = params.get("error_description") {
+                failure = failure.cause(description.clone());
+            }
+            return Err(failure.fix("forkforge login --sso").into());
+        }
+        return params
+            .get("code")
+            .cloned()
+            .ok_or_else(|| "The identity provider redirected back without a code".into());
+    }
+}
+
+/// Trade the authorization code for a ForkForge API token
+async fn request_api_token(
+    ctx: &ClientContext,
+    request: &OidcTokenRequest,
+) -> Result<ApiTokenResponse, Box<dyn std::error::Error>> {
+    let api = &ctx.infra().api;
+    let token_url = api.url("/auth/oidc/token");
+    let response = api
+        .post("/auth/oidc/token")
+        .json(request)
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed("Requesting an API token", &token_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read API token response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Requesting an API token", status, &body).into());
+    }
+
+    Ok(serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse API token JSON: {e}\nBody: {body}"))?)
+}
@@ -0,0 +1,253 @@
+//! # Plan Comparison
+//!
+//! `forkforge upgrade` shows the plans served by `GET /billing/plans`, so the
+//! limits and prices shown always match what the server enforces and charges.
+//!
+//! `--tier` opens a Stripe Checkout page to subscribe, and `--manage` opens
+//! the Stripe billing portal; payment details never pass through the CLI.
+//! `--country` sets the billing country Stripe Tax charges tax for.
+
+use colored::*;
+use common::{ApiResponse, BillingRedirect, CheckoutSessionRequest, SetBillingCountryRequest};
+use domain::models::{Plan, PlanPrice, User};
+use infra::retry::send_idempotent;
+
+use crate::client_config::ClientContext;
+use crate::errors::CliError;
+
+/// Currencies Stripe bills in whole units rather than hundredths
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "bif", "clp", "djf", "gnf", "jpy", "kmf", "krw", "mga", "pyg", "rwf", "ugx", "vnd", "vuv",
+    "xaf", "xof", "xpf",
+];
+
+/// Fetch the plan catalog from the API
+async fn fetch_plans(ctx: &ClientContext) -> Result<Vec<Plan>, Box<dyn std::error::Error>> {
+    let plans_url = format!("{}/billing/plans", ctx.config.api_url());
+    let response = send_idempotent(ctx.http_client().get(&plans_url))
+        .await
+        .map_err(|e| CliError::request_failed("Fetching plans", &plans_url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read plans response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Fetching plans", status, &body).into());
+    }
+
+    let plans: ApiResponse<Vec<Plan>> = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse plans JSON: {e}\nBody: {body}"))?;
+
+    Ok(plans.data)
+}
+
+/// Render a price like `12.00 EUR / month`
+fn format_price(price: &PlanPrice) -> String {
+    let currency = price.currency.to_uppercase();
+    let amount = if ZERO_DECIMAL_CURRENCIES.contains(&price.currency.as_str()) {
+        price.unit_amount.to_string()
+    } else {
+        format!("{}.{:02}", price.unit_amount / 100, price.unit_amount % 100)
+    };
+
+    format!("{amount} {currency} / {}", price.interval)
+}
+
+/// Pick the price to show, preferring the requested currency
+fn select_price<'a>(plan: &'a Plan, currency: Option<&str>) -> Option<&'a PlanPrice> {
+    currency
+        .and_then(|currency| {
+            plan.prices
+                .iter()
+                .find(|price| price.currency.eq_ignore_ascii_case(currency))
+        })
+        .or_else(|| plan.prices.first())
+}
+
+/// Print the plan comparison table
+pub async fn run(
+    ctx: &ClientContext,
+    currency: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let plans = fetch_plans(ctx).await?;
+
+    println!("\n{}", "ForkForge Plans".bright_white().bold());
+    println!(
+        "{:<8} {:>10} {:>10} {:>10}  Price",
+        "Plan", "Sessions", "Snapshots", "Duration"
+    );
+    for plan in &plans {
+        let price = select_price(plan, currency)
+            .map(format_price)
+            .unwrap_or_else(|| "-".to_string());
+        let duration = plan
+            .limits
+            .max_session_minutes
+            .map(|minutes| match minutes % 60 {
+                0 => format!("{}h", minutes / 60),
+                _ => format!("{minutes}m"),
+            })
+            .unwrap_or_else(|| "unlimited".to_string());
+        println!(
+            "{} {:>10} {:>10} {:>10}  {}",
+            format!("{:<8}", plan.name).bright_white().bold(),
+            plan.limits.max_concurrent_sessions,
+            plan.limits.max_snapshots_per_session,
+            duration,
+            price
+        );
+    }
+
+    if let Some(currency) = currency {
+        let missing = plans.iter().any(|plan| {
+            !plan
+                .prices
+                .iter()
+                .any(|price| price.currency.eq_ignore_ascii_case(currency))
+        });
+        if missing {
+            println!(
+                "\n{}",
+                format!(
+                    "Prices are not available in {} for every plan; showing the default currency.",
+                    currency.to_uppercase()
+                )
+                .yellow()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Open a checkout page subscribing the user to `tier`
+pub async fn open_checkout(
+    ctx: &ClientContext,
+    tier: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let url = request_redirect(
+        ctx,
+        "Starting checkout",
+        "checkout-session",
+        Some(&CheckoutSessionRequest {
+            tier: tier.to_string(),
+        }),
+    )
+    .await?;
+    open_in_browser(
+        &url,
+        "Complete checkout in your browser; your plan updates once payment succeeds.",
+    );
+    Ok(())
+}
+
+/// Open the billing portal for the user's account
+pub async fn open_portal(ctx: &ClientContext) -> Result<(), Box<dyn std::error::Error>> {
+    let url = request_redirect(ctx, "Opening the billing portal", "portal", None).await?;
+    open_in_browser(
+        &url,
+        "Manage your plan, payment method and invoices in your browser.",
+    );
+    Ok(())
+}
+
+/// Set the billing country used to work out tax
+pub async fn set_country(
+    ctx: &ClientContext,
+    country: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let url = format!("{}/billing/country", ctx.config.api_url());
+    let request =
+        ctx.http_client()
+            .put(&url)
+            .bearer_auth(api_token)
+            .json(&SetBillingCountryRequest {
+                country: country.to_string(),
+            });
+    let response = send_idempotent(request)
+        .await
+        .map_err(|e| CliError::request_failed("Setting billing country", &url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read billing response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api("Setting billing country", status, &body).into());
+    }
+
+    let user: ApiResponse<User> = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse billing JSON: {e}\nBody: {body}"))?;
+    println!(
+        "{} Billing country set to {}",
+        "✓".bright_green(),
+        user.data
+            .billing_country
+            .unwrap_or_default()
+            .bright_white()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Ask the API for a Stripe page URL
+///
+/// Not retried: each request creates a new Stripe session.
+async fn request_redirect(
+    ctx: &ClientContext,
+    action: &str,
+    endpoint: &str,
+    body: Option<&CheckoutSessionRequest>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let api_token = ctx
+        .config
+        .api_token
+        .as_deref()
+        .ok_or_else(CliError::not_logged_in)?;
+
+    let url = format!("{}/billing/{endpoint}", ctx.config.api_url());
+    let mut request = ctx.http_client().post(&url).bearer_auth(api_token);
+    if let Some(body) = body {
+        request = request.json(body);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| CliError::request_failed(action, &url, &e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read billing response: {e}"))?;
+
+    if !status.is_success() {
+        return Err(CliError::api(action, status, &body).into());
+    }
+
+    let redirect: ApiResponse<BillingRedirect> = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse billing JSON: {e}\nBody: {body}"))?;
+
+    Ok(redirect.data.url)
+}
+
+fn open_in_browser(url: &str, next_step: &str) {
+    println!("{} {}", "→".bright_yellow(), url.bright_white());
+    if let Err(e) = open::that(url) {
+        eprintln!("{} Failed to open browser: {}", "✗".bright_red(), e);
+        println!("{}", "Please open the URL above manually.".yellow());
+        return;
+    }
+    println!("{} {}", "✓".bright_green(), next_step.green());
+}
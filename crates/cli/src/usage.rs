@@ -0,0 +1,202 @@
+//! # Usage Heartbeats
+//!
+//! While `up --session` runs a validator, its elapsed time and peak memory
+//! are reported to `POST /sessions/{session}/usage` every minute and once more on
+//! shutdown. Heartbeats carry running totals, so only the latest one per
+//! session matters: if the API can't be reached it is kept in
+//! `~/.config/forkforge/pending_usage.json` and resent by the next `up`.
+
+use common::SessionUsageRequest;
+use infra::retry::send_idempotent;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::client_config::ClientContext;
+
+/// How often a running session reports usage
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reports usage for one session while its validator runs
+pub struct UsageReporter {
+    ctx: ClientContext,
+    /// Session ID or slug
+    session: String,
+    started: Instant,
+    peak_memory_bytes: AtomicU64,
+}
+
+/// Whether a heartbeat reached the server
+enum Delivery {
+    Sent,
+    /// The server rejected it; resending won't help
+    Rejected,
+    /// The server couldn't be reached; worth resending later
+    Unreachable,
+}
+
+impl UsageReporter {
+    pub fn new(ctx: ClientContext, session: String) -> Self {
+        Self {
+            ctx,
+            session,
+            started: Instant::now(),
+            peak_memory_bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Send a heartbeat every minute for the validator process `pid`
+    pub async fn run(&self, pid: Option<u32>) {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        // The first tick completes immediately; nothing has run yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Some(peak) = pid.and_then(peak_memory_bytes) {
+                self.peak_memory_bytes.fetch_max(peak, Ordering::Relaxed);
+            }
+            self.report().await;
+        }
+    }
+
+    /// Send the current totals, buffering them if the API is unreachable
+    pub async fn report(&self) {
+        let heartbeat = SessionUsageRequest {
+            elapsed_seconds: self.started.elapsed().as_secs(),
+            peak_memory_bytes: self.peak_memory_bytes.load(Ordering::Relaxed),
+        };
+
+        match send(&self.ctx, &self.session, &heartbeat).await {
+            Delivery::Sent | Delivery::Rejected => {
+                if let Err(e) = update_pending(|pending| {
+                    pending.remove(&self.session);
+                }) {
+                    tracing::debug!(error = %e, "Failed to update pending usage");
+                }
+            }
+            Delivery::Unreachable => {
+                if let Err(e) = update_pending(|pending| {
+                    pending.insert(self.session.clone(), heartbeat);
+                }) {
+                    tracing::warn!(error = %e, "Failed to buffer session usage");
+                }
+            }
+        }
+    }
+}
+
+/// Resend heartbeats buffered while the API was unreachable
+pub async fn flush_pending(ctx: &ClientContext) {
+    let pending = match read_pending() {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read pending usage");
+            return;
+        }
+    };
+
+    for (session, heartbeat) in pending {
+        match send(ctx, &session, &heartbeat).await {
+            Delivery::Sent | Delivery::Rejected => {
+                if let Err(e) = update_pending(|pending| {
+                    pending.remove(&session);
+                }) {
+                    tracing::debug!(error = %e, "Failed to update pending usage");
+                }
+            }
+            // Still offline; try again next time
+            Delivery::Unreachable => return,
+        }
+    }
+}
+
+async fn send(ctx: &ClientContext, session: &str, heartbeat: &SessionUsageRequest) -> Delivery {
+    let Some(api_token) = ctx.config.api_token.as_deref() else {
+        return Delivery::Unreachable;
+    };
+
+    let usage_url = format!("{}/sessions/{session}/usage", ctx.config.api_url());
+    let request = ctx
+        .http_client()
+        .post(&usage_url)
+        .bearer_auth(api_token)
+        .json(heartbeat);
+
+    // Heartbeats are running totals, so a retried one can't double count
+    match send_idempotent(request).await {
+        Ok(response) if response.status().is_success() => Delivery::Sent,
+        Ok(response) if response.status().is_server_error() => Delivery::Unreachable,
+        Ok(response) => {
+            tracing::warn!(%session, status = %response.status(), "Session usage rejected");
+            Delivery::Rejected
+        }
+        Err(e) => {
+            tracing::debug!(error = %e, "Failed to send session usage");
+            Delivery::Unreachable
+        }
+    }
+}
+
+/// Highest resident memory of a process so far
+#[cfg(target_os = "linux")]
+fn peak_memory_bytes(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory_bytes(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Path of the file buffering unsent heartbeats
+fn pending_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(home
+        .join(".config")
+        .join("forkforge")
+        .join("pending_usage.json"))
+}
+
+fn read_pending() -> Result<HashMap<String, SessionUsageRequest>, Box<dyn std::error::Error>> {
+    let path = pending_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    Ok(serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?)
+}
+
+fn update_pending(
+    change: impl FnOnce(&mut HashMap<String, SessionUsageRequest>),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = pending_path()?;
+    let mut pending = read_pending()?;
+    change(&mut pending);
+
+    if pending.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&pending)?)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,350 @@
+//! # Validator Launcher
+//!
+//! Spawns `solana-test-validator` as a managed child process forked from a
+//! remote cluster, streams its logs to the terminal, and shuts it down
+//! cleanly on Ctrl-C or when `forkforge down` sends SIGTERM.
+//!
+//! Accounts to clone are fetched from the fork source in parallel batches
+//! before the validator starts and loaded with `--account`, rather than left
+//! to the validator's one-request-per-account `--clone`. Anything the
+//! prefetch misses falls back to `--clone`.
+//!
+//! With `--profile-startup`, the time taken by each startup stage is
+//! reported against the cold-start budget once the validator's RPC is
+//! healthy.
+//!
+//! With `--session`, usage heartbeats are reported for the ForkForge session
+//! while the validator runs.
+//!
+//! Each run listens on the ports the runtime registry reserved for it, so
+//! several validators can run at once.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use colored::*;
+use domain::models::AccountState;
+use infra::HeliusClient;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::errors::CliError;
+use crate::runtime::{self, Ports, RunningSession};
+use crate::usage::UsageReporter;
+
+/// Target time from `up` to a usable validator
+const STARTUP_BUDGET: Duration = Duration::from_secs(10);
+
+/// How often to check whether the validator's RPC is healthy
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Settings for a single local validator run
+pub struct ValidatorConfig {
+    /// Validator binary (`solana-test-validator` from Solana or Agave)
+    pub binary: String,
+    /// RPC endpoint of the cluster to fork from
+    pub fork_rpc_url: String,
+    /// Registry entry holding this run's ports and ledger directory
+    pub runtime: RunningSession,
+    /// Accounts and programs to clone from the fork source
+    pub clone_accounts: Vec<String>,
+    /// Print a per-stage startup timing report
+    pub profile_startup: bool,
+    /// Reports usage for the ForkForge session this validator runs
+    pub usage: Option<UsageReporter>,
+}
+
+/// Elapsed time at the end of each startup stage
+struct StartupProfile {
+    started: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StartupProfile {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            stages: Vec::new(),
+        }
+    }
+
+    /// Record that a stage finished now
+    fn mark(&mut self, stage: &'static str) {
+        self.stages.push((stage, self.started.elapsed()));
+    }
+
+    fn report(&self) {
+        println!("\n{}", "Startup profile".bright_white().bold());
+        let mut previous = Duration::ZERO;
+        for (stage, elapsed) in &self.stages {
+            println!(
+                "  {:<20} {:>7.2}s",
+                stage,
+                elapsed.saturating_sub(previous).as_secs_f64()
+            );
+            previous = *elapsed;
+        }
+
+        let total = format!("{:>7.2}s", previous.as_secs_f64());
+        let budget = format!("(budget {}s)", STARTUP_BUDGET.as_secs());
+        if previous <= STARTUP_BUDGET {
+            println!("  {:<20} {} {}", "total", total.green(), budget);
+        } else {
+            println!("  {:<20} {} {}", "total", total.red(), budget);
+        }
+    }
+}
+
+/// Fetch the accounts to clone and write each to a JSON file for `--account`
+///
+/// Returns the files written, keyed by pubkey. Upgradeable programs come
+/// with their program data account.
+async fn prefetch_accounts(
+    fork_rpc_url: &str,
+    pubkeys: &[String],
+    dir: &Path,
+) -> Result<Vec<(String, PathBuf)>, Box<dyn std::error::Error>> {
+    let client = HeliusClient::with_rpc_url(fork_rpc_url.to_string(), reqwest::Client::new());
+    let accounts = client.get_accounts_with_program_data(pubkeys).await?;
+
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let mut files = Vec::with_capacity(accounts.len());
+    for account in &accounts {
+        let path = dir.join(format!("{}.json", account.pubkey));
+        std::fs::write(&path, serde_json::to_vec(&account_json(account))?)?;
+        files.push((account.pubkey.clone(), path));
+    }
+    Ok(files)
+}
+
+/// An account in the `solana account --output json` format `--account` reads
+fn account_json(account: &AccountState) -> serde_json::Value {
+    serde_json::json!({
+        "pubkey": account.pubkey,
+        "account": {
+            "lamports": account.lamports,
+            "data": [BASE64.encode(&account.data), "base64"],
+            "owner": account.owner,
+            "executable": account.executable,
+            "rentEpoch": account.rent_epoch,
+            "space": account.data.len(),
+        },
+    })
+}
+
+/// Wait until the validator's RPC reports healthy
+async fn wait_for_rpc_health(profile: &mut StartupProfile, ports: Ports) {
+    // A configured proxy can't reach the local validator
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap_or_default();
+    let url = format!("http://127.0.0.1:{}", ports.rpc);
+    let request = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "getHealth" });
+
+    let mut responding = false;
+    loop {
+        if let Ok(response) = client.post(&url).json(&request).send().await {
+            if !responding {
+                responding = true;
+                profile.mark("rpc listening");
+            }
+            let healthy = response
+                .json::<serde_json::Value>()
+                .await
+                .is_ok_and(|body| body["result"] == "ok");
+            if healthy {
+                profile.mark("rpc healthy");
+                return;
+            }
+        }
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+    }
+}
+
+/// Stream lines from a validator output pipe to the terminal
+async fn stream_logs<R: AsyncRead + Unpin>(reader: R, is_stderr: bool) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if is_stderr {
+            eprintln!("{} {}", "validator │".bright_cyan(), line.yellow());
+        } else {
+            println!("{} {}", "validator │".bright_cyan(), line);
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM from `forkforge down`
+async fn shutdown_requested() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!(error = %e, "Can't listen for SIGTERM"),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Launch the validator and block until it exits or is asked to stop
+pub async fn run(config: ValidatorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let mut profile = StartupProfile::new();
+    let ports = config.runtime.ports;
+
+    let accounts_dir = config.runtime.ledger_dir.with_extension("accounts");
+    let mut prefetched = Vec::new();
+    if !config.clone_accounts.is_empty() {
+        match prefetch_accounts(&config.fork_rpc_url, &config.clone_accounts, &accounts_dir).await {
+            Ok(files) => prefetched = files,
+            Err(e) => tracing::warn!(
+                error = %e,
+                "Failed to prefetch accounts, the validator will clone them"
+            ),
+        }
+        profile.mark("accounts prefetched");
+    }
+
+    let mut command = Command::new(&config.binary);
+    command
+        .arg("--url")
+        .arg(&config.fork_rpc_url)
+        .arg("--ledger")
+        .arg(&config.runtime.ledger_dir)
+        .arg("--rpc-port")
+        .arg(ports.rpc.to_string())
+        .arg("--faucet-port")
+        .arg(ports.faucet.to_string())
+        .arg("--reset")
+        // Log to stderr instead of drawing the interactive dashboard
+        .arg("--log")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    for (pubkey, path) in &prefetched {
+        command.arg("--account").arg(pubkey).arg(path);
+    }
+    for account in &config.clone_accounts {
+        if !prefetched.iter().any(|(pubkey, _)| pubkey == account) {
+            command.arg("--clone").arg(account);
+        }
+    }
+
+    tracing::debug!(command = ?command.as_std(), "Launching validator");
+    let mut child = command.spawn().map_err(|e| {
+        CliError::new(format!("Failed to launch {}", config.binary))
+            .cause(format!("{e} (is the Solana CLI installed and on PATH?)"))
+            .fix("sh -c \"$(curl -sSfL https://release.anza.xyz/stable/install)\"")
+    })?;
+    profile.mark("process spawned");
+
+    let pid = child.id();
+    if let Some(pid) = pid
+        && let Err(e) = runtime::set_validator_pid(&config.runtime.name, pid)
+    {
+        tracing::warn!(error = %e, "Failed to record validator PID");
+    }
+
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(stream_logs(stdout, false));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(stream_logs(stderr, true));
+    }
+
+    println!(
+        "{} {} {}",
+        "✓".bright_green(),
+        "Validator forked from".green(),
+        config.fork_rpc_url.bright_blue()
+    );
+    println!(
+        "  {} http://127.0.0.1:{}  {} ws://127.0.0.1:{}  ({})",
+        "RPC".bright_white(),
+        ports.rpc,
+        "WebSocket".bright_white(),
+        ports.ws,
+        config.runtime.name
+    );
+
+    if config.profile_startup {
+        tokio::spawn(async move {
+            wait_for_rpc_health(&mut profile, ports).await;
+            profile.report();
+        });
+    }
+
+    let heartbeats = async {
+        match &config.usage {
+            Some(reporter) => reporter.run(pid).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let result: Result<(), Box<dyn std::error::Error>> = tokio::select! {
+        status = child.wait() => match status {
+            Ok(status) if !status.success() => Err(format!("Validator exited with {status}").into()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        },
+        _ = shutdown_requested() => {
+            println!("\n{} {}", "→".bright_yellow(), "Shutting down validator...".yellow());
+            child.kill().await.map_err(Into::into)
+        }
+        _ = heartbeats => Ok(()),
+    };
+
+    // Report final usage however the validator stopped
+    if let Some(reporter) = &config.usage {
+        reporter.report().await;
+    }
+    if !prefetched.is_empty()
+        && let Err(e) = std::fs::remove_dir_all(&accounts_dir)
+    {
+        tracing::warn!(error = %e, "Failed to delete prefetched accounts");
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_json() {
+        let account = AccountState {
+            pubkey: "So11111111111111111111111111111111111111112".to_string(),
+            lamports: 1_000,
+            owner: "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string(),
+            data: vec![1, 2, 3],
+            executable: false,
+            rent_epoch: u64::MAX,
+        };
+
+        let json = account_json(&account);
+        assert_eq!(json["pubkey"], account.pubkey);
+        assert_eq!(
+            json["account"]["data"],
+            serde_json::json!(["AQID", "base64"])
+        );
+        assert_eq!(json["account"]["owner"], account.owner);
+        assert_eq!(json["account"]["rentEpoch"], u64::MAX);
+        assert_eq!(json["account"]["space"], 3);
+    }
+}
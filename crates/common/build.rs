@@ -0,0 +1,59 @@
+//! Records the git commit and build time for `common::build_info`
+//!
+//! Outside a git checkout, e.g. in Docker builds where `.git` is ignored,
+//! the commit is read from `FORKFORGE_GIT_SHA` instead.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Run git in the crate directory, returning trimmed stdout on success
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=FORKFORGE_GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let sha = std::env::var("FORKFORGE_GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty());
+    let (sha, dirty) = match sha {
+        Some(sha) => (Some(sha), false),
+        None => {
+            let sha = git(&["rev-parse", "HEAD"]);
+            let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.is_empty());
+            (sha, dirty)
+        }
+    };
+    if let Some(sha) = &sha {
+        println!("cargo:rustc-env=FORKFORGE_BUILD_GIT_SHA={sha}");
+    }
+    println!("cargo:rustc-env=FORKFORGE_BUILD_GIT_DIRTY={dirty}");
+
+    // New commits move HEAD or a branch ref; staging changes touches the index
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]).map(PathBuf::from) {
+        for path in ["HEAD", "index"] {
+            println!("cargo:rerun-if-changed={}", git_dir.join(path).display());
+        }
+        if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}", git_dir.join(branch).display());
+        }
+    }
+
+    // Reproducible builds pin the timestamp
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(chrono::Utc::now);
+    println!(
+        "cargo:rustc-env=FORKFORGE_BUILD_TIME={}",
+        built_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+}
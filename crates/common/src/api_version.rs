@@ -0,0 +1,14 @@
+//! # API Version
+//!
+//! The version of the HTTP API, shared by the server and the CLI. Routes
+//! live under [`API_PREFIX`]; clients may also name the version they expect
+//! in the [`API_VERSION_HEADER`] request header.
+
+/// The API version this build serves and speaks
+pub const API_VERSION: &str = "1";
+
+/// Path prefix of every route of [`API_VERSION`]
+pub const API_PREFIX: &str = "/v1";
+
+/// Header naming the API version a client expects, and the one that served it
+pub const API_VERSION_HEADER: &str = "forkforge-api-version";
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetBillingCountryRequest {
+    /// ISO 3166-1 alpha-2 country code (e.g., "DE")
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckoutSessionRequest {
+    /// Tier to subscribe to: "entry", "lite" or "pro"
+    pub tier: String,
+}
+
+/// A payment provider page to send the user to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingRedirect {
+    pub url: String,
+}
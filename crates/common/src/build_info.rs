@@ -0,0 +1,71 @@
+//! # Build Information
+//!
+//! The API and CLI embed the git commit they were built from, whether the
+//! working tree had uncommitted changes, and when they were built, so a bug
+//! report identifies the exact build. `build.rs` records them; builds outside
+//! a git checkout take the commit from `FORKFORGE_GIT_SHA`.
+
+use serde::{Deserialize, Serialize};
+
+/// Characters of the commit shown in short descriptions
+const SHORT_SHA_LEN: usize = 12;
+
+/// Where and when a binary was built
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Full commit hash; `None` when built without git or `FORKFORGE_GIT_SHA`
+    pub git_sha: Option<String>,
+    /// Whether tracked files had uncommitted changes
+    pub git_dirty: bool,
+    /// RFC 3339 build time, or `SOURCE_DATE_EPOCH` when set
+    pub built_at: String,
+}
+
+impl BuildInfo {
+    /// This build
+    pub fn current() -> Self {
+        Self {
+            git_sha: option_env!("FORKFORGE_BUILD_GIT_SHA").map(str::to_string),
+            git_dirty: env!("FORKFORGE_BUILD_GIT_DIRTY") == "true",
+            built_at: env!("FORKFORGE_BUILD_TIME").to_string(),
+        }
+    }
+
+    /// Abbreviated commit, with `-dirty` for uncommitted changes
+    pub fn short_sha(&self) -> String {
+        let sha = self.git_sha.as_deref().unwrap_or("unknown");
+        let sha = &sha[..sha.len().min(SHORT_SHA_LEN)];
+        if self.git_dirty {
+            format!("{sha}-dirty")
+        } else {
+            sha.to_string()
+        }
+    }
+
+    /// `version` with the commit, e.g. `0.1.0 (3f2a9c1d4e5b)`
+    pub fn describe(&self, version: &str) -> String {
+        format!("{version} ({})", self.short_sha())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let build = BuildInfo {
+            git_sha: Some("3f2a9c1d4e5b6a7b8c9d0e1f2a3b4c5d6e7f8a9b".to_string()),
+            git_dirty: true,
+            built_at: "2025-02-18T09:30:00Z".to_string(),
+        };
+        assert_eq!(build.describe("0.1.0"), "0.1.0 (3f2a9c1d4e5b-dirty)");
+
+        let unknown = BuildInfo {
+            git_sha: None,
+            git_dirty: false,
+            ..build
+        };
+        assert_eq!(unknown.describe("0.1.0"), "0.1.0 (unknown)");
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Which CLI versions the API still supports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CliVersionResponse {
+    /// Oldest supported CLI version (e.g. "0.3.0"); any version when unset
+    pub min_version: Option<String>,
+}
+
+/// Parse a `major.minor.patch` version, ignoring any `-pre` or `+build` suffix
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
+
+/// Whether `version` is older than `min_version`
+///
+/// Unparseable versions are treated as supported, so a typo on either side
+/// never locks users out.
+pub fn is_older_than(version: &str, min_version: &str) -> bool {
+    match (parse_version(version), parse_version(min_version)) {
+        (Some(version), Some(min_version)) => version < min_version,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparison() {
+        assert_eq!(parse_version("1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2.3-beta.1"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+
+        assert!(is_older_than("0.9.9", "0.10.0"));
+        assert!(!is_older_than("0.10.0", "0.10.0"));
+        assert!(!is_older_than("1.0.0", "0.10.0"));
+        assert!(!is_older_than("0.1.0", "latest"));
+    }
+}
@@ -50,6 +50,38 @@ pub struct CheckUserAuthorisedResponse {
     /// Granted scopes (may differ from requested)
     #[serde(rename = "scope")]
     pub _scope: String,
+    /// Trades for a new access token; only issued when the GitHub app uses
+    /// expiring user tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Seconds until `access_token` expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<i64>,
+    /// Seconds until `refresh_token` expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token_expires_in: Option<i64>,
+}
+
+/// Progress of a device-flow login, from `GET /auth/github/status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorizationStatus {
+    /// The user hasn't approved or denied the login yet
+    Pending,
+    /// The user approved the login; the response carries their tokens
+    Complete,
+    /// The user denied the login
+    Denied,
+    /// The device code expired before the user approved it
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationStatusResponse {
+    pub status: AuthorizationStatus,
+    /// GitHub tokens, present only when `status` is `complete`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<CheckUserAuthorisedResponse>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +90,12 @@ pub struct GitHubUser {
     pub id: u64,
     /// The GitHub username of the repository owner i.e. katooshka
     pub login: String,
+    /// Primary email, including a private one when the token may read it
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Display name from the GitHub profile
+    #[serde(default)]
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,3 +103,24 @@ pub struct UserLoginResponse {
     pub user: GitHubUser,
     pub access_token: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenRequest {
+    /// GitHub access token proving the caller's identity
+    pub access_token: String,
+    /// GitHub refresh token, stored so the server can renew expiring tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Seconds until `access_token` expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<i64>,
+    /// Seconds until `refresh_token` expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token_expires_in: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenResponse {
+    /// ForkForge API token, sent as `Authorization: Bearer <token>`
+    pub token: String,
+}
@@ -1,5 +1,21 @@
+pub mod api_version;
+pub mod billing;
+pub mod build_info;
+pub mod cli;
 pub mod config;
 pub mod github;
+pub mod response;
+pub mod sessions;
+pub mod sso;
+pub mod users;
 
-pub use config::Config;
+pub use api_version::*;
+pub use billing::*;
+pub use build_info::BuildInfo;
+pub use cli::CliVersionResponse;
+pub use config::{Config, DeploymentMode};
 pub use github::*;
+pub use response::*;
+pub use sessions::*;
+pub use sso::*;
+pub use users::*;
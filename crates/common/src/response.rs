@@ -0,0 +1,130 @@
+//! # Response Envelope
+//!
+//! Every API response body is JSON wrapped in one of two envelopes, shared
+//! by the server and the CLI:
+//!
+//! ```json
+//! {"data": [...], "pagination": {"next_cursor": "20", "total": 45}}
+//! {"error": {"code": "not_found", "message": "Not found: Session ..."}}
+//! ```
+//!
+//! `pagination` is only present on lists. Pass `next_cursor` back as
+//! `?cursor=` to fetch the next page; it is absent on the last page.
+
+use serde::{Deserialize, Serialize};
+
+/// Successful response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data,
+            pagination: None,
+        }
+    }
+
+    /// One page of a list
+    pub fn page(data: T, pagination: Pagination) -> Self {
+        Self {
+            data,
+            pagination: Some(pagination),
+        }
+    }
+}
+
+/// Where a page sits in its list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    /// Opaque cursor for the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Items in the whole list, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+impl Pagination {
+    /// Pagination for the page of `limit` items starting at `offset`
+    ///
+    /// A page shorter than `limit` is the last one.
+    pub fn offset(offset: u32, limit: u32, returned: usize, total: Option<u64>) -> Self {
+        let next = u64::from(offset) + u64::from(limit);
+        let has_more = returned >= limit as usize && total.is_none_or(|total| next < total);
+        Self {
+            next_cursor: has_more.then(|| next.to_string()),
+            total,
+        }
+    }
+
+    /// Pagination for a list returned whole
+    pub fn complete(total: usize) -> Self {
+        Self {
+            next_cursor: None,
+            total: Some(total as u64),
+        }
+    }
+
+    /// Offset a cursor from `offset` points at, or `None` if it isn't one
+    pub fn parse_cursor(cursor: &str) -> Option<u32> {
+        cursor.parse().ok()
+    }
+}
+
+/// Failed response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorResponse {
+    pub error: ErrorBody,
+}
+
+/// What went wrong, with a stable `code` clients can branch on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    /// Invalid request fields, for validation failures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    /// ID of the failed request, to quote when reporting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// A problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_pagination() {
+        let page = Pagination::offset(0, 20, 20, Some(45));
+        assert_eq!(page.next_cursor.as_deref(), Some("20"));
+        assert_eq!(Pagination::parse_cursor("20"), Some(20));
+
+        // Short pages, and full pages that reach the total, are the last
+        assert_eq!(Pagination::offset(40, 20, 5, Some(45)).next_cursor, None);
+        assert_eq!(Pagination::offset(20, 20, 20, Some(40)).next_cursor, None);
+        assert_eq!(
+            Pagination::offset(20, 20, 20, None).next_cursor.as_deref(),
+            Some("40")
+        );
+
+        let json = serde_json::to_string(&ApiResponse::new(1)).unwrap();
+        assert_eq!(json, r#"{"data":1}"#);
+        let json = serde_json::to_string(&ApiResponse::page(vec![1], page)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"data":[1],"pagination":{"next_cursor":"20","total":45}}"#
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    /// User-facing session name (e.g., "panic-2245"); a slug such as
+    /// "brave-otter-42" is generated when empty
+    #[serde(default)]
+    pub name: String,
+    /// Mainnet slot to fork from; latest slot when omitted
+    #[serde(default)]
+    pub fork_slot: Option<u64>,
+    /// Mainnet accounts and programs to copy into the fork
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// Programs to copy, along with their program data accounts
+    #[serde(default)]
+    pub programs: Vec<String>,
+    /// Token mints to copy
+    #[serde(default)]
+    pub mints: Vec<String>,
+    /// Region to run the session in; the user's preferred region, then the
+    /// server's default, when omitted
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl CreateSessionRequest {
+    /// Every account, program and mint to copy, without duplicates
+    pub fn pubkeys(&self) -> Vec<String> {
+        let mut pubkeys: Vec<String> = Vec::new();
+        for pubkey in self
+            .accounts
+            .iter()
+            .chain(&self.programs)
+            .chain(&self.mints)
+        {
+            if !pubkeys.contains(pubkey) {
+                pubkeys.push(pubkey.clone());
+            }
+        }
+        pubkeys
+    }
+}
+
+/// Request to change a session's details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSessionRequest {
+    /// New user-facing name; the session keeps its slug
+    pub name: String,
+}
+
+/// Request to save a snapshot of a running session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSnapshotRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Request to restore a snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreSnapshotRequest {
+    /// Session ID or slug to restore into; a new session is created when omitted
+    #[serde(default)]
+    pub session: Option<String>,
+}
+
+/// Request to stop several sessions at once
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchStopSessionsRequest {
+    /// Session IDs, slugs or ID prefixes
+    pub ids: Vec<String>,
+}
+
+/// Request to delete several snapshots at once
+///
+/// Snapshots listed in `ids` and those matching `filter` are both deleted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchDeleteSnapshotsRequest {
+    /// Snapshot IDs or ID prefixes
+    #[serde(default)]
+    pub ids: Vec<String>,
+    /// Server-side selection, e.g. "older-than:30d"
+    #[serde(default)]
+    pub filter: Option<String>,
+}
+
+/// Outcome for one item of a batch request
+///
+/// Batches succeed partially: each item reports its own result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    /// The item as given in the request, or its ID when selected by filter
+    pub id: String,
+    pub ok: bool,
+    /// Set when `ok` is false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchItemError>,
+}
+
+/// Why one item of a batch failed, using the API's error codes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemError {
+    pub code: String,
+    pub message: String,
+}
+
+/// Usage heartbeat from the client running a session
+///
+/// Values are running totals since the session started, so resending a
+/// heartbeat is harmless.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUsageRequest {
+    /// Seconds the session has been running
+    pub elapsed_seconds: u64,
+    /// Highest resident memory of the validator so far, if known
+    #[serde(default)]
+    pub peak_memory_bytes: u64,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// How clients start a single sign-on, from `GET /auth/oidc/config`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfigResponse {
+    /// Where the user's browser is sent to sign in
+    pub authorization_endpoint: String,
+    pub client_id: String,
+    /// Space-separated scopes to request
+    pub scopes: String,
+}
+
+/// Sent to `POST /auth/oidc/token` to trade an authorization code for a
+/// ForkForge API token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcTokenRequest {
+    /// Code the issuer sent to `redirect_uri`
+    pub code: String,
+    /// PKCE secret whose challenge started the sign-in
+    pub code_verifier: String,
+    /// Redirect URI the sign-in was started with
+    pub redirect_uri: String,
+}
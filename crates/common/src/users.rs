@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of `PATCH /me`; omitted fields are left unchanged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateProfileRequest {
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Name shown instead of the email address; an empty one clears it
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Where to send account mail instead of the GitHub email; an empty one
+    /// clears it
+    #[serde(default)]
+    pub contact_email: Option<String>,
+    /// Region new sessions are placed in (e.g. "eu"); an empty one clears it
+    #[serde(default)]
+    pub preferred_region: Option<String>,
+}
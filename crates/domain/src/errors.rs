@@ -5,6 +5,12 @@ pub enum DomainError {
     NotFound(String),
     Unauthorized(String),
     InvalidInput(String),
+    /// The user's plan doesn't allow this
+    QuotaExceeded(String),
+    /// The record changed since the caller read it
+    Conflict(String),
+    /// The caller is known, but not allowed to do this
+    Forbidden(String),
     ExternalService(String),
     Internal(String),
 }
@@ -15,6 +21,9 @@ impl fmt::Display for DomainError {
             DomainError::NotFound(msg) => write!(f, "Not found: {msg}"),
             DomainError::Unauthorized(msg) => write!(f, "Unauthorized: {msg}"),
             DomainError::InvalidInput(msg) => write!(f, "Invalid input: {msg}"),
+            DomainError::QuotaExceeded(msg) => write!(f, "Quota exceeded: {msg}"),
+            DomainError::Conflict(msg) => write!(f, "Conflict: {msg}"),
+            DomainError::Forbidden(msg) => write!(f, "Forbidden: {msg}"),
             DomainError::ExternalService(msg) => write!(f, "External service error: {msg}"),
             DomainError::Internal(msg) => write!(f, "Internal error: {msg}"),
         }
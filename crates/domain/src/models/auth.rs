@@ -1,14 +1,123 @@
+use super::ids::UserId;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthToken {
     pub id: Uuid,
-    pub user_id: Uuid,
+    pub user_id: UserId,
     pub token_hash: String,
     pub name: Option<String>,
     pub last_used_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
+
+/// OAuth tokens a user's login provider issued, e.g. GitHub
+///
+/// Providers configured with expiring tokens also issue a refresh token,
+/// which is traded for a new pair before the access token expires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// When `access_token` stops working; `None` if it never expires
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When `refresh_token` stops working
+    pub refresh_token_expires_at: Option<DateTime<Utc>>,
+}
+
+impl ProviderToken {
+    /// Whether the access token expires before `at`
+    pub fn expires_before(&self, at: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= at)
+    }
+}
+
+/// A device-flow login waiting for the user to enter their code
+///
+/// Kept until the login finishes or the code expires, so a login can be
+/// picked up again after the server restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingDeviceFlow {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds the provider asks clients to wait between polls
+    pub interval: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PendingDeviceFlow {
+    /// Whether the code can no longer be entered at `at`
+    pub fn is_expired_at(&self, at: DateTime<Utc>) -> bool {
+        self.expires_at <= at
+    }
+}
+
+/// Provider accounts allowed on a private server: everyone in a GitHub
+/// organization, or in one team of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessGroup {
+    Org(String),
+    Team { org: String, team: String },
+}
+
+impl AccessGroup {
+    /// Parse `org` or `org/team`, where `team` is the team's slug
+    pub fn parse(group: &str) -> Option<Self> {
+        let group = group.trim();
+        let group = match group.split_once('/') {
+            None => AccessGroup::Org(group.to_string()),
+            Some((org, team)) => AccessGroup::Team {
+                org: org.to_string(),
+                team: team.to_string(),
+            },
+        };
+        let valid = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        };
+        match &group {
+            AccessGroup::Org(org) => valid(org),
+            AccessGroup::Team { org, team } => valid(org) && valid(team),
+        }
+        .then_some(group)
+    }
+}
+
+impl fmt::Display for AccessGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessGroup::Org(org) => write!(f, "{org}"),
+            AccessGroup::Team { org, team } => write!(f, "{org}/{team}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_access_group() {
+        assert_eq!(
+            AccessGroup::parse("acme"),
+            Some(AccessGroup::Org("acme".to_string()))
+        );
+        assert_eq!(
+            AccessGroup::parse(" acme/platform-eng "),
+            Some(AccessGroup::Team {
+                org: "acme".to_string(),
+                team: "platform-eng".to_string(),
+            })
+        );
+        for invalid in ["", "acme/", "/eng", "acme/eng/infra", "ac me"] {
+            assert_eq!(AccessGroup::parse(invalid), None, "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::ids::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// An audited change to a user's billing state
+///
+/// `before` and `after` hold the affected values (e.g. the old and new tier)
+/// so support and accounting can reconstruct what changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingEvent {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub kind: BillingEventKind,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    /// Stripe event that caused the change, if any
+    pub stripe_event_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingEventKind {
+    TierChanged,
+    PaymentFailed,
+    Refunded,
+    ReconciliationFix,
+}
+
+impl BillingEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BillingEventKind::TierChanged => "tier_changed",
+            BillingEventKind::PaymentFailed => "payment_failed",
+            BillingEventKind::Refunded => "refunded",
+            BillingEventKind::ReconciliationFix => "reconciliation_fix",
+        }
+    }
+}
+
+impl FromStr for BillingEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tier_changed" => Ok(BillingEventKind::TierChanged),
+            "payment_failed" => Ok(BillingEventKind::PaymentFailed),
+            "refunded" => Ok(BillingEventKind::Refunded),
+            "reconciliation_fix" => Ok(BillingEventKind::ReconciliationFix),
+            _ => Err(format!("Unknown billing event kind: {s}")),
+        }
+    }
+}
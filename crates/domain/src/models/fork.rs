@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// Mainnet account captured for a fork
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountState {
+    /// Base58 account address
+    pub pubkey: String,
+    pub lamports: u64,
+    /// Base58 address of the owning program
+    pub owner: String,
+    pub data: Vec<u8>,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+/// Everything a fork needs from mainnet, read at a single slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkState {
+    pub slot: u64,
+    /// Accounts the user asked for
+    pub accounts: Vec<AccountState>,
+    /// Executable accounts plus their program data accounts
+    pub programs: Vec<AccountState>,
+    /// Clock and rent sysvars
+    pub sysvars: Vec<AccountState>,
+}
+
+impl ForkState {
+    /// All captured accounts, regardless of kind
+    pub fn all_accounts(&self) -> impl Iterator<Item = &AccountState> {
+        self.accounts
+            .iter()
+            .chain(&self.programs)
+            .chain(&self.sysvars)
+    }
+}
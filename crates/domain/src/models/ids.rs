@@ -0,0 +1,102 @@
+//! # Typed IDs
+//!
+//! Users, sessions and snapshots are all identified by UUIDs. Wrapping each
+//! in its own type means a snapshot ID can't be passed where a session ID is
+//! expected. They serialize as plain UUID strings, and infrastructure stores
+//! them the same way it stored bare UUIDs.
+//!
+//! New IDs are UUIDv7, so they sort by creation time; see
+//! `services::ids`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(Uuid);
+
+        impl $name {
+            /// A new time-ordered ID, sorting after every ID made before it
+            pub fn new_v7() -> Self {
+                Self(Uuid::now_v7())
+            }
+
+            /// A new random ID
+            pub fn new_v4() -> Self {
+                Self(Uuid::new_v4())
+            }
+
+            pub fn as_uuid(&self) -> Uuid {
+                self.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(id: Uuid) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for Uuid {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = uuid::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Uuid::parse_str(s).map(Self)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// Identifies a `User`
+    UserId
+);
+id_type!(
+    /// Identifies a `ForkSession`
+    SessionId
+);
+id_type!(
+    /// Identifies a `Snapshot`
+    SnapshotId
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_serialize_as_plain_uuids() {
+        let id = SessionId::new_v4();
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{}\"", id.as_uuid()));
+        assert_eq!(serde_json::from_str::<SessionId>(&json).unwrap(), id);
+        assert_eq!(id.to_string().parse::<SessionId>().unwrap(), id);
+    }
+
+    #[test]
+    fn test_new_ids_sort_by_creation() {
+        let ids: Vec<SnapshotId> = (0..100).map(|_| SnapshotId::new_v7()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        // Stored as text, they sort the same way
+        assert!(ids
+            .windows(2)
+            .all(|pair| pair[0].to_string() < pair[1].to_string()));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::user::SubscriptionTier;
+
+/// Claims embedded in a signed self-hosted license key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub licensee: String,
+    pub tier: SubscriptionTier,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl License {
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Whether the license had expired by `now`
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Whether this license unlocks Pro features on a self-hosted server
+    pub fn unlocks_pro(&self) -> bool {
+        self.tier == SubscriptionTier::Pro && !self.is_expired()
+    }
+}
@@ -1,5 +1,27 @@
 pub mod auth;
+pub mod billing;
+pub mod fork;
+pub mod ids;
+pub mod license;
+pub mod plan;
+pub mod region;
+pub mod retention;
+pub mod session;
+pub mod snapshot;
+pub mod stats;
+pub mod subscription;
 pub mod user;
 
 pub use auth::*;
+pub use billing::*;
+pub use fork::*;
+pub use ids::*;
+pub use license::*;
+pub use plan::*;
+pub use region::*;
+pub use retention::*;
+pub use session::*;
+pub use snapshot::*;
+pub use stats::*;
+pub use subscription::*;
 pub use user::*;
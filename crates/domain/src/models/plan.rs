@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::user::SubscriptionTier;
+
+/// A subscription tier as offered to customers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plan {
+    pub tier: SubscriptionTier,
+    pub name: String,
+    pub limits: PlanLimits,
+    pub features: Vec<String>,
+    /// One entry per currency the plan is sold in, base currency first
+    pub prices: Vec<PlanPrice>,
+}
+
+/// Catalog entry defining what a tier includes
+///
+/// Stored in the `plans` table so limits can change without a release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanDefinition {
+    pub tier: SubscriptionTier,
+    pub name: String,
+    pub limits: PlanLimits,
+    /// Feature flags enabled for the tier (e.g. "collaborators")
+    pub features: Vec<String>,
+}
+
+/// Usage limits enforced for a tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanLimits {
+    pub max_concurrent_sessions: u32,
+    pub max_snapshots_per_session: u32,
+    /// Longest a single session may run; unlimited when `None`
+    pub max_session_minutes: Option<u32>,
+    /// API requests allowed per minute
+    pub api_requests_per_minute: u32,
+}
+
+/// Price of a plan in a single currency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanPrice {
+    /// Lowercase ISO 4217 code, as used by Stripe (e.g. "usd")
+    pub currency: String,
+    /// Amount in the currency's smallest unit (e.g. cents)
+    pub unit_amount: i64,
+    /// Billing interval ("month" or "year")
+    pub interval: String,
+}
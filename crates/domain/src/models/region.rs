@@ -0,0 +1,152 @@
+use crate::errors::DomainError;
+use serde::{Deserialize, Serialize};
+
+/// Longest region name, e.g. "us-east"
+pub const MAX_REGION_LEN: usize = 32;
+
+/// A placement target sessions can run in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    /// Short name users pick it by, e.g. "eu"
+    pub name: String,
+    /// Base URL clients reach the region's sessions through
+    pub gateway_url: String,
+}
+
+/// Check a region name: lowercase letters, digits and dashes, e.g. "eu-west"
+pub fn validate_region(region: &str) -> Result<(), DomainError> {
+    let is_region_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+    if region.is_empty() || region.len() > MAX_REGION_LEN || !region.chars().all(is_region_char) {
+        return Err(DomainError::InvalidInput(format!(
+            "Region {region:?} must be up to {MAX_REGION_LEN} lowercase letters, digits and dashes"
+        )));
+    }
+    Ok(())
+}
+
+/// Regions configured at startup, and the one sessions go to by default
+///
+/// Without any regions, sessions aren't placed and record no region.
+#[derive(Debug, Clone, Default)]
+pub struct RegionCatalog {
+    regions: Vec<Region>,
+    default: Option<String>,
+}
+
+impl RegionCatalog {
+    /// Build a catalog, checking names are valid and unique
+    ///
+    /// The default must be one of `regions`; without one, the first region
+    /// is the default.
+    pub fn new(regions: Vec<Region>, default: Option<String>) -> Result<Self, DomainError> {
+        for (i, region) in regions.iter().enumerate() {
+            validate_region(&region.name)?;
+            if regions[..i].iter().any(|other| other.name == region.name) {
+                return Err(DomainError::InvalidInput(format!(
+                    "Region {} is configured twice",
+                    region.name
+                )));
+            }
+        }
+
+        let default = match default {
+            Some(name) if !regions.iter().any(|region| region.name == name) => {
+                return Err(DomainError::InvalidInput(format!(
+                    "Default region {name:?} is not a configured region"
+                )));
+            }
+            Some(name) => Some(name),
+            None => regions.first().map(|region| region.name.clone()),
+        };
+
+        Ok(Self { regions, default })
+    }
+
+    /// All regions, in configured order
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+
+    /// Region a new session is placed in
+    ///
+    /// An explicitly `requested` region must exist. Otherwise the user's
+    /// `preferred` region is used if it still exists, then the default.
+    pub fn place(
+        &self,
+        requested: Option<&str>,
+        preferred: Option<&str>,
+    ) -> Result<Option<&Region>, DomainError> {
+        if let Some(name) = requested {
+            return self.find(name).map(Some).ok_or_else(|| {
+                let available: Vec<&str> = self.regions.iter().map(|r| r.name.as_str()).collect();
+                DomainError::InvalidInput(if available.is_empty() {
+                    "This server doesn't offer regions".to_string()
+                } else {
+                    format!(
+                        "Unknown region {name:?}; available regions are {}",
+                        available.join(", ")
+                    )
+                })
+            });
+        }
+
+        Ok(preferred
+            .and_then(|name| self.find(name))
+            .or_else(|| self.default.as_deref().and_then(|name| self.find(name))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(name: &str) -> Region {
+        Region {
+            name: name.to_string(),
+            gateway_url: format!("https://{name}.forkforge.dev"),
+        }
+    }
+
+    #[test]
+    fn test_place_sessions() {
+        let catalog =
+            RegionCatalog::new(vec![region("us"), region("eu")], Some("eu".to_string())).unwrap();
+
+        let placed = |requested, preferred| {
+            catalog
+                .place(requested, preferred)
+                .map(|region| region.map(|r| r.name.clone()))
+        };
+        assert_eq!(
+            placed(Some("us"), Some("eu")).unwrap().as_deref(),
+            Some("us")
+        );
+        assert_eq!(placed(None, Some("us")).unwrap().as_deref(), Some("us"));
+        // A preferred region that was removed falls back to the default
+        assert_eq!(placed(None, Some("ap")).unwrap().as_deref(), Some("eu"));
+        assert_eq!(placed(None, None).unwrap().as_deref(), Some("eu"));
+        assert!(matches!(
+            placed(Some("ap"), None),
+            Err(DomainError::InvalidInput(_))
+        ));
+
+        // Without regions nothing is placed, and none can be asked for
+        let none = RegionCatalog::default();
+        assert_eq!(none.place(None, Some("eu")).unwrap(), None);
+        assert!(none.place(Some("eu"), None).is_err());
+    }
+
+    #[test]
+    fn test_new_checks_regions() {
+        let first = RegionCatalog::new(vec![region("us"), region("eu")], None).unwrap();
+        assert_eq!(first.place(None, None).unwrap(), Some(&region("us")));
+
+        assert!(RegionCatalog::new(vec![region("us"), region("us")], None).is_err());
+        assert!(RegionCatalog::new(vec![region("US")], None).is_err());
+        assert!(RegionCatalog::new(vec![region("us")], Some("eu".to_string())).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A data retention rule applied by the retention job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionRule {
+    /// Delete API tokens not used since the cutoff
+    UnusedAuthTokens,
+    /// Strip personal data from users deleted before the cutoff
+    DeletedUsers,
+    /// Delete billing audit events older than the cutoff
+    BillingEvents,
+}
+
+impl RetentionRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetentionRule::UnusedAuthTokens => "unused_auth_tokens",
+            RetentionRule::DeletedUsers => "deleted_users",
+            RetentionRule::BillingEvents => "billing_events",
+        }
+    }
+}
+
+/// What one rule purged, or would purge in a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub rule: RetentionRule,
+    /// Records older than this were affected
+    pub cutoff: DateTime<Utc>,
+    pub affected: u64,
+    pub dry_run: bool,
+}
@@ -0,0 +1,297 @@
+use super::ids::{SessionId, UserId};
+use crate::errors::DomainError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Longest session or snapshot name
+pub const MAX_NAME_LEN: usize = 64;
+
+/// A forked Solana validator session owned by a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkSession {
+    pub id: SessionId,
+    pub user_id: UserId,
+    pub name: String,
+    /// Short name unique among the owner's sessions, e.g. `brave-otter-42`
+    pub slug: String,
+    pub status: SessionStatus,
+    /// Mainnet slot the fork was started from; `None` means latest
+    pub fork_slot: Option<u64>,
+    /// Region the session is placed in; `None` when the server has no regions
+    pub region: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ForkSession {
+    /// A new pending session created at `now`, checking its name and slug
+    pub fn new(
+        user_id: UserId,
+        name: String,
+        slug: String,
+        fork_slot: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, DomainError> {
+        validate_name("Session", &name)?;
+        let is_slug = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+        if slug.is_empty() || slug.len() > MAX_NAME_LEN || !slug.chars().all(is_slug) {
+            return Err(DomainError::InvalidInput(format!(
+                "Session slug {slug:?} must be up to {MAX_NAME_LEN} lowercase letters, digits and dashes"
+            )));
+        }
+
+        Ok(Self {
+            id: SessionId::new_v7(),
+            user_id,
+            name,
+            slug,
+            status: SessionStatus::Pending,
+            fork_slot,
+            region: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+/// Check a session, snapshot or display name: present, short and printable
+pub(crate) fn validate_name(kind: &str, name: &str) -> Result<(), DomainError> {
+    if name.trim().is_empty() {
+        return Err(DomainError::InvalidInput(format!(
+            "{kind} name must not be empty"
+        )));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(DomainError::InvalidInput(format!(
+            "{kind} name must be at most {MAX_NAME_LEN} characters"
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(DomainError::InvalidInput(format!(
+            "{kind} name must not contain control characters"
+        )));
+    }
+    Ok(())
+}
+
+/// Attempts at finding a free slug before session creation gives up
+pub const MAX_SLUG_ATTEMPTS: u32 = 10;
+
+/// Longest slug derived from a session name
+const MAX_SLUG_LEN: usize = 40;
+
+const SLUG_ADJECTIVES: [&str; 16] = [
+    "brave", "calm", "clever", "eager", "fuzzy", "gentle", "happy", "jolly", "keen", "lucky",
+    "mellow", "nimble", "proud", "quick", "sunny", "witty",
+];
+
+const SLUG_ANIMALS: [&str; 16] = [
+    "badger", "beaver", "falcon", "ferret", "gecko", "heron", "koala", "lemur", "lynx", "marten",
+    "otter", "panda", "puffin", "raven", "walrus", "yak",
+];
+
+/// Lowercase `name`, keeping letters and digits and joining the rest with dashes
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_SLUG_LEN);
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Random human-friendly slug such as `brave-otter-42`
+pub fn random_slug() -> String {
+    let bytes = *Uuid::new_v4().as_bytes();
+    format!(
+        "{}-{}-{}",
+        SLUG_ADJECTIVES[bytes[0] as usize % SLUG_ADJECTIVES.len()],
+        SLUG_ANIMALS[bytes[1] as usize % SLUG_ANIMALS.len()],
+        10 + bytes[2] % 90
+    )
+}
+
+/// Slug to try for a session named `name` on the given attempt
+///
+/// A name is used as its own slug first, then with a number appended.
+/// Names with nothing to slugify get a fresh random slug each attempt.
+pub fn slug_candidate(name: &str, attempt: u32) -> String {
+    let base = slugify(name);
+    match (base.is_empty(), attempt) {
+        (true, _) => random_slug(),
+        (false, 0) => base,
+        (false, n) => format!("{base}-{}", n + 1),
+    }
+}
+
+/// A session as shown in listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    #[serde(flatten)]
+    pub session: ForkSession,
+    pub snapshot_count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Pending,
+    Running,
+    Stopped,
+    Failed,
+}
+
+impl SessionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionStatus::Pending => "pending",
+            SessionStatus::Running => "running",
+            SessionStatus::Stopped => "stopped",
+            SessionStatus::Failed => "failed",
+        }
+    }
+
+    /// Whether the session lifecycle allows moving from this status to `next`
+    ///
+    /// Stopped sessions can be started again; failed sessions are final.
+    pub fn can_transition_to(&self, next: SessionStatus) -> bool {
+        matches!(
+            (self, next),
+            (
+                SessionStatus::Pending | SessionStatus::Stopped,
+                SessionStatus::Running
+            ) | (
+                SessionStatus::Pending | SessionStatus::Running,
+                SessionStatus::Stopped | SessionStatus::Failed
+            )
+        )
+    }
+}
+
+impl FromStr for SessionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(SessionStatus::Pending),
+            "running" => Ok(SessionStatus::Running),
+            "stopped" => Ok(SessionStatus::Stopped),
+            "failed" => Ok(SessionStatus::Failed),
+            _ => Err(format!("Unknown session status: {s}")),
+        }
+    }
+}
+
+/// Access level granted to a collaborator on someone else's session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollaboratorAccess {
+    Read,
+    Write,
+}
+
+impl CollaboratorAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollaboratorAccess::Read => "read",
+            CollaboratorAccess::Write => "write",
+        }
+    }
+}
+
+impl FromStr for CollaboratorAccess {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(CollaboratorAccess::Read),
+            "write" => Ok(CollaboratorAccess::Write),
+            _ => Err(format!("Unknown collaborator access: {s}")),
+        }
+    }
+}
+
+/// Usage reported by the client running a session
+///
+/// Heartbeats carry running totals, so the stored usage is the largest
+/// value seen and a replayed or reordered heartbeat changes nothing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub session_id: SessionId,
+    pub elapsed_seconds: u64,
+    pub peak_memory_bytes: u64,
+    pub last_heartbeat_at: DateTime<Utc>,
+}
+
+/// Sessions found open in the database with nothing running them
+///
+/// A session counts as a zombie once its client has stopped sending usage
+/// heartbeats, e.g. because the machine running it crashed before `down`
+/// could mark it stopped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZombieReport {
+    /// Sessions silent since before this were zombies
+    pub cutoff: DateTime<Utc>,
+    /// Zombies found, with the status the database still gave them
+    pub zombies: Vec<ZombieSession>,
+    /// How many were marked failed; 0 in a dry run
+    pub failed: u64,
+    pub dry_run: bool,
+}
+
+/// A session the database thinks is live but nothing is running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZombieSession {
+    pub session_id: SessionId,
+    pub status: SessionStatus,
+    /// Last heartbeat, or `None` if it never sent one
+    pub last_heartbeat_at: Option<DateTime<Utc>>,
+}
+
+/// A user granted access to a session they don't own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCollaborator {
+    pub session_id: SessionId,
+    pub user_id: UserId,
+    pub access: CollaboratorAccess,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_session_checks_name_and_slug() {
+        let user_id = UserId::new_v4();
+        let session = ForkSession::new(
+            user_id,
+            "Panic 2245".to_string(),
+            "panic-2245".to_string(),
+            None,
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(session.status, SessionStatus::Pending);
+
+        let invalid = [
+            (" ".to_string(), "blank".to_string()),
+            ("x".repeat(MAX_NAME_LEN + 1), "long".to_string()),
+            ("tab\there".to_string(), "tab".to_string()),
+            ("fine".to_string(), "Not A Slug".to_string()),
+            ("fine".to_string(), String::new()),
+        ];
+        for (name, slug) in invalid {
+            assert!(matches!(
+                ForkSession::new(user_id, name, slug, None, Utc::now()),
+                Err(DomainError::InvalidInput(_))
+            ));
+        }
+    }
+}
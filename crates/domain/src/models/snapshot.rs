@@ -0,0 +1,124 @@
+use super::ids::{SessionId, SnapshotId, UserId};
+use super::session::validate_name;
+use crate::errors::DomainError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Longest snapshot description
+pub const MAX_DESCRIPTION_LEN: usize = 1024;
+
+/// A saved point-in-time state of a fork session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: SnapshotId,
+    pub session_id: SessionId,
+    pub user_id: UserId,
+    pub name: String,
+    pub description: Option<String>,
+    /// Mainnet slot of the session's state when taken; `None` means latest
+    pub fork_slot: Option<u64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Snapshot {
+    /// A new snapshot of a session taken at `now`, checking its name and
+    /// description
+    pub fn new(
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+        fork_slot: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, DomainError> {
+        validate_name("Snapshot", &name)?;
+        if description
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LEN)
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "Snapshot description must be at most {MAX_DESCRIPTION_LEN} characters"
+            )));
+        }
+
+        Ok(Self {
+            id: SnapshotId::new_v7(),
+            session_id,
+            user_id,
+            name,
+            description,
+            fork_slot,
+            created_at: now,
+        })
+    }
+}
+
+/// Server-side selection of snapshots for bulk operations
+///
+/// Written as `older-than:<n><unit>`, where the unit is `h` (hours) or
+/// `d` (days), e.g. `older-than:30d`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFilter {
+    OlderThan(Duration),
+}
+
+impl FromStr for SnapshotFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Unknown snapshot filter {s:?}; expected e.g. older-than:30d");
+
+        let age = s
+            .strip_prefix("older-than:")
+            .filter(|age| age.is_ascii())
+            .ok_or_else(invalid)?;
+        let (amount, unit) = age.split_at(age.len().saturating_sub(1));
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        let age = match unit {
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            _ => None,
+        }
+        .filter(|age| *age > Duration::zero())
+        .ok_or_else(invalid)?;
+
+        Ok(SnapshotFilter::OlderThan(age))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_snapshot_checks_name_and_description() {
+        let (session_id, user_id) = (SessionId::new_v4(), UserId::new_v4());
+        let now = Utc::now();
+        let snapshot = Snapshot::new(
+            session_id,
+            user_id,
+            "before".to_string(),
+            None,
+            Some(7),
+            now,
+        );
+        assert_eq!(snapshot.unwrap().fork_slot, Some(7));
+
+        assert!(matches!(
+            Snapshot::new(session_id, user_id, String::new(), None, None, now),
+            Err(DomainError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            Snapshot::new(
+                session_id,
+                user_id,
+                "before".to_string(),
+                Some("x".repeat(MAX_DESCRIPTION_LEN + 1)),
+                None,
+                now
+            ),
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::SubscriptionTier;
+
+/// Business KPIs for the admin dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStats {
+    /// New users per UTC day, oldest first; days without signups are omitted
+    pub signups_per_day: Vec<DailyCount>,
+    /// Sessions currently pending or running
+    pub active_sessions: u64,
+    /// How many users have each number of active sessions, fewest first
+    ///
+    /// Users without an active session aren't counted.
+    pub quota_utilization: Vec<UtilizationBucket>,
+    /// Paid subscription churn per tier over the reporting window
+    ///
+    /// Tiers that have never had a subscription are omitted.
+    pub churn: Vec<TierChurn>,
+    /// Share of subscriptions live during the window that were cancelled in
+    /// it, across all tiers; 0 when there were none
+    pub churn_rate: f64,
+}
+
+/// A count for one UTC day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCount {
+    pub day: NaiveDate,
+    pub count: u64,
+}
+
+/// Subscription churn for one tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierChurn {
+    pub tier: SubscriptionTier,
+    /// Subscriptions whose tier still applies (active or past due)
+    pub subscribed: u64,
+    /// Subscriptions cancelled within the window
+    pub cancelled: u64,
+    /// Live subscriptions with a payment that failed within the window
+    pub payment_failed: u64,
+}
+
+/// Number of users with a given number of active sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtilizationBucket {
+    pub active_sessions: u64,
+    pub users: u64,
+}
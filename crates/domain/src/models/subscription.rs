@@ -0,0 +1,67 @@
+use super::ids::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::models::user::SubscriptionTier;
+
+/// A user's paid subscription, mirrored from the payment provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub user_id: UserId,
+    pub tier: SubscriptionTier,
+    pub status: SubscriptionStatus,
+    /// Payment provider subscription identifier (e.g. Stripe's `sub_...`)
+    pub provider_subscription_id: String,
+    /// Payments failed since the last successful one
+    pub failed_payment_count: u32,
+    pub last_payment_failed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Subscription {
+    /// Whether the subscription's tier applies to the user's requests
+    ///
+    /// Past-due subscriptions keep their tier while the provider retries
+    /// the payment.
+    pub fn grants_tier(&self) -> bool {
+        matches!(
+            self.status,
+            SubscriptionStatus::Active | SubscriptionStatus::PastDue
+        )
+    }
+}
+
+/// Whether a subscription's tier currently applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Active,
+    /// A payment failed; the provider is retrying it
+    PastDue,
+    Cancelled,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::Active => "active",
+            SubscriptionStatus::PastDue => "past_due",
+            SubscriptionStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for SubscriptionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(SubscriptionStatus::Active),
+            "past_due" => Ok(SubscriptionStatus::PastDue),
+            "cancelled" => Ok(SubscriptionStatus::Cancelled),
+            _ => Err(format!("Unknown subscription status: {s}")),
+        }
+    }
+}
@@ -1,13 +1,415 @@
+use super::ids::UserId;
+use super::region::validate_region;
+use super::session::validate_name;
+use crate::errors::DomainError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use std::str::FromStr;
+
+/// Longest email address, per RFC 5321
+const MAX_EMAIL_LEN: usize = 254;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
-    pub id: Uuid,
+    pub id: UserId,
     pub primary_email: String,
+    /// Shown instead of the email address when set
+    pub display_name: Option<String>,
+    /// Where to send account mail instead of `primary_email`, which comes
+    /// from GitHub
+    pub contact_email: Option<String>,
+    /// Region new sessions are placed in unless another is asked for
+    pub preferred_region: Option<String>,
     pub github_user_id: Option<i64>,
     pub stripe_customer_id: Option<String>,
+    /// ISO 3166-1 alpha-2 country used for tax calculation (e.g. "DE")
+    pub billing_country: Option<String>,
+    pub status: UserStatus,
+    #[serde(default)]
+    pub role: UserRole,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+impl User {
+    /// A new active user created at `now`, checking the email address
+    pub fn new(
+        primary_email: String,
+        github_user_id: Option<i64>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, DomainError> {
+        if !is_email(&primary_email) {
+            return Err(DomainError::InvalidInput(format!(
+                "{primary_email:?} is not an email address"
+            )));
+        }
+
+        Ok(Self {
+            id: UserId::new_v7(),
+            primary_email,
+            display_name: None,
+            contact_email: None,
+            preferred_region: None,
+            github_user_id,
+            stripe_customer_id: None,
+            billing_country: None,
+            status: UserStatus::Active,
+            role: UserRole::Member,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+/// Changes to some of a user's fields
+///
+/// Fields left as `None` keep their stored value, so concurrent patches of
+/// different fields don't overwrite each other.
+///
+/// ```
+/// # use domain::models::UserPatch;
+/// let patch = UserPatch::new()
+///     .primary_email("ada@example.com")
+///     .display_name(Some("Ada"));
+/// assert!(!patch.is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserPatch {
+    pub primary_email: Option<String>,
+    /// `Some(None)` clears the display name
+    pub display_name: Option<Option<String>>,
+    /// `Some(None)` clears the contact email
+    pub contact_email: Option<Option<String>>,
+    /// `Some(None)` clears the preferred region
+    pub preferred_region: Option<Option<String>>,
+    pub stripe_customer_id: Option<String>,
+    pub status: Option<UserStatus>,
+    pub role: Option<UserRole>,
+}
+
+impl UserPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn primary_email(mut self, email: impl Into<String>) -> Self {
+        self.primary_email = Some(email.into());
+        self
+    }
+
+    pub fn display_name(mut self, name: Option<impl Into<String>>) -> Self {
+        self.display_name = Some(name.map(Into::into));
+        self
+    }
+
+    pub fn contact_email(mut self, email: Option<impl Into<String>>) -> Self {
+        self.contact_email = Some(email.map(Into::into));
+        self
+    }
+
+    pub fn preferred_region(mut self, region: Option<impl Into<String>>) -> Self {
+        self.preferred_region = Some(region.map(Into::into));
+        self
+    }
+
+    pub fn stripe_customer_id(mut self, customer_id: impl Into<String>) -> Self {
+        self.stripe_customer_id = Some(customer_id.into());
+        self
+    }
+
+    pub fn status(mut self, status: UserStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn role(mut self, role: UserRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Whether the patch changes nothing
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Check the email addresses, display name and region it sets
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let contact_email = self.contact_email.as_ref().and_then(Option::as_ref);
+        if let Some(email) = self
+            .primary_email
+            .iter()
+            .chain(contact_email)
+            .find(|email| !is_email(email))
+        {
+            return Err(DomainError::InvalidInput(format!(
+                "{email:?} is not an email address"
+            )));
+        }
+        if let Some(Some(name)) = &self.display_name {
+            validate_name("Display", name)?;
+        }
+        if let Some(Some(region)) = &self.preferred_region {
+            validate_region(region)?;
+        }
+        Ok(())
+    }
+}
+
+/// Profile fields a user may change themselves
+///
+/// `None` leaves a field unchanged. For the optional fields, a blank value
+/// clears them.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileChanges {
+    pub primary_email: Option<String>,
+    pub display_name: Option<String>,
+    pub contact_email: Option<String>,
+    pub preferred_region: Option<String>,
+}
+
+impl ProfileChanges {
+    /// The patch making these changes, with values trimmed and regions
+    /// lowercased
+    pub fn into_patch(self) -> UserPatch {
+        fn cleared_if_blank(value: &str) -> Option<&str> {
+            let value = value.trim();
+            (!value.is_empty()).then_some(value)
+        }
+
+        let mut patch = UserPatch::new();
+        if let Some(email) = self.primary_email {
+            patch = patch.primary_email(email.trim());
+        }
+        if let Some(name) = self.display_name {
+            patch = patch.display_name(cleared_if_blank(&name));
+        }
+        if let Some(email) = self.contact_email {
+            patch = patch.contact_email(cleared_if_blank(&email));
+        }
+        if let Some(region) = self.preferred_region {
+            patch = patch.preferred_region(cleared_if_blank(&region).map(str::to_lowercase));
+        }
+        patch
+    }
+}
+
+/// Attributes an identity provider manages for the users it provisions
+///
+/// `None` leaves a field unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryChanges {
+    pub primary_email: Option<String>,
+    /// `Some(None)` clears the display name
+    pub display_name: Option<Option<String>>,
+    pub role: Option<UserRole>,
+    /// `false` suspends the user, `true` lifts a suspension
+    pub active: Option<bool>,
+}
+
+impl DirectoryChanges {
+    /// The patch making these changes, apart from `active`
+    pub fn to_patch(&self) -> UserPatch {
+        let mut patch = UserPatch::new();
+        if let Some(email) = &self.primary_email {
+            patch = patch.primary_email(email.trim());
+        }
+        if let Some(name) = &self.display_name {
+            patch = patch.display_name(
+                name.as_deref()
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty()),
+            );
+        }
+        if let Some(role) = self.role {
+            patch = patch.role(role);
+        }
+        patch
+    }
+}
+
+/// Loose `local@domain.tld` check; deliverability is the provider's problem
+fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    email.len() <= MAX_EMAIL_LEN
+        && !local.is_empty()
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Account state controlling whether a user may use the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatus {
+    Active,
+    Suspended,
+    Deleted,
+}
+
+impl UserStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::Suspended => "suspended",
+            UserStatus::Deleted => "deleted",
+        }
+    }
+}
+
+impl FromStr for UserStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(UserStatus::Active),
+            "suspended" => Ok(UserStatus::Suspended),
+            "deleted" => Ok(UserStatus::Deleted),
+            _ => Err(format!("Unknown user status: {s}")),
+        }
+    }
+}
+
+/// What a user may do beyond using their own account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    Member,
+    /// May call the admin endpoints with their own API token
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Member => "member",
+            UserRole::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "member" => Ok(UserRole::Member),
+            "admin" => Ok(UserRole::Admin),
+            _ => Err(format!("Unknown user role: {s}")),
+        }
+    }
+}
+
+/// Subscription tier determining feature access and usage limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionTier {
+    Entry,
+    Lite,
+    Pro,
+}
+
+impl SubscriptionTier {
+    pub const ALL: [SubscriptionTier; 3] = [
+        SubscriptionTier::Entry,
+        SubscriptionTier::Lite,
+        SubscriptionTier::Pro,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionTier::Entry => "entry",
+            SubscriptionTier::Lite => "lite",
+            SubscriptionTier::Pro => "pro",
+        }
+    }
+}
+
+impl FromStr for SubscriptionTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "entry" => Ok(SubscriptionTier::Entry),
+            "lite" => Ok(SubscriptionTier::Lite),
+            "pro" => Ok(SubscriptionTier::Pro),
+            _ => Err(format!("Unknown subscription tier: {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_user_checks_email() {
+        let now = Utc::now();
+        let user = User::new("ada@example.com".to_string(), Some(42), now).unwrap();
+        assert_eq!(user.status, UserStatus::Active);
+        assert_eq!(user.created_at, now);
+        assert!(User::new("123+ada@users.noreply.github.com".to_string(), None, now).is_ok());
+
+        for invalid in [
+            "",
+            "ada",
+            "@example.com",
+            "ada@",
+            "ada@localhost",
+            "a da@x.io",
+            "a@b@c.io",
+        ] {
+            assert!(
+                matches!(
+                    User::new(invalid.to_string(), None, now),
+                    Err(DomainError::InvalidInput(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_user_patch() {
+        assert!(UserPatch::new().is_empty());
+        assert!(UserPatch::new().validate().is_ok());
+
+        let clear = UserPatch::new().display_name(None::<String>);
+        assert_eq!(clear.display_name, Some(None));
+        assert!(!clear.is_empty());
+        assert!(clear.validate().is_ok());
+
+        for invalid in [
+            UserPatch::new().primary_email("ada"),
+            UserPatch::new().display_name(Some("  ")),
+            UserPatch::new().display_name(Some("line\nbreak")),
+            UserPatch::new().contact_email(Some("ada")),
+            UserPatch::new().preferred_region(Some("EU West")),
+        ] {
+            assert!(
+                matches!(invalid.validate(), Err(DomainError::InvalidInput(_))),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_profile_changes_trim_and_clear() {
+        let patch = ProfileChanges {
+            primary_email: None,
+            display_name: Some(" Ada ".to_string()),
+            contact_email: Some(" ".to_string()),
+            preferred_region: Some("EU".to_string()),
+        }
+        .into_patch();
+
+        assert_eq!(patch.primary_email, None);
+        assert_eq!(patch.display_name, Some(Some("Ada".to_string())));
+        assert_eq!(patch.contact_email, Some(None));
+        assert_eq!(patch.preferred_region, Some(Some("eu".to_string())));
+        assert!(patch.validate().is_ok());
+    }
+}
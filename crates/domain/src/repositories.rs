@@ -12,8 +12,9 @@
 //! - No implementation details or database-specific types
 
 use crate::errors::DomainError;
-use crate::models::{AuthToken, User};
+use crate::models::{AuthToken, PendingDeviceFlow, ProviderToken, User, UserId, UserPatch};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 /// Repository for user data operations
@@ -22,16 +23,42 @@ use uuid::Uuid;
 /// retrieval by various identifiers, and updates.
 #[async_trait]
 pub trait UserRepository: Send + Sync {
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, DomainError>;
+    async fn find_by_id(&self, id: UserId) -> Result<Option<User>, DomainError>;
     async fn find_by_email(&self, email: &str) -> Result<Option<User>, DomainError>;
     async fn find_by_github_id(&self, github_id: i64) -> Result<Option<User>, DomainError>;
+    /// The user signing in as `subject` at the OpenID Connect `issuer`
+    async fn find_by_oidc_subject(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, DomainError>;
+    /// Let the user sign in as `subject` at the OpenID Connect `issuer`
+    async fn link_oidc_subject(
+        &self,
+        id: UserId,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<(), DomainError>;
     async fn find_by_stripe_customer_id(
         &self,
         stripe_customer_id: &str,
     ) -> Result<Option<User>, DomainError>;
     async fn create(&self, user: &User) -> Result<User, DomainError>;
+    /// Update a user, returning it with its new `updated_at`
+    ///
+    /// Fails with `Conflict` if the stored user's `updated_at` no longer
+    /// matches `user.updated_at`, i.e. someone else updated it first.
     async fn update(&self, user: &User) -> Result<User, DomainError>;
-    async fn delete(&self, id: Uuid) -> Result<(), DomainError>;
+    /// Change only the fields set in `patch`, returning the updated user
+    ///
+    /// Unlike `update` this never conflicts, since the fields it leaves out
+    /// aren't written back.
+    async fn patch(&self, id: UserId, patch: &UserPatch) -> Result<User, DomainError>;
+    async fn delete(&self, id: UserId) -> Result<(), DomainError>;
+    /// Page through the users that aren't deleted, oldest first
+    async fn find_users_page(&self, limit: u32, offset: u32) -> Result<Vec<User>, DomainError>;
+    /// How many users aren't deleted
+    async fn count_users(&self) -> Result<u64, DomainError>;
 }
 
 /// Repository for authentication tokens
@@ -40,11 +67,32 @@ pub trait UserRepository: Send + Sync {
 #[async_trait]
 pub trait AuthRepository: Send + Sync {
     async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<AuthToken>, DomainError>;
-    async fn find_by_user_id(&self, user_id: Uuid) -> Result<Vec<AuthToken>, DomainError>;
+    async fn find_by_user_id(&self, user_id: UserId) -> Result<Vec<AuthToken>, DomainError>;
     async fn create(&self, token: &AuthToken) -> Result<AuthToken, DomainError>;
     async fn update_last_used(&self, id: Uuid) -> Result<(), DomainError>;
     async fn delete(&self, id: Uuid) -> Result<(), DomainError>;
     async fn delete_expired(&self) -> Result<u64, DomainError>;
+
+    /// Store the user's login provider tokens, replacing any previous ones
+    async fn save_provider_token(
+        &self,
+        user_id: UserId,
+        token: &ProviderToken,
+    ) -> Result<(), DomainError>;
+    async fn find_provider_token(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<ProviderToken>, DomainError>;
+
+    /// Remember a device-flow login until it finishes or expires
+    async fn save_device_flow(&self, flow: &PendingDeviceFlow) -> Result<(), DomainError>;
+    async fn find_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<Option<PendingDeviceFlow>, DomainError>;
+    async fn delete_device_flow(&self, device_code: &str) -> Result<(), DomainError>;
+    /// Delete device flows that expired by `now`, returning how many were removed
+    async fn delete_expired_device_flows(&self, now: DateTime<Utc>) -> Result<u64, DomainError>;
 }
 
 /// Repository for Github data
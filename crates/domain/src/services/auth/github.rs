@@ -1,12 +1,16 @@
 use anyhow::Error;
-use chrono::Utc;
-use uuid::Uuid;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
 
 use crate::errors::DomainError;
-use crate::models::AuthToken;
+use crate::models::{AccessGroup, AuthToken, PendingDeviceFlow, ProviderToken, UserId};
 use crate::repositories::AuthRepository;
 use crate::services::auth::types::{AuthError, DeviceCodeResponse};
 use crate::services::auth::{ApiToken, AuthenticatedUser, TokenService};
+use crate::services::clock::{system_clock, Clock};
+use crate::services::ids::{time_ordered_ids, IdGenerator};
 
 /// Domain-defined contract for device flow authentication
 ///
@@ -18,12 +22,30 @@ pub trait DeviceFlowProvider: Send + Sync {
     async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError>;
 
     /// Poll for user authorization completion
-    async fn poll_authorization(&self, device_code: &str) -> Result<String, AuthError>;
+    async fn poll_authorization(&self, device_code: &str) -> Result<ProviderToken, AuthError>;
+
+    /// Trade a refresh token for a new token pair
+    async fn refresh_token(&self, refresh_token: &str) -> Result<ProviderToken, DomainError>;
 
     /// Fetch user information using an access token
     async fn get_user(&self, access_token: &str) -> Result<AuthenticatedUser, DomainError>;
+
+    /// Whether `username`, who `access_token` belongs to, is an active
+    /// member of `group`
+    async fn is_member(
+        &self,
+        access_token: &str,
+        username: &str,
+        group: &AccessGroup,
+    ) -> Result<bool, DomainError>;
 }
 
+/// Provider tokens this close to expiry are refreshed before use
+const PROVIDER_TOKEN_REFRESH_MARGIN: Duration = Duration::minutes(5);
+
+/// How often group membership is rechecked unless configured otherwise
+const DEFAULT_GROUP_RECHECK: Duration = Duration::hours(1);
+
 /// Domain service for authentication operations
 ///
 /// This service orchestrates authentication flows using the injected provider.
@@ -31,6 +53,13 @@ pub trait DeviceFlowProvider: Send + Sync {
 pub struct AuthService<P: DeviceFlowProvider, R: AuthRepository> {
     provider: P,
     auth_repository: R,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    /// Groups a user must be in one of; empty lets everyone in
+    required_groups: Vec<AccessGroup>,
+    group_recheck: Duration,
+    /// When each user last passed the group check
+    group_checked_at: Mutex<HashMap<UserId, DateTime<Utc>>>,
 }
 
 impl<P: DeviceFlowProvider, R: AuthRepository> AuthService<P, R> {
@@ -38,29 +67,254 @@ impl<P: DeviceFlowProvider, R: AuthRepository> AuthService<P, R> {
         Self {
             provider,
             auth_repository,
+            clock: system_clock(),
+            ids: time_ordered_ids(),
+            required_groups: Vec::new(),
+            group_recheck: DEFAULT_GROUP_RECHECK,
+            group_checked_at: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Create a new API token for an authenticated user
+    /// Only let in users who are members of one of `groups`, rechecking
+    /// each user's membership once `recheck` has passed
+    pub fn with_required_groups(mut self, groups: Vec<AccessGroup>, recheck: Duration) -> Self {
+        self.required_groups = groups;
+        self.group_recheck = recheck;
+        self
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give new rows IDs from `ids` instead of time-ordered UUIDs
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Start the device flow, returning the code the user must enter
+    ///
+    /// The flow is stored until [`Self::finish_device_flow`] or its expiry,
+    /// so it can be resumed if the process handling it goes away.
+    pub async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        let response = self.provider.request_device_code().await?;
+        self.auth_repository
+            .save_device_flow(&PendingDeviceFlow {
+                device_code: response.device_code.clone(),
+                user_code: response.user_code.clone(),
+                verification_uri: response.verification_uri.clone(),
+                interval: response.interval,
+                expires_at: self.clock.now() + Duration::seconds(i64::from(response.expires_in)),
+            })
+            .await?;
+        Ok(response)
+    }
+
+    /// The stored device flow for `device_code`, unless it has expired
+    pub async fn resumable_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<Option<PendingDeviceFlow>, DomainError> {
+        Ok(self
+            .auth_repository
+            .find_device_flow(device_code)
+            .await?
+            .filter(|flow| !flow.is_expired_at(self.clock.now())))
+    }
+
+    /// Forget a device flow whose outcome has been handed out
+    pub async fn finish_device_flow(&self, device_code: &str) -> Result<(), DomainError> {
+        self.auth_repository.delete_device_flow(device_code).await
+    }
+
+    /// Delete device flows past their expiry, returning how many were removed
+    pub async fn purge_expired_device_flows(&self) -> Result<u64, DomainError> {
+        self.auth_repository
+            .delete_expired_device_flows(self.clock.now())
+            .await
+    }
+
+    /// Wait for the user to authorize the device, returning the provider tokens
+    pub async fn wait_for_authorization(
+        &self,
+        device_code: &str,
+    ) -> Result<ProviderToken, AuthError> {
+        self.provider.poll_authorization(device_code).await
+    }
+
+    /// Delete API tokens past their expiry, returning how many were removed
+    pub async fn purge_expired_tokens(&self) -> Result<u64, DomainError> {
+        self.auth_repository.delete_expired().await
+    }
+
+    /// Remember a user's provider tokens so they can be refreshed later
+    pub async fn save_provider_token(
+        &self,
+        user_id: UserId,
+        token: &ProviderToken,
+    ) -> Result<(), DomainError> {
+        self.auth_repository
+            .save_provider_token(user_id, token)
+            .await
+    }
+
+    /// A working provider access token for the user
+    ///
+    /// Tokens about to expire are refreshed first, so callers never hand
+    /// the provider a stale one.
+    pub async fn provider_access_token(&self, user_id: UserId) -> Result<String, DomainError> {
+        let token = self.stored_provider_token(user_id).await?;
+        if token.refresh_token.is_some()
+            && token.expires_before(self.clock.now() + PROVIDER_TOKEN_REFRESH_MARGIN)
+        {
+            return Ok(self.refresh_provider_token(user_id).await?.access_token);
+        }
+
+        if token.expires_before(self.clock.now()) {
+            return Err(DomainError::Unauthorized(
+                "Provider token has expired, log in again".to_string(),
+            ));
+        }
+        Ok(token.access_token)
+    }
+
+    /// Trade the user's stored refresh token for a new token pair
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::Unauthorized` if there is no usable refresh
+    /// token, in which case the user has to log in again.
+    pub async fn refresh_provider_token(
+        &self,
+        user_id: UserId,
+    ) -> Result<ProviderToken, DomainError> {
+        let token = self.stored_provider_token(user_id).await?;
+        let refresh_token = token
+            .refresh_token
+            .filter(|_| {
+                token
+                    .refresh_token_expires_at
+                    .is_none_or(|expires_at| expires_at > self.clock.now())
+            })
+            .ok_or_else(|| {
+                DomainError::Unauthorized("No valid refresh token stored, log in again".to_string())
+            })?;
+
+        let refreshed = self.provider.refresh_token(&refresh_token).await?;
+        self.auth_repository
+            .save_provider_token(user_id, &refreshed)
+            .await?;
+        Ok(refreshed)
+    }
+
+    async fn stored_provider_token(&self, user_id: UserId) -> Result<ProviderToken, DomainError> {
+        self.auth_repository
+            .find_provider_token(user_id)
+            .await?
+            .ok_or_else(|| {
+                DomainError::Unauthorized("No provider token stored, log in again".to_string())
+            })
+    }
+
+    /// Fetch the provider identity behind an access token
+    pub async fn get_user(&self, access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        self.provider.get_user(access_token).await
+    }
+
+    /// Check that a provider identity is in one of the required groups
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::Forbidden` if groups are required and the user
+    /// is in none of them.
+    pub async fn check_group_access(
+        &self,
+        access_token: &str,
+        user: &AuthenticatedUser,
+    ) -> Result<(), DomainError> {
+        if self.required_groups.is_empty() {
+            return Ok(());
+        }
+
+        for group in &self.required_groups {
+            if self
+                .provider
+                .is_member(access_token, &user.username, group)
+                .await?
+            {
+                return Ok(());
+            }
+        }
+        Err(DomainError::Forbidden(format!(
+            "{} is not a member of an organization or team allowed on this server",
+            user.username
+        )))
+    }
+
+    /// Recheck a user's group membership once the last check is too old
+    ///
+    /// Uses the stored provider token, so a user who left every required
+    /// group loses access within the recheck interval. If the provider
+    /// can't be reached, a user who passed before keeps access until the
+    /// next recheck rather than being locked out by the outage.
+    pub async fn ensure_group_access(&self, user_id: UserId) -> Result<(), DomainError> {
+        if self.required_groups.is_empty() {
+            return Ok(());
+        }
+
+        let now = self.clock.now();
+        let checked_at = self.group_checked_at.lock().unwrap().get(&user_id).copied();
+        if checked_at.is_some_and(|at| now - at < self.group_recheck) {
+            return Ok(());
+        }
+
+        let result = async {
+            let access_token = self.provider_access_token(user_id).await?;
+            let user = self.provider.get_user(&access_token).await?;
+            self.check_group_access(&access_token, &user).await
+        }
+        .await;
+        match result {
+            Ok(()) => {}
+            Err(DomainError::ExternalService(e)) if checked_at.is_some() => {
+                tracing::warn!(%user_id, error = %e, "Could not recheck group membership");
+            }
+            Err(e) => {
+                self.group_checked_at.lock().unwrap().remove(&user_id);
+                return Err(e);
+            }
+        }
+
+        self.group_checked_at.lock().unwrap().insert(user_id, now);
+        Ok(())
+    }
+
+    /// Create a new API token for a user
+    ///
+    /// Only the salted hash is stored; the returned token is the only copy
+    /// of the secret.
     pub async fn create_api_token(
         &self,
-        _user: AuthenticatedUser,
-        user_id: Uuid,
+        user_id: UserId,
+        name: Option<String>,
     ) -> Result<ApiToken, DomainError> {
         // Generate new token
-        let token = TokenService::generate_api_token();
+        let secret = TokenService::generate_api_token();
 
         // Hash with user_id as salt
-        let token_hash = TokenService::hash_token(&token, &user_id.to_string());
+        let token_hash = TokenService::hash_token(&secret, &user_id.to_string());
 
         // Create credentials record
         let credentials = AuthToken {
-            id: Uuid::new_v4(),
+            id: self.ids.new_id(),
             user_id,
             token_hash,
-            name: todo!(),
+            name,
             expires_at: None, // No expiry for now
-            created_at: Utc::now(),
+            created_at: self.clock.now(),
             last_used_at: None,
         };
 
@@ -69,19 +323,51 @@ impl<P: DeviceFlowProvider, R: AuthRepository> AuthService<P, R> {
 
         // Return unhashed token to user
         Ok(ApiToken {
-            token,
+            token: TokenService::format_api_token(user_id, &secret),
             expiry: None,
         })
     }
 
+    /// Resolve the user owning an API token, recording that the token was used
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::Unauthorized` if the token is malformed, unknown
+    /// or expired.
+    pub async fn authenticate(&self, api_token: &str) -> Result<UserId, DomainError> {
+        let invalid = || DomainError::Unauthorized("Invalid API token".to_string());
+
+        let (user_id, secret) = TokenService::parse_api_token(api_token).ok_or_else(invalid)?;
+        let token_hash = TokenService::hash_token(secret, &user_id.to_string());
+
+        let token = self
+            .auth_repository
+            .find_by_token_hash(&token_hash)
+            .await?
+            .filter(|token| token.user_id == user_id)
+            .ok_or_else(invalid)?;
+
+        if token
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= self.clock.now())
+        {
+            return Err(DomainError::Unauthorized(
+                "API token has expired".to_string(),
+            ));
+        }
+
+        self.auth_repository.update_last_used(token.id).await?;
+        Ok(user_id)
+    }
+
     pub async fn complete_auth_flow(&self, _device_code: &str) -> Result<(), Error> {
         let device_code_response = self.provider.request_device_code().await?;
         // NOTE: We wait here for the user to use the OTP.
-        let access_token = self
+        let token = self
             .provider
             .poll_authorization(&device_code_response.device_code)
             .await?;
-        let _user_details = self.provider.get_user(&access_token).await?;
+        let _user_details = self.provider.get_user(&token.access_token).await?;
 
         // TODO: Need to get or create user_id here before creating token
         // For now, just return Ok - the actual user creation/lookup logic
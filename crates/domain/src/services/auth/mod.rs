@@ -1,4 +1,5 @@
 pub mod github;
+pub mod oidc;
 pub mod token_service;
 pub mod types;
 
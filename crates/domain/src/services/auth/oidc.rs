@@ -0,0 +1,36 @@
+use crate::errors::DomainError;
+
+/// What an OpenID Connect provider asserts about a signed-in user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcClaims {
+    /// Issuer identifier of the provider, e.g. `https://login.corp.example`
+    pub issuer: String,
+    /// The user's ID at the provider, stable for the life of their account
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider checked that the user owns `email`
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// Domain-defined contract for OpenID Connect single sign-on
+///
+/// Users sign in at the provider's authorization endpoint with the
+/// authorization code flow and PKCE; the code they come back with is
+/// redeemed here for their identity.
+#[async_trait::async_trait]
+pub trait OidcProvider: Send + Sync {
+    /// Where users are sent to sign in
+    async fn authorization_endpoint(&self) -> Result<String, DomainError>;
+
+    /// Redeem an authorization code for the claims of the user who signed in
+    ///
+    /// `code_verifier` is the PKCE secret whose challenge started the sign-in,
+    /// and `redirect_uri` the URI the code was sent to.
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<OidcClaims, DomainError>;
+}
@@ -1,3 +1,4 @@
+use crate::models::UserId;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
@@ -21,6 +22,21 @@ impl TokenService {
         let result = hasher.finalize();
         format!("{result:x}")
     }
+
+    /// Format the token handed to users as `<user_id>.<secret>`
+    ///
+    /// Embedding the user ID lets the server recompute the salted hash of
+    /// the secret without a lookup table.
+    pub fn format_api_token(user_id: UserId, secret: &str) -> String {
+        format!("{user_id}.{secret}")
+    }
+
+    /// Split a user-facing token into its user ID and secret
+    pub fn parse_api_token(token: &str) -> Option<(UserId, &str)> {
+        let (user_id, secret) = token.split_once('.')?;
+        let user_id = user_id.parse().ok()?;
+        (!secret.is_empty()).then_some((user_id, secret))
+    }
 }
 
 #[cfg(test)]
@@ -61,4 +77,21 @@ mod tests {
         let different_salt_hash = TokenService::hash_token(token, "different-user-id");
         assert_ne!(hash1, different_salt_hash);
     }
+
+    #[test]
+    fn test_api_token_round_trip() {
+        let user_id = UserId::new_v4();
+        let secret = TokenService::generate_api_token();
+        let token = TokenService::format_api_token(user_id, &secret);
+
+        assert_eq!(
+            TokenService::parse_api_token(&token),
+            Some((user_id, secret.as_str()))
+        );
+
+        // Malformed tokens don't parse
+        assert_eq!(TokenService::parse_api_token(&secret), None);
+        assert_eq!(TokenService::parse_api_token("not-a-uuid.secret"), None);
+        assert_eq!(TokenService::parse_api_token(&format!("{user_id}.")), None);
+    }
 }
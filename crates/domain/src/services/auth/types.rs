@@ -52,6 +52,23 @@ pub struct CheckAuthorisationResponse {
     pub access_token: String,
     pub token_type: String,
     pub scope: String,
+    /// Only issued when the app uses expiring user tokens
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Seconds until `access_token` expires
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    /// Seconds until `refresh_token` expires
+    #[serde(default)]
+    pub refresh_token_expires_in: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub client_id: String,
+    pub client_secret: String,
+    pub grant_type: String,
+    pub refresh_token: String,
 }
 
 /// Domain-agnostic authenticated user
@@ -69,6 +86,9 @@ pub struct AuthenticatedUser {
     pub email: Option<String>,
     /// Display name if provided
     pub display_name: Option<String>,
+    /// When the account was created at the provider, if it says
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 /// Legacy type for compatibility - to be moved to infrastructure
@@ -78,6 +98,9 @@ pub struct GitHubUser {
     pub login: String,
     pub email: Option<String>,
     pub name: Option<String>,
+    /// When the account was created, e.g. `2011-01-25T18:44:36Z`
+    #[serde(default)]
+    pub created_at: Option<String>,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,105 @@
+use crate::errors::DomainError;
+use crate::models::user::SubscriptionTier;
+use crate::models::{Subscription, UserId, UserPatch};
+use crate::repositories::UserRepository;
+use crate::services::billing::subscriptions::SubscriptionRepository;
+use crate::services::billing::{CustomerId, PaymentProcessor};
+
+/// Domain service sending users to the payment provider's hosted pages
+///
+/// Users start paying through a hosted checkout page and manage their
+/// subscription (plan changes, cards, invoices, cancellation) through the
+/// provider's billing portal; both return to `return_url` when done.
+pub struct CheckoutService<P, R>
+where
+    P: PaymentProcessor,
+    R: UserRepository + SubscriptionRepository,
+{
+    processor: P,
+    repository: R,
+    return_url: String,
+}
+
+impl<P, R> CheckoutService<P, R>
+where
+    P: PaymentProcessor,
+    R: UserRepository + SubscriptionRepository,
+{
+    pub fn new(processor: P, repository: R, return_url: String) -> Self {
+        Self {
+            processor,
+            repository,
+            return_url,
+        }
+    }
+
+    /// URL of a checkout page subscribing the user to `tier`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::InvalidInput` if the user already has a live
+    /// subscription; plan changes go through the billing portal so the
+    /// user isn't charged twice.
+    pub async fn checkout_url(
+        &self,
+        user_id: UserId,
+        tier: SubscriptionTier,
+    ) -> Result<String, DomainError> {
+        let subscription = SubscriptionRepository::find_by_user(&self.repository, user_id).await?;
+        if subscription.as_ref().is_some_and(Subscription::grants_tier) {
+            return Err(DomainError::InvalidInput(
+                "Already subscribed; change plans through the billing portal".to_string(),
+            ));
+        }
+
+        let customer_id = self.ensure_customer(user_id).await?;
+        self.processor
+            .create_checkout_session(
+                &customer_id,
+                tier,
+                &format!("{}?checkout=success", self.return_url),
+                &format!("{}?checkout=cancelled", self.return_url),
+            )
+            .await
+    }
+
+    /// URL of the billing portal for the user's customer account
+    pub async fn portal_url(&self, user_id: UserId) -> Result<String, DomainError> {
+        let customer_id = self.ensure_customer(user_id).await?;
+        self.processor
+            .create_portal_session(&customer_id, &self.return_url)
+            .await
+    }
+
+    /// The user's customer ID, creating the customer on first use
+    ///
+    /// Called at login so users have a customer before they reach checkout;
+    /// checkout and the portal call it again in case that failed.
+    pub async fn ensure_customer(&self, user_id: UserId) -> Result<CustomerId, DomainError> {
+        let user = UserRepository::find_by_id(&self.repository, user_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("User {user_id}")))?;
+        if let Some(customer_id) = user.stripe_customer_id {
+            return Ok(CustomerId(customer_id));
+        }
+
+        let customer_id = self
+            .processor
+            .create_customer(&user.primary_email, &user.id.to_string())
+            .await?;
+        if let Some(country) = &user.billing_country {
+            self.processor
+                .update_customer_country(&customer_id, country)
+                .await?;
+        }
+
+        self.repository
+            .patch(
+                user.id,
+                &UserPatch::new().stripe_customer_id(customer_id.0.clone()),
+            )
+            .await?;
+
+        Ok(customer_id)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::errors::DomainError;
+use crate::models::{BillingEvent, BillingEventKind, UserId};
+use crate::services::clock::{system_clock, Clock};
+use crate::services::ids::{time_ordered_ids, IdGenerator};
+use std::sync::Arc;
+
+/// Domain-defined contract for the billing audit log
+#[async_trait::async_trait]
+pub trait BillingEventRepository: Send + Sync {
+    /// Append an event to the log
+    async fn record(&self, event: &BillingEvent) -> Result<BillingEvent, DomainError>;
+
+    /// List all events for a user, oldest first
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<BillingEvent>, DomainError>;
+
+    /// Page through a user's events, oldest first
+    async fn find_page_by_user(
+        &self,
+        user_id: UserId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<BillingEvent>, DomainError>;
+}
+
+/// Domain service for the append-only billing audit log
+pub struct BillingEventService<R: BillingEventRepository> {
+    repository: R,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl<R: BillingEventRepository> BillingEventService<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            clock: system_clock(),
+            ids: time_ordered_ids(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give new rows IDs from `ids` instead of time-ordered UUIDs
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Record a billing mutation with its before/after values
+    pub async fn record(
+        &self,
+        user_id: UserId,
+        kind: BillingEventKind,
+        before: Option<serde_json::Value>,
+        after: Option<serde_json::Value>,
+        stripe_event_id: Option<String>,
+    ) -> Result<BillingEvent, DomainError> {
+        let event = BillingEvent {
+            id: self.ids.new_id(),
+            user_id,
+            kind,
+            before,
+            after,
+            stripe_event_id,
+            created_at: self.clock.now(),
+        };
+
+        self.repository.record(&event).await
+    }
+
+    /// Full billing history for a user
+    pub async fn list_events(&self, user_id: UserId) -> Result<Vec<BillingEvent>, DomainError> {
+        self.repository.list_by_user(user_id).await
+    }
+
+    /// Page through a user's billing history
+    pub async fn find_events(
+        &self,
+        user_id: UserId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<BillingEvent>, DomainError> {
+        self.repository
+            .find_page_by_user(user_id, limit, offset)
+            .await
+    }
+}
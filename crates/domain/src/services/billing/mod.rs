@@ -1 +1,91 @@
-// Placeholder
+//! # Billing
+//!
+//! Domain contract for payment processing. The infrastructure layer's
+//! `StripeSdk` implements `PaymentProcessor`; the domain never talks to
+//! Stripe directly.
+
+pub mod checkout;
+pub mod events;
+pub mod plans;
+pub mod subscriptions;
+pub mod webhooks;
+
+use crate::errors::DomainError;
+use crate::models::user::SubscriptionTier;
+use crate::models::PlanPrice;
+
+/// Payment provider customer identifier (e.g. Stripe's `cus_...`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomerId(pub String);
+
+/// Payment provider subscription identifier (e.g. Stripe's `sub_...`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionId(pub String);
+
+/// Domain-defined contract for payment processing
+#[async_trait::async_trait]
+pub trait PaymentProcessor: Send + Sync {
+    /// Create a customer for a ForkForge user
+    async fn create_customer(
+        &self,
+        email: &str,
+        external_id: &str,
+    ) -> Result<CustomerId, DomainError>;
+
+    /// Set the customer's billing country so the provider can calculate tax
+    async fn update_customer_country(
+        &self,
+        customer_id: &CustomerId,
+        country: &str,
+    ) -> Result<(), DomainError>;
+
+    /// Current prices for a tier in every currency it is sold in
+    async fn tier_prices(&self, tier: SubscriptionTier) -> Result<Vec<PlanPrice>, DomainError>;
+
+    /// Subscribe a customer to a tier
+    async fn create_subscription(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+    ) -> Result<SubscriptionId, DomainError>;
+
+    /// Move an existing subscription to a different tier
+    async fn update_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+        new_tier: SubscriptionTier,
+    ) -> Result<(), DomainError>;
+
+    /// Cancel a subscription
+    async fn cancel_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+    ) -> Result<(), DomainError>;
+
+    /// Start a hosted checkout subscribing the customer to a tier, returning its URL
+    async fn create_checkout_session(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+        success_url: &str,
+        cancel_url: &str,
+    ) -> Result<String, DomainError>;
+
+    /// Open a billing portal session for the customer, returning its URL
+    async fn create_portal_session(
+        &self,
+        customer_id: &CustomerId,
+        return_url: &str,
+    ) -> Result<String, DomainError>;
+
+    /// Tier sold at a price, or `None` if the price isn't one of ours
+    async fn tier_for_price(&self, price_id: &str)
+        -> Result<Option<SubscriptionTier>, DomainError>;
+
+    /// Check that a webhook payload was signed by the payment provider
+    async fn verify_webhook_signature(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<bool, DomainError>;
+}
@@ -0,0 +1,104 @@
+use crate::errors::DomainError;
+use crate::models::{Plan, PlanDefinition, PlanLimits, SubscriptionTier};
+use crate::services::billing::PaymentProcessor;
+use std::sync::Arc;
+
+/// Domain-defined contract for reading the plan catalog
+#[async_trait::async_trait]
+pub trait PlanRepository: Send + Sync {
+    /// All plan definitions, in display order
+    async fn list_plans(&self) -> Result<Vec<PlanDefinition>, DomainError>;
+}
+
+/// Tier definitions loaded once at startup
+///
+/// Limits and feature flags live in storage rather than code so they can be
+/// changed without a release; changes take effect on the next restart.
+#[derive(Debug, Clone)]
+pub struct PlanCatalog {
+    plans: Vec<PlanDefinition>,
+}
+
+impl PlanCatalog {
+    /// Build a catalog, checking that every tier is defined exactly once
+    pub fn new(plans: Vec<PlanDefinition>) -> Result<Self, DomainError> {
+        for tier in SubscriptionTier::ALL {
+            let count = plans.iter().filter(|plan| plan.tier == tier).count();
+            if count != 1 {
+                return Err(DomainError::Internal(format!(
+                    "Plan catalog must define the {} tier exactly once, found {count}",
+                    tier.as_str()
+                )));
+            }
+        }
+
+        Ok(Self { plans })
+    }
+
+    /// Load the catalog from storage
+    pub async fn load<R: PlanRepository>(repository: &R) -> Result<Self, DomainError> {
+        Self::new(repository.list_plans().await?)
+    }
+
+    /// All plan definitions, in display order
+    pub fn plans(&self) -> &[PlanDefinition] {
+        &self.plans
+    }
+
+    /// Definition of a single tier
+    pub fn plan(&self, tier: SubscriptionTier) -> &PlanDefinition {
+        self.plans
+            .iter()
+            .find(|plan| plan.tier == tier)
+            .expect("PlanCatalog::new checks every tier is defined")
+    }
+
+    /// Usage limits for a tier
+    pub fn limits(&self, tier: SubscriptionTier) -> PlanLimits {
+        self.plan(tier).limits
+    }
+
+    /// Whether a tier includes a feature flag
+    pub fn has_feature(&self, tier: SubscriptionTier, feature: &str) -> bool {
+        self.plan(tier).features.iter().any(|f| f == feature)
+    }
+}
+
+/// Domain service describing the plans customers can subscribe to
+///
+/// Tier names, limits and features come from the plan catalog; prices come
+/// from the payment processor so they always match what customers are
+/// charged.
+pub struct PlanService<P: PaymentProcessor> {
+    catalog: Arc<PlanCatalog>,
+    /// Absent when billing is not configured; plans are then listed
+    /// without prices
+    processor: Option<P>,
+}
+
+impl<P: PaymentProcessor> PlanService<P> {
+    pub fn new(catalog: Arc<PlanCatalog>, processor: Option<P>) -> Self {
+        Self { catalog, processor }
+    }
+
+    /// All plans with their limits and localized prices
+    pub async fn list_plans(&self) -> Result<Vec<Plan>, DomainError> {
+        let mut plans = Vec::with_capacity(self.catalog.plans().len());
+        for definition in self.catalog.plans() {
+            let prices = match &self.processor {
+                Some(processor) => processor.tier_prices(definition.tier).await?,
+                None => Vec::new(),
+            };
+
+            plans.push(Plan {
+                tier: definition.tier,
+                name: definition.name.clone(),
+                limits: definition.limits,
+                features: definition.features.clone(),
+                prices,
+            });
+        }
+
+        Ok(plans)
+    }
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use crate::errors::DomainError;
+use crate::models::user::SubscriptionTier;
+use crate::models::{BillingEvent, BillingEventKind, Subscription, SubscriptionStatus, UserId};
+use crate::services::billing::events::BillingEventRepository;
+use crate::services::clock::{system_clock, Clock};
+use crate::services::ids::{time_ordered_ids, IdGenerator};
+
+/// Domain-defined contract for subscription persistence
+#[async_trait::async_trait]
+pub trait SubscriptionRepository: Send + Sync {
+    /// The user's subscription, if they have ever had one
+    async fn find_by_user(&self, user_id: UserId) -> Result<Option<Subscription>, DomainError>;
+
+    /// Insert the user's subscription, replacing any previous one
+    async fn upsert(&self, subscription: &Subscription) -> Result<Subscription, DomainError>;
+}
+
+/// Domain service keeping subscriptions in step with the payment provider
+///
+/// Every change is also appended to the billing audit log, tagged with the
+/// provider event that caused it.
+pub struct SubscriptionService<R>
+where
+    R: SubscriptionRepository + BillingEventRepository,
+{
+    repository: R,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl<R> SubscriptionService<R>
+where
+    R: SubscriptionRepository + BillingEventRepository,
+{
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            clock: system_clock(),
+            ids: time_ordered_ids(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give new rows IDs from `ids` instead of time-ordered UUIDs
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// The user's subscription, if they have ever had one
+    pub async fn get_subscription(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<Subscription>, DomainError> {
+        self.repository.find_by_user(user_id).await
+    }
+
+    /// Tier whose limits apply to the user
+    ///
+    /// Users without a live subscription are on Entry.
+    pub async fn effective_tier(&self, user_id: UserId) -> Result<SubscriptionTier, DomainError> {
+        Ok(self
+            .repository
+            .find_by_user(user_id)
+            .await?
+            .filter(Subscription::grants_tier)
+            .map_or(SubscriptionTier::Entry, |s| s.tier))
+    }
+
+    /// Start (or restart) a paid subscription on `tier`
+    pub async fn activate_subscription(
+        &self,
+        user_id: UserId,
+        tier: SubscriptionTier,
+        provider_subscription_id: &str,
+        stripe_event_id: Option<String>,
+    ) -> Result<Subscription, DomainError> {
+        self.update_subscription(
+            user_id,
+            tier,
+            SubscriptionStatus::Active,
+            provider_subscription_id,
+            stripe_event_id,
+        )
+        .await
+    }
+
+    /// Bring the stored subscription in line with the provider's
+    pub async fn update_subscription(
+        &self,
+        user_id: UserId,
+        tier: SubscriptionTier,
+        status: SubscriptionStatus,
+        provider_subscription_id: &str,
+        stripe_event_id: Option<String>,
+    ) -> Result<Subscription, DomainError> {
+        let existing = self.repository.find_by_user(user_id).await?;
+        let now = self.clock.now();
+        // Becoming active means the outstanding payment went through
+        let (failed_payment_count, last_payment_failed_at) = match &existing {
+            Some(s) if status != SubscriptionStatus::Active => {
+                (s.failed_payment_count, s.last_payment_failed_at)
+            }
+            _ => (0, None),
+        };
+        let subscription = self
+            .repository
+            .upsert(&Subscription {
+                user_id,
+                tier,
+                status,
+                provider_subscription_id: provider_subscription_id.to_string(),
+                failed_payment_count,
+                last_payment_failed_at,
+                created_at: existing.as_ref().map_or(now, |s| s.created_at),
+                updated_at: now,
+            })
+            .await?;
+
+        let unchanged = existing
+            .as_ref()
+            .is_some_and(|s| s.tier == tier && s.status == status);
+        if !unchanged {
+            self.record_change(
+                user_id,
+                existing.as_ref(),
+                Some(&subscription),
+                stripe_event_id,
+            )
+            .await?;
+        }
+
+        Ok(subscription)
+    }
+
+    /// Mark the user's subscription cancelled
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::NotFound` if the user has no subscription.
+    pub async fn cancel_subscription(
+        &self,
+        user_id: UserId,
+        stripe_event_id: Option<String>,
+    ) -> Result<Subscription, DomainError> {
+        let existing = self
+            .repository
+            .find_by_user(user_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Subscription for user {user_id}")))?;
+
+        self.update_subscription(
+            user_id,
+            existing.tier,
+            SubscriptionStatus::Cancelled,
+            &existing.provider_subscription_id,
+            stripe_event_id,
+        )
+        .await
+    }
+
+    /// Record a failed payment, marking the subscription past due
+    pub async fn record_payment_failure(
+        &self,
+        user_id: UserId,
+        details: serde_json::Value,
+        stripe_event_id: Option<String>,
+    ) -> Result<(), DomainError> {
+        if let Some(mut subscription) = self.repository.find_by_user(user_id).await? {
+            let now = self.clock.now();
+            // Cancelled subscriptions stay cancelled
+            if subscription.status == SubscriptionStatus::Active {
+                subscription.status = SubscriptionStatus::PastDue;
+            }
+            subscription.failed_payment_count += 1;
+            subscription.last_payment_failed_at = Some(now);
+            subscription.updated_at = now;
+            self.repository.upsert(&subscription).await?;
+        }
+
+        self.repository
+            .record(&BillingEvent {
+                id: self.ids.new_id(),
+                user_id,
+                kind: BillingEventKind::PaymentFailed,
+                before: None,
+                after: Some(details),
+                stripe_event_id,
+                created_at: self.clock.now(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Append a tier or status change to the billing audit log
+    async fn record_change(
+        &self,
+        user_id: UserId,
+        before: Option<&Subscription>,
+        after: Option<&Subscription>,
+        stripe_event_id: Option<String>,
+    ) -> Result<(), DomainError> {
+        let snapshot = |s: &Subscription| serde_json::json!({ "tier": s.tier.as_str(), "status": s.status.as_str() });
+
+        self.repository
+            .record(&BillingEvent {
+                id: self.ids.new_id(),
+                user_id,
+                kind: BillingEventKind::TierChanged,
+                before: before.map(snapshot),
+                after: after.map(snapshot),
+                stripe_event_id,
+                created_at: self.clock.now(),
+            })
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::errors::DomainError;
+use crate::models::{BillingEvent, BillingEventKind, SubscriptionStatus, User};
+use crate::repositories::UserRepository;
+use crate::services::billing::events::BillingEventRepository;
+use crate::services::billing::subscriptions::{SubscriptionRepository, SubscriptionService};
+use crate::services::billing::PaymentProcessor;
+use crate::services::clock::{system_clock, Clock};
+use crate::services::ids::{time_ordered_ids, IdGenerator};
+
+/// Domain-defined contract for remembering which webhook events were handled
+///
+/// Stripe delivers events at least once, so redeliveries are skipped by ID.
+#[async_trait::async_trait]
+pub trait ProcessedEventRepository: Send + Sync {
+    /// Whether an event has already been handled
+    async fn is_event_processed(&self, event_id: &str) -> Result<bool, DomainError>;
+
+    /// Remember that an event was handled
+    async fn mark_event_processed(
+        &self,
+        event_id: &str,
+        event_type: &str,
+    ) -> Result<(), DomainError>;
+}
+
+/// A verified webhook event from Stripe
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: serde_json::Value,
+    pub created: i64,
+}
+
+/// The refunded charge or credit note carried by a refund event
+#[derive(Debug, Deserialize)]
+struct RefundObject {
+    id: String,
+    customer: Option<String>,
+    /// Set on charges
+    amount_refunded: Option<i64>,
+    /// Set on credit notes
+    total: Option<i64>,
+    currency: String,
+}
+
+/// The subscription carried by `customer.subscription.*` events
+#[derive(Debug, Deserialize)]
+struct SubscriptionObject {
+    id: String,
+    customer: String,
+    status: String,
+    items: StripeList<SubscriptionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeList<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionItem {
+    price: PriceObject,
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceObject {
+    id: String,
+}
+
+/// The invoice carried by `invoice.payment_failed` events
+#[derive(Debug, Deserialize)]
+struct InvoiceObject {
+    id: String,
+    customer: Option<String>,
+    amount_due: Option<i64>,
+    currency: Option<String>,
+    attempt_count: Option<i64>,
+}
+
+/// Map a Stripe subscription status onto ours
+///
+/// Incomplete subscriptions haven't been paid for yet, so they don't grant
+/// their tier until Stripe reports them active.
+fn subscription_status(stripe_status: &str) -> SubscriptionStatus {
+    match stripe_status {
+        "active" | "trialing" => SubscriptionStatus::Active,
+        "past_due" | "unpaid" | "incomplete" => SubscriptionStatus::PastDue,
+        _ => SubscriptionStatus::Cancelled,
+    }
+}
+
+fn event_object<T: serde::de::DeserializeOwned>(event: &WebhookEvent) -> Result<T, DomainError> {
+    serde_json::from_value(event.data["object"].clone()).map_err(|e| {
+        DomainError::InvalidInput(format!("Malformed {} event: {e}", event.event_type))
+    })
+}
+
+/// Domain service for processing Stripe webhooks
+pub struct StripeWebhookService<P, R, S>
+where
+    P: PaymentProcessor,
+    R: UserRepository + BillingEventRepository + ProcessedEventRepository,
+    S: SubscriptionRepository + BillingEventRepository,
+{
+    processor: P,
+    repository: R,
+    subscriptions: SubscriptionService<S>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl<P, R, S> StripeWebhookService<P, R, S>
+where
+    P: PaymentProcessor,
+    R: UserRepository + BillingEventRepository + ProcessedEventRepository,
+    S: SubscriptionRepository + BillingEventRepository,
+{
+    pub fn new(processor: P, repository: R, subscriptions: SubscriptionService<S>) -> Self {
+        Self {
+            processor,
+            repository,
+            subscriptions,
+            clock: system_clock(),
+            ids: time_ordered_ids(),
+        }
+    }
+
+    /// Read the time from `clock`, here and in the subscription service
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.subscriptions = self.subscriptions.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Give new rows IDs from `ids`, here and in the subscription service
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.subscriptions = self.subscriptions.with_id_generator(ids.clone());
+        self.ids = ids;
+        self
+    }
+
+    /// Verify a raw webhook payload against its `Stripe-Signature` header and handle it
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::Unauthorized` if the signature doesn't match and
+    /// `DomainError::InvalidInput` if the payload isn't a Stripe event.
+    pub async fn process_webhook(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<WebhookEvent, DomainError> {
+        if !self
+            .processor
+            .verify_webhook_signature(payload, signature)
+            .await?
+        {
+            return Err(DomainError::Unauthorized(
+                "Invalid webhook signature".to_string(),
+            ));
+        }
+
+        let event: WebhookEvent = serde_json::from_slice(payload)
+            .map_err(|e| DomainError::InvalidInput(format!("Malformed webhook event: {e}")))?;
+
+        if self.repository.is_event_processed(&event.id).await? {
+            tracing::debug!(event_id = %event.id, "Skipping already processed Stripe event");
+            return Ok(event);
+        }
+
+        // Only remembered once handled, so a failed event is retried by Stripe
+        self.handle_event(&event).await?;
+        self.repository
+            .mark_event_processed(&event.id, &event.event_type)
+            .await?;
+        Ok(event)
+    }
+
+    async fn handle_event(&self, event: &WebhookEvent) -> Result<(), DomainError> {
+        match event.event_type.as_str() {
+            "charge.refunded" | "credit_note.created" => self.record_refund(event).await,
+            "customer.subscription.created"
+            | "customer.subscription.updated"
+            | "customer.subscription.deleted" => self.sync_subscription(event).await,
+            "invoice.payment_failed" => self.record_payment_failure(event).await,
+            _ => {
+                tracing::info!(
+                    event_id = %event.id,
+                    event_type = %event.event_type,
+                    "Received unhandled Stripe event"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// The user behind a Stripe customer, if we know them
+    ///
+    /// Events for customers we don't know about can't be attributed; they are
+    /// acknowledged rather than failed, or Stripe would retry them forever.
+    async fn find_customer(
+        &self,
+        event: &WebhookEvent,
+        customer: Option<&str>,
+    ) -> Result<Option<User>, DomainError> {
+        let user = match customer {
+            Some(customer_id) => {
+                self.repository
+                    .find_by_stripe_customer_id(customer_id)
+                    .await?
+            }
+            None => None,
+        };
+
+        if user.is_none() {
+            tracing::warn!(
+                event_id = %event.id,
+                event_type = %event.event_type,
+                customer = ?customer,
+                "Ignoring Stripe event for unknown customer"
+            );
+        }
+        Ok(user)
+    }
+
+    /// Apply a created, updated or deleted subscription to the user's account
+    async fn sync_subscription(&self, event: &WebhookEvent) -> Result<(), DomainError> {
+        let subscription: SubscriptionObject = event_object(event)?;
+        let Some(user) = self
+            .find_customer(event, Some(&subscription.customer))
+            .await?
+        else {
+            return Ok(());
+        };
+        let stripe_event_id = Some(event.id.clone());
+
+        if event.event_type == "customer.subscription.deleted" {
+            return match self
+                .subscriptions
+                .cancel_subscription(user.id, stripe_event_id)
+                .await
+            {
+                // Nothing to cancel if we never saw it start
+                Ok(_) | Err(DomainError::NotFound(_)) => Ok(()),
+                Err(e) => Err(e),
+            };
+        }
+
+        let price_id = subscription
+            .items
+            .data
+            .first()
+            .map(|item| item.price.id.as_str())
+            .ok_or_else(|| {
+                DomainError::InvalidInput(format!(
+                    "Stripe subscription {} has no items",
+                    subscription.id
+                ))
+            })?;
+        let Some(tier) = self.processor.tier_for_price(price_id).await? else {
+            tracing::warn!(
+                event_id = %event.id,
+                price_id,
+                "Ignoring subscription to a price no tier is sold at"
+            );
+            return Ok(());
+        };
+
+        let status = subscription_status(&subscription.status);
+        if event.event_type == "customer.subscription.created"
+            && status == SubscriptionStatus::Active
+        {
+            self.subscriptions
+                .activate_subscription(user.id, tier, &subscription.id, stripe_event_id)
+                .await?;
+        } else {
+            self.subscriptions
+                .update_subscription(user.id, tier, status, &subscription.id, stripe_event_id)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Record a failed invoice payment against the customer's subscription
+    async fn record_payment_failure(&self, event: &WebhookEvent) -> Result<(), DomainError> {
+        let invoice: InvoiceObject = event_object(event)?;
+        let Some(user) = self
+            .find_customer(event, invoice.customer.as_deref())
+            .await?
+        else {
+            return Ok(());
+        };
+
+        self.subscriptions
+            .record_payment_failure(
+                user.id,
+                serde_json::json!({
+                    "invoice": invoice.id,
+                    "amount": invoice.amount_due,
+                    "currency": invoice.currency,
+                    "attempt_count": invoice.attempt_count,
+                }),
+                Some(event.id.clone()),
+            )
+            .await
+    }
+
+    /// Record a refunded charge or credit note in the billing audit log
+    async fn record_refund(&self, event: &WebhookEvent) -> Result<(), DomainError> {
+        let refund: RefundObject = event_object(event)?;
+        let Some(user) = self
+            .find_customer(event, refund.customer.as_deref())
+            .await?
+        else {
+            return Ok(());
+        };
+
+        self.repository
+            .record(&BillingEvent {
+                id: self.ids.new_id(),
+                user_id: user.id,
+                kind: BillingEventKind::Refunded,
+                before: None,
+                after: Some(serde_json::json!({
+                    "refund_source": refund.id,
+                    "amount": refund.amount_refunded.or(refund.total),
+                    "currency": refund.currency,
+                })),
+                stripe_event_id: Some(event.id.clone()),
+                created_at: self.clock.now(),
+            })
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,79 @@
-// Placeholder for forking service
-// This module will contain:
-// - Account cloning logic
-// - Validator spawning and management
-// - RPC interactions with mainnet
+//! # Forking
+//!
+//! Domain contract for reading mainnet state when a fork is created. The
+//! infrastructure layer's `HeliusClient` implements `ForkStateProvider`.
+
+use crate::errors::DomainError;
+use crate::models::{AccountState, ForkState};
+
+/// Domain-defined contract for fetching mainnet state at fork time
+///
+/// `slot` is the earliest slot the state may reflect. Providers backed by a
+/// regular RPC node serve the latest state and only guarantee it is not
+/// older than `slot`; exact historical state needs an archival source.
+#[async_trait::async_trait]
+pub trait ForkStateProvider: Send + Sync {
+    /// Latest confirmed slot
+    async fn current_slot(&self) -> Result<u64, DomainError>;
+
+    /// Fetch accounts; missing accounts are left out of the result
+    async fn fetch_accounts(
+        &self,
+        pubkeys: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError>;
+
+    /// Fetch programs together with any program data accounts they use
+    async fn fetch_programs(
+        &self,
+        program_ids: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError>;
+
+    /// Fetch the clock and rent sysvars
+    async fn fetch_sysvars(&self, slot: u64) -> Result<Vec<AccountState>, DomainError>;
+}
+
+/// Capture the state of the listed accounts at `slot`, or the latest slot
+///
+/// Executable accounts are refetched as programs so their program data
+/// comes along. Fails if any listed account doesn't exist.
+pub async fn capture_fork_state<F: ForkStateProvider>(
+    provider: &F,
+    pubkeys: &[String],
+    slot: Option<u64>,
+) -> Result<ForkState, DomainError> {
+    let slot = match slot {
+        Some(slot) => slot,
+        None => provider.current_slot().await?,
+    };
+
+    let fetched = provider.fetch_accounts(pubkeys, slot).await?;
+    if let Some(missing) = pubkeys
+        .iter()
+        .find(|pubkey| !fetched.iter().any(|account| &account.pubkey == *pubkey))
+    {
+        return Err(DomainError::InvalidInput(format!(
+            "Account {missing} does not exist on mainnet"
+        )));
+    }
+
+    let (program_accounts, accounts): (Vec<_>, Vec<_>) =
+        fetched.into_iter().partition(|account| account.executable);
+    let program_ids: Vec<String> = program_accounts
+        .into_iter()
+        .map(|account| account.pubkey)
+        .collect();
+    let programs = if program_ids.is_empty() {
+        Vec::new()
+    } else {
+        provider.fetch_programs(&program_ids, slot).await?
+    };
+
+    Ok(ForkState {
+        slot,
+        accounts,
+        programs,
+        sysvars: provider.fetch_sysvars(slot).await?,
+    })
+}
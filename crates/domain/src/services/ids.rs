@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+/// Domain-defined source of IDs for new rows
+pub trait IdGenerator: Send + Sync {
+    fn new_id(&self) -> Uuid;
+}
+
+/// UUIDv7 IDs, which start with their creation time
+///
+/// New rows land at the end of primary key indexes instead of at random
+/// places in them, and IDs sort in the order they were created.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeOrderedIds;
+
+impl IdGenerator for TimeOrderedIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// Random UUIDv4 IDs, which reveal nothing about when they were created
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// The generator services use unless given another
+pub(crate) fn time_ordered_ids() -> Arc<dyn IdGenerator> {
+    Arc::new(TimeOrderedIds)
+}
@@ -0,0 +1,48 @@
+use crate::errors::DomainError;
+use crate::models::License;
+use crate::services::clock::{system_clock, Clock};
+use std::sync::Arc;
+
+/// Domain-defined contract for offline license verification
+///
+/// Implementations check the key's signature locally and never call home,
+/// so self-hosted deployments work without network access.
+pub trait LicenseVerifier: Send + Sync {
+    /// Verify a license key's signature and decode its claims
+    fn verify(&self, license_key: &str) -> Result<License, DomainError>;
+}
+
+/// Domain service for self-hosted license validation
+pub struct LicenseService<V: LicenseVerifier> {
+    verifier: V,
+    clock: Arc<dyn Clock>,
+}
+
+impl<V: LicenseVerifier> LicenseService<V> {
+    pub fn new(verifier: V) -> Self {
+        Self {
+            verifier,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Validate a license key, rejecting keys that are expired
+    pub fn validate(&self, license_key: &str) -> Result<License, DomainError> {
+        let license = self.verifier.verify(license_key.trim())?;
+
+        if license.is_expired_at(self.clock.now()) {
+            return Err(DomainError::Unauthorized(format!(
+                "License for {} expired at {}",
+                license.licensee, license.expires_at
+            )));
+        }
+
+        Ok(license)
+    }
+}
@@ -1,7 +1,15 @@
 pub mod auth;
 pub mod billing;
+pub mod clock;
 pub mod forking;
 pub mod http;
 pub mod http_service;
+pub mod ids;
+pub mod license;
+pub mod quota;
+pub mod resolver;
+pub mod retention;
 pub mod sessions;
 pub mod snapshots;
+pub mod stats;
+pub mod users;
@@ -0,0 +1,143 @@
+use crate::errors::DomainError;
+use crate::models::{SessionId, SessionStatus, SessionUsage, SubscriptionTier, UserId};
+use crate::services::billing::plans::PlanCatalog;
+use crate::services::clock::{system_clock, Clock};
+use crate::services::sessions::SessionRepository;
+use crate::services::snapshots::SnapshotRepository;
+use std::sync::Arc;
+
+/// Slack allowed for client clocks when checking reported elapsed time
+const CLOCK_SKEW_SECONDS: i64 = 5 * 60;
+
+/// Domain service enforcing per-tier usage limits from the plan catalog
+///
+/// Limits that are hit return `DomainError::QuotaExceeded`, whose message
+/// says which plan allows more.
+pub struct QuotaService<S: SessionRepository + SnapshotRepository> {
+    catalog: Arc<PlanCatalog>,
+    sessions: S,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: SessionRepository + SnapshotRepository> QuotaService<S> {
+    pub fn new(catalog: Arc<PlanCatalog>, sessions: S) -> Self {
+        Self {
+            catalog,
+            sessions,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Check the user may start another session on their tier
+    pub async fn check_session_quota(
+        &self,
+        user_id: UserId,
+        tier: SubscriptionTier,
+    ) -> Result<(), DomainError> {
+        let plan = self.catalog.plan(tier);
+        let active = self
+            .sessions
+            .list_by_user(user_id)
+            .await?
+            .iter()
+            .filter(|session| {
+                matches!(
+                    session.status,
+                    SessionStatus::Pending | SessionStatus::Running
+                )
+            })
+            .count();
+
+        if active >= plan.limits.max_concurrent_sessions as usize {
+            return Err(DomainError::QuotaExceeded(format!(
+                "The {} plan allows {} concurrent session(s); stop a session or upgrade",
+                plan.name, plan.limits.max_concurrent_sessions
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check another snapshot may be taken of a session on its owner's tier
+    pub async fn check_snapshot_quota(
+        &self,
+        session_id: SessionId,
+        tier: SubscriptionTier,
+    ) -> Result<(), DomainError> {
+        let plan = self.catalog.plan(tier);
+        let taken = SnapshotRepository::list_by_session(&self.sessions, session_id)
+            .await?
+            .len();
+
+        if taken >= plan.limits.max_snapshots_per_session as usize {
+            return Err(DomainError::QuotaExceeded(format!(
+                "The {} plan allows {} snapshot(s) per session; delete a snapshot or upgrade",
+                plan.name, plan.limits.max_snapshots_per_session
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Record a usage heartbeat from the client running a session
+    ///
+    /// Heartbeats carry running totals and are merged by keeping the largest
+    /// values, so clients can resend buffered heartbeats safely. A session
+    /// that has run longer than its owner's `tier` allows is stopped, and the
+    /// heartbeat is answered with `QuotaExceeded`.
+    pub async fn record_usage(
+        &self,
+        session_id: SessionId,
+        elapsed_seconds: u64,
+        peak_memory_bytes: u64,
+        tier: SubscriptionTier,
+    ) -> Result<SessionUsage, DomainError> {
+        let session = SessionRepository::find_by_id(&self.sessions, session_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+
+        // A session can't have run longer than it has existed
+        let lifetime = (self.clock.now() - session.created_at).num_seconds() + CLOCK_SKEW_SECONDS;
+        if elapsed_seconds > lifetime.max(0) as u64 {
+            return Err(DomainError::InvalidInput(format!(
+                "Reported {elapsed_seconds}s of usage for a session created {}s ago",
+                lifetime - CLOCK_SKEW_SECONDS
+            )));
+        }
+
+        let usage = SessionRepository::record_usage(
+            &self.sessions,
+            session_id,
+            elapsed_seconds,
+            peak_memory_bytes,
+        )
+        .await?;
+
+        let plan = self.catalog.plan(tier);
+        if let Some(max_minutes) = plan.limits.max_session_minutes {
+            if usage.elapsed_seconds > u64::from(max_minutes) * 60 {
+                if matches!(
+                    session.status,
+                    SessionStatus::Pending | SessionStatus::Running
+                ) {
+                    self.sessions
+                        .update_status(session_id, session.status, SessionStatus::Stopped)
+                        .await?;
+                }
+                return Err(DomainError::QuotaExceeded(format!(
+                    "The {} plan allows sessions of up to {max_minutes} minute(s); \
+                     the session was stopped, upgrade for longer sessions",
+                    plan.name
+                )));
+            }
+        }
+
+        Ok(usage)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::errors::DomainError;
+use crate::models::{SessionId, SnapshotId, UserId};
+use crate::services::sessions::SessionRepository;
+use crate::services::snapshots::SnapshotRepository;
+use std::fmt::Display;
+
+/// Shortest ID prefix accepted, so a stray character can't match everything
+pub const MIN_PREFIX_LEN: usize = 4;
+
+/// Candidates listed when a prefix is ambiguous
+const MAX_CANDIDATES: u32 = 5;
+
+/// Resolves what users type in place of full UUIDs
+///
+/// Sessions can be named by ID, slug or ID prefix, and snapshots by ID or
+/// ID prefix, like Git and Docker do. Slugs and prefixes only match the
+/// user's own sessions and snapshots; shared ones need the full ID.
+pub struct Resolver<R: SessionRepository + SnapshotRepository> {
+    repository: R,
+}
+
+impl<R: SessionRepository + SnapshotRepository> Resolver<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    /// Resolve a session ID, slug or unambiguous ID prefix
+    pub async fn resolve_session(
+        &self,
+        user_id: UserId,
+        key: &str,
+    ) -> Result<SessionId, DomainError> {
+        if let Ok(id) = key.parse() {
+            return Ok(id);
+        }
+
+        if let Some(session) = self.repository.find_by_slug(user_id, key).await? {
+            return Ok(session.id);
+        }
+
+        let matches = match id_prefix(key) {
+            Some(prefix) => {
+                SessionRepository::find_ids_by_prefix(
+                    &self.repository,
+                    user_id,
+                    &prefix,
+                    MAX_CANDIDATES,
+                )
+                .await?
+            }
+            None => Vec::new(),
+        };
+        single_match("Session", key, matches)
+    }
+
+    /// Resolve a snapshot ID or unambiguous ID prefix
+    pub async fn resolve_snapshot(
+        &self,
+        user_id: UserId,
+        key: &str,
+    ) -> Result<SnapshotId, DomainError> {
+        if let Ok(id) = key.parse() {
+            return Ok(id);
+        }
+
+        let matches = match id_prefix(key) {
+            Some(prefix) => {
+                SnapshotRepository::find_ids_by_prefix(
+                    &self.repository,
+                    user_id,
+                    &prefix,
+                    MAX_CANDIDATES,
+                )
+                .await?
+            }
+            None => Vec::new(),
+        };
+        single_match("Snapshot", key, matches)
+    }
+}
+
+/// Normalise `key` as a UUID prefix, if it could be one
+fn id_prefix(key: &str) -> Option<String> {
+    let prefix = key.to_ascii_lowercase();
+    let is_hex = prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    if prefix.len() >= MIN_PREFIX_LEN && is_hex {
+        Some(prefix)
+    } else {
+        None
+    }
+}
+
+fn single_match<T: Copy + Display>(
+    kind: &str,
+    key: &str,
+    matches: Vec<T>,
+) -> Result<T, DomainError> {
+    match matches.as_slice() {
+        [] => Err(DomainError::NotFound(format!("{kind} {key}"))),
+        [id] => Ok(*id),
+        candidates => {
+            let candidates: Vec<String> = candidates.iter().map(T::to_string).collect();
+            Err(DomainError::InvalidInput(format!(
+                "{kind} prefix {key} is ambiguous; it matches {}. Use more characters",
+                candidates.join(", ")
+            )))
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::errors::DomainError;
+use crate::models::{RetentionReport, RetentionRule};
+use crate::services::clock::{system_clock, Clock};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+/// Domain-defined contract for purging data past its retention period
+///
+/// With `dry_run` set, each method only counts what it would affect.
+#[async_trait::async_trait]
+pub trait RetentionRepository: Send + Sync {
+    /// Delete API tokens last used (or, if never used, created) before `cutoff`
+    async fn purge_unused_auth_tokens(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError>;
+
+    /// Replace the personal data of users deleted before `cutoff` with placeholders
+    async fn anonymize_deleted_users(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError>;
+
+    /// Delete billing audit events created before `cutoff`
+    async fn purge_billing_events(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError>;
+
+    /// Append a completed purge to the retention audit log
+    async fn record_run(&self, report: &RetentionReport) -> Result<(), DomainError>;
+}
+
+/// Retention period per rule, in days; `None` keeps data forever
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetentionPolicy {
+    pub unused_auth_token_days: Option<u32>,
+    pub deleted_user_days: Option<u32>,
+    pub billing_event_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    fn rules(&self) -> Vec<(RetentionRule, u32)> {
+        [
+            (RetentionRule::UnusedAuthTokens, self.unused_auth_token_days),
+            (RetentionRule::DeletedUsers, self.deleted_user_days),
+            (RetentionRule::BillingEvents, self.billing_event_days),
+        ]
+        .into_iter()
+        .filter_map(|(rule, days)| days.map(|days| (rule, days)))
+        .collect()
+    }
+}
+
+/// Domain service applying the retention policy
+pub struct RetentionService<R: RetentionRepository> {
+    repository: R,
+    policy: RetentionPolicy,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: RetentionRepository> RetentionService<R> {
+    pub fn new(repository: R, policy: RetentionPolicy) -> Self {
+        Self {
+            repository,
+            policy,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Apply every enabled rule, reporting what each affected
+    ///
+    /// Real runs are recorded in the retention audit log; dry runs change
+    /// nothing.
+    pub async fn run(&self, dry_run: bool) -> Result<Vec<RetentionReport>, DomainError> {
+        let now = self.clock.now();
+        let mut reports = Vec::new();
+
+        for (rule, days) in self.policy.rules() {
+            let cutoff = now - Duration::days(i64::from(days));
+            let affected = match rule {
+                RetentionRule::UnusedAuthTokens => {
+                    self.repository
+                        .purge_unused_auth_tokens(cutoff, dry_run)
+                        .await?
+                }
+                RetentionRule::DeletedUsers => {
+                    self.repository
+                        .anonymize_deleted_users(cutoff, dry_run)
+                        .await?
+                }
+                RetentionRule::BillingEvents => {
+                    self.repository
+                        .purge_billing_events(cutoff, dry_run)
+                        .await?
+                }
+            };
+
+            let report = RetentionReport {
+                rule,
+                cutoff,
+                affected,
+                dry_run,
+            };
+            if !dry_run {
+                self.repository.record_run(&report).await?;
+            }
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+}
@@ -1,46 +1,402 @@
 use crate::errors::DomainError;
-use crate::models::ForkSession;
-use uuid::Uuid;
+use crate::models::session::validate_name;
+use crate::models::{
+    AccountState, CollaboratorAccess, ForkSession, SessionCollaborator, SessionId, SessionStatus,
+    SessionSummary, SessionUsage, UserId, ZombieReport, ZombieSession,
+};
+use crate::services::clock::{system_clock, Clock};
+use crate::services::forking::{capture_fork_state, ForkStateProvider};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
 
 /// Domain-defined contract for session management
 #[async_trait::async_trait]
 pub trait SessionRepository: Send + Sync {
     /// Create a new fork session
-    async fn create(&self, user_id: Uuid, name: String) -> Result<ForkSession, DomainError>;
+    ///
+    /// The session gets the first free slug from `slug_candidate`, trying up
+    /// to `MAX_SLUG_ATTEMPTS` times. An empty name is replaced by the slug.
+    async fn create(
+        &self,
+        user_id: UserId,
+        name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<ForkSession, DomainError>;
 
     /// Find session by ID
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<ForkSession>, DomainError>;
+    async fn find_by_id(&self, id: SessionId) -> Result<Option<ForkSession>, DomainError>;
+
+    /// Find one of a user's sessions by its slug
+    async fn find_by_slug(
+        &self,
+        user_id: UserId,
+        slug: &str,
+    ) -> Result<Option<ForkSession>, DomainError>;
+
+    /// IDs of a user's sessions starting with a lowercase UUID prefix, at most `limit`
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SessionId>, DomainError>;
+
+    /// List all sessions owned by a user, newest first
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<ForkSession>, DomainError>;
+
+    /// Page through a user's sessions, newest first, with snapshot counts
+    async fn find_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SessionSummary>, DomainError>;
+
+    /// Count a user's sessions, optionally only those in `status`
+    async fn count_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+    ) -> Result<u64, DomainError>;
 
-    /// Update session
+    /// Update a session, returning it with its new `updated_at`
+    ///
+    /// Fails with `Conflict` if the stored session's `updated_at` no longer
+    /// matches `session.updated_at`, i.e. someone else updated it first.
     async fn update(&self, session: &ForkSession) -> Result<ForkSession, DomainError>;
+
+    /// Move a session from `from` to `to`
+    ///
+    /// Returns `None` without changing anything if the session is no longer
+    /// in `from`, so concurrent transitions can't both succeed.
+    async fn update_status(
+        &self,
+        id: SessionId,
+        from: SessionStatus,
+        to: SessionStatus,
+    ) -> Result<Option<ForkSession>, DomainError>;
+
+    /// Stop every pending or running session owned by a user, returning how many stopped
+    async fn stop_all_by_user(&self, user_id: UserId) -> Result<u64, DomainError>;
+
+    /// Store the mainnet accounts captured for a session
+    async fn save_accounts(
+        &self,
+        session_id: SessionId,
+        accounts: &[AccountState],
+    ) -> Result<(), DomainError>;
+
+    /// Accounts captured for a session
+    async fn list_accounts(&self, session_id: SessionId) -> Result<Vec<AccountState>, DomainError>;
+
+    /// Merge a usage heartbeat into the session's totals, keeping the largest values
+    async fn record_usage(
+        &self,
+        session_id: SessionId,
+        elapsed_seconds: u64,
+        peak_memory_bytes: u64,
+    ) -> Result<SessionUsage, DomainError>;
+
+    /// Pending or running sessions with no heartbeat since `cutoff`
+    ///
+    /// Pending sessions that never sent a heartbeat haven't been started and
+    /// aren't included; running ones are, if they were last updated before
+    /// `cutoff`.
+    async fn find_zombies(&self, cutoff: DateTime<Utc>) -> Result<Vec<ZombieSession>, DomainError>;
+
+    /// Grant a user access to a session, replacing any existing grant
+    async fn upsert_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        access: CollaboratorAccess,
+    ) -> Result<SessionCollaborator, DomainError>;
+
+    /// Find a user's collaborator grant on a session
+    async fn find_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+    ) -> Result<Option<SessionCollaborator>, DomainError>;
 }
 
 /// Domain service for session operations
 pub struct SessionService<R: SessionRepository> {
     repository: R,
+    clock: Arc<dyn Clock>,
 }
 
 impl<R: SessionRepository> SessionService<R> {
     pub fn new(repository: R) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            clock: system_clock(),
+        }
     }
 
-    /// Create a new fork session
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Create a new fork session placed in `region`
     pub async fn create_session(
         &self,
-        user_id: Uuid,
+        user_id: UserId,
+        name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+    ) -> Result<ForkSession, DomainError> {
+        self.repository
+            .create(user_id, name, fork_slot, region, self.clock.now())
+            .await
+    }
+
+    /// Create a fork session seeded with a snapshot of mainnet accounts
+    ///
+    /// State is captured before the session is created, so an unknown
+    /// account fails the request without leaving a session behind. The
+    /// session records the slot the state was read at.
+    pub async fn create_session_with_accounts<F: ForkStateProvider>(
+        &self,
+        provider: &F,
+        user_id: UserId,
         name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+        accounts: &[String],
     ) -> Result<ForkSession, DomainError> {
-        self.repository.create(user_id, name).await
+        let state = capture_fork_state(provider, accounts, fork_slot).await?;
+        let session = self
+            .repository
+            .create(user_id, name, Some(state.slot), region, self.clock.now())
+            .await?;
+
+        let captured: Vec<AccountState> = state.all_accounts().cloned().collect();
+        self.repository.save_accounts(session.id, &captured).await?;
+
+        Ok(session)
     }
 
     /// Get session by ID
-    pub async fn get_session(&self, id: Uuid) -> Result<Option<ForkSession>, DomainError> {
+    pub async fn get_session(&self, id: SessionId) -> Result<Option<ForkSession>, DomainError> {
         self.repository.find_by_id(id).await
     }
 
+    /// List sessions owned by a user
+    pub async fn list_sessions(&self, user_id: UserId) -> Result<Vec<ForkSession>, DomainError> {
+        self.repository.list_by_user(user_id).await
+    }
+
+    /// Page through a user's sessions, optionally only those in `status`
+    pub async fn find_sessions(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SessionSummary>, DomainError> {
+        self.repository
+            .find_by_user(user_id, status, limit, offset)
+            .await
+    }
+
+    /// How many sessions `find_sessions` pages through
+    pub async fn count_sessions(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+    ) -> Result<u64, DomainError> {
+        self.repository.count_by_user(user_id, status).await
+    }
+
     /// Update existing session
     pub async fn update_session(&self, session: &ForkSession) -> Result<ForkSession, DomainError> {
         self.repository.update(session).await
     }
+
+    /// Rename a session, keeping its slug
+    ///
+    /// With `expected_version`, the rename only applies if the session's
+    /// `updated_at` still matches it, so clients don't overwrite changes
+    /// they haven't seen.
+    pub async fn rename_session(
+        &self,
+        id: SessionId,
+        name: String,
+        expected_version: Option<DateTime<Utc>>,
+    ) -> Result<ForkSession, DomainError> {
+        validate_name("Session", &name)?;
+        let session = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {id}")))?;
+
+        if expected_version.is_some_and(|version| version != session.updated_at) {
+            return Err(DomainError::Conflict(format!(
+                "Session {id} was modified since it was read"
+            )));
+        }
+
+        self.repository
+            .update(&ForkSession { name, ..session })
+            .await
+    }
+
+    /// Mark a session as running, e.g. once its validator is up
+    ///
+    /// Also restarts stopped sessions.
+    pub async fn start_session(&self, id: SessionId) -> Result<ForkSession, DomainError> {
+        self.transition(id, SessionStatus::Running).await
+    }
+
+    /// Stop a pending or running session
+    pub async fn stop_session(&self, id: SessionId) -> Result<ForkSession, DomainError> {
+        self.transition(id, SessionStatus::Stopped).await
+    }
+
+    /// Mark a pending or running session as failed
+    pub async fn fail_session(&self, id: SessionId) -> Result<ForkSession, DomainError> {
+        self.transition(id, SessionStatus::Failed).await
+    }
+
+    /// Mark sessions that stopped sending heartbeats `stale_after` ago as failed
+    ///
+    /// With `dry_run` set, zombies are only reported. A zombie that changed
+    /// status since it was found is left alone.
+    pub async fn fail_zombies(
+        &self,
+        stale_after: chrono::Duration,
+        dry_run: bool,
+    ) -> Result<ZombieReport, DomainError> {
+        let cutoff = self.clock.now() - stale_after;
+        let zombies = self.repository.find_zombies(cutoff).await?;
+
+        let mut failed = 0;
+        if !dry_run {
+            for zombie in &zombies {
+                let updated = self
+                    .repository
+                    .update_status(zombie.session_id, zombie.status, SessionStatus::Failed)
+                    .await?;
+                if updated.is_some() {
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok(ZombieReport {
+            cutoff,
+            zombies,
+            failed,
+            dry_run,
+        })
+    }
+
+    /// Move a session to `next`
+    ///
+    /// Fails with `Conflict` if another request changed the session's status
+    /// between the read and the update.
+    async fn transition(
+        &self,
+        id: SessionId,
+        next: SessionStatus,
+    ) -> Result<ForkSession, DomainError> {
+        let session = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {id}")))?;
+
+        if !session.status.can_transition_to(next) {
+            return Err(DomainError::InvalidInput(format!(
+                "Session {id} is {} and cannot become {}",
+                session.status.as_str(),
+                next.as_str()
+            )));
+        }
+
+        self.repository
+            .update_status(id, session.status, next)
+            .await?
+            .ok_or_else(|| DomainError::Conflict(format!("Session {id} changed status, try again")))
+    }
+
+    /// Grant another user read or write access to a session
+    ///
+    /// Only the session owner may add collaborators.
+    pub async fn add_collaborator(
+        &self,
+        owner_id: UserId,
+        session_id: SessionId,
+        collaborator_id: UserId,
+        access: CollaboratorAccess,
+    ) -> Result<SessionCollaborator, DomainError> {
+        self.authorize_owner(session_id, owner_id).await?;
+
+        if collaborator_id == owner_id {
+            return Err(DomainError::InvalidInput(
+                "Session owner is already a collaborator".to_string(),
+            ));
+        }
+
+        self.repository
+            .upsert_collaborator(session_id, collaborator_id, access)
+            .await
+    }
+
+    /// Load a session if the user owns it
+    pub async fn authorize_owner(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+    ) -> Result<ForkSession, DomainError> {
+        let session = self
+            .repository
+            .find_by_id(session_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+
+        if session.user_id != user_id {
+            return Err(DomainError::Forbidden(format!(
+                "Only the owner of session {session_id} can do this"
+            )));
+        }
+        Ok(session)
+    }
+
+    /// Load a session if the user owns it or holds at least `required` access
+    pub async fn authorize_access(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        required: CollaboratorAccess,
+    ) -> Result<ForkSession, DomainError> {
+        let session = self
+            .repository
+            .find_by_id(session_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+
+        if session.user_id == user_id {
+            return Ok(session);
+        }
+
+        match self
+            .repository
+            .find_collaborator(session_id, user_id)
+            .await?
+        {
+            Some(collaborator) if collaborator.access >= required => Ok(session),
+            _ => Err(DomainError::Forbidden(format!(
+                "No {} access to session {session_id}",
+                required.as_str()
+            ))),
+        }
+    }
 }
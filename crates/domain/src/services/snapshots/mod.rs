@@ -1 +1,187 @@
-// Placeholder
+use crate::errors::DomainError;
+use crate::models::{
+    ForkSession, SessionId, SessionStatus, Snapshot, SnapshotFilter, SnapshotId, UserId,
+};
+use crate::services::clock::{system_clock, Clock};
+use crate::services::sessions::SessionRepository;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Domain-defined contract for snapshot persistence
+#[async_trait::async_trait]
+pub trait SnapshotRepository: Send + Sync {
+    /// Create a new snapshot of a session
+    ///
+    /// The session's captured accounts and fork slot are saved with it.
+    async fn create(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Snapshot, DomainError>;
+
+    /// Find snapshot by ID
+    async fn find_by_id(&self, id: SnapshotId) -> Result<Option<Snapshot>, DomainError>;
+
+    /// List all snapshots taken of a session, newest first
+    async fn list_by_session(&self, session_id: SessionId) -> Result<Vec<Snapshot>, DomainError>;
+
+    /// IDs of snapshots starting with a lowercase UUID prefix, at most `limit`
+    ///
+    /// Only snapshots the user took or that were taken of their sessions match.
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError>;
+
+    /// IDs of snapshots created before `cutoff`, oldest first, at most `limit`
+    ///
+    /// Matches the same snapshots as `find_ids_by_prefix`.
+    async fn find_ids_created_before(
+        &self,
+        user_id: UserId,
+        cutoff: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError>;
+
+    /// Replace a session's captured accounts and fork slot with a snapshot's
+    async fn restore(
+        &self,
+        snapshot_id: SnapshotId,
+        session_id: SessionId,
+    ) -> Result<(), DomainError>;
+
+    /// Delete snapshot
+    async fn delete(&self, id: SnapshotId) -> Result<(), DomainError>;
+}
+
+/// Domain service for snapshot operations
+pub struct SnapshotService<R: SnapshotRepository + SessionRepository> {
+    repository: R,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: SnapshotRepository + SessionRepository> SnapshotService<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Create a new snapshot of a session
+    pub async fn create_snapshot(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+    ) -> Result<Snapshot, DomainError> {
+        SnapshotRepository::create(
+            &self.repository,
+            session_id,
+            user_id,
+            name,
+            description,
+            self.clock.now(),
+        )
+        .await
+    }
+
+    /// Get snapshot by ID
+    pub async fn get_snapshot(&self, id: SnapshotId) -> Result<Option<Snapshot>, DomainError> {
+        SnapshotRepository::find_by_id(&self.repository, id).await
+    }
+
+    /// List snapshots taken of a session
+    pub async fn list_snapshots(
+        &self,
+        session_id: SessionId,
+    ) -> Result<Vec<Snapshot>, DomainError> {
+        self.repository.list_by_session(session_id).await
+    }
+
+    /// IDs of the user's snapshots matching `filter`, at most `limit`
+    pub async fn find_matching(
+        &self,
+        user_id: UserId,
+        filter: SnapshotFilter,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError> {
+        match filter {
+            SnapshotFilter::OlderThan(age) => {
+                self.repository
+                    .find_ids_created_before(user_id, self.clock.now() - age, limit)
+                    .await
+            }
+        }
+    }
+
+    /// Restore a snapshot into `target`, or into a new session owned by `user_id`
+    ///
+    /// New sessions are named after the snapshot and placed in the region of
+    /// the session it was taken from. An existing session must not be
+    /// running, since its validator would keep the state it started with.
+    pub async fn restore_snapshot(
+        &self,
+        snapshot_id: SnapshotId,
+        user_id: UserId,
+        target: Option<SessionId>,
+    ) -> Result<ForkSession, DomainError> {
+        let snapshot = SnapshotRepository::find_by_id(&self.repository, snapshot_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Snapshot {snapshot_id}")))?;
+
+        let session_id = match target {
+            Some(session_id) => {
+                let session = SessionRepository::find_by_id(&self.repository, session_id)
+                    .await?
+                    .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+                if session.status == SessionStatus::Running {
+                    return Err(DomainError::InvalidInput(format!(
+                        "Session {} is running; stop it before restoring into it",
+                        session.slug
+                    )));
+                }
+                session.id
+            }
+            None => {
+                // The new session runs where the snapshot was taken
+                let region = SessionRepository::find_by_id(&self.repository, snapshot.session_id)
+                    .await?
+                    .and_then(|source| source.region);
+                SessionRepository::create(
+                    &self.repository,
+                    user_id,
+                    snapshot.name.clone(),
+                    snapshot.fork_slot,
+                    region,
+                    self.clock.now(),
+                )
+                .await?
+                .id
+            }
+        };
+
+        self.repository.restore(snapshot.id, session_id).await?;
+
+        SessionRepository::find_by_id(&self.repository, session_id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))
+    }
+
+    /// Delete snapshot
+    pub async fn delete_snapshot(&self, id: SnapshotId) -> Result<(), DomainError> {
+        self.repository.delete(id).await
+    }
+}
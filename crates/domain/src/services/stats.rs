@@ -0,0 +1,69 @@
+use crate::errors::DomainError;
+use crate::models::{AdminStats, DailyCount, TierChurn, UtilizationBucket};
+use crate::services::clock::{system_clock, Clock};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+/// Domain-defined contract for aggregate queries behind the admin dashboard
+#[async_trait::async_trait]
+pub trait StatsRepository: Send + Sync {
+    /// Users created per UTC day since `since`, oldest first
+    async fn signups_per_day(&self, since: DateTime<Utc>) -> Result<Vec<DailyCount>, DomainError>;
+
+    /// Sessions currently pending or running
+    async fn count_active_sessions(&self) -> Result<u64, DomainError>;
+
+    /// Users grouped by how many pending or running sessions they have
+    async fn active_session_distribution(&self) -> Result<Vec<UtilizationBucket>, DomainError>;
+
+    /// Live, cancelled and payment-failed subscription counts per tier, with
+    /// cancellations and failures counted from `since`
+    async fn subscription_churn(&self, since: DateTime<Utc>)
+        -> Result<Vec<TierChurn>, DomainError>;
+}
+
+/// Domain service computing admin dashboard KPIs
+pub struct StatsService<R: StatsRepository> {
+    repository: R,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: StatsRepository> StatsService<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// KPIs with signups covering the last `days` days
+    pub async fn admin_stats(&self, days: u32) -> Result<AdminStats, DomainError> {
+        let since = self.clock.now() - Duration::days(i64::from(days));
+
+        let churn = self.repository.subscription_churn(since).await?;
+
+        Ok(AdminStats {
+            signups_per_day: self.repository.signups_per_day(since).await?,
+            active_sessions: self.repository.count_active_sessions().await?,
+            quota_utilization: self.repository.active_session_distribution().await?,
+            churn_rate: churn_rate(&churn),
+            churn,
+        })
+    }
+}
+
+/// Cancelled subscriptions over every subscription live during the window
+fn churn_rate(churn: &[TierChurn]) -> f64 {
+    let cancelled: u64 = churn.iter().map(|tier| tier.cancelled).sum();
+    let subscribed: u64 = churn.iter().map(|tier| tier.subscribed).sum();
+    if cancelled + subscribed == 0 {
+        return 0.0;
+    }
+    cancelled as f64 / (cancelled + subscribed) as f64
+}
@@ -0,0 +1,409 @@
+use crate::errors::DomainError;
+use crate::models::session::validate_name;
+use crate::models::{DirectoryChanges, ProfileChanges, User, UserId, UserPatch, UserStatus};
+use crate::repositories::UserRepository;
+use crate::services::auth::oidc::OidcClaims;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::clock::{system_clock, Clock};
+use crate::services::sessions::SessionRepository;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+/// Domain of the addresses GitHub gives users who keep their email private
+const GITHUB_NOREPLY_DOMAIN: &str = "@users.noreply.github.com";
+
+/// Checks on new GitHub accounts, to keep throwaway accounts from farming
+/// free sessions
+///
+/// Only signups are checked; existing users keep logging in.
+#[derive(Debug, Clone, Default)]
+pub struct SignupRules {
+    /// Youngest GitHub account that may sign up
+    pub min_account_age: Option<Duration>,
+    /// Lowercase email domains that may not sign up, e.g. `mailinator.com`
+    pub blocked_email_domains: Vec<String>,
+}
+
+impl SignupRules {
+    /// Reject a GitHub account that breaks a rule at `now`
+    ///
+    /// An account whose age GitHub doesn't share counts as too new.
+    fn check(
+        &self,
+        github_user: &AuthenticatedUser,
+        now: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        if let Some(min_age) = self.min_account_age {
+            let old_enough = github_user
+                .created_at
+                .is_some_and(|created_at| now - created_at >= min_age);
+            if !old_enough {
+                return Err(DomainError::Forbidden(format!(
+                    "GitHub accounts must be at least {} days old to sign up",
+                    min_age.num_days()
+                )));
+            }
+        }
+
+        if let Some(domain) = github_user
+            .email
+            .as_deref()
+            .and_then(|email| email.rsplit_once('@'))
+            .map(|(_, domain)| domain.to_ascii_lowercase())
+        {
+            if self.blocked_email_domains.contains(&domain) {
+                return Err(DomainError::Forbidden(format!(
+                    "Email addresses at {domain} can't be used to sign up"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Domain service for account administration
+pub struct UserService<U: UserRepository, S: SessionRepository> {
+    users: U,
+    sessions: S,
+    signup_rules: SignupRules,
+    clock: Arc<dyn Clock>,
+}
+
+impl<U: UserRepository, S: SessionRepository> UserService<U, S> {
+    pub fn new(users: U, sessions: S) -> Self {
+        Self {
+            users,
+            sessions,
+            signup_rules: SignupRules::default(),
+            clock: system_clock(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Check new GitHub accounts against `rules` before creating users
+    pub fn with_signup_rules(mut self, rules: SignupRules) -> Self {
+        self.signup_rules = rules;
+        self
+    }
+
+    /// Get user by ID
+    pub async fn get_user(&self, id: UserId) -> Result<Option<User>, DomainError> {
+        self.users.find_by_id(id).await
+    }
+
+    /// Find the user linked to a GitHub account, creating one on first login
+    ///
+    /// Users created with GitHub's noreply address get their real email once
+    /// GitHub shares it. GitHub's display name is copied over until the user
+    /// sets one of their own, and dropped if it isn't a valid display name.
+    /// New accounts must pass the signup rules.
+    pub async fn find_or_create_github_user(
+        &self,
+        github_user: &AuthenticatedUser,
+    ) -> Result<User, DomainError> {
+        let github_id: i64 = github_user.provider_id.parse().map_err(|_| {
+            DomainError::ExternalService(format!(
+                "Unexpected GitHub user ID: {}",
+                github_user.provider_id
+            ))
+        })?;
+        let display_name = github_user
+            .display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| validate_name("Display", name).is_ok());
+
+        if let Some(user) = self.users.find_by_github_id(github_id).await? {
+            let mut patch = UserPatch::new();
+            if let Some(email) = &github_user.email {
+                if user.primary_email.ends_with(GITHUB_NOREPLY_DOMAIN) {
+                    patch = patch.primary_email(email);
+                }
+            }
+            if user.display_name.is_none() && display_name.is_some() {
+                patch = patch.display_name(display_name);
+            }
+            if patch.is_empty() {
+                return Ok(user);
+            }
+            patch.validate()?;
+            return self.users.patch(user.id, &patch).await;
+        }
+
+        // Accounts provisioned ahead of their owner's first login are claimed
+        // by email; GitHub only shares verified addresses
+        if let Some(email) = &github_user.email {
+            if let Some(user) = self.users.find_by_email(email).await? {
+                if user.github_user_id.is_none() {
+                    let display_name = user
+                        .display_name
+                        .clone()
+                        .or(display_name.map(str::to_string));
+                    return self
+                        .users
+                        .update(&User {
+                            github_user_id: Some(github_id),
+                            display_name,
+                            ..user
+                        })
+                        .await;
+                }
+            }
+        }
+
+        self.signup_rules.check(github_user, self.clock.now())?;
+
+        // Accounts with a private email and no verified primary fall back to
+        // GitHub's noreply address
+        let email = github_user.email.clone().unwrap_or_else(|| {
+            format!(
+                "{github_id}+{}{GITHUB_NOREPLY_DOMAIN}",
+                github_user.username
+            )
+        });
+        let mut user = User::new(email, Some(github_id), self.clock.now())?;
+        user.display_name = display_name.map(str::to_string);
+        self.users.create(&user).await
+    }
+
+    /// Find the user signing in through single sign-on, creating one on
+    /// first sign-in
+    ///
+    /// A new identity is linked to the account already using its email, but
+    /// only if the provider verified the address; otherwise anyone able to
+    /// set their email at the provider could take that account over.
+    pub async fn find_or_create_oidc_user(&self, claims: &OidcClaims) -> Result<User, DomainError> {
+        if let Some(user) = self
+            .users
+            .find_by_oidc_subject(&claims.issuer, &claims.subject)
+            .await?
+        {
+            return Ok(user);
+        }
+
+        let email = claims
+            .email
+            .as_deref()
+            .map(str::trim)
+            .filter(|email| !email.is_empty())
+            .ok_or_else(|| {
+                DomainError::Forbidden(
+                    "Your identity provider did not share an email address".to_string(),
+                )
+            })?;
+
+        let user = match self.users.find_by_email(email).await? {
+            Some(_) if !claims.email_verified => {
+                return Err(DomainError::Conflict(format!(
+                    "An account already uses {email}, but your identity provider hasn't \
+                     verified that you own it"
+                )));
+            }
+            Some(user) => user,
+            None => {
+                let mut user = User::new(email.to_string(), None, self.clock.now())?;
+                user.display_name = claims
+                    .name
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|name| validate_name("Display", name).is_ok())
+                    .map(str::to_string);
+                self.users.create(&user).await?
+            }
+        };
+        self.users
+            .link_oidc_subject(user.id, &claims.issuer, &claims.subject)
+            .await?;
+        Ok(user)
+    }
+
+    /// Change the profile fields a user manages themselves
+    ///
+    /// Values are trimmed, and a blank display name, contact email or
+    /// region clears it.
+    pub async fn update_profile(
+        &self,
+        id: UserId,
+        changes: ProfileChanges,
+    ) -> Result<User, DomainError> {
+        let patch = changes.into_patch();
+        patch.validate()?;
+
+        if patch.is_empty() {
+            return self
+                .users
+                .find_by_id(id)
+                .await?
+                .ok_or_else(|| DomainError::NotFound(format!("User {id}")));
+        }
+        self.users.patch(id, &patch).await
+    }
+
+    /// Set the country used to calculate tax on a user's invoices
+    ///
+    /// `country` must be an ISO 3166-1 alpha-2 code; it is stored uppercase.
+    /// With `expected_version`, the change only applies if the user's
+    /// `updated_at` still matches it.
+    pub async fn set_billing_country(
+        &self,
+        id: UserId,
+        country: &str,
+        expected_version: Option<DateTime<Utc>>,
+    ) -> Result<User, DomainError> {
+        let country = country.trim().to_ascii_uppercase();
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(DomainError::InvalidInput(format!(
+                "Invalid country code '{country}', expected ISO 3166-1 alpha-2 (e.g. DE)"
+            )));
+        }
+
+        let user = self
+            .users
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("User {id}")))?;
+
+        if expected_version.is_some_and(|version| version != user.updated_at) {
+            return Err(DomainError::Conflict(format!(
+                "User {id} was modified since it was read"
+            )));
+        }
+
+        self.users
+            .update(&User {
+                billing_country: Some(country),
+                ..user
+            })
+            .await
+    }
+
+    /// Page through the users that aren't deleted, oldest first, with how
+    /// many there are
+    pub async fn list_users(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<User>, u64), DomainError> {
+        let users = self.users.find_users_page(limit, offset).await?;
+        let total = self.users.count_users().await?;
+        Ok((users, total))
+    }
+
+    /// The user that isn't deleted with `email`, if any
+    pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DomainError> {
+        Ok(self
+            .users
+            .find_by_email(email.trim())
+            .await?
+            .filter(|user| user.status != UserStatus::Deleted))
+    }
+
+    /// Create an account ahead of its owner's first login, as directory
+    /// sync does
+    ///
+    /// The first GitHub login or single sign-on with the same verified email
+    /// claims it. Fails with `Conflict` if a user already has the email.
+    pub async fn provision_user(
+        &self,
+        email: &str,
+        changes: DirectoryChanges,
+    ) -> Result<User, DomainError> {
+        let email = email.trim();
+        if self.users.find_by_email(email).await?.is_some() {
+            return Err(DomainError::Conflict(format!(
+                "A user with email {email} already exists"
+            )));
+        }
+
+        let patch = changes.to_patch();
+        patch.validate()?;
+        let mut user = User::new(email.to_string(), None, self.clock.now())?;
+        user.display_name = patch.display_name.flatten();
+        user.role = patch.role.unwrap_or_default();
+        if changes.active == Some(false) {
+            user.status = UserStatus::Suspended;
+        }
+        self.users.create(&user).await
+    }
+
+    /// Apply changes from the directory to a provisioned user
+    ///
+    /// Deactivating a user suspends them and stops their sessions.
+    pub async fn apply_directory_changes(
+        &self,
+        id: UserId,
+        changes: DirectoryChanges,
+    ) -> Result<User, DomainError> {
+        let user = self
+            .users
+            .find_by_id(id)
+            .await?
+            .filter(|user| user.status != UserStatus::Deleted)
+            .ok_or_else(|| DomainError::NotFound(format!("User {id}")))?;
+
+        let patch = changes.to_patch();
+        patch.validate()?;
+        let mut user = if patch.is_empty() {
+            user
+        } else {
+            self.users.patch(id, &patch).await?
+        };
+
+        match (changes.active, user.status) {
+            (Some(false), UserStatus::Active) => user = self.suspend_user(id).await?,
+            (Some(true), UserStatus::Suspended) => user = self.unsuspend_user(id).await?,
+            _ => {}
+        }
+        Ok(user)
+    }
+
+    /// Remove a user who left the directory
+    ///
+    /// They can no longer log in, their sessions are stopped, and their
+    /// personal data is anonymized once the retention period has passed.
+    pub async fn deprovision_user(&self, id: UserId) -> Result<(), DomainError> {
+        let deleted = self
+            .users
+            .find_by_id(id)
+            .await?
+            .is_none_or(|user| user.status == UserStatus::Deleted);
+        if deleted {
+            return Err(DomainError::NotFound(format!("User {id}")));
+        }
+
+        self.set_status(id, UserStatus::Deleted).await?;
+        self.sessions.stop_all_by_user(id).await?;
+        Ok(())
+    }
+
+    /// Suspend a user and stop all of their running sessions
+    pub async fn suspend_user(&self, id: UserId) -> Result<User, DomainError> {
+        let user = self.set_status(id, UserStatus::Suspended).await?;
+        self.sessions.stop_all_by_user(id).await?;
+        Ok(user)
+    }
+
+    /// Lift a suspension; stopped sessions stay stopped
+    pub async fn unsuspend_user(&self, id: UserId) -> Result<User, DomainError> {
+        self.set_status(id, UserStatus::Active).await
+    }
+
+    async fn set_status(&self, id: UserId, status: UserStatus) -> Result<User, DomainError> {
+        let user = self
+            .users
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("User {id}")))?;
+
+        if user.status == UserStatus::Deleted {
+            return Err(DomainError::InvalidInput(format!("User {id} is deleted")));
+        }
+
+        self.users.update(&User { status, ..user }).await
+    }
+}
@@ -11,12 +11,28 @@
 //! - Currently supports SQLite with plans for PostgreSQL support
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use domain::errors::DomainError;
-use domain::models::{AuthToken, User};
+use domain::models::{
+    AccountState, AuthToken, BillingEvent, CollaboratorAccess, DailyCount, ForkSession,
+    MAX_SLUG_ATTEMPTS, PendingDeviceFlow, PlanDefinition, PlanLimits, ProviderToken,
+    RetentionReport, SessionCollaborator, SessionId, SessionStatus, SessionSummary, SessionUsage,
+    Snapshot, SnapshotId, Subscription, TierChurn, User, UserId, UserPatch, UtilizationBucket,
+    ZombieSession, slug_candidate,
+};
 use domain::repositories::{AuthRepository, UserRepository};
+use domain::services::billing::events::BillingEventRepository;
+use domain::services::billing::plans::PlanRepository;
+use domain::services::billing::subscriptions::SubscriptionRepository;
+use domain::services::billing::webhooks::ProcessedEventRepository;
+use domain::services::retention::RetentionRepository;
+use domain::services::sessions::SessionRepository;
+use domain::services::snapshots::SnapshotRepository;
+use domain::services::stats::StatsRepository;
+use sqlx::QueryBuilder;
 use sqlx::migrate::Migrator;
-use sqlx::sqlite::SqliteConnectOptions;
 pub use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions};
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -62,6 +78,13 @@ impl DbRepo {
         Ok(Self { pool })
     }
 
+    /// Creates a database repository from an existing connection pool
+    ///
+    /// Useful for tests that need a single-connection in-memory database.
+    pub fn from_pool(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
     /// Returns a reference to the underlying SQLite connection pool
     ///
     /// This is exposed for advanced use cases where direct pool access is needed.
@@ -78,72 +101,1753 @@ impl DbRepo {
         Ok(())
     }
 
+    /// Version of the latest applied migration, or `None` before any have run
+    pub async fn migration_version(&self) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+            .fetch_one(&self.pool)
+            .await
+    }
+
     pub async fn close(&self) {
         self.pool.close().await;
     }
 }
 
+/// Maps SQLx errors to domain errors
+///
+/// Unique violations become `Conflict` and foreign key violations `InvalidInput`,
+/// since they are caused by duplicate or dangling client data; everything else
+/// is an internal failure.
+fn db_error(e: sqlx::Error) -> DomainError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            DomainError::Conflict(format!("Conflicting record: {db}"))
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            DomainError::InvalidInput(format!("Referenced record does not exist: {db}"))
+        }
+        _ => DomainError::Internal(format!("Database error: {e}")),
+    }
+}
+
+fn parse_uuid<T: FromStr<Err = uuid::Error>>(id: &str) -> Result<T, DomainError> {
+    id.parse()
+        .map_err(|e| DomainError::Internal(format!("Invalid UUID {id}: {e}")))
+}
+
+/// Row in the `users` table
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: String,
+    email: String,
+    display_name: Option<String>,
+    contact_email: Option<String>,
+    preferred_region: Option<String>,
+    github_id: Option<i64>,
+    stripe_customer_id: Option<String>,
+    billing_country: Option<String>,
+    status: String,
+    role: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<UserRow> for User {
+    type Error = DomainError;
+
+    fn try_from(row: UserRow) -> Result<Self, Self::Error> {
+        Ok(User {
+            id: parse_uuid(&row.id)?,
+            primary_email: row.email,
+            display_name: row.display_name,
+            contact_email: row.contact_email,
+            preferred_region: row.preferred_region,
+            github_user_id: row.github_id,
+            stripe_customer_id: row.stripe_customer_id,
+            billing_country: row.billing_country,
+            status: row.status.parse().map_err(DomainError::Internal)?,
+            role: row.role.parse().map_err(DomainError::Internal)?,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+const USER_COLUMNS: &str = "id, email, display_name, contact_email, preferred_region, \
+     github_id, stripe_customer_id, billing_country, status, role, created_at, updated_at";
+
+impl DbRepo {
+    async fn find_user_where<T>(&self, column: &str, value: T) -> Result<Option<User>, DomainError>
+    where
+        T: for<'q> sqlx::Encode<'q, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send,
+    {
+        let query = format!("SELECT {USER_COLUMNS} FROM users WHERE {column} = ?");
+        sqlx::query_as::<_, UserRow>(&query)
+            .bind(value)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(User::try_from)
+            .transpose()
+    }
+}
+
 #[async_trait]
 impl UserRepository for DbRepo {
-    async fn find_by_id(&self, _id: Uuid) -> Result<Option<User>, DomainError> {
-        todo!("Implement find_by_id")
+    async fn find_by_id(&self, id: UserId) -> Result<Option<User>, DomainError> {
+        self.find_user_where("id", id.to_string()).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, DomainError> {
+        self.find_user_where("email", email.to_string()).await
+    }
+
+    async fn find_by_github_id(&self, github_id: i64) -> Result<Option<User>, DomainError> {
+        self.find_user_where("github_id", github_id).await
     }
 
-    async fn find_by_email(&self, _email: &str) -> Result<Option<User>, DomainError> {
-        todo!("Implement find_by_email")
+    async fn find_by_oidc_subject(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, DomainError> {
+        let query = format!(
+            "SELECT {USER_COLUMNS} FROM users WHERE id = \
+             (SELECT user_id FROM oidc_identities WHERE issuer = ? AND subject = ?)"
+        );
+        sqlx::query_as::<_, UserRow>(&query)
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(User::try_from)
+            .transpose()
     }
 
-    async fn find_by_github_id(&self, _github_id: i64) -> Result<Option<User>, DomainError> {
-        todo!("Implement find_by_github_id")
+    async fn link_oidc_subject(
+        &self,
+        id: UserId,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO oidc_identities (issuer, subject, user_id, created_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(issuer)
+        .bind(subject)
+        .bind(id.to_string())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(())
     }
 
     async fn find_by_stripe_customer_id(
         &self,
-        _stripe_customer_id: &str,
+        stripe_customer_id: &str,
     ) -> Result<Option<User>, DomainError> {
-        todo!("Implement find_by_stripe_customer_id")
+        self.find_user_where("stripe_customer_id", stripe_customer_id.to_string())
+            .await
+    }
+
+    async fn create(&self, user: &User) -> Result<User, DomainError> {
+        sqlx::query(
+            "INSERT INTO users (id, email, display_name, contact_email, preferred_region, \
+             github_id, stripe_customer_id, billing_country, status, role, created_at, \
+             updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(user.id.to_string())
+        .bind(&user.primary_email)
+        .bind(&user.display_name)
+        .bind(&user.contact_email)
+        .bind(&user.preferred_region)
+        .bind(user.github_user_id)
+        .bind(&user.stripe_customer_id)
+        .bind(&user.billing_country)
+        .bind(user.status.as_str())
+        .bind(user.role.as_str())
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(user.clone())
     }
 
-    async fn create(&self, _user: &User) -> Result<User, DomainError> {
-        todo!("Implement create user")
+    async fn update(&self, user: &User) -> Result<User, DomainError> {
+        let updated_at = Utc::now();
+
+        let result = sqlx::query(
+            "UPDATE users SET email = ?, display_name = ?, contact_email = ?, \
+             preferred_region = ?, github_id = ?, stripe_customer_id = ?, billing_country = ?, \
+             status = ?, role = ?, updated_at = ? WHERE id = ? AND updated_at = ?",
+        )
+        .bind(&user.primary_email)
+        .bind(&user.display_name)
+        .bind(&user.contact_email)
+        .bind(&user.preferred_region)
+        .bind(user.github_user_id)
+        .bind(&user.stripe_customer_id)
+        .bind(&user.billing_country)
+        .bind(user.status.as_str())
+        .bind(user.role.as_str())
+        .bind(updated_at)
+        .bind(user.id.to_string())
+        .bind(user.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(match UserRepository::find_by_id(self, user.id).await? {
+                Some(_) => {
+                    DomainError::Conflict(format!("User {} was modified concurrently", user.id))
+                }
+                None => DomainError::NotFound(format!("User {}", user.id)),
+            });
+        }
+
+        Ok(User {
+            updated_at,
+            ..user.clone()
+        })
     }
 
-    async fn update(&self, _user: &User) -> Result<User, DomainError> {
-        todo!("Implement update user")
+    async fn patch(&self, id: UserId, patch: &UserPatch) -> Result<User, DomainError> {
+        let mut query = QueryBuilder::<Sqlite>::new("UPDATE users SET ");
+        let mut columns = query.separated(", ");
+        if let Some(email) = &patch.primary_email {
+            columns.push("email = ").push_bind_unseparated(email);
+        }
+        if let Some(display_name) = &patch.display_name {
+            columns
+                .push("display_name = ")
+                .push_bind_unseparated(display_name);
+        }
+        if let Some(contact_email) = &patch.contact_email {
+            columns
+                .push("contact_email = ")
+                .push_bind_unseparated(contact_email);
+        }
+        if let Some(region) = &patch.preferred_region {
+            columns
+                .push("preferred_region = ")
+                .push_bind_unseparated(region);
+        }
+        if let Some(customer_id) = &patch.stripe_customer_id {
+            columns
+                .push("stripe_customer_id = ")
+                .push_bind_unseparated(customer_id);
+        }
+        if let Some(status) = patch.status {
+            columns
+                .push("status = ")
+                .push_bind_unseparated(status.as_str());
+        }
+        if let Some(role) = patch.role {
+            columns.push("role = ").push_bind_unseparated(role.as_str());
+        }
+        columns
+            .push("updated_at = ")
+            .push_bind_unseparated(Utc::now());
+        query
+            .push(" WHERE id = ")
+            .push_bind(id.to_string())
+            .push(format!(" RETURNING {USER_COLUMNS}"));
+
+        query
+            .build_query_as::<UserRow>()
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| DomainError::NotFound(format!("User {id}")))?
+            .try_into()
     }
 
-    async fn delete(&self, _id: Uuid) -> Result<(), DomainError> {
-        todo!("Implement delete user")
+    async fn delete(&self, id: UserId) -> Result<(), DomainError> {
+        let result = sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DomainError::NotFound(format!("User {id}")));
+        }
+
+        Ok(())
+    }
+
+    async fn find_users_page(&self, limit: u32, offset: u32) -> Result<Vec<User>, DomainError> {
+        let query = format!(
+            "SELECT {USER_COLUMNS} FROM users WHERE status != 'deleted' \
+             ORDER BY created_at, id LIMIT ? OFFSET ?"
+        );
+        sqlx::query_as::<_, UserRow>(&query)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(User::try_from)
+            .collect()
+    }
+
+    async fn count_users(&self) -> Result<u64, DomainError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE status != 'deleted'")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(count as u64)
+    }
+}
+
+/// Row in the `fork_sessions` table
+#[derive(sqlx::FromRow)]
+struct SessionRow {
+    id: String,
+    user_id: String,
+    name: String,
+    slug: String,
+    status: String,
+    fork_slot: Option<i64>,
+    region: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SessionRow> for ForkSession {
+    type Error = DomainError;
+
+    fn try_from(row: SessionRow) -> Result<Self, Self::Error> {
+        Ok(ForkSession {
+            id: parse_uuid(&row.id)?,
+            user_id: parse_uuid(&row.user_id)?,
+            name: row.name,
+            slug: row.slug,
+            status: row.status.parse().map_err(DomainError::Internal)?,
+            fork_slot: row.fork_slot.map(|slot| slot as u64),
+            region: row.region,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, name, slug, status, fork_slot, region, created_at, updated_at";
+
+/// Session row with its snapshot count, for listings
+#[derive(sqlx::FromRow)]
+struct SessionSummaryRow {
+    #[sqlx(flatten)]
+    session: SessionRow,
+    snapshot_count: i64,
+}
+
+impl TryFrom<SessionSummaryRow> for SessionSummary {
+    type Error = DomainError;
+
+    fn try_from(row: SessionSummaryRow) -> Result<Self, Self::Error> {
+        Ok(SessionSummary {
+            session: row.session.try_into()?,
+            snapshot_count: row.snapshot_count as u64,
+        })
+    }
+}
+
+/// Row in the `session_accounts` table
+#[derive(sqlx::FromRow)]
+struct SessionAccountRow {
+    pubkey: String,
+    lamports: i64,
+    owner: String,
+    data: Vec<u8>,
+    executable: bool,
+    rent_epoch: i64,
+}
+
+impl From<SessionAccountRow> for AccountState {
+    fn from(row: SessionAccountRow) -> Self {
+        // u64 values are stored bit-for-bit in SQLite's signed INTEGER
+        AccountState {
+            pubkey: row.pubkey,
+            lamports: row.lamports as u64,
+            owner: row.owner,
+            data: row.data,
+            executable: row.executable,
+            rent_epoch: row.rent_epoch as u64,
+        }
+    }
+}
+
+const SESSION_ACCOUNT_COLUMNS: &str = "pubkey, lamports, owner, data, executable, rent_epoch";
+
+/// Row in the `session_usage` table
+#[derive(sqlx::FromRow)]
+struct SessionUsageRow {
+    session_id: String,
+    elapsed_seconds: i64,
+    peak_memory_bytes: i64,
+    last_heartbeat_at: DateTime<Utc>,
+}
+
+impl TryFrom<SessionUsageRow> for SessionUsage {
+    type Error = DomainError;
+
+    fn try_from(row: SessionUsageRow) -> Result<Self, Self::Error> {
+        Ok(SessionUsage {
+            session_id: parse_uuid(&row.session_id)?,
+            elapsed_seconds: row.elapsed_seconds as u64,
+            peak_memory_bytes: row.peak_memory_bytes as u64,
+            last_heartbeat_at: row.last_heartbeat_at,
+        })
+    }
+}
+
+const SESSION_USAGE_COLUMNS: &str =
+    "session_id, elapsed_seconds, peak_memory_bytes, last_heartbeat_at";
+
+/// Live session found by `find_zombies`
+#[derive(sqlx::FromRow)]
+struct ZombieRow {
+    id: String,
+    status: String,
+    last_heartbeat_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ZombieRow> for ZombieSession {
+    type Error = DomainError;
+
+    fn try_from(row: ZombieRow) -> Result<Self, Self::Error> {
+        Ok(ZombieSession {
+            session_id: parse_uuid(&row.id)?,
+            status: row.status.parse().map_err(DomainError::Internal)?,
+            last_heartbeat_at: row.last_heartbeat_at,
+        })
     }
 }
 
 #[async_trait]
-impl AuthRepository for DbRepo {
-    async fn find_by_token_hash(
+impl SessionRepository for DbRepo {
+    async fn create(
+        &self,
+        user_id: UserId,
+        name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<ForkSession, DomainError> {
+        // The unique (user_id, slug) index settles races between creations
+        for attempt in 0..MAX_SLUG_ATTEMPTS {
+            let slug = slug_candidate(&name, attempt);
+            let name = if name.is_empty() {
+                slug.clone()
+            } else {
+                name.clone()
+            };
+            let session = ForkSession {
+                region: region.clone(),
+                ..ForkSession::new(user_id, name, slug, fork_slot, created_at)?
+            };
+
+            let result = sqlx::query(
+                "INSERT INTO fork_sessions \
+                 (id, user_id, name, slug, status, fork_slot, region, created_at, updated_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(session.id.to_string())
+            .bind(session.user_id.to_string())
+            .bind(&session.name)
+            .bind(&session.slug)
+            .bind(session.status.as_str())
+            .bind(session.fork_slot.map(|slot| slot as i64))
+            .bind(&session.region)
+            .bind(session.created_at)
+            .bind(session.updated_at)
+            .execute(&self.pool)
+            .await;
+
+            match result {
+                Ok(_) => return Ok(session),
+                Err(sqlx::Error::Database(db)) if db.is_unique_violation() => continue,
+                Err(e) => return Err(db_error(e)),
+            }
+        }
+
+        Err(DomainError::InvalidInput(format!(
+            "No free slug for session {name:?}; choose another name"
+        )))
+    }
+
+    async fn find_by_id(&self, id: SessionId) -> Result<Option<ForkSession>, DomainError> {
+        let query = format!("SELECT {SESSION_COLUMNS} FROM fork_sessions WHERE id = ?");
+        sqlx::query_as::<_, SessionRow>(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(ForkSession::try_from)
+            .transpose()
+    }
+
+    async fn find_by_slug(
+        &self,
+        user_id: UserId,
+        slug: &str,
+    ) -> Result<Option<ForkSession>, DomainError> {
+        let query =
+            format!("SELECT {SESSION_COLUMNS} FROM fork_sessions WHERE user_id = ? AND slug = ?");
+        sqlx::query_as::<_, SessionRow>(&query)
+            .bind(user_id.to_string())
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(ForkSession::try_from)
+            .transpose()
+    }
+
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SessionId>, DomainError> {
+        // Callers pass hex digits and dashes only, so there are no LIKE wildcards
+        sqlx::query_scalar::<_, String>(
+            "SELECT id FROM fork_sessions WHERE user_id = ? AND id LIKE ? ORDER BY id LIMIT ?",
+        )
+        .bind(user_id.to_string())
+        .bind(format!("{prefix}%"))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|id| parse_uuid(id))
+        .collect()
+    }
+
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<ForkSession>, DomainError> {
+        let query = format!(
+            "SELECT {SESSION_COLUMNS} FROM fork_sessions WHERE user_id = ? ORDER BY created_at DESC"
+        );
+        sqlx::query_as::<_, SessionRow>(&query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(ForkSession::try_from)
+            .collect()
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SessionSummary>, DomainError> {
+        let query = format!(
+            "SELECT {SESSION_COLUMNS}, \
+             (SELECT COUNT(*) FROM snapshots WHERE snapshots.session_id = fork_sessions.id) \
+             AS snapshot_count \
+             FROM fork_sessions WHERE user_id = ? AND (? IS NULL OR status = ?) \
+             ORDER BY created_at DESC LIMIT ? OFFSET ?"
+        );
+        let status = status.map(|status| status.as_str());
+        sqlx::query_as::<_, SessionSummaryRow>(&query)
+            .bind(user_id.to_string())
+            .bind(status)
+            .bind(status)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(SessionSummary::try_from)
+            .collect()
+    }
+
+    async fn count_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+    ) -> Result<u64, DomainError> {
+        let status = status.map(|status| status.as_str());
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM fork_sessions WHERE user_id = ? AND (? IS NULL OR status = ?)",
+        )
+        .bind(user_id.to_string())
+        .bind(status)
+        .bind(status)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(count as u64)
+    }
+
+    async fn update(&self, session: &ForkSession) -> Result<ForkSession, DomainError> {
+        let updated_at = Utc::now();
+
+        let result = sqlx::query(
+            "UPDATE fork_sessions SET name = ?, status = ?, updated_at = ? \
+             WHERE id = ? AND updated_at = ?",
+        )
+        .bind(&session.name)
+        .bind(session.status.as_str())
+        .bind(updated_at)
+        .bind(session.id.to_string())
+        .bind(session.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(
+                match SessionRepository::find_by_id(self, session.id).await? {
+                    Some(_) => DomainError::Conflict(format!(
+                        "Session {} was modified concurrently",
+                        session.id
+                    )),
+                    None => DomainError::NotFound(format!("Session {}", session.id)),
+                },
+            );
+        }
+
+        Ok(ForkSession {
+            updated_at,
+            ..session.clone()
+        })
+    }
+
+    async fn update_status(
+        &self,
+        id: SessionId,
+        from: SessionStatus,
+        to: SessionStatus,
+    ) -> Result<Option<ForkSession>, DomainError> {
+        let result = sqlx::query(
+            "UPDATE fork_sessions SET status = ?, updated_at = ? WHERE id = ? AND status = ?",
+        )
+        .bind(to.as_str())
+        .bind(Utc::now())
+        .bind(id.to_string())
+        .bind(from.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        SessionRepository::find_by_id(self, id).await
+    }
+
+    async fn stop_all_by_user(&self, user_id: UserId) -> Result<u64, DomainError> {
+        let result = sqlx::query(
+            "UPDATE fork_sessions SET status = ?, updated_at = ? \
+             WHERE user_id = ? AND status IN (?, ?)",
+        )
+        .bind(SessionStatus::Stopped.as_str())
+        .bind(Utc::now())
+        .bind(user_id.to_string())
+        .bind(SessionStatus::Pending.as_str())
+        .bind(SessionStatus::Running.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn save_accounts(
+        &self,
+        session_id: SessionId,
+        accounts: &[AccountState],
+    ) -> Result<(), DomainError> {
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        for account in accounts {
+            sqlx::query(
+                "INSERT INTO session_accounts \
+                 (session_id, pubkey, lamports, owner, data, executable, rent_epoch) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT (session_id, pubkey) DO UPDATE SET \
+                 lamports = excluded.lamports, owner = excluded.owner, data = excluded.data, \
+                 executable = excluded.executable, rent_epoch = excluded.rent_epoch",
+            )
+            .bind(session_id.to_string())
+            .bind(&account.pubkey)
+            .bind(account.lamports as i64)
+            .bind(&account.owner)
+            .bind(&account.data)
+            .bind(account.executable)
+            .bind(account.rent_epoch as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+        tx.commit().await.map_err(db_error)
+    }
+
+    async fn list_accounts(&self, session_id: SessionId) -> Result<Vec<AccountState>, DomainError> {
+        let query = format!(
+            "SELECT {SESSION_ACCOUNT_COLUMNS} FROM session_accounts \
+             WHERE session_id = ? ORDER BY pubkey"
+        );
+        Ok(sqlx::query_as::<_, SessionAccountRow>(&query)
+            .bind(session_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(AccountState::from)
+            .collect())
+    }
+
+    async fn record_usage(
+        &self,
+        session_id: SessionId,
+        elapsed_seconds: u64,
+        peak_memory_bytes: u64,
+    ) -> Result<SessionUsage, DomainError> {
+        // Taking the maximum makes replayed heartbeats harmless
+        let query = format!(
+            "INSERT INTO session_usage ({SESSION_USAGE_COLUMNS}) VALUES (?, ?, ?, ?) \
+             ON CONFLICT (session_id) DO UPDATE SET \
+             elapsed_seconds = MAX(elapsed_seconds, excluded.elapsed_seconds), \
+             peak_memory_bytes = MAX(peak_memory_bytes, excluded.peak_memory_bytes), \
+             last_heartbeat_at = excluded.last_heartbeat_at \
+             RETURNING {SESSION_USAGE_COLUMNS}"
+        );
+        sqlx::query_as::<_, SessionUsageRow>(&query)
+            .bind(session_id.to_string())
+            .bind(elapsed_seconds as i64)
+            .bind(peak_memory_bytes as i64)
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_error)?
+            .try_into()
+    }
+
+    async fn find_zombies(&self, cutoff: DateTime<Utc>) -> Result<Vec<ZombieSession>, DomainError> {
+        sqlx::query_as::<_, ZombieRow>(
+            "SELECT fork_sessions.id, fork_sessions.status, session_usage.last_heartbeat_at \
+             FROM fork_sessions LEFT JOIN session_usage ON session_usage.session_id = fork_sessions.id \
+             WHERE fork_sessions.status IN (?, ?) \
+             AND COALESCE(session_usage.last_heartbeat_at, \
+                 CASE WHEN fork_sessions.status = ? THEN fork_sessions.updated_at END) < ? \
+             ORDER BY fork_sessions.created_at",
+        )
+        .bind(SessionStatus::Pending.as_str())
+        .bind(SessionStatus::Running.as_str())
+        .bind(SessionStatus::Running.as_str())
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(ZombieSession::try_from)
+        .collect()
+    }
+
+    async fn upsert_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        access: CollaboratorAccess,
+    ) -> Result<SessionCollaborator, DomainError> {
+        let collaborator = SessionCollaborator {
+            session_id,
+            user_id,
+            access,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            "INSERT INTO session_collaborators (session_id, user_id, access, created_at) \
+             VALUES (?, ?, ?, ?) \
+             ON CONFLICT (session_id, user_id) DO UPDATE SET access = excluded.access",
+        )
+        .bind(session_id.to_string())
+        .bind(user_id.to_string())
+        .bind(access.as_str())
+        .bind(collaborator.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(collaborator)
+    }
+
+    async fn find_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+    ) -> Result<Option<SessionCollaborator>, DomainError> {
+        let row: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT access, created_at FROM session_collaborators \
+             WHERE session_id = ? AND user_id = ?",
+        )
+        .bind(session_id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        row.map(|(access, created_at)| {
+            Ok(SessionCollaborator {
+                session_id,
+                user_id,
+                access: access.parse().map_err(DomainError::Internal)?,
+                created_at,
+            })
+        })
+        .transpose()
+    }
+}
+
+/// Row in the `snapshots` table
+#[derive(sqlx::FromRow)]
+struct SnapshotRow {
+    id: String,
+    session_id: String,
+    user_id: String,
+    name: String,
+    description: Option<String>,
+    fork_slot: Option<i64>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<SnapshotRow> for Snapshot {
+    type Error = DomainError;
+
+    fn try_from(row: SnapshotRow) -> Result<Self, Self::Error> {
+        Ok(Snapshot {
+            id: parse_uuid(&row.id)?,
+            session_id: parse_uuid(&row.session_id)?,
+            user_id: parse_uuid(&row.user_id)?,
+            name: row.name,
+            description: row.description,
+            fork_slot: row.fork_slot.map(|slot| slot as u64),
+            created_at: row.created_at,
+        })
+    }
+}
+
+const SNAPSHOT_COLUMNS: &str = "id, session_id, user_id, name, description, fork_slot, created_at";
+
+#[async_trait]
+impl SnapshotRepository for DbRepo {
+    async fn create(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Snapshot, DomainError> {
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+
+        let fork_slot: Option<i64> =
+            sqlx::query_scalar("SELECT fork_slot FROM fork_sessions WHERE id = ?")
+                .bind(session_id.to_string())
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+
+        let snapshot = Snapshot::new(
+            session_id,
+            user_id,
+            name,
+            description,
+            fork_slot.map(|slot| slot as u64),
+            created_at,
+        )?;
+
+        let query =
+            format!("INSERT INTO snapshots ({SNAPSHOT_COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?)");
+        sqlx::query(&query)
+            .bind(snapshot.id.to_string())
+            .bind(snapshot.session_id.to_string())
+            .bind(snapshot.user_id.to_string())
+            .bind(&snapshot.name)
+            .bind(&snapshot.description)
+            .bind(fork_slot)
+            .bind(snapshot.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        let query = format!(
+            "INSERT INTO snapshot_accounts (snapshot_id, {SESSION_ACCOUNT_COLUMNS}) \
+             SELECT ?, {SESSION_ACCOUNT_COLUMNS} FROM session_accounts WHERE session_id = ?"
+        );
+        sqlx::query(&query)
+            .bind(snapshot.id.to_string())
+            .bind(session_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(snapshot)
+    }
+
+    async fn find_by_id(&self, id: SnapshotId) -> Result<Option<Snapshot>, DomainError> {
+        let query = format!("SELECT {SNAPSHOT_COLUMNS} FROM snapshots WHERE id = ?");
+        sqlx::query_as::<_, SnapshotRow>(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(Snapshot::try_from)
+            .transpose()
+    }
+
+    async fn list_by_session(&self, session_id: SessionId) -> Result<Vec<Snapshot>, DomainError> {
+        let query = format!(
+            "SELECT {SNAPSHOT_COLUMNS} FROM snapshots WHERE session_id = ? ORDER BY created_at DESC"
+        );
+        sqlx::query_as::<_, SnapshotRow>(&query)
+            .bind(session_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(Snapshot::try_from)
+            .collect()
+    }
+
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError> {
+        // Callers pass hex digits and dashes only, so there are no LIKE wildcards
+        sqlx::query_scalar::<_, String>(
+            "SELECT id FROM snapshots WHERE id LIKE ? AND (user_id = ? OR session_id IN \
+             (SELECT id FROM fork_sessions WHERE user_id = ?)) ORDER BY id LIMIT ?",
+        )
+        .bind(format!("{prefix}%"))
+        .bind(user_id.to_string())
+        .bind(user_id.to_string())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|id| parse_uuid(id))
+        .collect()
+    }
+
+    async fn find_ids_created_before(
+        &self,
+        user_id: UserId,
+        cutoff: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError> {
+        sqlx::query_scalar::<_, String>(
+            "SELECT id FROM snapshots WHERE created_at < ? AND (user_id = ? OR session_id IN \
+             (SELECT id FROM fork_sessions WHERE user_id = ?)) ORDER BY created_at LIMIT ?",
+        )
+        .bind(cutoff)
+        .bind(user_id.to_string())
+        .bind(user_id.to_string())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|id| parse_uuid(id))
+        .collect()
+    }
+
+    async fn restore(
         &self,
-        _token_hash: &str,
-    ) -> Result<Option<AuthToken>, DomainError> {
-        todo!("Implement find_by_token_hash")
+        snapshot_id: SnapshotId,
+        session_id: SessionId,
+    ) -> Result<(), DomainError> {
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+
+        let result = sqlx::query(
+            "UPDATE fork_sessions SET \
+             fork_slot = (SELECT fork_slot FROM snapshots WHERE id = ?), updated_at = ? \
+             WHERE id = ?",
+        )
+        .bind(snapshot_id.to_string())
+        .bind(Utc::now())
+        .bind(session_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        if result.rows_affected() == 0 {
+            return Err(DomainError::NotFound(format!("Session {session_id}")));
+        }
+
+        sqlx::query("DELETE FROM session_accounts WHERE session_id = ?")
+            .bind(session_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        let query = format!(
+            "INSERT INTO session_accounts (session_id, {SESSION_ACCOUNT_COLUMNS}) \
+             SELECT ?, {SESSION_ACCOUNT_COLUMNS} FROM snapshot_accounts WHERE snapshot_id = ?"
+        );
+        sqlx::query(&query)
+            .bind(session_id.to_string())
+            .bind(snapshot_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)
     }
 
-    async fn find_by_user_id(&self, _user_id: Uuid) -> Result<Vec<AuthToken>, DomainError> {
-        todo!("Implement find_by_user_id")
+    async fn delete(&self, id: SnapshotId) -> Result<(), DomainError> {
+        let result = sqlx::query("DELETE FROM snapshots WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DomainError::NotFound(format!("Snapshot {id}")));
+        }
+
+        Ok(())
     }
+}
 
-    async fn create(&self, _token: &AuthToken) -> Result<AuthToken, DomainError> {
-        todo!("Implement create auth token")
+/// Row in the `billing_events` table
+#[derive(sqlx::FromRow)]
+struct BillingEventRow {
+    id: String,
+    user_id: String,
+    kind: String,
+    before_value: Option<String>,
+    after_value: Option<String>,
+    stripe_event_id: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+fn parse_json(value: Option<String>) -> Result<Option<serde_json::Value>, DomainError> {
+    value
+        .map(|v| serde_json::from_str(&v))
+        .transpose()
+        .map_err(|e| DomainError::Internal(format!("Invalid JSON column: {e}")))
+}
+
+impl TryFrom<BillingEventRow> for BillingEvent {
+    type Error = DomainError;
+
+    fn try_from(row: BillingEventRow) -> Result<Self, Self::Error> {
+        Ok(BillingEvent {
+            id: parse_uuid(&row.id)?,
+            user_id: parse_uuid(&row.user_id)?,
+            kind: row.kind.parse().map_err(DomainError::Internal)?,
+            before: parse_json(row.before_value)?,
+            after: parse_json(row.after_value)?,
+            stripe_event_id: row.stripe_event_id,
+            created_at: row.created_at,
+        })
     }
+}
 
-    async fn update_last_used(&self, _id: Uuid) -> Result<(), DomainError> {
-        todo!("Implement update_last_used")
+const BILLING_EVENT_COLUMNS: &str =
+    "id, user_id, kind, before_value, after_value, stripe_event_id, created_at";
+
+#[async_trait]
+impl BillingEventRepository for DbRepo {
+    async fn record(&self, event: &BillingEvent) -> Result<BillingEvent, DomainError> {
+        sqlx::query(
+            "INSERT INTO billing_events \
+             (id, user_id, kind, before_value, after_value, stripe_event_id, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(event.id.to_string())
+        .bind(event.user_id.to_string())
+        .bind(event.kind.as_str())
+        .bind(event.before.as_ref().map(|v| v.to_string()))
+        .bind(event.after.as_ref().map(|v| v.to_string()))
+        .bind(&event.stripe_event_id)
+        .bind(event.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(event.clone())
     }
 
-    async fn delete(&self, _id: Uuid) -> Result<(), DomainError> {
-        todo!("Implement delete auth token")
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<BillingEvent>, DomainError> {
+        let query = format!(
+            "SELECT {BILLING_EVENT_COLUMNS} FROM billing_events WHERE user_id = ? ORDER BY created_at"
+        );
+        sqlx::query_as::<_, BillingEventRow>(&query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(BillingEvent::try_from)
+            .collect()
+    }
+
+    async fn find_page_by_user(
+        &self,
+        user_id: UserId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<BillingEvent>, DomainError> {
+        let query = format!(
+            "SELECT {BILLING_EVENT_COLUMNS} FROM billing_events WHERE user_id = ? \
+             ORDER BY created_at, id LIMIT ? OFFSET ?"
+        );
+        sqlx::query_as::<_, BillingEventRow>(&query)
+            .bind(user_id.to_string())
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(BillingEvent::try_from)
+            .collect()
+    }
+}
+
+/// Row in the `subscriptions` table
+#[derive(sqlx::FromRow)]
+struct SubscriptionRow {
+    user_id: String,
+    tier: String,
+    status: String,
+    provider_subscription_id: String,
+    failed_payment_count: i64,
+    last_payment_failed_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SubscriptionRow> for Subscription {
+    type Error = DomainError;
+
+    fn try_from(row: SubscriptionRow) -> Result<Self, Self::Error> {
+        Ok(Subscription {
+            user_id: parse_uuid(&row.user_id)?,
+            tier: row.tier.parse().map_err(DomainError::Internal)?,
+            status: row.status.parse().map_err(DomainError::Internal)?,
+            provider_subscription_id: row.provider_subscription_id,
+            failed_payment_count: row.failed_payment_count as u32,
+            last_payment_failed_at: row.last_payment_failed_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "user_id, tier, status, provider_subscription_id, \
+     failed_payment_count, last_payment_failed_at, created_at, updated_at";
+
+#[async_trait]
+impl SubscriptionRepository for DbRepo {
+    async fn find_by_user(&self, user_id: UserId) -> Result<Option<Subscription>, DomainError> {
+        let query = format!("SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE user_id = ?");
+        sqlx::query_as::<_, SubscriptionRow>(&query)
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(Subscription::try_from)
+            .transpose()
+    }
+
+    async fn upsert(&self, subscription: &Subscription) -> Result<Subscription, DomainError> {
+        sqlx::query(
+            "INSERT INTO subscriptions \
+             (user_id, tier, status, provider_subscription_id, \
+             failed_payment_count, last_payment_failed_at, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(user_id) DO UPDATE SET \
+             tier = excluded.tier, status = excluded.status, \
+             provider_subscription_id = excluded.provider_subscription_id, \
+             failed_payment_count = excluded.failed_payment_count, \
+             last_payment_failed_at = excluded.last_payment_failed_at, \
+             updated_at = excluded.updated_at",
+        )
+        .bind(subscription.user_id.to_string())
+        .bind(subscription.tier.as_str())
+        .bind(subscription.status.as_str())
+        .bind(&subscription.provider_subscription_id)
+        .bind(i64::from(subscription.failed_payment_count))
+        .bind(subscription.last_payment_failed_at)
+        .bind(subscription.created_at)
+        .bind(subscription.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(subscription.clone())
+    }
+}
+
+#[async_trait]
+impl ProcessedEventRepository for DbRepo {
+    async fn is_event_processed(&self, event_id: &str) -> Result<bool, DomainError> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM processed_webhook_events WHERE event_id = ?)",
+        )
+        .bind(event_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_error)
+    }
+
+    async fn mark_event_processed(
+        &self,
+        event_id: &str,
+        event_type: &str,
+    ) -> Result<(), DomainError> {
+        // A concurrent redelivery may have got here first; that's fine
+        sqlx::query(
+            "INSERT OR IGNORE INTO processed_webhook_events (event_id, event_type) VALUES (?, ?)",
+        )
+        .bind(event_id)
+        .bind(event_type)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StatsRepository for DbRepo {
+    async fn signups_per_day(&self, since: DateTime<Utc>) -> Result<Vec<DailyCount>, DomainError> {
+        // Timestamps are stored as text starting with the UTC date
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT substr(created_at, 1, 10) AS day, COUNT(*) FROM users \
+             WHERE created_at >= ? GROUP BY day ORDER BY day",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        rows.into_iter()
+            .map(|(day, count)| {
+                Ok(DailyCount {
+                    day: day
+                        .parse()
+                        .map_err(|e| DomainError::Internal(format!("Invalid date {day}: {e}")))?,
+                    count: count as u64,
+                })
+            })
+            .collect()
+    }
+
+    async fn count_active_sessions(&self) -> Result<u64, DomainError> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM fork_sessions WHERE status IN ('pending', 'running')",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(count as u64)
+    }
+
+    async fn active_session_distribution(&self) -> Result<Vec<UtilizationBucket>, DomainError> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT active, COUNT(*) FROM ( \
+                 SELECT COUNT(*) AS active FROM fork_sessions \
+                 WHERE status IN ('pending', 'running') GROUP BY user_id \
+             ) GROUP BY active ORDER BY active",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(active, users)| UtilizationBucket {
+                active_sessions: active as u64,
+                users: users as u64,
+            })
+            .collect())
+    }
+
+    async fn subscription_churn(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TierChurn>, DomainError> {
+        let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT tier, \
+                 SUM(status IN ('active', 'past_due')), \
+                 SUM(status = 'cancelled' AND updated_at >= ?), \
+                 SUM(status IN ('active', 'past_due') AND last_payment_failed_at >= ?) \
+             FROM subscriptions GROUP BY tier ORDER BY tier",
+        )
+        .bind(since)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        rows.into_iter()
+            .map(|(tier, subscribed, cancelled, payment_failed)| {
+                Ok(TierChurn {
+                    tier: tier.parse().map_err(DomainError::Internal)?,
+                    subscribed: subscribed as u64,
+                    cancelled: cancelled as u64,
+                    payment_failed: payment_failed as u64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Tokens past retention: not used, or never used and not created, since the cutoff
+const UNUSED_AUTH_TOKENS_WHERE: &str = "COALESCE(last_used_at, created_at) < ?";
+
+/// Deleted users past retention that still hold personal data
+const DELETED_USERS_WHERE: &str =
+    "status = 'deleted' AND updated_at < ? AND email NOT LIKE 'deleted-%@anonymized.invalid'";
+
+impl DbRepo {
+    async fn count_where(
+        &self,
+        table: &str,
+        filter: &str,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, DomainError> {
+        let count: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE {filter}"))
+                .bind(cutoff)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(db_error)?;
+        Ok(count as u64)
+    }
+
+    /// Delete rows matching `filter`, or only count them in a dry run
+    async fn purge_where(
+        &self,
+        table: &str,
+        filter: &str,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError> {
+        if dry_run {
+            return self.count_where(table, filter, cutoff).await;
+        }
+
+        let result = sqlx::query(&format!("DELETE FROM {table} WHERE {filter}"))
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl RetentionRepository for DbRepo {
+    async fn purge_unused_auth_tokens(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError> {
+        self.purge_where("auth_tokens", UNUSED_AUTH_TOKENS_WHERE, cutoff, dry_run)
+            .await
+    }
+
+    async fn anonymize_deleted_users(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError> {
+        if dry_run {
+            return self.count_where("users", DELETED_USERS_WHERE, cutoff).await;
+        }
+
+        for table in ["provider_tokens", "oidc_identities"] {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE user_id IN \
+                 (SELECT id FROM users WHERE {DELETED_USERS_WHERE})"
+            ))
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        }
+
+        // The placeholder email keeps the UNIQUE constraint satisfied and
+        // marks the row as already anonymized
+        let result = sqlx::query(&format!(
+            "UPDATE users SET email = 'deleted-' || id || '@anonymized.invalid', \
+             github_id = NULL, github_username = NULL, stripe_customer_id = NULL, \
+             billing_country = NULL, updated_at = ? \
+             WHERE {DELETED_USERS_WHERE}"
+        ))
+        .bind(Utc::now())
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(result.rows_affected())
+    }
+
+    async fn purge_billing_events(
+        &self,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<u64, DomainError> {
+        self.purge_where("billing_events", "created_at < ?", cutoff, dry_run)
+            .await
+    }
+
+    async fn record_run(&self, report: &RetentionReport) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO retention_runs (id, rule, cutoff, affected, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(report.rule.as_str())
+        .bind(report.cutoff)
+        .bind(report.affected as i64)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+}
+
+/// Row in the `plans` table
+#[derive(sqlx::FromRow)]
+struct PlanRow {
+    tier: String,
+    name: String,
+    max_concurrent_sessions: i64,
+    max_snapshots_per_session: i64,
+    max_session_minutes: Option<i64>,
+    api_requests_per_minute: i64,
+    features: String,
+}
+
+fn parse_limit(value: i64) -> Result<u32, DomainError> {
+    u32::try_from(value).map_err(|_| DomainError::Internal(format!("Invalid plan limit: {value}")))
+}
+
+impl TryFrom<PlanRow> for PlanDefinition {
+    type Error = DomainError;
+
+    fn try_from(row: PlanRow) -> Result<Self, Self::Error> {
+        Ok(PlanDefinition {
+            tier: row.tier.parse().map_err(DomainError::Internal)?,
+            name: row.name,
+            limits: PlanLimits {
+                max_concurrent_sessions: parse_limit(row.max_concurrent_sessions)?,
+                max_snapshots_per_session: parse_limit(row.max_snapshots_per_session)?,
+                max_session_minutes: row.max_session_minutes.map(parse_limit).transpose()?,
+                api_requests_per_minute: parse_limit(row.api_requests_per_minute)?,
+            },
+            features: serde_json::from_str(&row.features)
+                .map_err(|e| DomainError::Internal(format!("Invalid plan features: {e}")))?,
+        })
+    }
+}
+
+const PLAN_COLUMNS: &str = "tier, name, max_concurrent_sessions, max_snapshots_per_session, \
+     max_session_minutes, api_requests_per_minute, features";
+
+#[async_trait]
+impl PlanRepository for DbRepo {
+    async fn list_plans(&self) -> Result<Vec<PlanDefinition>, DomainError> {
+        let query = format!("SELECT {PLAN_COLUMNS} FROM plans ORDER BY sort_order, tier");
+        sqlx::query_as::<_, PlanRow>(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(PlanDefinition::try_from)
+            .collect()
+    }
+}
+
+/// Row in the `auth_tokens` table
+#[derive(sqlx::FromRow)]
+struct AuthTokenRow {
+    id: String,
+    user_id: String,
+    token_hash: String,
+    name: Option<String>,
+    last_used_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<AuthTokenRow> for AuthToken {
+    type Error = DomainError;
+
+    fn try_from(row: AuthTokenRow) -> Result<Self, Self::Error> {
+        Ok(AuthToken {
+            id: parse_uuid(&row.id)?,
+            user_id: parse_uuid(&row.user_id)?,
+            token_hash: row.token_hash,
+            name: row.name,
+            last_used_at: row.last_used_at,
+            expires_at: row.expires_at,
+            created_at: row.created_at,
+        })
+    }
+}
+
+const AUTH_TOKEN_COLUMNS: &str =
+    "id, user_id, token_hash, name, last_used_at, expires_at, created_at";
+
+/// Row in the `provider_tokens` table
+#[derive(sqlx::FromRow)]
+struct ProviderTokenRow {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    refresh_token_expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<ProviderTokenRow> for ProviderToken {
+    fn from(row: ProviderTokenRow) -> Self {
+        ProviderToken {
+            access_token: row.access_token,
+            refresh_token: row.refresh_token,
+            expires_at: row.expires_at,
+            refresh_token_expires_at: row.refresh_token_expires_at,
+        }
+    }
+}
+
+/// Row in the `device_flows` table
+#[derive(sqlx::FromRow)]
+struct DeviceFlowRow {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    interval_seconds: i64,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<DeviceFlowRow> for PendingDeviceFlow {
+    fn from(row: DeviceFlowRow) -> Self {
+        PendingDeviceFlow {
+            device_code: row.device_code,
+            user_code: row.user_code,
+            verification_uri: row.verification_uri,
+            interval: u32::try_from(row.interval_seconds).unwrap_or_default(),
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthRepository for DbRepo {
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<AuthToken>, DomainError> {
+        let query = format!("SELECT {AUTH_TOKEN_COLUMNS} FROM auth_tokens WHERE token_hash = ?");
+        sqlx::query_as::<_, AuthTokenRow>(&query)
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(db_error)?
+            .map(AuthToken::try_from)
+            .transpose()
+    }
+
+    async fn find_by_user_id(&self, user_id: UserId) -> Result<Vec<AuthToken>, DomainError> {
+        let query = format!(
+            "SELECT {AUTH_TOKEN_COLUMNS} FROM auth_tokens WHERE user_id = ? ORDER BY created_at DESC"
+        );
+        sqlx::query_as::<_, AuthTokenRow>(&query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(AuthToken::try_from)
+            .collect()
+    }
+
+    async fn create(&self, token: &AuthToken) -> Result<AuthToken, DomainError> {
+        sqlx::query(
+            "INSERT INTO auth_tokens \
+             (id, user_id, token_hash, name, last_used_at, expires_at, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(token.id.to_string())
+        .bind(token.user_id.to_string())
+        .bind(&token.token_hash)
+        .bind(&token.name)
+        .bind(token.last_used_at)
+        .bind(token.expires_at)
+        .bind(token.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(token.clone())
+    }
+
+    async fn update_last_used(&self, id: Uuid) -> Result<(), DomainError> {
+        let result = sqlx::query("UPDATE auth_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DomainError::NotFound(format!("Auth token {id}")));
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DomainError> {
+        let result = sqlx::query("DELETE FROM auth_tokens WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DomainError::NotFound(format!("Auth token {id}")));
+        }
+
+        Ok(())
     }
 
     async fn delete_expired(&self) -> Result<u64, DomainError> {
-        todo!("Implement delete_expired")
+        let result = sqlx::query("DELETE FROM auth_tokens WHERE expires_at <= ?")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn save_provider_token(
+        &self,
+        user_id: UserId,
+        token: &ProviderToken,
+    ) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO provider_tokens \
+             (user_id, access_token, refresh_token, expires_at, refresh_token_expires_at, \
+             updated_at) VALUES (?, ?, ?, ?, ?, ?) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+             access_token = excluded.access_token, \
+             refresh_token = excluded.refresh_token, \
+             expires_at = excluded.expires_at, \
+             refresh_token_expires_at = excluded.refresh_token_expires_at, \
+             updated_at = excluded.updated_at",
+        )
+        .bind(user_id.to_string())
+        .bind(&token.access_token)
+        .bind(&token.refresh_token)
+        .bind(token.expires_at)
+        .bind(token.refresh_token_expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn find_provider_token(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<ProviderToken>, DomainError> {
+        let row = sqlx::query_as::<_, ProviderTokenRow>(
+            "SELECT access_token, refresh_token, expires_at, refresh_token_expires_at \
+             FROM provider_tokens WHERE user_id = ?",
+        )
+        .bind(user_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(row.map(ProviderToken::from))
+    }
+
+    async fn save_device_flow(&self, flow: &PendingDeviceFlow) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO device_flows \
+             (device_code, user_code, verification_uri, interval_seconds, expires_at, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&flow.device_code)
+        .bind(&flow.user_code)
+        .bind(&flow.verification_uri)
+        .bind(i64::from(flow.interval))
+        .bind(flow.expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn find_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<Option<PendingDeviceFlow>, DomainError> {
+        let row = sqlx::query_as::<_, DeviceFlowRow>(
+            "SELECT device_code, user_code, verification_uri, interval_seconds, expires_at \
+             FROM device_flows WHERE device_code = ?",
+        )
+        .bind(device_code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_error)?;
+
+        Ok(row.map(PendingDeviceFlow::from))
+    }
+
+    async fn delete_device_flow(&self, device_code: &str) -> Result<(), DomainError> {
+        sqlx::query("DELETE FROM device_flows WHERE device_code = ?")
+            .bind(device_code)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_device_flows(&self, now: DateTime<Utc>) -> Result<u64, DomainError> {
+        let result = sqlx::query("DELETE FROM device_flows WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result.rows_affected())
     }
 }
 
@@ -0,0 +1,463 @@
+//! # Fault Injection
+//!
+//! `Faulty<T>` wraps an adapter — the database, GitHub, Stripe or the
+//! Helius RPC — and makes its calls slow or fail at random, so retries,
+//! zombie session reaping and other resilience features can be tested
+//! against something less reliable than a mock. Each call can be:
+//!
+//! - delayed by a random latency between `min_latency` and `max_latency`
+//! - failed before reaching the adapter, with probability `error_rate`
+//! - passed to the adapter and then reported as failed anyway, with
+//!   probability `partial_failure_rate`, as when a write lands but its
+//!   response is lost
+//!
+//! Enabled by the `faults` feature, for tests only:
+//!
+//! ```rust,ignore
+//! let repo = Faulty::new(FaultSource::Database, MockRepo::default(), FaultConfig {
+//!     error_rate: 0.2,
+//!     ..FaultConfig::default()
+//! });
+//! let sessions = SessionService::new(repo.clone());
+//! repo.set_config(FaultConfig::default()); // healthy again
+//! ```
+//!
+//! Clones share their configuration and fault count.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use domain::errors::DomainError;
+use domain::models::{
+    AccessGroup, AccountState, AuthToken, BillingEvent, CollaboratorAccess, ForkSession,
+    PendingDeviceFlow, PlanPrice, ProviderToken, SessionCollaborator, SessionId, SessionStatus,
+    SessionSummary, SessionUsage, Snapshot, SnapshotId, Subscription, SubscriptionTier, User,
+    UserId, UserPatch, ZombieSession,
+};
+use domain::repositories::{AuthRepository, UserRepository};
+use domain::services::auth::github::DeviceFlowProvider;
+use domain::services::auth::{AuthError, AuthenticatedUser, DeviceCodeResponse};
+use domain::services::billing::events::BillingEventRepository;
+use domain::services::billing::subscriptions::SubscriptionRepository;
+use domain::services::billing::webhooks::ProcessedEventRepository;
+use domain::services::billing::{CustomerId, PaymentProcessor, SubscriptionId};
+use domain::services::forking::ForkStateProvider;
+use domain::services::sessions::SessionRepository;
+use domain::services::snapshots::SnapshotRepository;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::retry::random_fraction;
+
+/// How unreliable a `Faulty` adapter is; the default injects nothing
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    /// Shortest delay added to each call
+    pub min_latency: Duration,
+    /// Longest delay added to each call
+    pub max_latency: Duration,
+    /// Chance, from 0 to 1, that a call fails without reaching the adapter
+    pub error_rate: f64,
+    /// Chance, from 0 to 1, that a call succeeds but is reported as failed
+    pub partial_failure_rate: f64,
+}
+
+/// The kind of adapter wrapped, which decides the error injected faults raise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultSource {
+    /// Faults look like a database error, `DomainError::Internal`
+    Database,
+    /// Faults look like a GitHub outage, `DomainError::ExternalService`
+    GitHub,
+    /// Faults look like a Stripe outage, `DomainError::ExternalService`
+    Stripe,
+    /// Faults look like an RPC node outage, `DomainError::ExternalService`
+    Rpc,
+}
+
+impl FaultSource {
+    fn error(self, message: String) -> DomainError {
+        match self {
+            FaultSource::Database => DomainError::Internal(message),
+            FaultSource::GitHub | FaultSource::Stripe | FaultSource::Rpc => {
+                DomainError::ExternalService(message)
+            }
+        }
+    }
+}
+
+/// An adapter whose calls are slowed down or failed according to a `FaultConfig`
+#[derive(Clone)]
+pub struct Faulty<T> {
+    inner: T,
+    source: FaultSource,
+    config: Arc<RwLock<FaultConfig>>,
+    injected: Arc<AtomicU64>,
+}
+
+impl<T> Faulty<T> {
+    pub fn new(source: FaultSource, inner: T, config: FaultConfig) -> Self {
+        Self {
+            inner,
+            source,
+            config: Arc::new(RwLock::new(config)),
+            injected: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// The wrapped adapter, to inspect what reached it
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Change how unreliable the adapter is from the next call on
+    pub fn set_config(&self, config: FaultConfig) {
+        *self.config.write().expect("fault config lock poisoned") = config;
+    }
+
+    /// Errors injected so far, whether before or after the adapter ran
+    pub fn injected(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    fn config(&self) -> FaultConfig {
+        self.config
+            .read()
+            .expect("fault config lock poisoned")
+            .clone()
+    }
+
+    fn inject(&self, message: String) -> DomainError {
+        self.injected.fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(source = ?self.source, "{message}");
+        self.source.error(message)
+    }
+
+    /// Delay the call, then maybe fail it before it reaches the adapter
+    async fn before(&self, method: &str) -> Result<(), DomainError> {
+        let config = self.config();
+        if config.max_latency > config.min_latency {
+            let spread = config.max_latency - config.min_latency;
+            tokio::time::sleep(config.min_latency + spread.mul_f64(random_fraction())).await;
+        } else if !config.min_latency.is_zero() {
+            tokio::time::sleep(config.min_latency).await;
+        }
+
+        if happens(config.error_rate) {
+            return Err(self.inject(format!("Injected {:?} fault in {method}", self.source)));
+        }
+        Ok(())
+    }
+
+    /// Maybe report a successful call as failed
+    fn after<R>(&self, method: &str, result: Result<R, DomainError>) -> Result<R, DomainError> {
+        if result.is_ok() && happens(self.config().partial_failure_rate) {
+            return Err(self.inject(format!(
+                "Injected {:?} fault after {method} completed",
+                self.source
+            )));
+        }
+        result
+    }
+}
+
+/// Whether an event with probability `rate` happens this time
+fn happens(rate: f64) -> bool {
+    rate > 0.0 && random_fraction() < rate
+}
+
+/// Implement `$trait` for `Faulty<T>` by injecting faults around each call
+/// to the wrapped adapter
+macro_rules! inject_faults {
+    ($trait:ident {
+        $(async fn $method:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;)*
+    }) => {
+        #[async_trait]
+        impl<T: $trait> $trait for Faulty<T> {
+            $(
+                async fn $method(&self $(, $arg: $ty)*) -> $ret {
+                    self.before(stringify!($method)).await?;
+                    let result = <T as $trait>::$method(&self.inner $(, $arg)*).await;
+                    self.after(stringify!($method), result)
+                }
+            )*
+        }
+    };
+}
+
+inject_faults!(UserRepository {
+    async fn find_by_id(&self, id: UserId) -> Result<Option<User>, DomainError>;
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, DomainError>;
+    async fn find_by_github_id(&self, github_id: i64) -> Result<Option<User>, DomainError>;
+    async fn find_by_oidc_subject(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, DomainError>;
+    async fn link_oidc_subject(
+        &self,
+        id: UserId,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<(), DomainError>;
+    async fn find_by_stripe_customer_id(
+        &self,
+        stripe_customer_id: &str,
+    ) -> Result<Option<User>, DomainError>;
+    async fn create(&self, user: &User) -> Result<User, DomainError>;
+    async fn update(&self, user: &User) -> Result<User, DomainError>;
+    async fn patch(&self, id: UserId, patch: &UserPatch) -> Result<User, DomainError>;
+    async fn delete(&self, id: UserId) -> Result<(), DomainError>;
+    async fn find_users_page(&self, limit: u32, offset: u32) -> Result<Vec<User>, DomainError>;
+    async fn count_users(&self) -> Result<u64, DomainError>;
+});
+
+inject_faults!(AuthRepository {
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<AuthToken>, DomainError>;
+    async fn find_by_user_id(&self, user_id: UserId) -> Result<Vec<AuthToken>, DomainError>;
+    async fn create(&self, token: &AuthToken) -> Result<AuthToken, DomainError>;
+    async fn update_last_used(&self, id: Uuid) -> Result<(), DomainError>;
+    async fn delete(&self, id: Uuid) -> Result<(), DomainError>;
+    async fn delete_expired(&self) -> Result<u64, DomainError>;
+    async fn save_provider_token(
+        &self,
+        user_id: UserId,
+        token: &ProviderToken,
+    ) -> Result<(), DomainError>;
+    async fn find_provider_token(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<ProviderToken>, DomainError>;
+    async fn save_device_flow(&self, flow: &PendingDeviceFlow) -> Result<(), DomainError>;
+    async fn find_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<Option<PendingDeviceFlow>, DomainError>;
+    async fn delete_device_flow(&self, device_code: &str) -> Result<(), DomainError>;
+    async fn delete_expired_device_flows(&self, now: DateTime<Utc>) -> Result<u64, DomainError>;
+});
+
+inject_faults!(SessionRepository {
+    async fn create(
+        &self,
+        user_id: UserId,
+        name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<ForkSession, DomainError>;
+    async fn find_by_id(&self, id: SessionId) -> Result<Option<ForkSession>, DomainError>;
+    async fn find_by_slug(
+        &self,
+        user_id: UserId,
+        slug: &str,
+    ) -> Result<Option<ForkSession>, DomainError>;
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SessionId>, DomainError>;
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<ForkSession>, DomainError>;
+    async fn find_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SessionSummary>, DomainError>;
+    async fn count_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+    ) -> Result<u64, DomainError>;
+    async fn update(&self, session: &ForkSession) -> Result<ForkSession, DomainError>;
+    async fn update_status(
+        &self,
+        id: SessionId,
+        from: SessionStatus,
+        to: SessionStatus,
+    ) -> Result<Option<ForkSession>, DomainError>;
+    async fn stop_all_by_user(&self, user_id: UserId) -> Result<u64, DomainError>;
+    async fn save_accounts(
+        &self,
+        session_id: SessionId,
+        accounts: &[AccountState],
+    ) -> Result<(), DomainError>;
+    async fn list_accounts(&self, session_id: SessionId) -> Result<Vec<AccountState>, DomainError>;
+    async fn record_usage(
+        &self,
+        session_id: SessionId,
+        elapsed_seconds: u64,
+        peak_memory_bytes: u64,
+    ) -> Result<SessionUsage, DomainError>;
+    async fn find_zombies(&self, cutoff: DateTime<Utc>) -> Result<Vec<ZombieSession>, DomainError>;
+    async fn upsert_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        access: CollaboratorAccess,
+    ) -> Result<SessionCollaborator, DomainError>;
+    async fn find_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+    ) -> Result<Option<SessionCollaborator>, DomainError>;
+});
+
+inject_faults!(SnapshotRepository {
+    async fn create(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Snapshot, DomainError>;
+    async fn find_by_id(&self, id: SnapshotId) -> Result<Option<Snapshot>, DomainError>;
+    async fn list_by_session(&self, session_id: SessionId) -> Result<Vec<Snapshot>, DomainError>;
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError>;
+    async fn find_ids_created_before(
+        &self,
+        user_id: UserId,
+        cutoff: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError>;
+    async fn restore(&self, snapshot_id: SnapshotId, session_id: SessionId) -> Result<(), DomainError>;
+    async fn delete(&self, id: SnapshotId) -> Result<(), DomainError>;
+});
+
+inject_faults!(SubscriptionRepository {
+    async fn find_by_user(&self, user_id: UserId) -> Result<Option<Subscription>, DomainError>;
+    async fn upsert(&self, subscription: &Subscription) -> Result<Subscription, DomainError>;
+});
+
+inject_faults!(BillingEventRepository {
+    async fn record(&self, event: &BillingEvent) -> Result<BillingEvent, DomainError>;
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<BillingEvent>, DomainError>;
+    async fn find_page_by_user(
+        &self,
+        user_id: UserId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<BillingEvent>, DomainError>;
+});
+
+inject_faults!(ProcessedEventRepository {
+    async fn is_event_processed(&self, event_id: &str) -> Result<bool, DomainError>;
+    async fn mark_event_processed(
+        &self,
+        event_id: &str,
+        event_type: &str,
+    ) -> Result<(), DomainError>;
+});
+
+inject_faults!(PaymentProcessor {
+    async fn create_customer(
+        &self,
+        email: &str,
+        external_id: &str,
+    ) -> Result<CustomerId, DomainError>;
+    async fn update_customer_country(
+        &self,
+        customer_id: &CustomerId,
+        country: &str,
+    ) -> Result<(), DomainError>;
+    async fn tier_prices(&self, tier: SubscriptionTier) -> Result<Vec<PlanPrice>, DomainError>;
+    async fn create_subscription(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+    ) -> Result<SubscriptionId, DomainError>;
+    async fn update_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+        new_tier: SubscriptionTier,
+    ) -> Result<(), DomainError>;
+    async fn cancel_subscription(
+        &self,
+        subscription_id: &SubscriptionId,
+    ) -> Result<(), DomainError>;
+    async fn create_checkout_session(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+        success_url: &str,
+        cancel_url: &str,
+    ) -> Result<String, DomainError>;
+    async fn create_portal_session(
+        &self,
+        customer_id: &CustomerId,
+        return_url: &str,
+    ) -> Result<String, DomainError>;
+    async fn tier_for_price(&self, price_id: &str) -> Result<Option<SubscriptionTier>, DomainError>;
+    async fn verify_webhook_signature(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<bool, DomainError>;
+});
+
+inject_faults!(ForkStateProvider {
+    async fn current_slot(&self) -> Result<u64, DomainError>;
+    async fn fetch_accounts(
+        &self,
+        pubkeys: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError>;
+    async fn fetch_programs(
+        &self,
+        program_ids: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError>;
+    async fn fetch_sysvars(&self, slot: u64) -> Result<Vec<AccountState>, DomainError>;
+});
+
+// Written out rather than generated: polling fails with `AuthError`
+#[async_trait]
+impl<T: DeviceFlowProvider> DeviceFlowProvider for Faulty<T> {
+    async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        self.before("request_device_code").await?;
+        let result = self.inner.request_device_code().await;
+        self.after("request_device_code", result)
+    }
+
+    async fn poll_authorization(&self, device_code: &str) -> Result<ProviderToken, AuthError> {
+        let internal = |e: DomainError| AuthError::InternalServerError {
+            debug_info: e.to_string(),
+        };
+        self.before("poll_authorization").await.map_err(internal)?;
+        let token = self.inner.poll_authorization(device_code).await?;
+        self.after("poll_authorization", Ok(token))
+            .map_err(internal)
+    }
+
+    async fn refresh_token(&self, refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        self.before("refresh_token").await?;
+        let result = self.inner.refresh_token(refresh_token).await;
+        self.after("refresh_token", result)
+    }
+
+    async fn get_user(&self, access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        self.before("get_user").await?;
+        let result = self.inner.get_user(access_token).await;
+        self.after("get_user", result)
+    }
+
+    async fn is_member(
+        &self,
+        access_token: &str,
+        username: &str,
+        group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        self.before("is_member").await?;
+        let result = self.inner.is_member(access_token, username, group).await;
+        self.after("is_member", result)
+    }
+}
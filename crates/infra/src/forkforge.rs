@@ -0,0 +1,104 @@
+//! # ForkForge API Client
+//!
+//! Client-side access to the ForkForge API. Paths are resolved against the
+//! configured base URL and requests carry the user's API token when there is
+//! one, so callers only deal with the endpoint itself.
+//!
+//! ## Security Note
+//!
+//! Only the user's own API token is attached; nothing here needs server-side
+//! secrets, so it is safe to ship in the CLI.
+
+use reqwest::{Client, Method, RequestBuilder, Response};
+
+use crate::retry::{self, RetryPolicy};
+
+/// HTTP client for the ForkForge API
+#[derive(Clone)]
+pub struct ForkForgeApiClient {
+    client: Client,
+    base_url: String,
+    api_token: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl ForkForgeApiClient {
+    /// Creates a client for the API at `base_url`, without a token
+    pub fn new(client: Client, base_url: impl Into<String>) -> Self {
+        Self {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_token: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Send `api_token` as a bearer token with every request
+    pub fn with_api_token(mut self, api_token: Option<String>) -> Self {
+        self.api_token = api_token;
+        self
+    }
+
+    /// Retry idempotent requests according to `retry_policy`
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The underlying HTTP client, for requests to other hosts
+    pub fn http_client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Absolute URL of an API path such as `/sessions`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// A request to an API path, authenticated when there's a token
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, self.url(path));
+        match &self.api_token {
+            Some(api_token) => request.bearer_auth(api_token),
+            None => request,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    /// Send a request that is safe to repeat, retrying transient failures
+    pub async fn send_idempotent(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        retry::send_with_retries(request, &self.retry_policy).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::AUTHORIZATION;
+
+    #[test]
+    fn test_builds_authenticated_api_requests() {
+        let api = ForkForgeApiClient::new(Client::new(), "http://localhost:3000/");
+        let request = api.get("/sessions").build().unwrap();
+        assert_eq!(request.url().as_str(), "http://localhost:3000/sessions");
+        assert!(request.headers().get(AUTHORIZATION).is_none());
+
+        let request = api
+            .with_api_token(Some("ff_abc".to_string()))
+            .post("/auth/token")
+            .build()
+            .unwrap();
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer ff_abc");
+    }
+}
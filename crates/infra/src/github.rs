@@ -3,14 +3,23 @@
 //! This module provides the concrete implementation of the DeviceFlowProvider trait
 //! for GitHub's OAuth device flow. It handles all GitHub-specific details including
 //! URLs, polling strategies, and error mapping.
+//!
+//! Both github.com and GitHub Enterprise Server are supported: the OAuth
+//! endpoints live under the instance's web URL, and the REST API under
+//! `api.github.com` or the instance's `/api/v3`.
+//!
+//! Organization and team membership is read with the user's own token,
+//! which needs the `read:org` scope.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use domain::errors::DomainError;
+use domain::models::{AccessGroup, ProviderToken};
 use domain::services::auth::AuthenticatedUser;
 use domain::services::auth::github::DeviceFlowProvider;
 use domain::services::auth::types::{
     AuthError, CheckAuthorisationRequest, CheckAuthorisationResponse, DeviceCodeRequest,
-    DeviceCodeResponse, GitHubUser,
+    DeviceCodeResponse, GitHubUser, RefreshTokenRequest,
 };
 use serde::Deserialize;
 use std::time::Duration;
@@ -18,8 +27,9 @@ use tokio::time::{Instant, sleep};
 
 use crate::http::HttpClient;
 
-const GITHUB_CHECK_USER_AUTHORISED_URL: &str = "https://github.com/login/oauth/access_token";
-const GITHUB_DEVICE_CODE_REQUEST_URL: &str = "https://github.com/login/device/code";
+/// Web URL of github.com; any other base URL is treated as GitHub Enterprise Server
+const GITHUB_COM_URL: &str = "https://github.com";
+const GITHUB_COM_API_URL: &str = "https://api.github.com";
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -32,6 +42,7 @@ enum GitHubDeviceFlowErrorType {
     IncorrectDeviceCode,
     AccessDenied,
     DeviceFlowDisabled,
+    BadRefreshToken,
 }
 
 #[derive(Debug, Deserialize)]
@@ -43,22 +54,120 @@ struct GitHubDeviceFlowError {
     _error_uri: String,
 }
 
+/// The user's membership of an organization or team
+#[derive(Debug, Deserialize)]
+struct GitHubMembership {
+    /// `active`, or `pending` until an invitation is accepted
+    state: String,
+}
+
+/// One of the addresses listed by `/user/emails`
+#[derive(Debug, Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
 /// GitHub-specific implementation of the DeviceFlowProvider
 ///
 /// This struct encapsulates all GitHub-specific OAuth device flow logic,
 /// keeping the domain layer pure and free from implementation details.
 pub struct GitHubDeviceFlowProvider {
     client_id: String,
+    /// Only needed to refresh expiring user tokens
+    client_secret: Option<String>,
+    /// Space-separated OAuth scopes
+    scopes: String,
+    device_code_url: String,
+    access_token_url: String,
+    api_url: String,
+    user_url: String,
+    emails_url: String,
     http_client: HttpClient,
 }
 
 impl GitHubDeviceFlowProvider {
-    pub fn new(client_id: String, http_client: HttpClient) -> Self {
+    /// Provider for the GitHub instance at `base_url`, e.g. `https://github.com`
+    pub fn new(
+        client_id: String,
+        client_secret: Option<String>,
+        base_url: &str,
+        scopes: String,
+        http_client: HttpClient,
+    ) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let api_url = if base_url == GITHUB_COM_URL {
+            GITHUB_COM_API_URL.to_string()
+        } else {
+            format!("{base_url}/api/v3")
+        };
+
         Self {
             client_id,
+            client_secret,
+            scopes,
+            device_code_url: format!("{base_url}/login/device/code"),
+            access_token_url: format!("{base_url}/login/oauth/access_token"),
+            user_url: format!("{api_url}/user"),
+            emails_url: format!("{api_url}/user/emails"),
+            api_url,
             http_client,
         }
     }
+
+    /// Request device codes and tokens from `oauth_url` instead of the
+    /// instance's web URL
+    pub fn with_oauth_url(mut self, oauth_url: &str) -> Self {
+        let oauth_url = oauth_url.trim_end_matches('/');
+        self.device_code_url = format!("{oauth_url}/login/device/code");
+        self.access_token_url = format!("{oauth_url}/login/oauth/access_token");
+        self
+    }
+
+    /// Fetch users from the REST API at `api_url` instead of the one
+    /// derived from the instance's web URL
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        let api_url = api_url.trim_end_matches('/');
+        self.user_url = format!("{api_url}/user");
+        self.emails_url = format!("{api_url}/user/emails");
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Where GitHub reports `username`'s membership of `group`
+    fn membership_url(&self, username: &str, group: &AccessGroup) -> String {
+        match group {
+            AccessGroup::Org(org) => format!("{}/user/memberships/orgs/{org}", self.api_url),
+            AccessGroup::Team { org, team } => format!(
+                "{}/orgs/{org}/teams/{team}/memberships/{username}",
+                self.api_url
+            ),
+        }
+    }
+
+    /// The user's verified primary email, which `/user` leaves out when private
+    ///
+    /// `None` if there is none, or if the token lacks the `user:email` scope.
+    async fn primary_email(&self, access_token: &str) -> Option<String> {
+        let response_text = match self
+            .http_client
+            .get_with_auth(&self.emails_url, access_token)
+            .await
+        {
+            Ok(response_text) => response_text,
+            Err(e) => {
+                tracing::debug!(error = %e, "Could not list GitHub emails");
+                return None;
+            }
+        };
+
+        serde_json::from_str::<Vec<GitHubEmail>>(&response_text)
+            .ok()?
+            .into_iter()
+            .find(|email| email.primary && email.verified)
+            .map(|email| email.email)
+    }
 }
 
 #[async_trait]
@@ -66,7 +175,7 @@ impl DeviceFlowProvider for GitHubDeviceFlowProvider {
     async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
         let request = DeviceCodeRequest {
             client_id: self.client_id.clone(),
-            scope: "user".to_owned(),
+            scope: self.scopes.clone(),
         };
 
         let body = serde_urlencoded::to_string(&request)
@@ -74,7 +183,7 @@ impl DeviceFlowProvider for GitHubDeviceFlowProvider {
 
         let response_text = self
             .http_client
-            .post_form(GITHUB_DEVICE_CODE_REQUEST_URL, &body)
+            .post_form(&self.device_code_url, &body)
             .await?;
 
         serde_json::from_str(&response_text).map_err(|e| {
@@ -82,7 +191,7 @@ impl DeviceFlowProvider for GitHubDeviceFlowProvider {
         })
     }
 
-    async fn poll_authorization(&self, device_code: &str) -> Result<String, AuthError> {
+    async fn poll_authorization(&self, device_code: &str) -> Result<ProviderToken, AuthError> {
         let request = CheckAuthorisationRequest {
             client_id: self.client_id.clone(),
             device_code: device_code.to_string(),
@@ -105,7 +214,7 @@ impl DeviceFlowProvider for GitHubDeviceFlowProvider {
 
             let response_text = self
                 .http_client
-                .post_form(GITHUB_CHECK_USER_AUTHORISED_URL, &body)
+                .post_form(&self.access_token_url, &body)
                 .await
                 .map_err(|e| AuthError::InternalServerError {
                     debug_info: format!("Failed to send request: {e}"),
@@ -154,25 +263,185 @@ impl DeviceFlowProvider for GitHubDeviceFlowProvider {
                     debug_info: format!("Failed to parse success response: {e}"),
                 })?;
 
-            return Ok(success_response.access_token);
+            return Ok(provider_token(success_response));
         }
     }
 
+    async fn refresh_token(&self, refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        let client_secret = self.client_secret.clone().ok_or_else(|| {
+            DomainError::Internal("GitHub client secret is required to refresh tokens".to_string())
+        })?;
+        let request = RefreshTokenRequest {
+            client_id: self.client_id.clone(),
+            client_secret,
+            grant_type: "refresh_token".to_owned(),
+            refresh_token: refresh_token.to_string(),
+        };
+
+        let body = serde_urlencoded::to_string(&request)
+            .map_err(|e| DomainError::Internal(format!("Failed to serialize request: {e}")))?;
+
+        let response_text = self
+            .http_client
+            .post_form(&self.access_token_url, &body)
+            .await?;
+
+        if let Ok(error_response) = serde_json::from_str::<GitHubDeviceFlowError>(&response_text) {
+            return Err(match error_response.error {
+                GitHubDeviceFlowErrorType::BadRefreshToken => DomainError::Unauthorized(
+                    "GitHub refresh token is invalid or expired, log in again".to_string(),
+                ),
+                GitHubDeviceFlowErrorType::IncorrectClientCredentials => {
+                    DomainError::Internal("Invalid GitHub client credentials".to_string())
+                }
+                error => {
+                    DomainError::ExternalService(format!("GitHub token refresh failed: {error:?}"))
+                }
+            });
+        }
+
+        let response: CheckAuthorisationResponse =
+            serde_json::from_str(&response_text).map_err(|e| {
+                DomainError::ExternalService(format!("Failed to parse GitHub response: {e}"))
+            })?;
+
+        Ok(provider_token(response))
+    }
+
     async fn get_user(&self, access_token: &str) -> Result<AuthenticatedUser, DomainError> {
         let response_text = self
             .http_client
-            .get_with_auth("https://api.github.com/user", access_token)
+            .get_with_auth(&self.user_url, access_token)
             .await?;
 
         let github_user: GitHubUser = serde_json::from_str(&response_text).map_err(|e| {
             DomainError::ExternalService(format!("Failed to parse GitHub user response: {e}"))
         })?;
 
+        let email = match github_user.email {
+            Some(email) => Some(email),
+            None => self.primary_email(access_token).await,
+        };
+
         Ok(AuthenticatedUser {
-            provider_id: "github".to_string(),
+            provider_id: github_user.id.to_string(),
             username: github_user.login,
-            email: github_user.email,
+            email,
             display_name: github_user.name,
+            created_at: github_user
+                .created_at
+                .as_deref()
+                .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+                .map(|created_at| created_at.with_timezone(&Utc)),
         })
     }
+
+    async fn is_member(
+        &self,
+        access_token: &str,
+        username: &str,
+        group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        let url = self.membership_url(username, group);
+        // GitHub answers 404 for non-members
+        let Some(response_text) = self
+            .http_client
+            .get_with_auth_if_found(&url, access_token)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let membership: GitHubMembership = serde_json::from_str(&response_text).map_err(|e| {
+            DomainError::ExternalService(format!("Failed to parse GitHub membership: {e}"))
+        })?;
+        Ok(membership.state == "active")
+    }
+}
+
+/// Tokens from an access token response, with lifetimes made absolute
+fn provider_token(response: CheckAuthorisationResponse) -> ProviderToken {
+    let now = Utc::now();
+    ProviderToken {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: response
+            .expires_in
+            .map(|seconds| now + chrono::Duration::seconds(seconds)),
+        refresh_token_expires_at: response
+            .refresh_token_expires_in
+            .map(|seconds| now + chrono::Duration::seconds(seconds)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(base_url: &str) -> GitHubDeviceFlowProvider {
+        GitHubDeviceFlowProvider::new(
+            "client".to_string(),
+            None,
+            base_url,
+            "read:user".to_string(),
+            HttpClient::new(reqwest::Client::new()),
+        )
+    }
+
+    #[test]
+    fn test_enterprise_urls() {
+        let github = provider("https://github.com/");
+        assert_eq!(
+            github.device_code_url,
+            "https://github.com/login/device/code"
+        );
+        assert_eq!(github.user_url, "https://api.github.com/user");
+        assert_eq!(github.emails_url, "https://api.github.com/user/emails");
+
+        let enterprise = provider("https://github.example.com");
+        assert_eq!(
+            enterprise.access_token_url,
+            "https://github.example.com/login/oauth/access_token"
+        );
+        assert_eq!(
+            enterprise.user_url,
+            "https://github.example.com/api/v3/user"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_overrides() {
+        let github = provider("https://acme.ghe.com")
+            .with_oauth_url("https://sso.acme.example/github/")
+            .with_api_url("https://api.acme.ghe.com");
+        assert_eq!(
+            github.device_code_url,
+            "https://sso.acme.example/github/login/device/code"
+        );
+        assert_eq!(
+            github.access_token_url,
+            "https://sso.acme.example/github/login/oauth/access_token"
+        );
+        assert_eq!(github.user_url, "https://api.acme.ghe.com/user");
+        assert_eq!(github.emails_url, "https://api.acme.ghe.com/user/emails");
+    }
+
+    #[test]
+    fn test_membership_urls() {
+        let github = provider("https://github.com");
+        assert_eq!(
+            github.membership_url("ada", &AccessGroup::Org("acme".to_string())),
+            "https://api.github.com/user/memberships/orgs/acme"
+        );
+        assert_eq!(
+            github.membership_url(
+                "ada",
+                &AccessGroup::Team {
+                    org: "acme".to_string(),
+                    team: "platform".to_string(),
+                }
+            ),
+            "https://api.github.com/orgs/acme/teams/platform/memberships/ada"
+        );
+    }
 }
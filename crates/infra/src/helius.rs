@@ -1,27 +1,453 @@
-//! # Helius RPC Integration Module (Placeholder)
+//! # Helius RPC Integration Module
 //!
-//! This module will provide integration with Helius RPC services for
-//! enhanced Solana blockchain interactions.
+//! JSON-RPC client for Solana mainnet state served by Helius. Implements the
+//! domain's `ForkStateProvider` to read accounts and programs when creating
+//! a fork.
 //!
-//! ## Planned Features
+//! ## Reliability
 //!
-//! - Enhanced RPC endpoints for Solana mainnet access
-//! - Websocket subscriptions for real-time updates
-//! - Historical data queries
-//! - Account state tracking
-//!
-//! ## Implementation Status
-//!
-//! Currently a placeholder. Implementation pending based on forking
-//! service requirements.
+//! Helius rate-limits per API key. Requests that are throttled (HTTP 429),
+//! hit a server error, time out or fail to connect are retried by
+//! `retry::send_upstream`, honouring `Retry-After` when Helius sends one.
+//! JSON-RPC errors (e.g. an invalid pubkey) are not retried.
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use domain::errors::DomainError;
+use domain::models::AccountState;
+use domain::services::forking::ForkStateProvider;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::task::JoinSet;
+
+use crate::retry::{self, RetryPolicy};
+
+/// Helius mainnet RPC endpoint; the API key is passed as a query parameter
+const HELIUS_MAINNET_URL: &str = "https://mainnet.helius-rpc.com";
+
+/// Most pubkeys Solana RPC accepts in one `getMultipleAccounts` call
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Loader owning upgradeable programs, whose code lives in a separate
+/// program data account
+const BPF_LOADER_UPGRADEABLE: &str = "BPFLoaderUpgradeab1e11111111111111111111111";
+
+/// `UpgradeableLoaderState::Program` discriminant, followed by the program
+/// data address
+const UPGRADEABLE_PROGRAM_TAG: [u8; 4] = [2, 0, 0, 0];
+
+/// Sysvars every fork needs so on-chain time and rent match mainnet
+const FORK_SYSVARS: [&str; 2] = [
+    "SysvarC1ock11111111111111111111111111111111",
+    "SysvarRent111111111111111111111111111111111",
+];
+
+/// State of a single on-chain account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub lamports: u64,
+    /// Base58 pubkey of the owning program
+    pub owner: String,
+    pub data: Vec<u8>,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+/// An account returned by `get_program_accounts`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyedAccount {
+    pub pubkey: String,
+    pub account: AccountInfo,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// Responses wrapped with the slot they were read at
+#[derive(Deserialize)]
+struct RpcContextual<T> {
+    value: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcAccount {
+    lamports: u64,
+    owner: String,
+    /// `[<base64 data>, "base64"]`
+    data: (String, String),
+    executable: bool,
+    rent_epoch: u64,
+}
+
+#[derive(Deserialize)]
+struct RpcKeyedAccount {
+    pubkey: String,
+    account: RpcAccount,
+}
 
-/// Placeholder for future Helius RPC client
-pub struct HeliusClient;
+impl TryFrom<RpcAccount> for AccountInfo {
+    type Error = DomainError;
+
+    fn try_from(account: RpcAccount) -> Result<Self, Self::Error> {
+        let (data, encoding) = account.data;
+        if encoding != "base64" {
+            return Err(DomainError::ExternalService(format!(
+                "Unexpected account data encoding: {encoding}"
+            )));
+        }
+
+        Ok(AccountInfo {
+            lamports: account.lamports,
+            owner: account.owner,
+            data: BASE64
+                .decode(data)
+                .map_err(|e| DomainError::ExternalService(format!("Invalid account data: {e}")))?,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+}
+
+/// Helius JSON-RPC client for account and slot queries
+#[derive(Clone)]
+pub struct HeliusClient {
+    rpc_url: String,
+    client: Client,
+    retry_policy: RetryPolicy,
+}
 
 impl HeliusClient {
-    /// Creates a new Helius client instance (placeholder)
-    #[allow(dead_code)]
-    pub fn new(_api_key: String) -> Self {
-        Self
+    /// Creates a client for Helius mainnet
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Helius API key
+    /// * `client` - Pre-configured reqwest Client used for RPC calls
+    pub fn new(api_key: String, client: Client) -> Self {
+        Self::with_rpc_url(format!("{HELIUS_MAINNET_URL}/?api-key={api_key}"), client)
+    }
+
+    /// Creates a client for any Solana JSON-RPC endpoint
+    pub fn with_rpc_url(rpc_url: String, client: Client) -> Self {
+        Self {
+            rpc_url,
+            client,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Retry transient failures according to `retry_policy`
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Current slot at `confirmed` commitment
+    pub async fn get_slot(&self) -> Result<u64, DomainError> {
+        self.call("getSlot", json!([{ "commitment": "confirmed" }]))
+            .await
+    }
+
+    /// Fetch one account, or `None` if it doesn't exist
+    pub async fn get_account(&self, pubkey: &str) -> Result<Option<AccountInfo>, DomainError> {
+        let response: RpcContextual<Option<RpcAccount>> = self
+            .call("getAccountInfo", json!([pubkey, account_config(None)]))
+            .await?;
+
+        response.value.map(AccountInfo::try_from).transpose()
+    }
+
+    /// Fetch several accounts, returned in the same order as `pubkeys`
+    ///
+    /// Large lists are split into batches of 100, the RPC limit, which are
+    /// requested at the same time.
+    pub async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[String],
+    ) -> Result<Vec<Option<AccountInfo>>, DomainError> {
+        self.multiple_accounts(pubkeys, None).await
+    }
+
+    async fn multiple_accounts(
+        &self,
+        pubkeys: &[String],
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<AccountInfo>>, DomainError> {
+        let mut batches = JoinSet::new();
+        for (i, batch) in pubkeys.chunks(MAX_ACCOUNTS_PER_REQUEST).enumerate() {
+            let client = self.clone();
+            let params = json!([batch, account_config(min_context_slot)]);
+            batches.spawn(async move {
+                let response: RpcContextual<Vec<Option<RpcAccount>>> =
+                    client.call("getMultipleAccounts", params).await?;
+                Ok::<_, DomainError>((i, response.value))
+            });
+        }
+
+        let mut responses = Vec::with_capacity(batches.len());
+        while let Some(joined) = batches.join_next().await {
+            let batch = joined.map_err(|e| {
+                DomainError::Internal(format!("getMultipleAccounts task failed: {e}"))
+            })??;
+            responses.push(batch);
+        }
+        responses.sort_by_key(|(i, _)| *i);
+
+        responses
+            .into_iter()
+            .flat_map(|(_, accounts)| accounts)
+            .map(|account| account.map(AccountInfo::try_from).transpose())
+            .collect()
+    }
+
+    /// Fetch every account owned by a program
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &str,
+    ) -> Result<Vec<KeyedAccount>, DomainError> {
+        let accounts: Vec<RpcKeyedAccount> = self
+            .call(
+                "getProgramAccounts",
+                json!([program_id, account_config(None)]),
+            )
+            .await?;
+
+        accounts
+            .into_iter()
+            .map(|keyed| {
+                Ok(KeyedAccount {
+                    pubkey: keyed.pubkey,
+                    account: keyed.account.try_into()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch the latest state of accounts to load into a local validator,
+    /// with the program data of any upgradeable programs among them
+    ///
+    /// Accounts that don't exist are left out.
+    pub async fn get_accounts_with_program_data(
+        &self,
+        pubkeys: &[String],
+    ) -> Result<Vec<AccountState>, DomainError> {
+        let mut accounts = self.account_states(pubkeys, None).await?;
+        let program_data: Vec<String> = accounts
+            .iter()
+            .filter_map(program_data_address)
+            .filter(|address| !pubkeys.contains(address))
+            .collect();
+        if !program_data.is_empty() {
+            accounts.extend(self.account_states(&program_data, None).await?);
+        }
+        Ok(accounts)
+    }
+
+    /// Fetch accounts as domain state, leaving out ones that don't exist
+    async fn account_states(
+        &self,
+        pubkeys: &[String],
+        slot: Option<u64>,
+    ) -> Result<Vec<AccountState>, DomainError> {
+        let accounts = self.multiple_accounts(pubkeys, slot).await?;
+        Ok(pubkeys
+            .iter()
+            .zip(accounts)
+            .filter_map(|(pubkey, account)| {
+                account.map(|account| AccountState {
+                    pubkey: pubkey.clone(),
+                    lamports: account.lamports,
+                    owner: account.owner,
+                    data: account.data,
+                    executable: account.executable,
+                    rent_epoch: account.rent_epoch,
+                })
+            })
+            .collect())
+    }
+
+    /// Sends a JSON-RPC request, retrying throttled and transient failures
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, DomainError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let request = self.client.post(&self.rpc_url).json(&body);
+
+        let response = retry::send_upstream(request, &self.retry_policy)
+            .await
+            .map_err(|e| {
+                // Never echo the URL; it contains the API key
+                DomainError::ExternalService(format!(
+                    "Helius {method} request failed: {}",
+                    e.without_url()
+                ))
+            })?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err(DomainError::ExternalService(format!(
+                "Helius returned {status} for {method}"
+            )));
+        }
+
+        let text = response.text().await.map_err(|e| {
+            DomainError::ExternalService(format!(
+                "Failed to read Helius response: {}",
+                e.without_url()
+            ))
+        })?;
+
+        if !status.is_success() {
+            return Err(DomainError::ExternalService(format!(
+                "Helius returned {status} for {method}: {text}"
+            )));
+        }
+
+        parse_response(method, &text)
+    }
+}
+
+#[async_trait]
+impl ForkStateProvider for HeliusClient {
+    async fn current_slot(&self) -> Result<u64, DomainError> {
+        self.get_slot().await
+    }
+
+    async fn fetch_accounts(
+        &self,
+        pubkeys: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError> {
+        self.account_states(pubkeys, Some(slot)).await
+    }
+
+    async fn fetch_programs(
+        &self,
+        program_ids: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError> {
+        let mut programs = self.account_states(program_ids, Some(slot)).await?;
+
+        // Upgradeable programs keep their code in a program data account
+        let program_data: Vec<String> = programs.iter().filter_map(program_data_address).collect();
+        if !program_data.is_empty() {
+            programs.extend(self.account_states(&program_data, Some(slot)).await?);
+        }
+
+        Ok(programs)
+    }
+
+    async fn fetch_sysvars(&self, slot: u64) -> Result<Vec<AccountState>, DomainError> {
+        let sysvars: Vec<String> = FORK_SYSVARS.iter().map(|s| s.to_string()).collect();
+        self.account_states(&sysvars, Some(slot)).await
+    }
+}
+
+/// Read options for account queries
+fn account_config(min_context_slot: Option<u64>) -> Value {
+    let mut config = json!({ "encoding": "base64", "commitment": "confirmed" });
+    if let Some(slot) = min_context_slot {
+        config["minContextSlot"] = json!(slot);
+    }
+    config
+}
+
+/// Program data address of an upgradeable program account
+fn program_data_address(program: &AccountState) -> Option<String> {
+    if program.owner != BPF_LOADER_UPGRADEABLE {
+        return None;
+    }
+
+    let address = program
+        .data
+        .strip_prefix(&UPGRADEABLE_PROGRAM_TAG)?
+        .get(..32)?;
+    Some(bs58::encode(address).into_string())
+}
+
+fn parse_response<T: DeserializeOwned>(method: &str, text: &str) -> Result<T, DomainError> {
+    let response: RpcResponse<T> = serde_json::from_str(text).map_err(|e| {
+        DomainError::ExternalService(format!("Unexpected Helius {method} response: {e}"))
+    })?;
+
+    if let Some(error) = response.error {
+        return Err(DomainError::ExternalService(format!(
+            "Helius {method} failed ({}): {}",
+            error.code, error.message
+        )));
+    }
+
+    response.result.ok_or_else(|| {
+        DomainError::ExternalService(format!("Helius {method} response has no result"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account_response() {
+        let text = r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":250000000},
+            "value":{"lamports":1461600,"owner":"TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "data":["AQID","base64"],"executable":false,"rentEpoch":18446744073709551615,"space":3}}}"#;
+
+        let response: RpcContextual<Option<RpcAccount>> =
+            parse_response("getAccountInfo", text).unwrap();
+        let account = AccountInfo::try_from(response.value.unwrap()).unwrap();
+
+        assert_eq!(account.lamports, 1_461_600);
+        assert_eq!(account.data, vec![1, 2, 3]);
+        assert_eq!(account.rent_epoch, u64::MAX);
+
+        // Missing accounts come back as a null value
+        let missing: RpcContextual<Option<RpcAccount>> = parse_response(
+            "getAccountInfo",
+            r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":null}}"#,
+        )
+        .unwrap();
+        assert!(missing.value.is_none());
+
+        let error = parse_response::<u64>(
+            "getSlot",
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"Invalid params"}}"#,
+        );
+        assert!(matches!(error, Err(DomainError::ExternalService(_))));
+    }
+
+    #[test]
+    fn test_program_data_address() {
+        let program_data = [7u8; 32];
+        let mut program = AccountState {
+            pubkey: "prog".to_string(),
+            lamports: 1,
+            owner: BPF_LOADER_UPGRADEABLE.to_string(),
+            data: [UPGRADEABLE_PROGRAM_TAG.as_slice(), &program_data].concat(),
+            executable: true,
+            rent_epoch: 0,
+        };
+        assert_eq!(
+            program_data_address(&program),
+            Some(bs58::encode(program_data).into_string())
+        );
+
+        // Programs owned by the non-upgradeable loaders carry their own code
+        program.owner = "BPFLoader2111111111111111111111111111111111".to_string();
+        assert_eq!(program_data_address(&program), None);
     }
 }
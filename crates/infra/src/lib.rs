@@ -13,20 +13,38 @@
 //! ## Modules
 //!
 //! - `db`: SQLite/SQLx database implementations of domain repository traits
+//! - `faults`: Latency and error injection around adapters, for resilience
+//!   tests (`faults` feature)
+//! - `forkforge`: Client for the ForkForge API, used by the CLI
 //! - `http`: Generic HTTP client adapter for OAuth and API operations
+//! - `license`: Offline ed25519 license key verification for self-hosted deployments
+//! - `oidc`: OpenID Connect single sign-on against a configured issuer
+//! - `retry`: Retries and rate-limit pacing for idempotent API requests, and
+//!   retries of outbound GitHub, Stripe and Helius requests
 //! - `stripe`: Stripe SDK integration for billing operations
-//! - `helius`: Placeholder for future Helius RPC integration
+//! - `helius`: Helius JSON-RPC client for mainnet account and slot queries
 
 pub mod db;
+#[cfg(feature = "faults")]
+pub mod faults;
+pub mod forkforge;
 pub mod github;
 pub mod helius;
 pub mod http;
+pub mod license;
+#[cfg(feature = "mocks")]
+pub mod mocks;
+pub mod oidc;
+pub mod retry;
 pub mod stripe;
 
 pub use db::{DbRepo, MIGRATOR};
+pub use forkforge::ForkForgeApiClient;
 pub use github::GitHubDeviceFlowProvider;
-pub use http::HttpClient;
-pub use stripe::StripeSdk;
+pub use helius::HeliusClient;
+pub use http::{HttpClient, OutboundConfig};
+pub use oidc::OidcClient;
+pub use stripe::{StripeProducts, StripeSdk};
 
 use domain::errors::DomainError;
 
@@ -52,12 +70,14 @@ use domain::errors::DomainError;
 /// let customer = infra.stripe.create_customer(...).await?;
 /// ```
 pub struct ServerInfra {
-    /// Database repository providing all data access operations
+    /// Database repository providing all data access operations (users, tokens, sessions, snapshots)
     pub db: DbRepo,
     /// HTTP client adapter for OAuth and API operations
     pub http: HttpClient,
     /// Stripe SDK for billing and payment processing (if configured)
     pub stripe: Option<StripeSdk>,
+    /// Helius RPC client for mainnet state (if configured)
+    pub helius: Option<HeliusClient>,
 }
 
 impl ServerInfra {
@@ -80,31 +100,61 @@ impl ServerInfra {
             .map_err(|e| DomainError::Internal(format!("Database initialization failed: {e}")))?;
 
         // Initialize HTTP client for adapters
-        let http_client = reqwest::Client::builder()
+        let outbound = OutboundConfig {
+            proxy: cfg.http_proxy.clone(),
+            extra_ca_certs: cfg.extra_ca_certs.clone(),
+        };
+        let http_client = outbound
+            .client_builder()?
             .timeout(std::time::Duration::from_secs(cfg.api_timeout_seconds))
             .build()
             .map_err(|e| {
                 DomainError::Internal(format!("HTTP client initialization failed: {e}"))
             })?;
 
+        // Shared by every adapter calling a third-party service
+        let retry_policy = retry::RetryPolicy {
+            max_retries: cfg.upstream_max_retries,
+            initial_backoff: std::time::Duration::from_millis(cfg.upstream_initial_backoff_ms),
+        };
+
         // Initialize HTTP client adapter
-        let http = HttpClient::new(http_client.clone());
+        let http = HttpClient::new(http_client.clone()).with_retry_policy(retry_policy);
 
         // Initialize Stripe SDK only if configured
         // TODO: This is kind hacky, we should have a better way to handle this
         let stripe = if let Some(stripe_secret_key) = &cfg.stripe_secret_key {
             if cfg.stripe_webhook_secret.is_empty() {
-                eprintln!("Warning: Stripe webhook secret is empty");
+                tracing::warn!("Stripe webhook secret is empty");
             }
-            Some(StripeSdk::new(
-                stripe_secret_key.clone(),
-                cfg.stripe_webhook_secret.clone(),
-            ))
+            Some(
+                StripeSdk::new(
+                    stripe_secret_key.clone(),
+                    cfg.stripe_webhook_secret.clone(),
+                    StripeProducts {
+                        entry: cfg.stripe_product_id_entry_tier.clone(),
+                        lite: cfg.stripe_product_id_lite_tier.clone(),
+                        pro: cfg.stripe_product_id_pro_tier.clone(),
+                    },
+                    http_client.clone(),
+                )
+                .with_retry_policy(retry_policy)
+                .with_api_url(&cfg.stripe_api_url),
+            )
         } else {
             None
         };
 
-        Ok(Self { db, http, stripe })
+        let helius = cfg.helius_api_key.clone().map(|api_key| {
+            HeliusClient::new(api_key, http_client.clone()).with_retry_policy(retry_policy)
+        });
+
+        Ok(Self {
+            db,
+            http,
+            stripe,
+            helius,
+        })
     }
 }
 
@@ -123,15 +173,25 @@ impl ServerInfra {
 /// # Example
 ///
 /// ```rust,ignore
-/// let config = ClientConfig::load()?;
-/// let infra = ClientInfra::new(&config)?;
+/// let infra = ClientInfra::new(
+///     &config.api_base_url,
+///     config.api_token,
+///     timeout,
+///     "forkforge/0.1.0",
+///     &OutboundConfig::default(),
+/// )?;
 ///
-/// // Use GitHub adapter with user's OAuth token
-/// let user_info = infra.github.get_with_auth(url, &user_token).await?;
+/// // Call the ForkForge API with the user's API token
+/// let response = infra.api.send_idempotent(infra.api.get("/sessions")).await?;
+///
+/// // Use the HTTP adapter with the user's OAuth token
+/// let user_info = infra.http.get_with_auth(url, &user_token).await?;
 /// ```
 pub struct ClientInfra {
     /// HTTP client adapter for OAuth operations using user-provided tokens
     pub http: HttpClient,
+    /// ForkForge API client carrying the user's API token
+    pub api: ForkForgeApiClient,
 }
 
 impl ClientInfra {
@@ -139,23 +199,36 @@ impl ClientInfra {
     ///
     /// # Arguments
     ///
-    /// * `cfg` - Application configuration (only uses timeout settings, no secrets)
+    /// * `api_base_url` - ForkForge API server, e.g. `https://api.forkforge.dev`
+    /// * `api_token` - The user's ForkForge API token, if they are logged in
+    /// * `timeout` - Timeout for every request
+    /// * `user_agent` - Sent with every request, identifying the client build
+    /// * `outbound` - Proxy and extra root certificates
     ///
     /// # Errors
     ///
     /// Returns `DomainError` if HTTP client initialization fails
-    pub fn new(cfg: &common::Config) -> Result<Self, DomainError> {
-        // Initialize HTTP client for adapters
-        let http_client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(cfg.api_timeout_seconds))
+    pub fn new(
+        api_base_url: &str,
+        api_token: Option<String>,
+        timeout: std::time::Duration,
+        user_agent: &str,
+        outbound: &OutboundConfig,
+    ) -> Result<Self, DomainError> {
+        // Initialize HTTP client shared by both adapters
+        let http_client = outbound
+            .client_builder()?
+            .timeout(timeout)
+            .user_agent(user_agent)
             .build()
             .map_err(|e| {
                 DomainError::Internal(format!("HTTP client initialization failed: {e}"))
             })?;
 
         // Initialize HTTP client adapter (uses user's OAuth tokens, not server secrets)
-        let http = HttpClient::new(http_client);
+        let http = HttpClient::new(http_client.clone());
+        let api = ForkForgeApiClient::new(http_client, api_base_url).with_api_token(api_token);
 
-        Ok(Self { http })
+        Ok(Self { http, api })
     }
 }
@@ -0,0 +1,160 @@
+//! # License Verification Module
+//!
+//! This module provides offline validation of ed25519-signed license keys
+//! for self-hosted deployments. Keys are verified against a public key
+//! embedded at build time, so Pro features unlock without calling home.
+//!
+//! ## Key Format
+//!
+//! `<base64url(payload)>.<base64url(signature)>` where the payload is the
+//! JSON-encoded `License` claims (licensee, tier, expiry).
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use domain::errors::DomainError;
+use domain::models::License;
+use domain::services::license::{LicenseService, LicenseVerifier};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+/// Base64-encoded ed25519 public key embedded by release builds
+const LICENSE_PUBLIC_KEY: Option<&str> = option_env!("FORKFORGE_LICENSE_PUBLIC_KEY");
+
+/// Ed25519 implementation of the LicenseVerifier
+pub struct Ed25519LicenseVerifier {
+    public_key: VerifyingKey,
+}
+
+impl Ed25519LicenseVerifier {
+    /// Creates a verifier for the given raw ed25519 public key
+    pub fn new(public_key: &[u8; 32]) -> Result<Self, DomainError> {
+        let public_key = VerifyingKey::from_bytes(public_key)
+            .map_err(|e| DomainError::Internal(format!("Invalid license public key: {e}")))?;
+
+        Ok(Self { public_key })
+    }
+
+    /// Creates a verifier using the public key embedded at build time
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainError::Internal` if the binary was built without
+    /// `FORKFORGE_LICENSE_PUBLIC_KEY` set.
+    pub fn embedded() -> Result<Self, DomainError> {
+        let encoded = LICENSE_PUBLIC_KEY.ok_or_else(|| {
+            DomainError::Internal("This build has no embedded license public key".to_string())
+        })?;
+
+        let bytes: [u8; 32] = STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                DomainError::Internal("Embedded license public key is malformed".to_string())
+            })?;
+
+        Self::new(&bytes)
+    }
+}
+
+impl LicenseVerifier for Ed25519LicenseVerifier {
+    fn verify(&self, license_key: &str) -> Result<License, DomainError> {
+        let (payload, signature) = license_key
+            .split_once('.')
+            .ok_or_else(|| DomainError::InvalidInput("Malformed license key".to_string()))?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|e| DomainError::InvalidInput(format!("Malformed license payload: {e}")))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|e| DomainError::InvalidInput(format!("Malformed license signature: {e}")))?;
+        let signature = Signature::from_slice(&signature)
+            .map_err(|e| DomainError::InvalidInput(format!("Malformed license signature: {e}")))?;
+
+        self.public_key
+            .verify(&payload, &signature)
+            .map_err(|_| DomainError::Unauthorized("License signature is invalid".to_string()))?;
+
+        serde_json::from_slice(&payload)
+            .map_err(|e| DomainError::InvalidInput(format!("Invalid license claims: {e}")))
+    }
+}
+
+/// Loads and validates the license installed at `path`
+///
+/// Returns `Ok(None)` when no license file exists, which leaves the server
+/// running with Pro features locked.
+pub fn load_license(path: &str) -> Result<Option<License>, DomainError> {
+    let license_key = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(DomainError::Internal(format!(
+                "Failed to read license file {path}: {e}"
+            )));
+        }
+    };
+
+    let service = LicenseService::new(Ed25519LicenseVerifier::embedded()?);
+    service.validate(&license_key).map(Some)
+}
+
+/// Validates a license key and installs it at `path`
+pub fn install_license(path: &str, license_key: &str) -> Result<License, DomainError> {
+    let service = LicenseService::new(Ed25519LicenseVerifier::embedded()?);
+    let license = service.validate(license_key)?;
+
+    std::fs::write(path, license_key.trim())
+        .map_err(|e| DomainError::Internal(format!("Failed to write license file {path}: {e}")))?;
+
+    Ok(license)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use domain::models::SubscriptionTier;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn sign(signing_key: &SigningKey, license: &License) -> String {
+        let payload = serde_json::to_vec(license).unwrap();
+        let signature = signing_key.sign(&payload);
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(&payload),
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    #[test]
+    fn test_verify_license() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let verifier =
+            Ed25519LicenseVerifier::new(&signing_key.verifying_key().to_bytes()).unwrap();
+        let service = LicenseService::new(verifier);
+
+        let license = License {
+            licensee: "Acme Corp".to_string(),
+            tier: SubscriptionTier::Pro,
+            expires_at: Utc::now() + Duration::days(30),
+        };
+        let key = sign(&signing_key, &license);
+
+        // Valid key decodes to the signed claims
+        let verified = service.validate(&key).unwrap();
+        assert_eq!(verified.licensee, "Acme Corp");
+        assert!(verified.unlocks_pro());
+
+        // Key signed by a different key is rejected
+        let other_key = SigningKey::from_bytes(&[9u8; 32]);
+        assert!(service.validate(&sign(&other_key, &license)).is_err());
+
+        // Expired license is rejected even with a valid signature
+        let expired = License {
+            expires_at: Utc::now() - Duration::days(1),
+            ..license
+        };
+        assert!(service.validate(&sign(&signing_key, &expired)).is_err());
+    }
+}
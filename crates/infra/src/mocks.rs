@@ -0,0 +1,934 @@
+//! # In-Memory Test Doubles
+//!
+//! `MockRepo` implements the user, auth, session, snapshot, subscription and
+//! billing event repositories over plain collections, so domain services can
+//! be tested without a database. Clones share their data, so a test can keep
+//! one to inspect what a service wrote. `MockDeviceFlowProvider` stands in
+//! for GitHub's device flow.
+//!
+//! Enabled by the `mocks` feature:
+//!
+//! ```rust,ignore
+//! let repo = MockRepo::default();
+//! let sessions = SessionService::new(repo.clone());
+//! repo.set_unavailable(true); // every call now fails with `Internal`
+//! ```
+//!
+//! Unlike `DbRepo`, foreign keys aren't enforced: a session can be created
+//! for a user that was never stored.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use domain::errors::DomainError;
+use domain::models::{
+    AccessGroup, AccountState, AuthToken, BillingEvent, CollaboratorAccess, ForkSession,
+    MAX_SLUG_ATTEMPTS, PendingDeviceFlow, ProviderToken, SessionCollaborator, SessionId,
+    SessionStatus, SessionSummary, SessionUsage, Snapshot, SnapshotId, Subscription, User, UserId,
+    UserPatch, UserStatus, ZombieSession, slug_candidate,
+};
+use domain::repositories::{AuthRepository, UserRepository};
+use domain::services::auth::github::DeviceFlowProvider;
+use domain::services::auth::{AuthError, AuthenticatedUser, DeviceCodeResponse};
+use domain::services::billing::events::BillingEventRepository;
+use domain::services::billing::subscriptions::SubscriptionRepository;
+use domain::services::sessions::SessionRepository;
+use domain::services::snapshots::SnapshotRepository;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Everything stored by a `MockRepo`, in insertion order where it matters
+#[derive(Default)]
+struct MockState {
+    /// When set, every call fails as if the database were down
+    unavailable: bool,
+    users: Vec<User>,
+    auth_tokens: Vec<AuthToken>,
+    provider_tokens: HashMap<UserId, ProviderToken>,
+    /// User signing in as each (issuer, subject)
+    oidc_subjects: HashMap<(String, String), UserId>,
+    device_flows: HashMap<String, PendingDeviceFlow>,
+    sessions: Vec<ForkSession>,
+    session_accounts: HashMap<SessionId, Vec<AccountState>>,
+    session_usage: HashMap<SessionId, SessionUsage>,
+    collaborators: Vec<SessionCollaborator>,
+    snapshots: Vec<Snapshot>,
+    snapshot_accounts: HashMap<SnapshotId, Vec<AccountState>>,
+    subscriptions: HashMap<UserId, Subscription>,
+    billing_events: Vec<BillingEvent>,
+}
+
+/// In-memory stand-in for `DbRepo`
+#[derive(Clone, Default)]
+pub struct MockRepo {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockRepo {
+    /// Make every call fail with `DomainError::Internal`, or work again
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.state.lock().unwrap().unavailable = unavailable;
+    }
+
+    /// Lock the stored data, failing if the repository is unavailable
+    fn state(&self) -> Result<MutexGuard<'_, MockState>, DomainError> {
+        let state = self.state.lock().unwrap();
+        if state.unavailable {
+            return Err(DomainError::Internal(
+                "Database error: mock repository is unavailable".to_string(),
+            ));
+        }
+        Ok(state)
+    }
+}
+
+/// Merge `accounts` into `stored` by pubkey, keeping them sorted by pubkey
+fn upsert_accounts(stored: &mut Vec<AccountState>, accounts: &[AccountState]) {
+    for account in accounts {
+        match stored.iter_mut().find(|a| a.pubkey == account.pubkey) {
+            Some(existing) => *existing = account.clone(),
+            None => stored.push(account.clone()),
+        }
+    }
+    stored.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+}
+
+#[async_trait]
+impl UserRepository for MockRepo {
+    async fn find_by_id(&self, id: UserId) -> Result<Option<User>, DomainError> {
+        Ok(self.state()?.users.iter().find(|u| u.id == id).cloned())
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, DomainError> {
+        Ok(self
+            .state()?
+            .users
+            .iter()
+            .find(|u| u.primary_email == email)
+            .cloned())
+    }
+
+    async fn find_by_github_id(&self, github_id: i64) -> Result<Option<User>, DomainError> {
+        Ok(self
+            .state()?
+            .users
+            .iter()
+            .find(|u| u.github_user_id == Some(github_id))
+            .cloned())
+    }
+
+    async fn find_by_oidc_subject(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<User>, DomainError> {
+        let state = self.state()?;
+        let Some(id) = state
+            .oidc_subjects
+            .get(&(issuer.to_string(), subject.to_string()))
+        else {
+            return Ok(None);
+        };
+        Ok(state.users.iter().find(|u| u.id == *id).cloned())
+    }
+
+    async fn link_oidc_subject(
+        &self,
+        id: UserId,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        if !state.users.iter().any(|u| u.id == id) {
+            return Err(DomainError::InvalidInput(format!(
+                "Referenced record does not exist: user {id}"
+            )));
+        }
+        let key = (issuer.to_string(), subject.to_string());
+        if state.oidc_subjects.contains_key(&key) {
+            return Err(DomainError::Conflict(format!(
+                "Conflicting record: OIDC subject {subject}"
+            )));
+        }
+        state.oidc_subjects.insert(key, id);
+        Ok(())
+    }
+
+    async fn find_by_stripe_customer_id(
+        &self,
+        stripe_customer_id: &str,
+    ) -> Result<Option<User>, DomainError> {
+        Ok(self
+            .state()?
+            .users
+            .iter()
+            .find(|u| u.stripe_customer_id.as_deref() == Some(stripe_customer_id))
+            .cloned())
+    }
+
+    async fn create(&self, user: &User) -> Result<User, DomainError> {
+        let mut state = self.state()?;
+        let taken = state.users.iter().any(|u| {
+            u.id == user.id
+                || u.primary_email == user.primary_email
+                || (user.github_user_id.is_some() && u.github_user_id == user.github_user_id)
+        });
+        if taken {
+            return Err(DomainError::Conflict(format!(
+                "Conflicting record: user {}",
+                user.primary_email
+            )));
+        }
+        state.users.push(user.clone());
+        Ok(user.clone())
+    }
+
+    async fn update(&self, user: &User) -> Result<User, DomainError> {
+        let mut state = self.state()?;
+        let stored = state
+            .users
+            .iter_mut()
+            .find(|u| u.id == user.id)
+            .ok_or_else(|| DomainError::NotFound(format!("User {}", user.id)))?;
+        if stored.updated_at != user.updated_at {
+            return Err(DomainError::Conflict(format!(
+                "User {} was modified concurrently",
+                user.id
+            )));
+        }
+        *stored = User {
+            updated_at: Utc::now(),
+            ..user.clone()
+        };
+        Ok(stored.clone())
+    }
+
+    async fn patch(&self, id: UserId, patch: &UserPatch) -> Result<User, DomainError> {
+        let mut state = self.state()?;
+        let user = state
+            .users
+            .iter_mut()
+            .find(|u| u.id == id)
+            .ok_or_else(|| DomainError::NotFound(format!("User {id}")))?;
+        if let Some(email) = &patch.primary_email {
+            user.primary_email = email.clone();
+        }
+        if let Some(display_name) = &patch.display_name {
+            user.display_name = display_name.clone();
+        }
+        if let Some(contact_email) = &patch.contact_email {
+            user.contact_email = contact_email.clone();
+        }
+        if let Some(region) = &patch.preferred_region {
+            user.preferred_region = region.clone();
+        }
+        if let Some(customer_id) = &patch.stripe_customer_id {
+            user.stripe_customer_id = Some(customer_id.clone());
+        }
+        if let Some(status) = patch.status {
+            user.status = status;
+        }
+        if let Some(role) = patch.role {
+            user.role = role;
+        }
+        user.updated_at = Utc::now();
+        Ok(user.clone())
+    }
+
+    async fn delete(&self, id: UserId) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        let before = state.users.len();
+        state.users.retain(|u| u.id != id);
+        if state.users.len() == before {
+            return Err(DomainError::NotFound(format!("User {id}")));
+        }
+        Ok(())
+    }
+
+    async fn find_users_page(&self, limit: u32, offset: u32) -> Result<Vec<User>, DomainError> {
+        let mut users: Vec<User> = self
+            .state()?
+            .users
+            .iter()
+            .filter(|u| u.status != UserStatus::Deleted)
+            .cloned()
+            .collect();
+        users.sort_by_key(|u| (u.created_at, u.id));
+        Ok(users
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn count_users(&self) -> Result<u64, DomainError> {
+        Ok(self
+            .state()?
+            .users
+            .iter()
+            .filter(|u| u.status != UserStatus::Deleted)
+            .count() as u64)
+    }
+}
+
+#[async_trait]
+impl AuthRepository for MockRepo {
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<AuthToken>, DomainError> {
+        Ok(self
+            .state()?
+            .auth_tokens
+            .iter()
+            .find(|t| t.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn find_by_user_id(&self, user_id: UserId) -> Result<Vec<AuthToken>, DomainError> {
+        Ok(self
+            .state()?
+            .auth_tokens
+            .iter()
+            .rev()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, token: &AuthToken) -> Result<AuthToken, DomainError> {
+        self.state()?.auth_tokens.push(token.clone());
+        Ok(token.clone())
+    }
+
+    async fn update_last_used(&self, id: Uuid) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        let token = state
+            .auth_tokens
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| DomainError::NotFound(format!("Auth token {id}")))?;
+        token.last_used_at = Some(Utc::now());
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        let before = state.auth_tokens.len();
+        state.auth_tokens.retain(|t| t.id != id);
+        if state.auth_tokens.len() == before {
+            return Err(DomainError::NotFound(format!("Auth token {id}")));
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<u64, DomainError> {
+        let mut state = self.state()?;
+        let now = Utc::now();
+        let before = state.auth_tokens.len();
+        state
+            .auth_tokens
+            .retain(|t| t.expires_at.is_none_or(|expires_at| expires_at > now));
+        Ok((before - state.auth_tokens.len()) as u64)
+    }
+
+    async fn save_provider_token(
+        &self,
+        user_id: UserId,
+        token: &ProviderToken,
+    ) -> Result<(), DomainError> {
+        self.state()?.provider_tokens.insert(user_id, token.clone());
+        Ok(())
+    }
+
+    async fn find_provider_token(
+        &self,
+        user_id: UserId,
+    ) -> Result<Option<ProviderToken>, DomainError> {
+        Ok(self.state()?.provider_tokens.get(&user_id).cloned())
+    }
+
+    async fn save_device_flow(&self, flow: &PendingDeviceFlow) -> Result<(), DomainError> {
+        self.state()?
+            .device_flows
+            .insert(flow.device_code.clone(), flow.clone());
+        Ok(())
+    }
+
+    async fn find_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<Option<PendingDeviceFlow>, DomainError> {
+        Ok(self.state()?.device_flows.get(device_code).cloned())
+    }
+
+    async fn delete_device_flow(&self, device_code: &str) -> Result<(), DomainError> {
+        self.state()?.device_flows.remove(device_code);
+        Ok(())
+    }
+
+    async fn delete_expired_device_flows(&self, now: DateTime<Utc>) -> Result<u64, DomainError> {
+        let mut state = self.state()?;
+        let before = state.device_flows.len();
+        state
+            .device_flows
+            .retain(|_, flow| !flow.is_expired_at(now));
+        Ok((before - state.device_flows.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MockRepo {
+    async fn create(
+        &self,
+        user_id: UserId,
+        name: String,
+        fork_slot: Option<u64>,
+        region: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<ForkSession, DomainError> {
+        let mut state = self.state()?;
+        for attempt in 0..MAX_SLUG_ATTEMPTS {
+            let slug = slug_candidate(&name, attempt);
+            let taken = state
+                .sessions
+                .iter()
+                .any(|s| s.user_id == user_id && s.slug == slug);
+            if taken {
+                continue;
+            }
+            let name = if name.is_empty() {
+                slug.clone()
+            } else {
+                name.clone()
+            };
+            let session = ForkSession {
+                region,
+                ..ForkSession::new(user_id, name, slug, fork_slot, created_at)?
+            };
+            state.sessions.push(session.clone());
+            return Ok(session);
+        }
+
+        Err(DomainError::InvalidInput(format!(
+            "No free slug for session {name:?}; choose another name"
+        )))
+    }
+
+    async fn find_by_id(&self, id: SessionId) -> Result<Option<ForkSession>, DomainError> {
+        Ok(self.state()?.sessions.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn find_by_slug(
+        &self,
+        user_id: UserId,
+        slug: &str,
+    ) -> Result<Option<ForkSession>, DomainError> {
+        Ok(self
+            .state()?
+            .sessions
+            .iter()
+            .find(|s| s.user_id == user_id && s.slug == slug)
+            .cloned())
+    }
+
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SessionId>, DomainError> {
+        let mut ids: Vec<SessionId> = self
+            .state()?
+            .sessions
+            .iter()
+            .filter(|s| s.user_id == user_id && s.id.to_string().starts_with(prefix))
+            .map(|s| s.id)
+            .collect();
+        ids.sort_by_key(SessionId::to_string);
+        ids.truncate(limit as usize);
+        Ok(ids)
+    }
+
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<ForkSession>, DomainError> {
+        Ok(self
+            .state()?
+            .sessions
+            .iter()
+            .rev()
+            .filter(|s| s.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SessionSummary>, DomainError> {
+        let state = self.state()?;
+        Ok(state
+            .sessions
+            .iter()
+            .rev()
+            .filter(|s| s.user_id == user_id && status.is_none_or(|status| s.status == status))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|session| SessionSummary {
+                session: session.clone(),
+                snapshot_count: state
+                    .snapshots
+                    .iter()
+                    .filter(|snapshot| snapshot.session_id == session.id)
+                    .count() as u64,
+            })
+            .collect())
+    }
+
+    async fn count_by_user(
+        &self,
+        user_id: UserId,
+        status: Option<SessionStatus>,
+    ) -> Result<u64, DomainError> {
+        Ok(self
+            .state()?
+            .sessions
+            .iter()
+            .filter(|s| s.user_id == user_id && status.is_none_or(|status| s.status == status))
+            .count() as u64)
+    }
+
+    async fn update(&self, session: &ForkSession) -> Result<ForkSession, DomainError> {
+        let mut state = self.state()?;
+        let stored = state
+            .sessions
+            .iter_mut()
+            .find(|s| s.id == session.id)
+            .ok_or_else(|| DomainError::NotFound(format!("Session {}", session.id)))?;
+        if stored.updated_at != session.updated_at {
+            return Err(DomainError::Conflict(format!(
+                "Session {} was modified concurrently",
+                session.id
+            )));
+        }
+        // Like the database, only the name and status are writable
+        stored.name = session.name.clone();
+        stored.status = session.status;
+        stored.updated_at = Utc::now();
+        Ok(ForkSession {
+            updated_at: stored.updated_at,
+            ..session.clone()
+        })
+    }
+
+    async fn update_status(
+        &self,
+        id: SessionId,
+        from: SessionStatus,
+        to: SessionStatus,
+    ) -> Result<Option<ForkSession>, DomainError> {
+        let mut state = self.state()?;
+        let Some(session) = state
+            .sessions
+            .iter_mut()
+            .find(|s| s.id == id && s.status == from)
+        else {
+            return Ok(None);
+        };
+        session.status = to;
+        session.updated_at = Utc::now();
+        Ok(Some(session.clone()))
+    }
+
+    async fn stop_all_by_user(&self, user_id: UserId) -> Result<u64, DomainError> {
+        let mut state = self.state()?;
+        let now = Utc::now();
+        let mut stopped = 0;
+        for session in state.sessions.iter_mut().filter(|s| {
+            s.user_id == user_id
+                && matches!(s.status, SessionStatus::Pending | SessionStatus::Running)
+        }) {
+            session.status = SessionStatus::Stopped;
+            session.updated_at = now;
+            stopped += 1;
+        }
+        Ok(stopped)
+    }
+
+    async fn save_accounts(
+        &self,
+        session_id: SessionId,
+        accounts: &[AccountState],
+    ) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        upsert_accounts(
+            state.session_accounts.entry(session_id).or_default(),
+            accounts,
+        );
+        Ok(())
+    }
+
+    async fn list_accounts(&self, session_id: SessionId) -> Result<Vec<AccountState>, DomainError> {
+        Ok(self
+            .state()?
+            .session_accounts
+            .get(&session_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn record_usage(
+        &self,
+        session_id: SessionId,
+        elapsed_seconds: u64,
+        peak_memory_bytes: u64,
+    ) -> Result<SessionUsage, DomainError> {
+        let mut state = self.state()?;
+        let usage = state
+            .session_usage
+            .entry(session_id)
+            .or_insert(SessionUsage {
+                session_id,
+                elapsed_seconds: 0,
+                peak_memory_bytes: 0,
+                last_heartbeat_at: Utc::now(),
+            });
+        usage.elapsed_seconds = usage.elapsed_seconds.max(elapsed_seconds);
+        usage.peak_memory_bytes = usage.peak_memory_bytes.max(peak_memory_bytes);
+        usage.last_heartbeat_at = Utc::now();
+        Ok(usage.clone())
+    }
+
+    async fn find_zombies(&self, cutoff: DateTime<Utc>) -> Result<Vec<ZombieSession>, DomainError> {
+        let state = self.state()?;
+        Ok(state
+            .sessions
+            .iter()
+            .filter_map(|session| {
+                let last_heartbeat_at = state
+                    .session_usage
+                    .get(&session.id)
+                    .map(|usage| usage.last_heartbeat_at);
+                let last_seen = match session.status {
+                    SessionStatus::Pending => last_heartbeat_at?,
+                    SessionStatus::Running => last_heartbeat_at.unwrap_or(session.updated_at),
+                    _ => return None,
+                };
+                (last_seen < cutoff).then_some(ZombieSession {
+                    session_id: session.id,
+                    status: session.status,
+                    last_heartbeat_at,
+                })
+            })
+            .collect())
+    }
+
+    async fn upsert_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        access: CollaboratorAccess,
+    ) -> Result<SessionCollaborator, DomainError> {
+        let mut state = self.state()?;
+        let collaborator = SessionCollaborator {
+            session_id,
+            user_id,
+            access,
+            created_at: Utc::now(),
+        };
+        match state
+            .collaborators
+            .iter_mut()
+            .find(|c| c.session_id == session_id && c.user_id == user_id)
+        {
+            Some(existing) => existing.access = access,
+            None => state.collaborators.push(collaborator.clone()),
+        }
+        Ok(collaborator)
+    }
+
+    async fn find_collaborator(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+    ) -> Result<Option<SessionCollaborator>, DomainError> {
+        Ok(self
+            .state()?
+            .collaborators
+            .iter()
+            .find(|c| c.session_id == session_id && c.user_id == user_id)
+            .cloned())
+    }
+}
+
+impl MockState {
+    /// Whether `user_id` took the snapshot or owns the session it was taken of
+    fn snapshot_visible_to(&self, snapshot: &Snapshot, user_id: UserId) -> bool {
+        snapshot.user_id == user_id
+            || self
+                .sessions
+                .iter()
+                .any(|s| s.id == snapshot.session_id && s.user_id == user_id)
+    }
+}
+
+#[async_trait]
+impl SnapshotRepository for MockRepo {
+    async fn create(
+        &self,
+        session_id: SessionId,
+        user_id: UserId,
+        name: String,
+        description: Option<String>,
+        created_at: DateTime<Utc>,
+    ) -> Result<Snapshot, DomainError> {
+        let mut state = self.state()?;
+        let fork_slot = state
+            .sessions
+            .iter()
+            .find(|s| s.id == session_id)
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?
+            .fork_slot;
+
+        let snapshot = Snapshot::new(
+            session_id,
+            user_id,
+            name,
+            description,
+            fork_slot,
+            created_at,
+        )?;
+        let accounts = state
+            .session_accounts
+            .get(&session_id)
+            .cloned()
+            .unwrap_or_default();
+        state.snapshot_accounts.insert(snapshot.id, accounts);
+        state.snapshots.push(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    async fn find_by_id(&self, id: SnapshotId) -> Result<Option<Snapshot>, DomainError> {
+        Ok(self.state()?.snapshots.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn list_by_session(&self, session_id: SessionId) -> Result<Vec<Snapshot>, DomainError> {
+        Ok(self
+            .state()?
+            .snapshots
+            .iter()
+            .rev()
+            .filter(|s| s.session_id == session_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_ids_by_prefix(
+        &self,
+        user_id: UserId,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError> {
+        let state = self.state()?;
+        let mut ids: Vec<SnapshotId> = state
+            .snapshots
+            .iter()
+            .filter(|s| s.id.to_string().starts_with(prefix))
+            .filter(|s| state.snapshot_visible_to(s, user_id))
+            .map(|s| s.id)
+            .collect();
+        ids.sort_by_key(SnapshotId::to_string);
+        ids.truncate(limit as usize);
+        Ok(ids)
+    }
+
+    async fn find_ids_created_before(
+        &self,
+        user_id: UserId,
+        cutoff: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<SnapshotId>, DomainError> {
+        let state = self.state()?;
+        Ok(state
+            .snapshots
+            .iter()
+            .filter(|s| s.created_at < cutoff && state.snapshot_visible_to(s, user_id))
+            .take(limit as usize)
+            .map(|s| s.id)
+            .collect())
+    }
+
+    async fn restore(
+        &self,
+        snapshot_id: SnapshotId,
+        session_id: SessionId,
+    ) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        let fork_slot = state
+            .snapshots
+            .iter()
+            .find(|s| s.id == snapshot_id)
+            .and_then(|s| s.fork_slot);
+        let session = state
+            .sessions
+            .iter_mut()
+            .find(|s| s.id == session_id)
+            .ok_or_else(|| DomainError::NotFound(format!("Session {session_id}")))?;
+        session.fork_slot = fork_slot;
+        session.updated_at = Utc::now();
+
+        let accounts = state
+            .snapshot_accounts
+            .get(&snapshot_id)
+            .cloned()
+            .unwrap_or_default();
+        state.session_accounts.insert(session_id, accounts);
+        Ok(())
+    }
+
+    async fn delete(&self, id: SnapshotId) -> Result<(), DomainError> {
+        let mut state = self.state()?;
+        let before = state.snapshots.len();
+        state.snapshots.retain(|s| s.id != id);
+        if state.snapshots.len() == before {
+            return Err(DomainError::NotFound(format!("Snapshot {id}")));
+        }
+        state.snapshot_accounts.remove(&id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for MockRepo {
+    async fn find_by_user(&self, user_id: UserId) -> Result<Option<Subscription>, DomainError> {
+        Ok(self.state()?.subscriptions.get(&user_id).cloned())
+    }
+
+    async fn upsert(&self, subscription: &Subscription) -> Result<Subscription, DomainError> {
+        self.state()?
+            .subscriptions
+            .insert(subscription.user_id, subscription.clone());
+        Ok(subscription.clone())
+    }
+}
+
+#[async_trait]
+impl BillingEventRepository for MockRepo {
+    async fn record(&self, event: &BillingEvent) -> Result<BillingEvent, DomainError> {
+        self.state()?.billing_events.push(event.clone());
+        Ok(event.clone())
+    }
+
+    async fn list_by_user(&self, user_id: UserId) -> Result<Vec<BillingEvent>, DomainError> {
+        Ok(self
+            .state()?
+            .billing_events
+            .iter()
+            .filter(|e| e.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_page_by_user(
+        &self,
+        user_id: UserId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<BillingEvent>, DomainError> {
+        Ok(self
+            .state()?
+            .billing_events
+            .iter()
+            .filter(|e| e.user_id == user_id)
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+/// How a mock device flow login ends
+#[derive(Debug, Clone)]
+pub enum MockAuthorization {
+    /// The user approved the device, and the provider issued these tokens
+    Approved(ProviderToken),
+    Denied,
+    TimedOut,
+}
+
+/// Device flow provider answering from fixed responses
+#[derive(Debug, Clone)]
+pub struct MockDeviceFlowProvider {
+    /// Answer to every `poll_authorization`
+    pub authorization: MockAuthorization,
+    /// Identity behind any access token; `None` rejects them all
+    pub user: Option<AuthenticatedUser>,
+    /// Token pair `refresh_token` issues; `None` rejects the refresh token
+    pub refreshed: Option<ProviderToken>,
+    /// Groups the user is a member of
+    pub groups: Vec<AccessGroup>,
+}
+
+impl MockDeviceFlowProvider {
+    /// A provider where the user approves the login as `username`
+    pub fn approving(username: &str) -> Self {
+        Self {
+            authorization: MockAuthorization::Approved(ProviderToken {
+                access_token: format!("gho_{username}"),
+                refresh_token: None,
+                expires_at: None,
+                refresh_token_expires_at: None,
+            }),
+            user: Some(AuthenticatedUser {
+                provider_id: "1".to_string(),
+                username: username.to_string(),
+                email: Some(format!("{username}@example.com")),
+                display_name: None,
+                created_at: None,
+            }),
+            refreshed: None,
+            groups: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DeviceFlowProvider for MockDeviceFlowProvider {
+    async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        Ok(DeviceCodeResponse {
+            device_code: "mock-device-code".to_string(),
+            user_code: "MOCK-CODE".to_string(),
+            verification_uri: "https://github.com/login/device".to_string(),
+            expires_in: 900,
+            interval: 5,
+        })
+    }
+
+    async fn poll_authorization(&self, _device_code: &str) -> Result<ProviderToken, AuthError> {
+        match &self.authorization {
+            MockAuthorization::Approved(token) => Ok(token.clone()),
+            MockAuthorization::Denied => Err(AuthError::UserDeniedAuthentication),
+            MockAuthorization::TimedOut => Err(AuthError::UserAuthenticationTimeout),
+        }
+    }
+
+    async fn refresh_token(&self, _refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        self.refreshed
+            .clone()
+            .ok_or_else(|| DomainError::Unauthorized("Refresh token rejected".to_string()))
+    }
+
+    async fn get_user(&self, _access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        self.user
+            .clone()
+            .ok_or_else(|| DomainError::Unauthorized("Invalid access token".to_string()))
+    }
+
+    async fn is_member(
+        &self,
+        _access_token: &str,
+        _username: &str,
+        group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        Ok(self.groups.contains(group))
+    }
+}
@@ -0,0 +1,296 @@
+//! # Request Retry
+//!
+//! Idempotent API calls are retried with exponential backoff when the
+//! connection drops or the server is briefly unavailable, so a flaky network
+//! doesn't abort a long workflow. `RetryPolicy` sets how many times and how
+//! soon; `send_idempotent` uses the default one. Backoff is jittered so
+//! clients that failed together don't all retry at the same moment.
+//!
+//! The server reports each client's per-minute budget in `X-RateLimit-*`
+//! headers. Once less than a tenth of it is left, requests are spaced out
+//! over the time until it resets, rather than running into `429`s.
+//!
+//! The server's own calls to GitHub, Stripe and Helius go through
+//! `send_upstream`, which retries server errors as well and counts retries
+//! per host for `upstream_retries`.
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many times, and how soon, failed requests are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on each further attempt
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Longest wait between attempts, including `Retry-After`, so the user isn't
+/// left staring at a silent terminal
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Requests are paced once fewer than `limit / LOW_BUDGET_DIVISOR` remain
+const LOW_BUDGET_DIVISOR: u64 = 10;
+
+/// Earliest time the next request should be sent, while the budget is low
+static NEXT_REQUEST_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Retry counts for each upstream host
+static UPSTREAM_RETRIES: Mutex<BTreeMap<String, UpstreamRetries>> = Mutex::new(BTreeMap::new());
+
+/// How often requests to one upstream host have been retried since startup
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpstreamRetries {
+    pub host: String,
+    /// Attempts repeated after a transient failure
+    pub retries: u64,
+    /// Requests that still failed transiently after the last retry
+    pub exhausted: u64,
+}
+
+/// Retry counts for every upstream host retried so far, ordered by host
+pub fn upstream_retries() -> Vec<UpstreamRetries> {
+    UPSTREAM_RETRIES.lock().unwrap().values().cloned().collect()
+}
+
+fn record_upstream(host: &str, count: impl FnOnce(&mut UpstreamRetries)) {
+    let mut retries = UPSTREAM_RETRIES.lock().unwrap();
+    count(
+        retries
+            .entry(host.to_string())
+            .or_insert_with(|| UpstreamRetries {
+                host: host.to_string(),
+                ..UpstreamRetries::default()
+            }),
+    );
+}
+
+/// Responses worth retrying: throttling and gateway/availability errors
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Upstream responses worth retrying: throttling and any server error
+fn is_upstream_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+fn header_u64(response: &Response, name: &str) -> Option<u64> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Spread the remaining budget over the time until it resets, if it's low
+fn pace(response: &Response) {
+    let (Some(limit), Some(remaining), Some(reset)) = (
+        header_u64(response, "x-ratelimit-limit"),
+        header_u64(response, "x-ratelimit-remaining"),
+        header_u64(response, "x-ratelimit-reset"),
+    ) else {
+        return;
+    };
+
+    let next = (remaining * LOW_BUDGET_DIVISOR < limit).then(|| {
+        let spacing = Duration::from_secs(reset) / (remaining as u32 + 1);
+        Instant::now() + spacing.min(MAX_RETRY_AFTER)
+    });
+    *NEXT_REQUEST_AT.lock().unwrap() = next;
+}
+
+/// Wait until the next request fits the server's rate limit
+async fn wait_for_budget() {
+    let next = *NEXT_REQUEST_AT.lock().unwrap();
+    if let Some(next) = next.filter(|next| *next > Instant::now()) {
+        tracing::debug!("Close to the rate limit, slowing down");
+        tokio::time::sleep_until(next.into()).await;
+    }
+}
+
+/// A random number in `[0, 1)`, from the standard library's hash seeds
+pub(crate) fn random_fraction() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Delay before retry number `attempt` (zero-based)
+///
+/// Without a `Retry-After`, the exponential delay is jittered to between
+/// half and all of itself.
+fn backoff(policy: &RetryPolicy, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after.map_or_else(
+        || {
+            let delay = policy
+                .initial_backoff
+                .saturating_mul(2u32.saturating_pow(attempt))
+                .min(MAX_RETRY_AFTER);
+            delay.mul_f64(0.5 + random_fraction() / 2.0)
+        },
+        |delay| delay.min(MAX_RETRY_AFTER),
+    )
+}
+
+/// Send a request, retrying dropped connections and transient server errors
+///
+/// Only use this for requests that are safe to repeat; a retried write may
+/// be applied twice. Requests with streaming bodies can't be replayed and
+/// are sent once.
+pub async fn send_idempotent(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    send_with_retries(request, &RetryPolicy::default()).await
+}
+
+/// `send_idempotent` with a custom retry policy
+pub async fn send_with_retries(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+) -> Result<Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        wait_for_budget().await;
+        let Some(this_attempt) = request.try_clone() else {
+            return request.send().await;
+        };
+
+        let result = this_attempt.send().await;
+        if let Ok(response) = &result {
+            pace(response);
+        }
+
+        let delay = match result {
+            Ok(response) if attempt < policy.max_retries && is_transient(response.status()) => {
+                tracing::warn!(status = %response.status(), "Server busy, retrying");
+                backoff(policy, attempt, retry_after(&response))
+            }
+            Err(e) if attempt < policy.max_retries && (e.is_connect() || e.is_timeout()) => {
+                tracing::warn!(error = %e, "Request failed, retrying");
+                backoff(policy, attempt, None)
+            }
+            result => return result,
+        };
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Send a request to a third-party service, retrying transient failures
+///
+/// Throttling, server errors, timeouts and failed connections are retried
+/// with `policy`, and each retry is counted against the request's host.
+/// Unlike `send_with_retries` it doesn't pace by `X-RateLimit-*` headers,
+/// which other services define differently. The last response is returned
+/// as is, so callers still see a final `5xx`.
+pub async fn send_upstream(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+) -> Result<Response, reqwest::Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = request.url().host_str().unwrap_or_default().to_string();
+
+    let mut attempt = 0;
+    loop {
+        let Some(this_attempt) = request.try_clone() else {
+            return client.execute(request).await;
+        };
+
+        let result = client.execute(this_attempt).await;
+        let retry_after = match &result {
+            Ok(response) if is_upstream_transient(response.status()) => Some(retry_after(response)),
+            Err(e) if e.is_connect() || e.is_timeout() => Some(None),
+            _ => None,
+        };
+        let Some(retry_after) = retry_after else {
+            return result;
+        };
+        if attempt >= policy.max_retries {
+            record_upstream(&host, |counts| counts.exhausted += 1);
+            return result;
+        }
+
+        // Errors aren't logged whole; their URL may carry an API key
+        match &result {
+            Ok(response) => {
+                tracing::warn!(%host, status = %response.status(), attempt, "Upstream busy, retrying");
+            }
+            Err(e) => {
+                tracing::warn!(%host, timeout = e.is_timeout(), attempt, "Upstream request failed, retrying");
+            }
+        }
+        record_upstream(&host, |counts| counts.retries += 1);
+        tokio::time::sleep(backoff(policy, attempt, retry_after)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+        };
+        let first = backoff(&policy, 0, None);
+        assert!(first >= Duration::from_millis(125) && first <= Duration::from_millis(250));
+        let third = backoff(&policy, 2, None);
+        assert!(third >= Duration::from_millis(500) && third <= Duration::from_secs(1));
+        assert!(backoff(&policy, 20, None) <= MAX_RETRY_AFTER);
+        assert_eq!(
+            backoff(&policy, 0, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            backoff(&policy, 0, Some(Duration::from_secs(60))),
+            MAX_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn test_upstream_retries_are_counted_per_host() {
+        record_upstream("api.stripe.com", |counts| counts.retries += 1);
+        record_upstream("api.stripe.com", |counts| counts.retries += 1);
+        record_upstream("api.stripe.com", |counts| counts.exhausted += 1);
+
+        let stripe = upstream_retries()
+            .into_iter()
+            .find(|counts| counts.host == "api.stripe.com")
+            .unwrap();
+        assert_eq!((stripe.retries, stripe.exhausted), (2, 1));
+    }
+}
@@ -10,13 +10,122 @@
 //!
 //! ## Implementation Status
 //!
-//! Currently provides stub implementations. Future versions will integrate
-//! with the official stripe-rust SDK or implement direct HTTP API calls.
+//! Calls the Stripe REST API directly with reqwest (form-encoded requests,
+//! bearer auth) and verifies webhook signatures with HMAC-SHA256.
+//!
+//! Transient failures are retried by `retry::send_upstream`. Each `POST`
+//! carries an `Idempotency-Key`, so Stripe applies a retried write once.
 
 use async_trait::async_trait;
+use chrono::Utc;
 use domain::errors::DomainError;
+use domain::models::PlanPrice;
 use domain::models::user::SubscriptionTier;
 use domain::services::billing::{CustomerId, PaymentProcessor, SubscriptionId};
+use hmac::{Hmac, Mac};
+use reqwest::header::HeaderValue;
+use reqwest::{Client, Method, RequestBuilder};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+
+use crate::retry::{self, RetryPolicy};
+
+/// Oldest webhook timestamp accepted, to limit replay of captured payloads
+const WEBHOOK_TOLERANCE_SECONDS: i64 = 300;
+
+/// Stripe REST API base URL, unless overridden with `with_api_url`
+const STRIPE_API_URL: &str = "https://api.stripe.com/v1";
+
+/// Stripe product IDs backing each subscription tier
+///
+/// Subscriptions are created against each product's default price, so
+/// prices can be changed in the Stripe dashboard without a config change.
+#[derive(Debug, Clone, Default)]
+pub struct StripeProducts {
+    pub entry: Option<String>,
+    pub lite: Option<String>,
+    pub pro: Option<String>,
+}
+
+impl StripeProducts {
+    fn product_id(&self, tier: SubscriptionTier) -> Result<&str, DomainError> {
+        let product_id = match tier {
+            SubscriptionTier::Entry => &self.entry,
+            SubscriptionTier::Lite => &self.lite,
+            SubscriptionTier::Pro => &self.pro,
+        };
+
+        product_id.as_deref().ok_or_else(|| {
+            DomainError::Internal(format!("No Stripe product configured for {tier:?} tier"))
+        })
+    }
+
+    /// Tier whose configured product is `product_id`
+    fn tier(&self, product_id: &str) -> Option<SubscriptionTier> {
+        SubscriptionTier::ALL
+            .into_iter()
+            .find(|tier| self.product_id(*tier).is_ok_and(|id| id == product_id))
+    }
+}
+
+#[derive(Deserialize)]
+struct StripeObject {
+    id: String,
+}
+
+/// A Checkout or Billing Portal session
+#[derive(Deserialize)]
+struct StripeRedirect {
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StripeProduct {
+    default_price: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StripePrice {
+    product: String,
+    currency: String,
+    unit_amount: Option<i64>,
+    recurring: Option<StripeRecurring>,
+    /// Only present when requested with `expand[]=currency_options`
+    #[serde(default)]
+    currency_options: BTreeMap<String, StripeCurrencyOption>,
+}
+
+#[derive(Deserialize)]
+struct StripeRecurring {
+    interval: String,
+}
+
+#[derive(Deserialize)]
+struct StripeCurrencyOption {
+    unit_amount: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct StripeSubscription {
+    items: StripeList<StripeObject>,
+}
+
+#[derive(Deserialize)]
+struct StripeList<T> {
+    data: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct StripeErrorResponse {
+    error: StripeErrorBody,
+}
+
+#[derive(Deserialize)]
+struct StripeErrorBody {
+    message: Option<String>,
+}
 
 /// Stripe SDK implementation for payment processing
 ///
@@ -28,11 +137,14 @@ use domain::services::billing::{CustomerId, PaymentProcessor, SubscriptionId};
 /// Contains sensitive API keys that must be kept server-side only.
 /// The `api_key` is used for API authentication, while `webhook_secret`
 /// is used to verify webhook signatures from Stripe.
+#[derive(Clone)]
 pub struct StripeSdk {
-    #[allow(dead_code)]
     api_key: String,
-    #[allow(dead_code)]
     webhook_secret: String,
+    products: StripeProducts,
+    client: Client,
+    retry_policy: RetryPolicy,
+    api_url: String,
 }
 
 impl StripeSdk {
@@ -42,13 +154,36 @@ impl StripeSdk {
     ///
     /// * `api_key` - Stripe secret API key (starts with "sk_")
     /// * `webhook_secret` - Webhook endpoint secret for signature verification
-    pub fn new(api_key: String, webhook_secret: String) -> Self {
+    /// * `products` - Stripe product IDs for each subscription tier
+    /// * `client` - Pre-configured reqwest Client used for API calls
+    pub fn new(
+        api_key: String,
+        webhook_secret: String,
+        products: StripeProducts,
+        client: Client,
+    ) -> Self {
         Self {
             api_key,
             webhook_secret,
+            products,
+            client,
+            retry_policy: RetryPolicy::default(),
+            api_url: STRIPE_API_URL.to_string(),
         }
     }
 
+    /// Retry transient failures according to `retry_policy`
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Send API calls to `api_url` instead of Stripe, e.g. a local fake in tests
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Creates a test/development instance with dummy credentials
     ///
     /// Useful for testing and development environments where actual
@@ -57,8 +192,72 @@ impl StripeSdk {
         Self {
             api_key: "sk_test_dummy".to_string(),
             webhook_secret: "whsec_test_dummy".to_string(),
+            products: StripeProducts::default(),
+            client: Client::new(),
+            retry_policy: RetryPolicy::default(),
+            api_url: STRIPE_API_URL.to_string(),
         }
     }
+
+    /// URL of an API endpoint, `path` starting with `/`
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.api_url)
+    }
+
+    /// Sends an authenticated request and decodes the JSON response
+    ///
+    /// Stripe error bodies are surfaced as `DomainError::ExternalService`.
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, DomainError> {
+        let (client, request) = request.bearer_auth(&self.api_key).build_split();
+        let mut request =
+            request.map_err(|e| DomainError::Internal(format!("Invalid Stripe request: {e}")))?;
+        // The same key on every retry, so Stripe only applies the write once
+        if request.method() == Method::POST {
+            request.headers_mut().insert(
+                "Idempotency-Key",
+                HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                    .expect("UUIDs are valid header values"),
+            );
+        }
+
+        let request = RequestBuilder::from_parts(client, request);
+        let response = retry::send_upstream(request, &self.retry_policy)
+            .await
+            .map_err(|e| DomainError::ExternalService(format!("Stripe request failed: {e}")))?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(|e| {
+            DomainError::ExternalService(format!("Failed to read Stripe response: {e}"))
+        })?;
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<StripeErrorResponse>(&body)
+                .ok()
+                .and_then(|e| e.error.message)
+                .unwrap_or(body);
+            return Err(DomainError::ExternalService(format!(
+                "Stripe returned {status}: {message}"
+            )));
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|e| DomainError::ExternalService(format!("Unexpected Stripe response: {e}")))
+    }
+
+    /// Resolves the default price of the product configured for a tier
+    async fn price_for_tier(&self, tier: SubscriptionTier) -> Result<String, DomainError> {
+        let product_id = self.products.product_id(tier)?;
+        let product: StripeProduct = self
+            .send(
+                self.client
+                    .get(self.url(&format!("/products/{product_id}"))),
+            )
+            .await?;
+
+        product.default_price.ok_or_else(|| {
+            DomainError::Internal(format!("Stripe product {product_id} has no default price"))
+        })
+    }
 }
 
 #[async_trait]
@@ -68,13 +267,68 @@ impl PaymentProcessor for StripeSdk {
         email: &str,
         external_id: &str,
     ) -> Result<CustomerId, DomainError> {
-        // Stub implementation
-        // In production, would pass external_id as metadata to Stripe
-        let _ = (email, external_id);
-        Ok(CustomerId(format!(
-            "cus_{}",
-            uuid::Uuid::new_v4().to_string().replace('-', "")
-        )))
+        let customer: StripeObject = self
+            .send(
+                self.client
+                    .post(self.url("/customers"))
+                    .form(&[("email", email), ("metadata[user_id]", external_id)]),
+            )
+            .await?;
+
+        Ok(CustomerId(customer.id))
+    }
+
+    async fn update_customer_country(
+        &self,
+        customer_id: &CustomerId,
+        country: &str,
+    ) -> Result<(), DomainError> {
+        // Stripe Tax calculates tax from the customer's address
+        let _: StripeObject = self
+            .send(
+                self.client
+                    .post(self.url(&format!("/customers/{}", customer_id.0)))
+                    .form(&[("address[country]", country)]),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn tier_prices(&self, tier: SubscriptionTier) -> Result<Vec<PlanPrice>, DomainError> {
+        let price_id = self.price_for_tier(tier).await?;
+        let price: StripePrice = self
+            .send(
+                self.client
+                    .get(self.url(&format!("/prices/{price_id}")))
+                    .query(&[("expand[]", "currency_options")]),
+            )
+            .await?;
+
+        let interval = price
+            .recurring
+            .map(|recurring| recurring.interval)
+            .unwrap_or_else(|| "month".to_string());
+
+        // Base currency first; currency_options repeats it, so skip it there
+        let base = price.unit_amount.map(|unit_amount| PlanPrice {
+            currency: price.currency.clone(),
+            unit_amount,
+            interval: interval.clone(),
+        });
+        let options = price
+            .currency_options
+            .into_iter()
+            .filter(|(currency, _)| *currency != price.currency)
+            .filter_map(|(currency, option)| {
+                Some(PlanPrice {
+                    currency,
+                    unit_amount: option.unit_amount?,
+                    interval: interval.clone(),
+                })
+            });
+
+        Ok(base.into_iter().chain(options).collect())
     }
 
     async fn create_subscription(
@@ -82,13 +336,15 @@ impl PaymentProcessor for StripeSdk {
         customer_id: &CustomerId,
         tier: SubscriptionTier,
     ) -> Result<SubscriptionId, DomainError> {
-        // Stub implementation
-        // In production, would map tier to Stripe price_id
-        let _ = (customer_id, tier);
-        Ok(SubscriptionId(format!(
-            "sub_{}",
-            uuid::Uuid::new_v4().to_string().replace('-', "")
-        )))
+        let price = self.price_for_tier(tier).await?;
+        let subscription: StripeObject = self
+            .send(self.client.post(self.url("/subscriptions")).form(&[
+                ("customer", customer_id.0.as_str()),
+                ("items[0][price]", price.as_str()),
+            ]))
+            .await?;
+
+        Ok(SubscriptionId(subscription.id))
     }
 
     async fn update_subscription(
@@ -96,8 +352,26 @@ impl PaymentProcessor for StripeSdk {
         subscription_id: &SubscriptionId,
         new_tier: SubscriptionTier,
     ) -> Result<(), DomainError> {
-        // Stub implementation
-        let _ = (subscription_id, new_tier);
+        let url = self.url(&format!("/subscriptions/{}", subscription_id.0));
+        let price = self.price_for_tier(new_tier).await?;
+
+        // Swap the price on the existing item rather than adding a second one
+        let subscription: StripeSubscription = self.send(self.client.get(&url)).await?;
+        let item = subscription.items.data.first().ok_or_else(|| {
+            DomainError::ExternalService(format!(
+                "Stripe subscription {} has no items",
+                subscription_id.0
+            ))
+        })?;
+
+        let _: StripeObject = self
+            .send(self.client.post(&url).form(&[
+                ("items[0][id]", item.id.as_str()),
+                ("items[0][price]", price.as_str()),
+                ("proration_behavior", "create_prorations"),
+            ]))
+            .await?;
+
         Ok(())
     }
 
@@ -105,18 +379,158 @@ impl PaymentProcessor for StripeSdk {
         &self,
         subscription_id: &SubscriptionId,
     ) -> Result<(), DomainError> {
-        // Stub implementation
-        let _ = subscription_id;
+        let _: StripeObject = self
+            .send(
+                self.client
+                    .delete(self.url(&format!("/subscriptions/{}", subscription_id.0))),
+            )
+            .await?;
+
         Ok(())
     }
 
+    async fn create_checkout_session(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+        success_url: &str,
+        cancel_url: &str,
+    ) -> Result<String, DomainError> {
+        let price = self.price_for_tier(tier).await?;
+        let session: StripeRedirect = self
+            .send(self.client.post(self.url("/checkout/sessions")).form(&[
+                ("mode", "subscription"),
+                ("customer", customer_id.0.as_str()),
+                ("line_items[0][price]", price.as_str()),
+                ("line_items[0][quantity]", "1"),
+                ("success_url", success_url),
+                ("cancel_url", cancel_url),
+                // Stripe Tax works out VAT and sales tax from the customer's
+                // address, which checkout saves back to the customer
+                ("automatic_tax[enabled]", "true"),
+                ("customer_update[address]", "auto"),
+            ]))
+            .await?;
+
+        session.url.ok_or_else(|| {
+            DomainError::ExternalService("Stripe checkout session has no URL".to_string())
+        })
+    }
+
+    async fn create_portal_session(
+        &self,
+        customer_id: &CustomerId,
+        return_url: &str,
+    ) -> Result<String, DomainError> {
+        let session: StripeRedirect = self
+            .send(
+                self.client
+                    .post(self.url("/billing_portal/sessions"))
+                    .form(&[
+                        ("customer", customer_id.0.as_str()),
+                        ("return_url", return_url),
+                    ]),
+            )
+            .await?;
+
+        session.url.ok_or_else(|| {
+            DomainError::ExternalService("Stripe billing portal session has no URL".to_string())
+        })
+    }
+
+    async fn tier_for_price(
+        &self,
+        price_id: &str,
+    ) -> Result<Option<SubscriptionTier>, DomainError> {
+        // Match on the product rather than the price, so subscriptions on a
+        // product's older prices keep their tier
+        let price: StripePrice = self
+            .send(self.client.get(self.url(&format!("/prices/{price_id}"))))
+            .await?;
+
+        Ok(self.products.tier(&price.product))
+    }
+
+    /// Verifies a `Stripe-Signature` header (`t=<timestamp>,v1=<hex hmac>,...`)
+    ///
+    /// The expected signature is HMAC-SHA256 over `<timestamp>.<payload>` keyed
+    /// with the webhook secret. Any `v1` entry may match, which lets Stripe roll
+    /// secrets. Timestamps outside the tolerance window are rejected.
     async fn verify_webhook_signature(
         &self,
-        _payload: &[u8],
-        _signature: &str,
+        payload: &[u8],
+        signature: &str,
     ) -> Result<bool, DomainError> {
-        // Stub implementation - in production, this would verify the signature
-        // using the webhook secret and HMAC-SHA256
-        Ok(true)
+        verify_signature(
+            &self.webhook_secret,
+            payload,
+            signature,
+            Utc::now().timestamp(),
+        )
+    }
+}
+
+fn verify_signature(
+    secret: &str,
+    payload: &[u8],
+    signature: &str,
+    now: i64,
+) -> Result<bool, DomainError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in signature.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+            _ => {}
+        }
+    }
+
+    let Some(timestamp) = timestamp else {
+        return Ok(false);
+    };
+    if (now - timestamp).abs() > WEBHOOK_TOLERANCE_SECONDS {
+        return Ok(false);
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| DomainError::Internal(format!("Invalid webhook secret: {e}")))?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+
+    // verify_slice compares in constant time
+    Ok(signatures
+        .iter()
+        .any(|candidate| mac.clone().verify_slice(candidate).is_ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, payload: &[u8], timestamp: i64) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{timestamp}.").as_bytes());
+        mac.update(payload);
+        format!(
+            "t={timestamp},v1={}",
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+
+    #[test]
+    fn test_verify_webhook_signature() {
+        let payload = br#"{"id":"evt_1","type":"invoice.paid"}"#;
+        let now = 1_700_000_000;
+        let header = sign("whsec_test", payload, now);
+
+        assert!(verify_signature("whsec_test", payload, &header, now).unwrap());
+
+        // Wrong secret, tampered payload and stale timestamps are rejected
+        assert!(!verify_signature("whsec_other", payload, &header, now).unwrap());
+        assert!(!verify_signature("whsec_test", b"{}", &header, now).unwrap());
+        assert!(!verify_signature("whsec_test", payload, &header, now + 301).unwrap());
+        assert!(!verify_signature("whsec_test", payload, "garbage", now).unwrap());
     }
 }
@@ -0,0 +1,327 @@
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{AccessGroup, AuthToken, ProviderToken, User, UserId, UserRole, UserStatus};
+use domain::repositories::{AuthRepository, UserRepository};
+use domain::services::auth::github::{AuthService, DeviceFlowProvider};
+use domain::services::auth::types::{AuthError, DeviceCodeResponse};
+use domain::services::auth::{AuthenticatedUser, TokenService};
+use domain::services::clock::ManualClock;
+use infra::DbRepo;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+/// Token tests never talk to an OAuth provider
+struct NoProvider;
+
+#[async_trait]
+impl DeviceFlowProvider for NoProvider {
+    async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        unimplemented!()
+    }
+
+    async fn poll_authorization(&self, _device_code: &str) -> Result<ProviderToken, AuthError> {
+        unimplemented!()
+    }
+
+    async fn refresh_token(&self, _refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        unimplemented!()
+    }
+
+    async fn get_user(&self, _access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        unimplemented!()
+    }
+
+    async fn is_member(
+        &self,
+        _access_token: &str,
+        _username: &str,
+        _group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        unimplemented!()
+    }
+}
+
+#[tokio::test]
+async fn test_authenticate_api_token() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = AuthService::new(NoProvider, repo.clone());
+
+    let api_token = service
+        .create_api_token(user_id, Some("CLI".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        service.authenticate(&api_token.token).await.unwrap(),
+        user_id
+    );
+
+    // Successful authentication records usage
+    let tokens = repo.find_by_user_id(user_id).await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].last_used_at.is_some());
+
+    // A tampered secret or a token claiming another user is rejected
+    let (_, secret) = TokenService::parse_api_token(&api_token.token).unwrap();
+    let tampered = TokenService::format_api_token(user_id, "wrong");
+    let other_user = TokenService::format_api_token(UserId::new_v4(), secret);
+    for token in [tampered.as_str(), other_user.as_str(), "garbage"] {
+        let result = service.authenticate(token).await;
+        assert!(matches!(result, Err(DomainError::Unauthorized(_))));
+    }
+}
+
+#[tokio::test]
+async fn test_expired_tokens() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = AuthService::new(NoProvider, repo.clone());
+
+    let secret = TokenService::generate_api_token();
+    let token = AuthToken {
+        id: Uuid::new_v4(),
+        user_id,
+        token_hash: TokenService::hash_token(&secret, &user_id.to_string()),
+        name: None,
+        last_used_at: None,
+        expires_at: Some(Utc::now() - Duration::hours(1)),
+        created_at: Utc::now(),
+    };
+    AuthRepository::create(&repo, &token).await.unwrap();
+
+    let result = service
+        .authenticate(&TokenService::format_api_token(user_id, &secret))
+        .await;
+    assert!(matches!(result, Err(DomainError::Unauthorized(_))));
+
+    assert_eq!(repo.delete_expired().await.unwrap(), 1);
+    assert!(repo.find_by_user_id(user_id).await.unwrap().is_empty());
+}
+
+/// Issues rotated tokens for refresh token "refresh-1"
+struct RefreshingProvider;
+
+#[async_trait]
+impl DeviceFlowProvider for RefreshingProvider {
+    async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        unimplemented!()
+    }
+
+    async fn poll_authorization(&self, _device_code: &str) -> Result<ProviderToken, AuthError> {
+        unimplemented!()
+    }
+
+    async fn refresh_token(&self, refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        if refresh_token != "refresh-1" {
+            return Err(DomainError::Unauthorized("Bad refresh token".to_string()));
+        }
+        Ok(ProviderToken {
+            access_token: "access-2".to_string(),
+            refresh_token: Some("refresh-2".to_string()),
+            expires_at: Some(Utc::now() + Duration::hours(8)),
+            refresh_token_expires_at: None,
+        })
+    }
+
+    async fn get_user(&self, _access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        unimplemented!()
+    }
+
+    async fn is_member(
+        &self,
+        _access_token: &str,
+        _username: &str,
+        _group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        unimplemented!()
+    }
+}
+
+#[tokio::test]
+async fn test_provider_tokens_refresh_before_expiry() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = AuthService::new(RefreshingProvider, repo.clone());
+
+    assert!(matches!(
+        service.provider_access_token(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+
+    // Fresh tokens are used as they are
+    let token = ProviderToken {
+        access_token: "access-1".to_string(),
+        refresh_token: Some("refresh-1".to_string()),
+        expires_at: Some(Utc::now() + Duration::hours(1)),
+        refresh_token_expires_at: Some(Utc::now() + Duration::days(30)),
+    };
+    service.save_provider_token(user_id, &token).await.unwrap();
+    assert_eq!(
+        service.provider_access_token(user_id).await.unwrap(),
+        "access-1"
+    );
+
+    // Nearly expired ones are refreshed, and the rotated pair is stored
+    service
+        .save_provider_token(
+            user_id,
+            &ProviderToken {
+                expires_at: Some(Utc::now() + Duration::minutes(1)),
+                ..token.clone()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        service.provider_access_token(user_id).await.unwrap(),
+        "access-2"
+    );
+    let stored = repo.find_provider_token(user_id).await.unwrap().unwrap();
+    assert_eq!(stored.refresh_token.as_deref(), Some("refresh-2"));
+
+    // An expired refresh token means logging in again
+    service
+        .save_provider_token(
+            user_id,
+            &ProviderToken {
+                refresh_token_expires_at: Some(Utc::now() - Duration::days(1)),
+                ..token
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        service.refresh_provider_token(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+}
+
+/// Issues one device code, valid for 15 minutes
+struct DeviceCodeProvider;
+
+#[async_trait]
+impl DeviceFlowProvider for DeviceCodeProvider {
+    async fn request_device_code(&self) -> Result<DeviceCodeResponse, DomainError> {
+        Ok(DeviceCodeResponse {
+            device_code: "device-1".to_string(),
+            user_code: "ABCD-1234".to_string(),
+            verification_uri: "https://github.com/login/device".to_string(),
+            expires_in: 900,
+            interval: 5,
+        })
+    }
+
+    async fn poll_authorization(&self, _device_code: &str) -> Result<ProviderToken, AuthError> {
+        unimplemented!()
+    }
+
+    async fn refresh_token(&self, _refresh_token: &str) -> Result<ProviderToken, DomainError> {
+        unimplemented!()
+    }
+
+    async fn get_user(&self, _access_token: &str) -> Result<AuthenticatedUser, DomainError> {
+        unimplemented!()
+    }
+
+    async fn is_member(
+        &self,
+        _access_token: &str,
+        _username: &str,
+        _group: &AccessGroup,
+    ) -> Result<bool, DomainError> {
+        unimplemented!()
+    }
+}
+
+#[tokio::test]
+async fn test_device_flows_are_resumable_until_expiry() {
+    let repo = test_repo().await;
+    let clock = ManualClock::default();
+    let service =
+        AuthService::new(DeviceCodeProvider, repo.clone()).with_clock(Arc::new(clock.clone()));
+
+    service.request_device_code().await.unwrap();
+    let flow = service
+        .resumable_device_flow("device-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(flow.user_code, "ABCD-1234");
+    assert_eq!(flow.interval, 5);
+    assert!(
+        service
+            .resumable_device_flow("device-2")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Expired codes can't be resumed, and are purged
+    clock.advance(Duration::minutes(16));
+    assert!(
+        service
+            .resumable_device_flow("device-1")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(service.purge_expired_device_flows().await.unwrap(), 1);
+    assert!(repo.find_device_flow("device-1").await.unwrap().is_none());
+
+    // Finished logins are forgotten straight away
+    service.request_device_code().await.unwrap();
+    service.finish_device_flow("device-1").await.unwrap();
+    assert!(repo.find_device_flow("device-1").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_oidc_subjects_are_unique_per_issuer() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let issuer = "https://login.corp.example";
+
+    repo.link_oidc_subject(user_id, issuer, "00u1")
+        .await
+        .unwrap();
+    let user = repo
+        .find_by_oidc_subject(issuer, "00u1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.id, user_id);
+    assert!(
+        repo.find_by_oidc_subject("https://other.example", "00u1")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let other = create_user(&repo).await;
+    assert!(matches!(
+        repo.link_oidc_subject(other, issuer, "00u1").await,
+        Err(DomainError::Conflict(_))
+    ));
+}
@@ -0,0 +1,358 @@
+mod common;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{
+    BillingEventKind, PlanPrice, SubscriptionStatus, SubscriptionTier, User, UserId, UserRole,
+    UserStatus,
+};
+use domain::repositories::UserRepository;
+use domain::services::billing::checkout::CheckoutService;
+use domain::services::billing::events::BillingEventService;
+use domain::services::billing::subscriptions::SubscriptionService;
+use domain::services::billing::webhooks::StripeWebhookService;
+use domain::services::billing::{CustomerId, PaymentProcessor, SubscriptionId};
+use infra::DbRepo;
+use serde_json::json;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    create_customer(repo, None).await
+}
+
+async fn create_customer(repo: &DbRepo, stripe_customer_id: Option<&str>) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: stripe_customer_id.map(str::to_string),
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+#[tokio::test]
+async fn test_record_and_list_billing_events() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = BillingEventService::new(repo.clone());
+
+    service
+        .record(
+            user_id,
+            BillingEventKind::TierChanged,
+            Some(json!({ "tier": "entry" })),
+            Some(json!({ "tier": "pro" })),
+            Some("evt_123".to_string()),
+        )
+        .await
+        .unwrap();
+    service
+        .record(user_id, BillingEventKind::PaymentFailed, None, None, None)
+        .await
+        .unwrap();
+
+    let events = service.list_events(user_id).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind, BillingEventKind::TierChanged);
+    assert_eq!(events[0].after, Some(json!({ "tier": "pro" })));
+    assert_eq!(events[0].stripe_event_id.as_deref(), Some("evt_123"));
+    assert_eq!(events[1].kind, BillingEventKind::PaymentFailed);
+
+    let page = service.find_events(user_id, 1, 1).await.unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, events[1].id);
+
+    // Events for unknown users are rejected by the foreign key
+    let orphan = service
+        .record(
+            UserId::new_v4(),
+            BillingEventKind::Refunded,
+            None,
+            None,
+            None,
+        )
+        .await;
+    assert!(orphan.is_err());
+}
+
+/// Accepts every webhook signature, sells each tier at `price_<tier>` and
+/// hands out fake hosted page URLs; other calls are unused
+struct TrustingProcessor;
+
+#[async_trait]
+impl PaymentProcessor for TrustingProcessor {
+    async fn create_customer(&self, _: &str, user_id: &str) -> Result<CustomerId, DomainError> {
+        Ok(CustomerId(format!("cus_{user_id}")))
+    }
+
+    async fn update_customer_country(&self, _: &CustomerId, _: &str) -> Result<(), DomainError> {
+        unimplemented!()
+    }
+
+    async fn tier_prices(&self, _: SubscriptionTier) -> Result<Vec<PlanPrice>, DomainError> {
+        unimplemented!()
+    }
+
+    async fn create_subscription(
+        &self,
+        _: &CustomerId,
+        _: SubscriptionTier,
+    ) -> Result<SubscriptionId, DomainError> {
+        unimplemented!()
+    }
+
+    async fn update_subscription(
+        &self,
+        _: &SubscriptionId,
+        _: SubscriptionTier,
+    ) -> Result<(), DomainError> {
+        unimplemented!()
+    }
+
+    async fn cancel_subscription(&self, _: &SubscriptionId) -> Result<(), DomainError> {
+        unimplemented!()
+    }
+
+    async fn create_checkout_session(
+        &self,
+        customer_id: &CustomerId,
+        tier: SubscriptionTier,
+        _: &str,
+        _: &str,
+    ) -> Result<String, DomainError> {
+        Ok(format!(
+            "https://checkout.test/{}/{}",
+            customer_id.0,
+            tier.as_str()
+        ))
+    }
+
+    async fn create_portal_session(
+        &self,
+        customer_id: &CustomerId,
+        _: &str,
+    ) -> Result<String, DomainError> {
+        Ok(format!("https://portal.test/{}", customer_id.0))
+    }
+
+    async fn tier_for_price(
+        &self,
+        price_id: &str,
+    ) -> Result<Option<SubscriptionTier>, DomainError> {
+        Ok(price_id
+            .strip_prefix("price_")
+            .and_then(|tier| tier.parse().ok()))
+    }
+
+    async fn verify_webhook_signature(&self, _: &[u8], _: &str) -> Result<bool, DomainError> {
+        Ok(true)
+    }
+}
+
+#[tokio::test]
+async fn test_refund_webhook_is_recorded() {
+    let repo = test_repo().await;
+    let user_id = create_customer(&repo, Some("cus_123")).await;
+    let webhooks = StripeWebhookService::new(
+        TrustingProcessor,
+        repo.clone(),
+        SubscriptionService::new(repo.clone()),
+    );
+
+    let refund = json!({
+        "id": "evt_refund",
+        "type": "charge.refunded",
+        "created": 1_700_000_000,
+        "data": { "object": {
+            "id": "ch_1",
+            "customer": "cus_123",
+            "amount_refunded": 900,
+            "currency": "usd",
+        }},
+    });
+    webhooks
+        .process_webhook(refund.to_string().as_bytes(), "t=0,v1=00")
+        .await
+        .unwrap();
+
+    // Refunds for unknown customers are acknowledged but not recorded
+    let unknown = json!({
+        "id": "evt_unknown",
+        "type": "credit_note.created",
+        "created": 1_700_000_000,
+        "data": { "object": {
+            "id": "cn_1",
+            "customer": "cus_unknown",
+            "total": 500,
+            "currency": "usd",
+        }},
+    });
+    webhooks
+        .process_webhook(unknown.to_string().as_bytes(), "t=0,v1=00")
+        .await
+        .unwrap();
+
+    let events = BillingEventService::new(repo)
+        .list_events(user_id)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, BillingEventKind::Refunded);
+    assert_eq!(events[0].stripe_event_id.as_deref(), Some("evt_refund"));
+    assert_eq!(events[0].after.as_ref().unwrap()["amount"], 900);
+}
+
+fn subscription_event(id: &str, event_type: &str, status: &str, price: &str) -> String {
+    json!({
+        "id": id,
+        "type": event_type,
+        "created": 1_700_000_000,
+        "data": { "object": {
+            "id": "sub_1",
+            "customer": "cus_123",
+            "status": status,
+            "items": { "data": [{ "price": { "id": price } }] },
+        }},
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn test_subscription_webhooks_update_subscription() {
+    let repo = test_repo().await;
+    let user_id = create_customer(&repo, Some("cus_123")).await;
+    let subscriptions = SubscriptionService::new(repo.clone());
+    let webhooks = StripeWebhookService::new(
+        TrustingProcessor,
+        repo.clone(),
+        SubscriptionService::new(repo.clone()),
+    );
+    let send = |payload: String| {
+        let webhooks = &webhooks;
+        async move {
+            webhooks
+                .process_webhook(payload.as_bytes(), "t=0,v1=00")
+                .await
+                .unwrap();
+        }
+    };
+
+    send(subscription_event(
+        "evt_created",
+        "customer.subscription.created",
+        "active",
+        "price_lite",
+    ))
+    .await;
+    let subscription = subscriptions
+        .get_subscription(user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscription.tier, SubscriptionTier::Lite);
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+    assert_eq!(subscription.provider_subscription_id, "sub_1");
+
+    send(subscription_event(
+        "evt_upgraded",
+        "customer.subscription.updated",
+        "active",
+        "price_pro",
+    ))
+    .await;
+    let failed = json!({
+        "id": "evt_failed",
+        "type": "invoice.payment_failed",
+        "created": 1_700_000_000,
+        "data": { "object": {
+            "id": "in_1",
+            "customer": "cus_123",
+            "amount_due": 2900,
+            "currency": "usd",
+            "attempt_count": 1,
+        }},
+    });
+    send(failed.to_string()).await;
+    let subscription = subscriptions
+        .get_subscription(user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscription.tier, SubscriptionTier::Pro);
+    assert_eq!(subscription.status, SubscriptionStatus::PastDue);
+
+    // Redelivered events are skipped rather than applied twice
+    send(failed.to_string()).await;
+
+    send(subscription_event(
+        "evt_deleted",
+        "customer.subscription.deleted",
+        "canceled",
+        "price_pro",
+    ))
+    .await;
+    let subscription = subscriptions
+        .get_subscription(user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscription.status, SubscriptionStatus::Cancelled);
+
+    let kinds: Vec<_> = BillingEventService::new(repo)
+        .list_events(user_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|event| event.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            BillingEventKind::TierChanged,
+            BillingEventKind::TierChanged,
+            BillingEventKind::PaymentFailed,
+            BillingEventKind::TierChanged,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_checkout_creates_customer_once() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let checkout = CheckoutService::new(
+        TrustingProcessor,
+        repo.clone(),
+        "https://forkforge.test/billing".to_string(),
+    );
+
+    let url = checkout
+        .checkout_url(user_id, SubscriptionTier::Lite)
+        .await
+        .unwrap();
+    assert_eq!(url, format!("https://checkout.test/cus_{user_id}/lite"));
+
+    // The customer created for checkout is reused by the portal
+    let user = repo.find_by_id(user_id).await.unwrap().unwrap();
+    assert_eq!(user.stripe_customer_id, Some(format!("cus_{user_id}")));
+    let url = checkout.portal_url(user_id).await.unwrap();
+    assert_eq!(url, format!("https://portal.test/cus_{user_id}"));
+
+    // Subscribed users change plans through the portal instead
+    SubscriptionService::new(repo.clone())
+        .activate_subscription(user_id, SubscriptionTier::Lite, "sub_1", None)
+        .await
+        .unwrap();
+    let again = checkout.checkout_url(user_id, SubscriptionTier::Pro).await;
+    assert!(matches!(again, Err(DomainError::InvalidInput(_))));
+}
@@ -0,0 +1,16 @@
+//! Helpers shared by the repository integration tests
+
+use infra::DbRepo;
+use sqlx::sqlite::SqlitePoolOptions;
+
+/// Single-connection in-memory database so every query sees the same schema
+pub async fn test_repo() -> DbRepo {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let repo = DbRepo::from_pool(pool);
+    repo.run_migrations().await.unwrap();
+    repo
+}
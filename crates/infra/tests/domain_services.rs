@@ -0,0 +1,819 @@
+//! Domain services against the in-memory mocks, covering the paths that
+//! are awkward to reach through a real database or GitHub.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use domain::errors::DomainError;
+use domain::models::{
+    AccessGroup, AccountState, CollaboratorAccess, DirectoryChanges, ProfileChanges, ProviderToken,
+    SessionId, SessionStatus, SnapshotFilter, SubscriptionStatus, SubscriptionTier, UserId,
+    UserRole, UserStatus,
+};
+use domain::repositories::AuthRepository;
+use domain::services::auth::github::AuthService;
+use domain::services::auth::oidc::OidcClaims;
+use domain::services::auth::{AuthError, AuthenticatedUser};
+use domain::services::billing::events::BillingEventRepository;
+use domain::services::billing::subscriptions::SubscriptionService;
+use domain::services::clock::{Clock, ManualClock};
+use domain::services::sessions::{SessionRepository, SessionService};
+use domain::services::snapshots::SnapshotService;
+use domain::services::users::{SignupRules, UserService};
+use infra::mocks::{MockAuthorization, MockDeviceFlowProvider, MockRepo};
+
+fn account(pubkey: &str) -> AccountState {
+    AccountState {
+        pubkey: pubkey.to_string(),
+        lamports: 1_000_000,
+        owner: "11111111111111111111111111111111".to_string(),
+        data: Vec::new(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_device_flow_outcomes() {
+    let approved = AuthService::new(
+        MockDeviceFlowProvider::approving("ada"),
+        MockRepo::default(),
+    );
+    let code = approved.request_device_code().await.unwrap();
+    let token = approved
+        .wait_for_authorization(&code.device_code)
+        .await
+        .unwrap();
+    assert_eq!(token.access_token, "gho_ada");
+    let user = approved.get_user(&token.access_token).await.unwrap();
+    assert_eq!(user.username, "ada");
+
+    let denied = AuthService::new(
+        MockDeviceFlowProvider {
+            authorization: MockAuthorization::Denied,
+            user: None,
+            ..MockDeviceFlowProvider::approving("ada")
+        },
+        MockRepo::default(),
+    );
+    assert!(matches!(
+        denied.wait_for_authorization("code").await,
+        Err(AuthError::UserDeniedAuthentication)
+    ));
+    assert!(matches!(
+        denied.get_user("gho_ada").await,
+        Err(DomainError::Unauthorized(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_required_groups() {
+    let platform = AccessGroup::Team {
+        org: "acme".to_string(),
+        team: "platform".to_string(),
+    };
+    let recheck = Duration::minutes(60);
+    let repo = MockRepo::default();
+    let clock = ManualClock::default();
+    let auth = AuthService::new(
+        MockDeviceFlowProvider {
+            groups: vec![platform.clone()],
+            ..MockDeviceFlowProvider::approving("ada")
+        },
+        repo.clone(),
+    )
+    .with_required_groups(
+        vec![AccessGroup::Org("other".to_string()), platform],
+        recheck,
+    )
+    .with_clock(Arc::new(clock.clone()));
+    let ada = auth.get_user("gho_ada").await.unwrap();
+    auth.check_group_access("gho_ada", &ada).await.unwrap();
+
+    let outsiders = AuthService::new(MockDeviceFlowProvider::approving("eve"), repo.clone())
+        .with_required_groups(vec![AccessGroup::Org("acme".to_string())], recheck);
+    let eve = outsiders.get_user("gho_eve").await.unwrap();
+    assert!(matches!(
+        outsiders.check_group_access("gho_eve", &eve).await,
+        Err(DomainError::Forbidden(_))
+    ));
+
+    // Rechecks use the stored provider token
+    let user_id = UserId::new_v4();
+    assert!(matches!(
+        auth.ensure_group_access(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+    let token = ProviderToken {
+        access_token: "gho_ada".to_string(),
+        refresh_token: None,
+        expires_at: None,
+        refresh_token_expires_at: None,
+    };
+    auth.save_provider_token(user_id, &token).await.unwrap();
+    auth.ensure_group_access(user_id).await.unwrap();
+
+    // Passing is remembered until the recheck is due
+    repo.set_unavailable(true);
+    clock.advance(Duration::minutes(59));
+    auth.ensure_group_access(user_id).await.unwrap();
+    clock.advance(Duration::minutes(2));
+    assert!(auth.ensure_group_access(user_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_api_tokens() {
+    let repo = MockRepo::default();
+    let auth = AuthService::new(MockDeviceFlowProvider::approving("ada"), repo.clone());
+    let user_id = UserId::new_v4();
+
+    let token = auth.create_api_token(user_id, None).await.unwrap();
+    assert_eq!(auth.authenticate(&token.token).await.unwrap(), user_id);
+    let stored = repo.find_by_user_id(user_id).await.unwrap();
+    assert!(stored[0].last_used_at.is_some());
+
+    // A valid secret presented for another user is rejected
+    let (_, secret) = token.token.split_once('.').unwrap();
+    let forged = format!("{}.{secret}", UserId::new_v4());
+    for bad in ["not-a-token", forged.as_str()] {
+        assert!(matches!(
+            auth.authenticate(bad).await,
+            Err(DomainError::Unauthorized(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_provider_token_refresh() {
+    let repo = MockRepo::default();
+    let refreshed = ProviderToken {
+        access_token: "ghu_new".to_string(),
+        refresh_token: Some("ghr_new".to_string()),
+        expires_at: Some(Utc::now() + Duration::hours(8)),
+        refresh_token_expires_at: None,
+    };
+    let auth = AuthService::new(
+        MockDeviceFlowProvider {
+            refreshed: Some(refreshed.clone()),
+            ..MockDeviceFlowProvider::approving("ada")
+        },
+        repo.clone(),
+    );
+    let user_id = UserId::new_v4();
+
+    assert!(matches!(
+        auth.provider_access_token(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+
+    // Tokens about to expire are refreshed and the new pair is stored
+    let expiring = ProviderToken {
+        access_token: "ghu_old".to_string(),
+        refresh_token: Some("ghr_old".to_string()),
+        expires_at: Some(Utc::now() + Duration::minutes(1)),
+        refresh_token_expires_at: None,
+    };
+    auth.save_provider_token(user_id, &expiring).await.unwrap();
+    assert_eq!(
+        auth.provider_access_token(user_id).await.unwrap(),
+        "ghu_new"
+    );
+    assert_eq!(
+        repo.find_provider_token(user_id).await.unwrap(),
+        Some(refreshed)
+    );
+
+    // A refresh token the provider rejects means logging in again
+    let rejecting = AuthService::new(MockDeviceFlowProvider::approving("ada"), repo.clone());
+    auth.save_provider_token(user_id, &expiring).await.unwrap();
+    assert!(matches!(
+        rejecting.refresh_provider_token(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_session_lifecycle() {
+    let sessions = SessionService::new(MockRepo::default());
+    let user_id = UserId::new_v4();
+
+    let session = sessions
+        .create_session(user_id, "Panic 2245".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(session.slug, "panic-2245");
+    let again = sessions
+        .create_session(user_id, "Panic 2245".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(again.slug, "panic-2245-2");
+
+    let running = sessions.start_session(session.id).await.unwrap();
+    assert_eq!(running.status, SessionStatus::Running);
+    sessions.fail_session(session.id).await.unwrap();
+    // Failed sessions are final
+    assert!(matches!(
+        sessions.start_session(session.id).await,
+        Err(DomainError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        sessions.stop_session(SessionId::new_v4()).await,
+        Err(DomainError::NotFound(_))
+    ));
+
+    // Renames based on a stale read are rejected
+    let stale = again.updated_at;
+    sessions
+        .rename_session(again.id, "first".to_string(), Some(stale))
+        .await
+        .unwrap();
+    assert!(matches!(
+        sessions
+            .rename_session(again.id, "second".to_string(), Some(stale))
+            .await,
+        Err(DomainError::Conflict(_))
+    ));
+    assert_eq!(sessions.count_sessions(user_id, None).await.unwrap(), 2);
+    assert_eq!(
+        sessions
+            .count_sessions(user_id, Some(SessionStatus::Failed))
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_session_collaborators() {
+    let sessions = SessionService::new(MockRepo::default());
+    let (owner, reader, stranger) = (UserId::new_v4(), UserId::new_v4(), UserId::new_v4());
+    let session = sessions
+        .create_session(owner, String::new(), None, None)
+        .await
+        .unwrap();
+
+    sessions
+        .add_collaborator(owner, session.id, reader, CollaboratorAccess::Read)
+        .await
+        .unwrap();
+    assert!(matches!(
+        sessions
+            .add_collaborator(reader, session.id, stranger, CollaboratorAccess::Read)
+            .await,
+        Err(DomainError::Forbidden(_))
+    ));
+    assert!(matches!(
+        sessions
+            .add_collaborator(owner, session.id, owner, CollaboratorAccess::Write)
+            .await,
+        Err(DomainError::InvalidInput(_))
+    ));
+
+    sessions
+        .authorize_access(session.id, reader, CollaboratorAccess::Read)
+        .await
+        .unwrap();
+    for (user, access) in [
+        (reader, CollaboratorAccess::Write),
+        (stranger, CollaboratorAccess::Read),
+    ] {
+        assert!(matches!(
+            sessions.authorize_access(session.id, user, access).await,
+            Err(DomainError::Forbidden(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_snapshot_restore() {
+    let repo = MockRepo::default();
+    let snapshots = SnapshotService::new(repo.clone());
+    let sessions = SessionService::new(repo.clone());
+    let user_id = UserId::new_v4();
+
+    let source = sessions
+        .create_session(
+            user_id,
+            "source".to_string(),
+            Some(300),
+            Some("eu".to_string()),
+        )
+        .await
+        .unwrap();
+    repo.save_accounts(source.id, &[account("a"), account("b")])
+        .await
+        .unwrap();
+    let snapshot = snapshots
+        .create_snapshot(source.id, user_id, "before".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.fork_slot, Some(300));
+
+    // Later changes to the session don't leak into the snapshot
+    repo.save_accounts(source.id, &[account("c")])
+        .await
+        .unwrap();
+    let restored = snapshots
+        .restore_snapshot(snapshot.id, user_id, None)
+        .await
+        .unwrap();
+    assert_eq!(restored.name, "before");
+    assert_eq!(restored.region.as_deref(), Some("eu"));
+    assert_eq!(restored.fork_slot, Some(300));
+    assert_eq!(repo.list_accounts(restored.id).await.unwrap().len(), 2);
+
+    sessions.start_session(source.id).await.unwrap();
+    assert!(matches!(
+        snapshots
+            .restore_snapshot(snapshot.id, user_id, Some(source.id))
+            .await,
+        Err(DomainError::InvalidInput(_))
+    ));
+
+    snapshots.delete_snapshot(snapshot.id).await.unwrap();
+    assert!(matches!(
+        snapshots.restore_snapshot(snapshot.id, user_id, None).await,
+        Err(DomainError::NotFound(_))
+    ));
+    assert!(matches!(
+        snapshots.delete_snapshot(snapshot.id).await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_user_administration() {
+    let repo = MockRepo::default();
+    let users = UserService::new(repo.clone(), repo.clone());
+    let sessions = SessionService::new(repo.clone());
+
+    let mut github_user = AuthenticatedUser {
+        provider_id: "42".to_string(),
+        username: "ada".to_string(),
+        email: None,
+        display_name: Some("Ada Lovelace".to_string()),
+        created_at: None,
+    };
+    let user = users
+        .find_or_create_github_user(&github_user)
+        .await
+        .unwrap();
+    assert_eq!(user.primary_email, "42+ada@users.noreply.github.com");
+    assert_eq!(user.display_name.as_deref(), Some("Ada Lovelace"));
+
+    // A later login picks up the email, but not over the user's own name
+    let user = users
+        .update_profile(
+            user.id,
+            ProfileChanges {
+                display_name: Some("Ada".to_string()),
+                ..ProfileChanges::default()
+            },
+        )
+        .await
+        .unwrap();
+    github_user.email = Some("ada@example.com".to_string());
+    let same = users
+        .find_or_create_github_user(&github_user)
+        .await
+        .unwrap();
+    assert_eq!(same.id, user.id);
+    assert_eq!(same.primary_email, "ada@example.com");
+    assert_eq!(same.display_name.as_deref(), Some("Ada"));
+
+    let invalid = AuthenticatedUser {
+        provider_id: "43".to_string(),
+        email: Some("not-an-email".to_string()),
+        ..github_user.clone()
+    };
+    assert!(matches!(
+        users.find_or_create_github_user(&invalid).await,
+        Err(DomainError::InvalidInput(_))
+    ));
+
+    let session = sessions
+        .create_session(user.id, String::new(), None, None)
+        .await
+        .unwrap();
+    sessions.start_session(session.id).await.unwrap();
+    let suspended = users.suspend_user(user.id).await.unwrap();
+    assert_eq!(suspended.status, UserStatus::Suspended);
+    let stopped = sessions.get_session(session.id).await.unwrap().unwrap();
+    assert_eq!(stopped.status, SessionStatus::Stopped);
+
+    assert!(matches!(
+        users.set_billing_country(user.id, "Germany", None).await,
+        Err(DomainError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        users
+            .set_billing_country(user.id, "de", Some(user.updated_at))
+            .await,
+        Err(DomainError::Conflict(_))
+    ));
+    let user = users
+        .set_billing_country(user.id, "de", None)
+        .await
+        .unwrap();
+    assert_eq!(user.billing_country.as_deref(), Some("DE"));
+
+    assert!(matches!(
+        users
+            .update_profile(UserId::new_v4(), ProfileChanges::default())
+            .await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_oidc_users() {
+    let repo = MockRepo::default();
+    let users = UserService::new(repo.clone(), repo.clone());
+    let claims = OidcClaims {
+        issuer: "https://login.corp.example".to_string(),
+        subject: "00u1".to_string(),
+        email: Some("grace@corp.example".to_string()),
+        email_verified: false,
+        name: Some("Grace Hopper".to_string()),
+    };
+
+    let user = users.find_or_create_oidc_user(&claims).await.unwrap();
+    assert_eq!(user.primary_email, "grace@corp.example");
+    assert_eq!(user.display_name.as_deref(), Some("Grace Hopper"));
+    assert_eq!(user.github_user_id, None);
+
+    // Later sign-ins find the user by subject, even with a new email
+    let renamed = OidcClaims {
+        email: Some("grace.hopper@corp.example".to_string()),
+        ..claims.clone()
+    };
+    let same = users.find_or_create_oidc_user(&renamed).await.unwrap();
+    assert_eq!(same.id, user.id);
+
+    // Another identity claiming the address only gets the account if the
+    // provider verified it
+    let github_user = users
+        .find_or_create_github_user(&AuthenticatedUser {
+            provider_id: "42".to_string(),
+            username: "ada".to_string(),
+            email: Some("ada@corp.example".to_string()),
+            display_name: None,
+            created_at: None,
+        })
+        .await
+        .unwrap();
+    let unverified = OidcClaims {
+        subject: "00u2".to_string(),
+        email: Some("ada@corp.example".to_string()),
+        ..claims.clone()
+    };
+    assert!(matches!(
+        users.find_or_create_oidc_user(&unverified).await,
+        Err(DomainError::Conflict(_))
+    ));
+    let verified = OidcClaims {
+        email_verified: true,
+        ..unverified
+    };
+    let linked = users.find_or_create_oidc_user(&verified).await.unwrap();
+    assert_eq!(linked.id, github_user.id);
+
+    let no_email = OidcClaims {
+        subject: "00u3".to_string(),
+        email: None,
+        ..claims
+    };
+    assert!(matches!(
+        users.find_or_create_oidc_user(&no_email).await,
+        Err(DomainError::Forbidden(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_signup_rules() {
+    let repo = MockRepo::default();
+    let users = UserService::new(repo.clone(), repo.clone()).with_signup_rules(SignupRules {
+        min_account_age: Some(Duration::days(30)),
+        blocked_email_domains: vec!["mailinator.com".to_string()],
+    });
+    let veteran = AuthenticatedUser {
+        provider_id: "42".to_string(),
+        username: "ada".to_string(),
+        email: Some("ada@example.com".to_string()),
+        display_name: None,
+        created_at: Some(Utc::now() - Duration::days(400)),
+    };
+
+    for rejected in [
+        AuthenticatedUser {
+            created_at: Some(Utc::now() - Duration::days(2)),
+            ..veteran.clone()
+        },
+        // GitHub always says; an account that doesn't is treated as new
+        AuthenticatedUser {
+            created_at: None,
+            ..veteran.clone()
+        },
+        AuthenticatedUser {
+            email: Some("ada@Mailinator.com".to_string()),
+            ..veteran.clone()
+        },
+    ] {
+        assert!(matches!(
+            users.find_or_create_github_user(&rejected).await,
+            Err(DomainError::Forbidden(_))
+        ));
+    }
+
+    let user = users.find_or_create_github_user(&veteran).await.unwrap();
+    // Only signups are checked
+    let returning = AuthenticatedUser {
+        created_at: None,
+        ..veteran
+    };
+    assert_eq!(
+        users
+            .find_or_create_github_user(&returning)
+            .await
+            .unwrap()
+            .id,
+        user.id
+    );
+}
+
+#[tokio::test]
+async fn test_signup_account_age_boundary() {
+    let repo = MockRepo::default();
+    let clock = ManualClock::default();
+    let users = UserService::new(repo.clone(), repo.clone())
+        .with_signup_rules(SignupRules {
+            min_account_age: Some(Duration::days(30)),
+            ..SignupRules::default()
+        })
+        .with_clock(Arc::new(clock.clone()));
+    let github_user = AuthenticatedUser {
+        provider_id: "42".to_string(),
+        username: "ada".to_string(),
+        email: Some("ada@example.com".to_string()),
+        display_name: None,
+        created_at: Some(clock.now() - Duration::days(30) + Duration::seconds(1)),
+    };
+
+    assert!(matches!(
+        users.find_or_create_github_user(&github_user).await,
+        Err(DomainError::Forbidden(_))
+    ));
+
+    clock.advance(Duration::seconds(1));
+    assert!(users.find_or_create_github_user(&github_user).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_directory_provisioning() {
+    let repo = MockRepo::default();
+    let users = UserService::new(repo.clone(), repo.clone());
+
+    let provisioned = users
+        .provision_user(
+            "ada@corp.example",
+            DirectoryChanges {
+                display_name: Some(Some(" Ada Lovelace ".to_string())),
+                role: Some(UserRole::Admin),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(provisioned.display_name.as_deref(), Some("Ada Lovelace"));
+    assert_eq!(provisioned.role, UserRole::Admin);
+    assert_eq!(provisioned.status, UserStatus::Active);
+    assert!(matches!(
+        users
+            .provision_user("ada@corp.example", DirectoryChanges::default())
+            .await,
+        Err(DomainError::Conflict(_))
+    ));
+
+    // The first GitHub login with the address claims the account
+    let claimed = users
+        .find_or_create_github_user(&AuthenticatedUser {
+            provider_id: "42".to_string(),
+            username: "ada".to_string(),
+            email: Some("ada@corp.example".to_string()),
+            display_name: None,
+            created_at: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(claimed.id, provisioned.id);
+    assert_eq!(claimed.github_user_id, Some(42));
+    assert_eq!(claimed.role, UserRole::Admin);
+
+    let deactivated = users
+        .apply_directory_changes(
+            provisioned.id,
+            DirectoryChanges {
+                display_name: Some(Some(" ".to_string())),
+                active: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(deactivated.status, UserStatus::Suspended);
+    assert_eq!(deactivated.display_name, None);
+    let reactivated = users
+        .apply_directory_changes(
+            provisioned.id,
+            DirectoryChanges {
+                active: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(reactivated.status, UserStatus::Active);
+
+    users.deprovision_user(provisioned.id).await.unwrap();
+    assert_eq!(
+        users
+            .get_user(provisioned.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .status,
+        UserStatus::Deleted
+    );
+    assert!(
+        users
+            .find_user_by_email("ada@corp.example")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(matches!(
+        users.deprovision_user(provisioned.id).await,
+        Err(DomainError::NotFound(_))
+    ));
+    assert!(matches!(
+        users
+            .apply_directory_changes(provisioned.id, DirectoryChanges::default())
+            .await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_subscription_changes() {
+    let repo = MockRepo::default();
+    let subscriptions = SubscriptionService::new(repo.clone());
+    let user_id = UserId::new_v4();
+
+    assert_eq!(
+        subscriptions.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Entry
+    );
+    assert!(matches!(
+        subscriptions.cancel_subscription(user_id, None).await,
+        Err(DomainError::NotFound(_))
+    ));
+
+    subscriptions
+        .activate_subscription(user_id, SubscriptionTier::Pro, "sub_1", None)
+        .await
+        .unwrap();
+    subscriptions
+        .record_payment_failure(user_id, serde_json::json!({ "invoice": "in_1" }), None)
+        .await
+        .unwrap();
+    let past_due = subscriptions
+        .get_subscription(user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(past_due.status, SubscriptionStatus::PastDue);
+    assert_eq!(past_due.failed_payment_count, 1);
+    // Past-due subscriptions keep their tier while the payment is retried
+    assert_eq!(
+        subscriptions.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Pro
+    );
+
+    subscriptions
+        .cancel_subscription(user_id, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        subscriptions.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Entry
+    );
+    let events = BillingEventRepository::list_by_user(&repo, user_id)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 3);
+}
+
+#[tokio::test]
+async fn test_repository_failures_reach_callers() {
+    let repo = MockRepo::default();
+    let sessions = SessionService::new(repo.clone());
+    let auth = AuthService::new(MockDeviceFlowProvider::approving("ada"), repo.clone());
+    let user_id = UserId::new_v4();
+    let session = sessions
+        .create_session(user_id, String::new(), None, None)
+        .await
+        .unwrap();
+
+    repo.set_unavailable(true);
+    assert!(matches!(
+        sessions.stop_session(session.id).await,
+        Err(DomainError::Internal(_))
+    ));
+    assert!(matches!(
+        auth.create_api_token(user_id, None).await,
+        Err(DomainError::Internal(_))
+    ));
+
+    repo.set_unavailable(false);
+    sessions.stop_session(session.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_new_records_are_stamped_by_the_clock() {
+    let repo = MockRepo::default();
+    let clock = ManualClock::new(Utc::now() - Duration::days(90));
+    let sessions = SessionService::new(repo.clone()).with_clock(Arc::new(clock.clone()));
+    let snapshots = SnapshotService::new(repo.clone()).with_clock(Arc::new(clock.clone()));
+    let users = UserService::new(repo.clone(), repo.clone()).with_clock(Arc::new(clock.clone()));
+
+    let user = users
+        .find_or_create_github_user(&AuthenticatedUser {
+            provider_id: "42".to_string(),
+            username: "ada".to_string(),
+            email: Some("ada@example.com".to_string()),
+            display_name: None,
+            created_at: None,
+        })
+        .await
+        .unwrap();
+    let session = sessions
+        .create_session(user.id, "replay".to_string(), None, None)
+        .await
+        .unwrap();
+    let snapshot = snapshots
+        .create_snapshot(session.id, user.id, "before".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(user.created_at, clock.now());
+    assert_eq!(session.created_at, clock.now());
+    assert_eq!(snapshot.created_at, clock.now());
+
+    // Ages are measured on the same clock
+    let older_than_30d = SnapshotFilter::OlderThan(Duration::days(30));
+    clock.advance(Duration::days(29));
+    assert!(
+        snapshots
+            .find_matching(user.id, older_than_30d, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    clock.advance(Duration::days(2));
+    assert_eq!(
+        snapshots
+            .find_matching(user.id, older_than_30d, 10)
+            .await
+            .unwrap(),
+        vec![snapshot.id]
+    );
+}
+
+#[tokio::test]
+async fn test_provider_token_expires_with_the_clock() {
+    let clock = ManualClock::default();
+    let auth = AuthService::new(
+        MockDeviceFlowProvider::approving("ada"),
+        MockRepo::default(),
+    )
+    .with_clock(Arc::new(clock.clone()));
+    let user_id = UserId::new_v4();
+
+    // Without a refresh token the stored token is used until it expires
+    let token = ProviderToken {
+        access_token: "ghu_ada".to_string(),
+        refresh_token: None,
+        expires_at: Some(clock.now() + Duration::hours(1)),
+        refresh_token_expires_at: None,
+    };
+    auth.save_provider_token(user_id, &token).await.unwrap();
+    assert_eq!(
+        auth.provider_access_token(user_id).await.unwrap(),
+        "ghu_ada"
+    );
+
+    clock.advance(Duration::hours(2));
+    assert!(matches!(
+        auth.provider_access_token(user_id).await,
+        Err(DomainError::Unauthorized(_))
+    ));
+}
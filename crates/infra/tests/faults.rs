@@ -0,0 +1,109 @@
+//! Domain services over adapters with injected latency and failures.
+
+use std::time::{Duration, Instant};
+
+use domain::errors::DomainError;
+use domain::models::UserId;
+use domain::repositories::UserRepository;
+use domain::services::auth::AuthError;
+use domain::services::auth::github::AuthService;
+use domain::services::sessions::{SessionRepository, SessionService};
+use infra::faults::{FaultConfig, FaultSource, Faulty};
+use infra::mocks::{MockDeviceFlowProvider, MockRepo};
+
+fn failing(error_rate: f64) -> FaultConfig {
+    FaultConfig {
+        error_rate,
+        ..FaultConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_errors_never_reach_the_adapter() {
+    let repo = Faulty::new(FaultSource::Database, MockRepo::default(), failing(1.0));
+    let sessions = SessionService::new(repo.clone());
+    let user_id = UserId::new_v4();
+
+    let result = sessions
+        .create_session(user_id, "replay".to_string(), None, None)
+        .await;
+    assert!(matches!(result, Err(DomainError::Internal(_))));
+    assert_eq!(repo.injected(), 1);
+    assert!(repo.inner().list_by_user(user_id).await.unwrap().is_empty());
+
+    // Healthy again once the faults are switched off
+    repo.set_config(FaultConfig::default());
+    sessions
+        .create_session(user_id, "replay".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(repo.inner().list_by_user(user_id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_partial_failures_still_reach_the_adapter() {
+    let repo = Faulty::new(
+        FaultSource::Database,
+        MockRepo::default(),
+        FaultConfig {
+            partial_failure_rate: 1.0,
+            ..FaultConfig::default()
+        },
+    );
+    let sessions = SessionService::new(repo.clone());
+    let user_id = UserId::new_v4();
+
+    // The write lands, but the caller is told it failed
+    let result = sessions
+        .create_session(user_id, "replay".to_string(), None, None)
+        .await;
+    assert!(result.is_err());
+    assert_eq!(repo.inner().list_by_user(user_id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_upstream_faults_and_latency() {
+    let github = Faulty::new(
+        FaultSource::GitHub,
+        MockDeviceFlowProvider::approving("ada"),
+        failing(1.0),
+    );
+    let auth = AuthService::new(github.clone(), MockRepo::default());
+
+    assert!(matches!(
+        auth.request_device_code().await,
+        Err(DomainError::ExternalService(_))
+    ));
+    assert!(matches!(
+        auth.wait_for_authorization("code").await,
+        Err(AuthError::InternalServerError { .. })
+    ));
+
+    github.set_config(FaultConfig {
+        min_latency: Duration::from_millis(30),
+        max_latency: Duration::from_millis(40),
+        ..FaultConfig::default()
+    });
+    let started = Instant::now();
+    let token = auth.wait_for_authorization("code").await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(30));
+    assert_eq!(token.access_token, "gho_ada");
+    assert_eq!(github.injected(), 2);
+}
+
+#[tokio::test]
+async fn test_error_rate_is_a_probability() {
+    let repo = Faulty::new(FaultSource::Database, MockRepo::default(), failing(0.5));
+
+    let mut failures = 0;
+    for _ in 0..400 {
+        if UserRepository::find_by_id(&repo, UserId::new_v4())
+            .await
+            .is_err()
+        {
+            failures += 1;
+        }
+    }
+    assert_eq!(repo.injected(), failures);
+    assert!((100..300).contains(&failures), "{failures} of 400 failed");
+}
@@ -0,0 +1,72 @@
+//! Outbound clients going through a configured proxy.
+
+use domain::errors::DomainError;
+use infra::OutboundConfig;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A proxy answering one request itself, returning its address and the
+/// request it received
+async fn fake_proxy() -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let request = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 4096];
+        let len = socket.read(&mut buf).await.unwrap();
+        socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\ncontent-length: 9\r\nconnection: close\r\n\r\nvia proxy",
+            )
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&buf[..len]).to_string()
+    });
+    (addr.to_string(), request)
+}
+
+#[tokio::test]
+async fn test_requests_go_through_the_proxy() {
+    let (proxy_addr, request) = fake_proxy().await;
+    let outbound = OutboundConfig {
+        proxy: Some(format!("http://ada:secret@{proxy_addr}")),
+        extra_ca_certs: None,
+    };
+    let client = outbound.client_builder().unwrap().build().unwrap();
+
+    let body = client
+        .get("http://api.forkforge.invalid/v1/health")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "via proxy");
+
+    let request = request.await.unwrap();
+    assert!(request.starts_with("GET http://api.forkforge.invalid/v1/health "));
+    // "ada:secret", base64-encoded
+    assert!(request.contains("proxy-authorization: Basic YWRhOnNlY3JldA=="));
+}
+
+#[test]
+fn test_invalid_settings_are_reported() {
+    let missing_certs = OutboundConfig {
+        proxy: None,
+        extra_ca_certs: Some("/nonexistent/ca.pem".to_string()),
+    };
+    assert!(matches!(
+        missing_certs.client_builder(),
+        Err(DomainError::Internal(_))
+    ));
+
+    let bad_proxy = OutboundConfig {
+        proxy: Some("not a url".to_string()),
+        extra_ca_certs: None,
+    };
+    assert!(matches!(
+        bad_proxy.client_builder(),
+        Err(DomainError::Internal(_))
+    ));
+}
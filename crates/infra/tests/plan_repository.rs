@@ -0,0 +1,202 @@
+mod common;
+
+use chrono::Utc;
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{SessionStatus, SubscriptionTier, User, UserId, UserRole, UserStatus};
+use domain::repositories::UserRepository;
+use domain::services::billing::plans::PlanCatalog;
+use domain::services::quota::QuotaService;
+use domain::services::sessions::SessionRepository;
+use domain::services::snapshots::SnapshotRepository;
+use infra::DbRepo;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_plan_catalog_loads_seeded_tiers() {
+    let repo = test_repo().await;
+    let catalog = PlanCatalog::load(&repo).await.unwrap();
+
+    let tiers: Vec<_> = catalog.plans().iter().map(|plan| plan.tier).collect();
+    assert_eq!(tiers, SubscriptionTier::ALL);
+    assert_eq!(
+        catalog
+            .limits(SubscriptionTier::Entry)
+            .max_concurrent_sessions,
+        1
+    );
+    assert_eq!(
+        catalog
+            .limits(SubscriptionTier::Pro)
+            .api_requests_per_minute,
+        1200
+    );
+    assert!(catalog.has_feature(SubscriptionTier::Pro, "collaborators"));
+    assert!(!catalog.has_feature(SubscriptionTier::Entry, "collaborators"));
+}
+
+#[tokio::test]
+async fn test_session_quota_uses_catalog_limits() {
+    let repo = test_repo().await;
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: "quota@example.com".to_string(),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(&repo, &user).await.unwrap();
+
+    let catalog = Arc::new(PlanCatalog::load(&repo).await.unwrap());
+    let quota = QuotaService::new(catalog, repo.clone());
+
+    quota
+        .check_session_quota(user.id, SubscriptionTier::Entry)
+        .await
+        .unwrap();
+    SessionRepository::create(&repo, user.id, "first".to_string(), None, None, Utc::now())
+        .await
+        .unwrap();
+
+    // Entry allows a single active session; Lite allows more
+    assert!(matches!(
+        quota
+            .check_session_quota(user.id, SubscriptionTier::Entry)
+            .await,
+        Err(DomainError::QuotaExceeded(_))
+    ));
+    quota
+        .check_session_quota(user.id, SubscriptionTier::Lite)
+        .await
+        .unwrap();
+
+    // Stopped sessions don't count
+    SessionRepository::stop_all_by_user(&repo, user.id)
+        .await
+        .unwrap();
+    quota
+        .check_session_quota(user.id, SubscriptionTier::Entry)
+        .await
+        .unwrap();
+}
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap();
+    user.id
+}
+
+#[tokio::test]
+async fn test_snapshot_quota_uses_catalog_limits() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let session =
+        SessionRepository::create(&repo, user_id, "snaps".to_string(), None, None, Utc::now())
+            .await
+            .unwrap();
+    let catalog = Arc::new(PlanCatalog::load(&repo).await.unwrap());
+    let quota = QuotaService::new(catalog, repo.clone());
+
+    // Entry allows three snapshots per session
+    for n in 0..3 {
+        quota
+            .check_snapshot_quota(session.id, SubscriptionTier::Entry)
+            .await
+            .unwrap();
+        SnapshotRepository::create(
+            &repo,
+            session.id,
+            user_id,
+            format!("snap-{n}"),
+            None,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert!(matches!(
+        quota
+            .check_snapshot_quota(session.id, SubscriptionTier::Entry)
+            .await,
+        Err(DomainError::QuotaExceeded(_))
+    ));
+    quota
+        .check_snapshot_quota(session.id, SubscriptionTier::Lite)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_session_duration_limit_stops_session() {
+    let repo = test_repo().await;
+    // Shorten the Entry limit so the test doesn't need a two-hour old session
+    sqlx::query("UPDATE plans SET max_session_minutes = 1 WHERE tier = 'entry'")
+        .execute(repo.pool())
+        .await
+        .unwrap();
+    let catalog = Arc::new(PlanCatalog::load(&repo).await.unwrap());
+    assert_eq!(
+        catalog.limits(SubscriptionTier::Pro).max_session_minutes,
+        None
+    );
+    let quota = QuotaService::new(catalog, repo.clone());
+
+    let user_id = create_user(&repo).await;
+    let session =
+        SessionRepository::create(&repo, user_id, "long".to_string(), None, None, Utc::now())
+            .await
+            .unwrap();
+    SessionRepository::update_status(
+        &repo,
+        session.id,
+        SessionStatus::Pending,
+        SessionStatus::Running,
+    )
+    .await
+    .unwrap();
+
+    quota
+        .record_usage(session.id, 60, 0, SubscriptionTier::Entry)
+        .await
+        .unwrap();
+    assert!(matches!(
+        quota
+            .record_usage(session.id, 61, 0, SubscriptionTier::Entry)
+            .await,
+        Err(DomainError::QuotaExceeded(_))
+    ));
+
+    // The usage is still recorded, and the session no longer runs
+    let stopped = SessionRepository::find_by_id(&repo, session.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stopped.status, SessionStatus::Stopped);
+    let usage = quota
+        .record_usage(session.id, 0, 0, SubscriptionTier::Pro)
+        .await
+        .unwrap();
+    assert_eq!(usage.elapsed_seconds, 61);
+}
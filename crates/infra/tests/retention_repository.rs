@@ -0,0 +1,141 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::test_repo;
+use domain::models::{
+    AuthToken, BillingEvent, BillingEventKind, RetentionRule, User, UserId, UserRole, UserStatus,
+};
+use domain::repositories::{AuthRepository, UserRepository};
+use domain::services::billing::events::BillingEventRepository;
+use domain::services::retention::{RetentionPolicy, RetentionService};
+use infra::DbRepo;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo, status: UserStatus, days_ago: i64) -> User {
+    let updated_at = Utc::now() - Duration::days(days_ago);
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: Some((Uuid::new_v4().as_u128() % 1_000_000_000) as i64),
+        stripe_customer_id: None,
+        billing_country: Some("DE".to_string()),
+        status,
+        role: UserRole::Member,
+        created_at: updated_at,
+        updated_at,
+    };
+    UserRepository::create(repo, &user).await.unwrap()
+}
+
+async fn create_token(repo: &DbRepo, user_id: UserId, last_used_days_ago: i64) -> AuthToken {
+    let token = AuthToken {
+        id: Uuid::new_v4(),
+        user_id,
+        token_hash: Uuid::new_v4().to_string(),
+        name: None,
+        last_used_at: Some(Utc::now() - Duration::days(last_used_days_ago)),
+        expires_at: None,
+        created_at: Utc::now() - Duration::days(365),
+    };
+    AuthRepository::create(repo, &token).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_retention_dry_run_then_purge() {
+    let repo = test_repo().await;
+    let active = create_user(&repo, UserStatus::Active, 0).await;
+    let deleted = create_user(&repo, UserStatus::Deleted, 45).await;
+    let recently_deleted = create_user(&repo, UserStatus::Deleted, 5).await;
+
+    let stale = create_token(&repo, active.id, 120).await;
+    let fresh = create_token(&repo, active.id, 1).await;
+
+    BillingEventRepository::record(
+        &repo,
+        &BillingEvent {
+            id: Uuid::new_v4(),
+            user_id: active.id,
+            kind: BillingEventKind::Refunded,
+            before: None,
+            after: None,
+            stripe_event_id: None,
+            created_at: Utc::now() - Duration::days(400),
+        },
+    )
+    .await
+    .unwrap();
+
+    let service = RetentionService::new(
+        repo.clone(),
+        RetentionPolicy {
+            unused_auth_token_days: Some(90),
+            deleted_user_days: Some(30),
+            billing_event_days: Some(365),
+        },
+    );
+
+    // A dry run reports without changing anything
+    let preview = service.run(true).await.unwrap();
+    let affected: Vec<(RetentionRule, u64)> =
+        preview.iter().map(|r| (r.rule, r.affected)).collect();
+    assert_eq!(
+        affected,
+        vec![
+            (RetentionRule::UnusedAuthTokens, 1),
+            (RetentionRule::DeletedUsers, 1),
+            (RetentionRule::BillingEvents, 1),
+        ]
+    );
+    assert!(
+        AuthRepository::find_by_token_hash(&repo, &stale.token_hash)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let reports = service.run(false).await.unwrap();
+    assert!(reports.iter().all(|r| r.affected == 1 && !r.dry_run));
+
+    assert!(
+        AuthRepository::find_by_token_hash(&repo, &stale.token_hash)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        AuthRepository::find_by_token_hash(&repo, &fresh.token_hash)
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert!(
+        BillingEventRepository::list_by_user(&repo, active.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let anonymized = UserRepository::find_by_id(&repo, deleted.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        anonymized.primary_email,
+        format!("deleted-{}@anonymized.invalid", deleted.id)
+    );
+    assert_eq!(anonymized.github_user_id, None);
+    assert_eq!(anonymized.billing_country, None);
+
+    let untouched = UserRepository::find_by_id(&repo, recently_deleted.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(untouched.primary_email, recently_deleted.primary_email);
+
+    // Already anonymized users aren't counted again
+    let again = service.run(true).await.unwrap();
+    assert!(again.iter().all(|r| r.affected == 0));
+}
@@ -0,0 +1,553 @@
+mod common;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{
+    AccountState, ForkSession, SessionId, SessionStatus, SubscriptionTier, User, UserId, UserRole,
+    UserStatus,
+};
+use domain::repositories::UserRepository;
+use domain::services::billing::plans::PlanCatalog;
+use domain::services::forking::ForkStateProvider;
+use domain::services::quota::QuotaService;
+use domain::services::resolver::Resolver;
+use domain::services::sessions::{SessionRepository, SessionService};
+use domain::services::snapshots::SnapshotRepository;
+use domain::services::users::UserService;
+use infra::DbRepo;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+#[tokio::test]
+async fn test_session_lifecycle() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+
+    let session = SessionRepository::create(
+        &repo,
+        user_id,
+        "panic-2245".to_string(),
+        Some(250_000_000),
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(session.status, SessionStatus::Pending);
+
+    let found = SessionRepository::find_by_id(&repo, session.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.name, "panic-2245");
+    assert_eq!(found.fork_slot, Some(250_000_000));
+
+    let running = SessionRepository::update(
+        &repo,
+        &ForkSession {
+            status: SessionStatus::Running,
+            ..found
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(running.status, SessionStatus::Running);
+
+    let sessions = repo.list_by_user(user_id).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].status, SessionStatus::Running);
+
+    // Other users see none of them
+    let other_user = create_user(&repo).await;
+    assert!(repo.list_by_user(other_user).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_suspending_user_stops_sessions() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = UserService::new(repo.clone(), repo.clone());
+
+    let session = SessionRepository::create(
+        &repo,
+        user_id,
+        "panic-2245".to_string(),
+        None,
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    let suspended = service.suspend_user(user_id).await.unwrap();
+    assert_eq!(suspended.status, UserStatus::Suspended);
+
+    let stopped = SessionRepository::find_by_id(&repo, session.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stopped.status, SessionStatus::Stopped);
+
+    let active = service.unsuspend_user(user_id).await.unwrap();
+    assert_eq!(active.status, UserStatus::Active);
+}
+
+/// Fork state provider serving a fixed set of accounts
+struct FixedState(Vec<AccountState>);
+
+#[async_trait]
+impl ForkStateProvider for FixedState {
+    async fn current_slot(&self) -> Result<u64, DomainError> {
+        Ok(300_000_000)
+    }
+
+    async fn fetch_accounts(
+        &self,
+        pubkeys: &[String],
+        _: u64,
+    ) -> Result<Vec<AccountState>, DomainError> {
+        Ok(self
+            .0
+            .iter()
+            .filter(|account| pubkeys.contains(&account.pubkey))
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_programs(
+        &self,
+        program_ids: &[String],
+        slot: u64,
+    ) -> Result<Vec<AccountState>, DomainError> {
+        self.fetch_accounts(program_ids, slot).await
+    }
+
+    async fn fetch_sysvars(&self, _: u64) -> Result<Vec<AccountState>, DomainError> {
+        Ok(Vec::new())
+    }
+}
+
+fn account(pubkey: &str, executable: bool) -> AccountState {
+    AccountState {
+        pubkey: pubkey.to_string(),
+        lamports: 1_000_000,
+        owner: "11111111111111111111111111111111".to_string(),
+        data: vec![1, 2, 3],
+        executable,
+        rent_epoch: u64::MAX,
+    }
+}
+
+#[tokio::test]
+async fn test_create_session_with_accounts() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+    let provider = FixedState(vec![account("wallet", false), account("program", true)]);
+
+    let session = service
+        .create_session_with_accounts(
+            &provider,
+            user_id,
+            "cloned".to_string(),
+            None,
+            None,
+            &["wallet".to_string(), "program".to_string()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(session.fork_slot, Some(300_000_000));
+
+    let accounts = SessionRepository::list_accounts(&repo, session.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        accounts,
+        vec![account("program", true), account("wallet", false)]
+    );
+
+    // Unknown accounts fail before a session is created
+    let result = service
+        .create_session_with_accounts(
+            &provider,
+            user_id,
+            "missing".to_string(),
+            None,
+            None,
+            &["nope".to_string()],
+        )
+        .await;
+    assert!(matches!(result, Err(DomainError::InvalidInput(_))));
+    assert_eq!(
+        SessionRepository::list_by_user(&repo, user_id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_session_status_transitions() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+
+    let session = service
+        .create_session(user_id, "lifecycle".to_string(), None, None)
+        .await
+        .unwrap();
+
+    let running = service.start_session(session.id).await.unwrap();
+    assert_eq!(running.status, SessionStatus::Running);
+
+    let stopped = service.stop_session(session.id).await.unwrap();
+    assert_eq!(stopped.status, SessionStatus::Stopped);
+
+    // Stopped sessions can be restarted, failed ones are final
+    service.start_session(session.id).await.unwrap();
+    let failed = service.fail_session(session.id).await.unwrap();
+    assert_eq!(failed.status, SessionStatus::Failed);
+    assert!(matches!(
+        service.stop_session(session.id).await,
+        Err(DomainError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        service.start_session(session.id).await,
+        Err(DomainError::InvalidInput(_))
+    ));
+
+    assert!(matches!(
+        service.stop_session(SessionId::new_v4()).await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_session_slugs_are_unique_per_user() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let other_user = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+
+    let first = service
+        .create_session(user_id, "Panic 2245!".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(first.slug, "panic-2245");
+
+    // A repeated name gets a numbered slug; other users' slugs don't clash
+    let second = service
+        .create_session(user_id, "panic 2245".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(second.slug, "panic-2245-2");
+    let theirs = service
+        .create_session(other_user, "panic-2245".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(theirs.slug, "panic-2245");
+
+    // Unnamed sessions are named after a generated adjective-animal-number slug
+    let unnamed = service
+        .create_session(user_id, String::new(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(unnamed.name, unnamed.slug);
+    assert_eq!(unnamed.slug.split('-').count(), 3);
+
+    // Slugs and IDs both resolve, but only to the caller's own sessions
+    let resolver = Resolver::new(repo.clone());
+    assert_eq!(
+        resolver
+            .resolve_session(user_id, "panic-2245-2")
+            .await
+            .unwrap(),
+        second.id
+    );
+    assert_eq!(
+        resolver
+            .resolve_session(user_id, &first.id.to_string())
+            .await
+            .unwrap(),
+        first.id
+    );
+    assert!(matches!(
+        resolver.resolve_session(other_user, "panic-2245-2").await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+/// Insert a session with a chosen ID, so tests control which prefixes clash
+async fn insert_session(repo: &DbRepo, id: &str, user_id: UserId) {
+    sqlx::query(
+        "INSERT INTO fork_sessions (id, user_id, name, slug, status) \
+         VALUES (?, ?, ?, ?, 'pending')",
+    )
+    .bind(id)
+    .bind(user_id.to_string())
+    .bind(id)
+    .bind(format!("session-{}", &id[..8]))
+    .execute(repo.pool())
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_resolve_session_id_prefix() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let other_user = create_user(&repo).await;
+    let resolver = Resolver::new(repo.clone());
+
+    insert_session(&repo, "abcd1111-0000-4000-8000-000000000000", user_id).await;
+    insert_session(&repo, "abcd2222-0000-4000-8000-000000000000", user_id).await;
+    insert_session(&repo, "abcd3333-0000-4000-8000-000000000000", other_user).await;
+
+    // Prefixes are case-insensitive and only match the caller's sessions
+    assert_eq!(
+        resolver.resolve_session(user_id, "ABCD1").await.unwrap(),
+        "abcd1111-0000-4000-8000-000000000000"
+            .parse::<SessionId>()
+            .unwrap()
+    );
+    assert!(matches!(
+        resolver.resolve_session(user_id, "abcd3").await,
+        Err(DomainError::NotFound(_))
+    ));
+
+    // An ambiguous prefix lists the candidates
+    match resolver.resolve_session(user_id, "abcd").await {
+        Err(DomainError::InvalidInput(message)) => {
+            assert!(message.contains("abcd1111-0000-4000-8000-000000000000"));
+            assert!(message.contains("abcd2222-0000-4000-8000-000000000000"));
+        }
+        other => panic!("expected an ambiguity error, got {other:?}"),
+    }
+
+    // Too short to be treated as a prefix
+    assert!(matches!(
+        resolver.resolve_session(user_id, "abc").await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_find_sessions_by_user() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+
+    let first = service
+        .create_session(user_id, "first".to_string(), None, None)
+        .await
+        .unwrap();
+    let second = service
+        .create_session(user_id, "second".to_string(), None, None)
+        .await
+        .unwrap();
+    service.start_session(second.id).await.unwrap();
+    SnapshotRepository::create(
+        &repo,
+        first.id,
+        user_id,
+        "snap".to_string(),
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    let all = service.find_sessions(user_id, None, 20, 0).await.unwrap();
+    assert_eq!(all.len(), 2);
+    let first_summary = all.iter().find(|s| s.session.id == first.id).unwrap();
+    assert_eq!(first_summary.snapshot_count, 1);
+
+    let running = service
+        .find_sessions(user_id, Some(SessionStatus::Running), 20, 0)
+        .await
+        .unwrap();
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].session.id, second.id);
+
+    let page = service.find_sessions(user_id, None, 1, 1).await.unwrap();
+    assert_eq!(page.len(), 1);
+
+    assert_eq!(service.count_sessions(user_id, None).await.unwrap(), 2);
+    assert_eq!(
+        service
+            .count_sessions(user_id, Some(SessionStatus::Running))
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_usage_heartbeats_are_idempotent() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let session =
+        SessionRepository::create(&repo, user_id, "usage".to_string(), None, None, Utc::now())
+            .await
+            .unwrap();
+    let catalog = Arc::new(PlanCatalog::load(&repo).await.unwrap());
+    let quota = QuotaService::new(catalog, repo.clone());
+
+    quota
+        .record_usage(session.id, 60, 1_000, SubscriptionTier::Pro)
+        .await
+        .unwrap();
+    quota
+        .record_usage(session.id, 120, 4_000, SubscriptionTier::Pro)
+        .await
+        .unwrap();
+
+    // A replayed older heartbeat doesn't lower the totals
+    let usage = quota
+        .record_usage(session.id, 60, 2_000, SubscriptionTier::Pro)
+        .await
+        .unwrap();
+    assert_eq!(usage.elapsed_seconds, 120);
+    assert_eq!(usage.peak_memory_bytes, 4_000);
+
+    // The session hasn't existed long enough to have run for an hour
+    assert!(matches!(
+        quota
+            .record_usage(session.id, 3_600, 0, SubscriptionTier::Pro)
+            .await,
+        Err(DomainError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        quota
+            .record_usage(SessionId::new_v4(), 1, 0, SubscriptionTier::Pro)
+            .await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_stale_session_updates_conflict() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+
+    let session = SessionRepository::create(
+        &repo,
+        user_id,
+        "panic-2245".to_string(),
+        None,
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    // Two clients read the same version; the second write loses
+    let renamed = SessionRepository::update(
+        &repo,
+        &ForkSession {
+            name: "dashboard".to_string(),
+            ..session.clone()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        SessionRepository::update(
+            &repo,
+            &ForkSession {
+                name: "cli".to_string(),
+                ..session.clone()
+            },
+        )
+        .await,
+        Err(DomainError::Conflict(_))
+    ));
+
+    assert!(matches!(
+        service
+            .rename_session(session.id, "cli".to_string(), Some(session.updated_at))
+            .await,
+        Err(DomainError::Conflict(_))
+    ));
+    let latest = service
+        .rename_session(session.id, "cli".to_string(), Some(renamed.updated_at))
+        .await
+        .unwrap();
+    assert_eq!(latest.name, "cli");
+    assert_eq!(latest.slug, session.slug);
+
+    let found = SessionRepository::find_by_id(&repo, session.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.name, "cli");
+    assert_eq!(found.updated_at, latest.updated_at);
+}
+
+#[tokio::test]
+async fn test_fail_zombie_sessions() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SessionService::new(repo.clone());
+    let create = |name: &str| service.create_session(user_id, name.to_string(), None, None);
+
+    let heartbeating = create("heartbeating").await.unwrap();
+    SessionRepository::record_usage(&repo, heartbeating.id, 60, 1_000)
+        .await
+        .unwrap();
+    let running = create("running").await.unwrap();
+    service.start_session(running.id).await.unwrap();
+    // Never started, so nothing is expected to be running it
+    let pending = create("pending").await.unwrap();
+
+    let report = service
+        .fail_zombies(chrono::Duration::minutes(10), false)
+        .await
+        .unwrap();
+    assert!(report.zombies.is_empty());
+
+    let report = service
+        .fail_zombies(chrono::Duration::zero(), true)
+        .await
+        .unwrap();
+    let mut zombies: Vec<SessionId> = report.zombies.iter().map(|z| z.session_id).collect();
+    zombies.sort();
+    let mut expected = vec![heartbeating.id, running.id];
+    expected.sort();
+    assert_eq!(zombies, expected);
+    assert_eq!(report.failed, 0);
+
+    let report = service
+        .fail_zombies(chrono::Duration::zero(), false)
+        .await
+        .unwrap();
+    assert_eq!(report.failed, 2);
+    for id in [heartbeating.id, running.id] {
+        let session = service.get_session(id).await.unwrap().unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+    }
+    let pending = service.get_session(pending.id).await.unwrap().unwrap();
+    assert_eq!(pending.status, SessionStatus::Pending);
+}
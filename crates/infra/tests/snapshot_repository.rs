@@ -0,0 +1,238 @@
+mod common;
+
+use chrono::Utc;
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{
+    AccountState, SessionStatus, SnapshotFilter, SnapshotId, User, UserId, UserRole, UserStatus,
+};
+use domain::repositories::UserRepository;
+use domain::services::resolver::Resolver;
+use domain::services::sessions::SessionRepository;
+use domain::services::snapshots::SnapshotService;
+use infra::DbRepo;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+fn account(pubkey: &str, lamports: u64) -> AccountState {
+    AccountState {
+        pubkey: pubkey.to_string(),
+        lamports,
+        owner: "11111111111111111111111111111111".to_string(),
+        data: vec![1, 2, 3],
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+#[tokio::test]
+async fn test_restore_snapshot_into_new_session() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SnapshotService::new(repo.clone());
+
+    let session = SessionRepository::create(
+        &repo,
+        user_id,
+        "panic".to_string(),
+        Some(250),
+        Some("eu".to_string()),
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(session.region.as_deref(), Some("eu"));
+    repo.save_accounts(session.id, &[account("Alice", 10)])
+        .await
+        .unwrap();
+
+    let snapshot = service
+        .create_snapshot(session.id, user_id, "before-liquidation".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.fork_slot, Some(250));
+
+    // Later changes to the session don't touch the snapshot
+    repo.save_accounts(session.id, &[account("Alice", 0), account("Bob", 5)])
+        .await
+        .unwrap();
+
+    let restored = service
+        .restore_snapshot(snapshot.id, user_id, None)
+        .await
+        .unwrap();
+    assert_ne!(restored.id, session.id);
+    assert_eq!(restored.slug, "before-liquidation");
+    assert_eq!(restored.fork_slot, Some(250));
+    // The restored session runs where the snapshot was taken
+    assert_eq!(restored.region.as_deref(), Some("eu"));
+    assert_eq!(
+        repo.list_accounts(restored.id).await.unwrap(),
+        vec![account("Alice", 10)]
+    );
+}
+
+#[tokio::test]
+async fn test_restore_snapshot_into_existing_session() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SnapshotService::new(repo.clone());
+
+    let session = SessionRepository::create(
+        &repo,
+        user_id,
+        "source".to_string(),
+        Some(100),
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    repo.save_accounts(session.id, &[account("Alice", 10)])
+        .await
+        .unwrap();
+    let snapshot = service
+        .create_snapshot(session.id, user_id, "snap".to_string(), None)
+        .await
+        .unwrap();
+
+    let target = SessionRepository::create(
+        &repo,
+        user_id,
+        "target".to_string(),
+        Some(900),
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    repo.save_accounts(target.id, &[account("Bob", 5)])
+        .await
+        .unwrap();
+
+    // A running validator wouldn't pick up the restored state
+    repo.update_status(target.id, SessionStatus::Pending, SessionStatus::Running)
+        .await
+        .unwrap();
+    assert!(matches!(
+        service
+            .restore_snapshot(snapshot.id, user_id, Some(target.id))
+            .await,
+        Err(DomainError::InvalidInput(_))
+    ));
+    repo.update_status(target.id, SessionStatus::Running, SessionStatus::Stopped)
+        .await
+        .unwrap();
+
+    let restored = service
+        .restore_snapshot(snapshot.id, user_id, Some(target.id))
+        .await
+        .unwrap();
+    assert_eq!(restored.id, target.id);
+    assert_eq!(restored.fork_slot, Some(100));
+    assert_eq!(
+        repo.list_accounts(target.id).await.unwrap(),
+        vec![account("Alice", 10)]
+    );
+
+    assert!(matches!(
+        service
+            .restore_snapshot(SnapshotId::new_v4(), user_id, None)
+            .await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_resolve_snapshot_id_prefix() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let other_user = create_user(&repo).await;
+    let service = SnapshotService::new(repo.clone());
+    let resolver = Resolver::new(repo.clone());
+
+    let session =
+        SessionRepository::create(&repo, user_id, "source".to_string(), None, None, Utc::now())
+            .await
+            .unwrap();
+    let snapshot = service
+        .create_snapshot(session.id, user_id, "snap".to_string(), None)
+        .await
+        .unwrap();
+
+    let prefix = &snapshot.id.to_string()[..8];
+    assert_eq!(
+        resolver.resolve_snapshot(user_id, prefix).await.unwrap(),
+        snapshot.id
+    );
+    assert!(matches!(
+        resolver.resolve_snapshot(other_user, prefix).await,
+        Err(DomainError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_find_snapshots_older_than() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let other_user = create_user(&repo).await;
+    let service = SnapshotService::new(repo.clone());
+
+    let session =
+        SessionRepository::create(&repo, user_id, "source".to_string(), None, None, Utc::now())
+            .await
+            .unwrap();
+    let old = service
+        .create_snapshot(session.id, user_id, "old".to_string(), None)
+        .await
+        .unwrap();
+    service
+        .create_snapshot(session.id, user_id, "new".to_string(), None)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE snapshots SET created_at = ? WHERE id = ?")
+        .bind(Utc::now() - chrono::Duration::days(45))
+        .bind(old.id.to_string())
+        .execute(repo.pool())
+        .await
+        .unwrap();
+
+    let filter: SnapshotFilter = "older-than:30d".parse().unwrap();
+    assert_eq!(
+        service.find_matching(user_id, filter, 10).await.unwrap(),
+        vec![old.id]
+    );
+    assert!(
+        service
+            .find_matching(other_user, filter, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    for invalid in [
+        "older-than:30",
+        "older-than:0d",
+        "older-than:30w",
+        "newer-than:1d",
+    ] {
+        assert!(invalid.parse::<SnapshotFilter>().is_err(), "{invalid}");
+    }
+}
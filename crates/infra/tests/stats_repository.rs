@@ -0,0 +1,130 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::test_repo;
+use domain::models::{
+    Subscription, SubscriptionStatus, SubscriptionTier, User, UserId, UserRole, UserStatus,
+};
+use domain::repositories::UserRepository;
+use domain::services::billing::subscriptions::SubscriptionRepository;
+use domain::services::sessions::SessionRepository;
+use domain::services::stats::StatsService;
+use infra::DbRepo;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo, days_ago: i64) -> UserId {
+    let created_at = Utc::now() - Duration::days(days_ago);
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at,
+        updated_at: created_at,
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+async fn subscribe(
+    repo: &DbRepo,
+    tier: SubscriptionTier,
+    status: SubscriptionStatus,
+    days_ago: i64,
+    payment_failed_days_ago: Option<i64>,
+) {
+    let updated_at = Utc::now() - Duration::days(days_ago);
+    let subscription = Subscription {
+        user_id: create_user(repo, 90).await,
+        tier,
+        status,
+        provider_subscription_id: format!("sub_{}", Uuid::new_v4().simple()),
+        failed_payment_count: u32::from(payment_failed_days_ago.is_some()),
+        last_payment_failed_at: payment_failed_days_ago
+            .map(|days| Utc::now() - Duration::days(days)),
+        created_at: updated_at - Duration::days(30),
+        updated_at,
+    };
+    SubscriptionRepository::upsert(repo, &subscription)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_stats() {
+    let repo = test_repo().await;
+    let alice = create_user(&repo, 0).await;
+    let bob = create_user(&repo, 0).await;
+    create_user(&repo, 2).await;
+    // Outside the reporting window
+    create_user(&repo, 60).await;
+
+    SessionRepository::create(&repo, alice, "a1".to_string(), None, None, Utc::now())
+        .await
+        .unwrap();
+    SessionRepository::create(&repo, alice, "a2".to_string(), None, None, Utc::now())
+        .await
+        .unwrap();
+    // Stopped sessions aren't active
+    SessionRepository::create(&repo, bob, "b1".to_string(), None, None, Utc::now())
+        .await
+        .unwrap();
+    SessionRepository::stop_all_by_user(&repo, bob)
+        .await
+        .unwrap();
+    SessionRepository::create(&repo, bob, "b2".to_string(), None, None, Utc::now())
+        .await
+        .unwrap();
+
+    let stats = StatsService::new(repo.clone())
+        .admin_stats(30)
+        .await
+        .unwrap();
+
+    let signups: Vec<u64> = stats.signups_per_day.iter().map(|d| d.count).collect();
+    assert_eq!(signups, vec![1, 2]);
+    assert!(stats.signups_per_day[0].day < stats.signups_per_day[1].day);
+
+    assert_eq!(stats.active_sessions, 3);
+
+    let buckets: Vec<(u64, u64)> = stats
+        .quota_utilization
+        .iter()
+        .map(|b| (b.active_sessions, b.users))
+        .collect();
+    assert_eq!(buckets, vec![(1, 1), (2, 1)]);
+    assert!(stats.churn.is_empty());
+    assert_eq!(stats.churn_rate, 0.0);
+}
+
+#[tokio::test]
+async fn test_admin_stats_churn() {
+    let repo = test_repo().await;
+    use SubscriptionStatus::*;
+    use SubscriptionTier::*;
+    subscribe(&repo, Lite, Active, 5, None).await;
+    subscribe(&repo, Lite, PastDue, 1, Some(1)).await;
+    subscribe(&repo, Lite, Cancelled, 3, None).await;
+    subscribe(&repo, Pro, Active, 5, None).await;
+    // Outside the reporting window
+    subscribe(&repo, Pro, Active, 60, Some(45)).await;
+    subscribe(&repo, Pro, Cancelled, 60, None).await;
+
+    let stats = StatsService::new(repo.clone())
+        .admin_stats(30)
+        .await
+        .unwrap();
+
+    let churn: Vec<(SubscriptionTier, u64, u64, u64)> = stats
+        .churn
+        .iter()
+        .map(|t| (t.tier, t.subscribed, t.cancelled, t.payment_failed))
+        .collect();
+    assert_eq!(churn, vec![(Lite, 2, 1, 1), (Pro, 2, 0, 0)]);
+    assert_eq!(stats.churn_rate, 0.2);
+}
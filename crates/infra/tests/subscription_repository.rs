@@ -0,0 +1,93 @@
+mod common;
+
+use chrono::Utc;
+use common::test_repo;
+use domain::models::{SubscriptionStatus, SubscriptionTier, User, UserId, UserRole, UserStatus};
+use domain::repositories::UserRepository;
+use domain::services::billing::subscriptions::SubscriptionService;
+use infra::DbRepo;
+use serde_json::json;
+use uuid::Uuid;
+
+async fn create_user(repo: &DbRepo) -> UserId {
+    let user = User {
+        id: UserId::new_v4(),
+        primary_email: format!("{}@example.com", Uuid::new_v4()),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: None,
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    UserRepository::create(repo, &user).await.unwrap().id
+}
+
+#[tokio::test]
+async fn test_subscription_lifecycle() {
+    let repo = test_repo().await;
+    let user_id = create_user(&repo).await;
+    let service = SubscriptionService::new(repo.clone());
+
+    assert!(service.get_subscription(user_id).await.unwrap().is_none());
+    assert_eq!(
+        service.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Entry
+    );
+
+    service
+        .activate_subscription(user_id, SubscriptionTier::Pro, "sub_1", None)
+        .await
+        .unwrap();
+    assert_eq!(
+        service.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Pro
+    );
+
+    // Failed payments are counted, and the tier holds while they're retried
+    for _ in 0..2 {
+        service
+            .record_payment_failure(user_id, json!({ "invoice": "in_1" }), None)
+            .await
+            .unwrap();
+    }
+    let subscription = service.get_subscription(user_id).await.unwrap().unwrap();
+    assert_eq!(subscription.status, SubscriptionStatus::PastDue);
+    assert_eq!(subscription.failed_payment_count, 2);
+    assert!(subscription.last_payment_failed_at.is_some());
+    assert_eq!(
+        service.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Pro
+    );
+
+    // A successful payment clears the history
+    service
+        .update_subscription(
+            user_id,
+            SubscriptionTier::Pro,
+            SubscriptionStatus::Active,
+            "sub_1",
+            None,
+        )
+        .await
+        .unwrap();
+    let subscription = service.get_subscription(user_id).await.unwrap().unwrap();
+    assert_eq!(subscription.failed_payment_count, 0);
+    assert!(subscription.last_payment_failed_at.is_none());
+
+    service.cancel_subscription(user_id, None).await.unwrap();
+    assert_eq!(
+        service.effective_tier(user_id).await.unwrap(),
+        SubscriptionTier::Entry
+    );
+
+    // Subscriptions can't be stored for unknown users
+    let orphan = service
+        .activate_subscription(UserId::new_v4(), SubscriptionTier::Lite, "sub_2", None)
+        .await;
+    assert!(orphan.is_err());
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use chrono::Utc;
+use common::test_repo;
+use domain::errors::DomainError;
+use domain::models::{ProfileChanges, User, UserId, UserPatch, UserRole, UserStatus};
+use domain::repositories::UserRepository;
+use domain::services::users::UserService;
+
+fn new_user(email: &str, github_id: i64) -> User {
+    User {
+        id: UserId::new_v4(),
+        primary_email: email.to_string(),
+        display_name: None,
+        contact_email: None,
+        preferred_region: None,
+        github_user_id: Some(github_id),
+        stripe_customer_id: None,
+        billing_country: None,
+        status: UserStatus::Active,
+        role: UserRole::Member,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_create_and_find_user() {
+    let repo = test_repo().await;
+    let user = new_user("alice@example.com", 42);
+    repo.create(&user).await.unwrap();
+
+    let by_id = repo.find_by_id(user.id).await.unwrap().unwrap();
+    assert_eq!(by_id.primary_email, "alice@example.com");
+
+    let by_email = repo.find_by_email("alice@example.com").await.unwrap();
+    assert_eq!(by_email.unwrap().id, user.id);
+
+    let by_github = repo.find_by_github_id(42).await.unwrap();
+    assert_eq!(by_github.unwrap().id, user.id);
+
+    // Unknown lookups return None rather than an error
+    assert!(repo.find_by_id(UserId::new_v4()).await.unwrap().is_none());
+    assert!(repo.find_by_github_id(7).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_create_duplicate_email_is_rejected() {
+    let repo = test_repo().await;
+    repo.create(&new_user("bob@example.com", 1)).await.unwrap();
+
+    let result = repo.create(&new_user("bob@example.com", 2)).await;
+    assert!(matches!(result, Err(DomainError::Conflict(_))));
+}
+
+#[tokio::test]
+async fn test_update_user() {
+    let repo = test_repo().await;
+    let user = repo
+        .create(&new_user("carol@example.com", 3))
+        .await
+        .unwrap();
+
+    let updated = repo
+        .update(&User {
+            stripe_customer_id: Some("cus_123".to_string()),
+            status: UserStatus::Suspended,
+            ..user.clone()
+        })
+        .await
+        .unwrap();
+    assert!(updated.updated_at >= user.updated_at);
+
+    // A write based on the old version is rejected
+    let stale = repo
+        .update(&User {
+            billing_country: Some("DE".to_string()),
+            ..user.clone()
+        })
+        .await;
+    assert!(matches!(stale, Err(DomainError::Conflict(_))));
+
+    let found = repo
+        .find_by_stripe_customer_id("cus_123")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, user.id);
+    assert_eq!(found.status, UserStatus::Suspended);
+
+    // Updating a user that doesn't exist is NotFound
+    let missing = repo.update(&new_user("dave@example.com", 4)).await;
+    assert!(matches!(missing, Err(DomainError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_patch_user() {
+    let repo = test_repo().await;
+    let user = repo.create(&new_user("gina@example.com", 7)).await.unwrap();
+    // Written by someone else after `user` was read; the patch must keep it
+    repo.update(&User {
+        billing_country: Some("FR".to_string()),
+        ..user.clone()
+    })
+    .await
+    .unwrap();
+
+    let patched = repo
+        .patch(
+            user.id,
+            &UserPatch::new()
+                .primary_email("gina@example.org")
+                .display_name(Some("Gina"))
+                .status(UserStatus::Suspended),
+        )
+        .await
+        .unwrap();
+    assert_eq!(patched.primary_email, "gina@example.org");
+    assert_eq!(patched.display_name.as_deref(), Some("Gina"));
+    assert_eq!(patched.status, UserStatus::Suspended);
+    assert_eq!(patched.billing_country.as_deref(), Some("FR"));
+    assert_eq!(patched.github_user_id, Some(7));
+
+    let cleared = repo
+        .patch(user.id, &UserPatch::new().display_name(None::<String>))
+        .await
+        .unwrap();
+    assert_eq!(cleared.display_name, None);
+    assert_eq!(cleared.primary_email, "gina@example.org");
+
+    // Taking another user's email is rejected like a duplicate create
+    repo.create(&new_user("hal@example.com", 8)).await.unwrap();
+    let taken = repo
+        .patch(user.id, &UserPatch::new().primary_email("hal@example.com"))
+        .await;
+    assert!(matches!(taken, Err(DomainError::Conflict(_))));
+
+    let missing = repo
+        .patch(
+            UserId::new_v4(),
+            &UserPatch::new().status(UserStatus::Active),
+        )
+        .await;
+    assert!(matches!(missing, Err(DomainError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_update_profile() {
+    let repo = test_repo().await;
+    let user = repo.create(&new_user("ivy@example.com", 9)).await.unwrap();
+    let service = UserService::new(repo.clone(), repo.clone());
+
+    let updated = service
+        .update_profile(
+            user.id,
+            ProfileChanges {
+                display_name: Some("Ivy".to_string()),
+                contact_email: Some(" ivy@work.example ".to_string()),
+                preferred_region: Some("EU".to_string()),
+                ..ProfileChanges::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.contact_email.as_deref(), Some("ivy@work.example"));
+    assert_eq!(updated.preferred_region.as_deref(), Some("eu"));
+    // The GitHub email is kept alongside the contact email
+    assert_eq!(updated.primary_email, "ivy@example.com");
+
+    let cleared = service
+        .update_profile(
+            user.id,
+            ProfileChanges {
+                contact_email: Some(String::new()),
+                ..ProfileChanges::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(cleared.contact_email, None);
+    assert_eq!(cleared.display_name.as_deref(), Some("Ivy"));
+    assert_eq!(cleared.preferred_region.as_deref(), Some("eu"));
+
+    let invalid = service
+        .update_profile(
+            user.id,
+            ProfileChanges {
+                preferred_region: Some("eu west".to_string()),
+                ..ProfileChanges::default()
+            },
+        )
+        .await;
+    assert!(matches!(invalid, Err(DomainError::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn test_delete_user() {
+    let repo = test_repo().await;
+    let user = repo.create(&new_user("erin@example.com", 5)).await.unwrap();
+
+    repo.delete(user.id).await.unwrap();
+    assert!(repo.find_by_id(user.id).await.unwrap().is_none());
+
+    let missing = repo.delete(user.id).await;
+    assert!(matches!(missing, Err(DomainError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_set_billing_country() {
+    let repo = test_repo().await;
+    let user = repo
+        .create(&new_user("frank@example.com", 6))
+        .await
+        .unwrap();
+    let service = UserService::new(repo.clone(), repo.clone());
+
+    let updated = service
+        .set_billing_country(user.id, " de ", None)
+        .await
+        .unwrap();
+    assert_eq!(updated.billing_country.as_deref(), Some("DE"));
+
+    let found = repo.find_by_id(user.id).await.unwrap().unwrap();
+    assert_eq!(found.billing_country.as_deref(), Some("DE"));
+
+    for invalid in ["", "DEU", "1A"] {
+        let result = service.set_billing_country(user.id, invalid, None).await;
+        assert!(matches!(result, Err(DomainError::InvalidInput(_))));
+    }
+}
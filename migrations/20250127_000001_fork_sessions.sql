@@ -0,0 +1,12 @@
+-- Fork sessions: Forked Solana validators owned by a user
+
+CREATE TABLE fork_sessions (
+    id TEXT PRIMARY KEY,                    -- UUID v4
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,                     -- User-facing session name
+    status TEXT NOT NULL DEFAULT 'pending', -- pending | running | stopped | failed
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_fork_sessions_user_id ON fork_sessions(user_id);
@@ -0,0 +1,13 @@
+-- Snapshots: Point-in-time saves of a fork session
+
+CREATE TABLE snapshots (
+    id TEXT PRIMARY KEY,                    -- UUID v4
+    session_id TEXT NOT NULL REFERENCES fork_sessions(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,                     -- User-facing snapshot name (e.g., "panic-2245")
+    description TEXT,                       -- Optional notes on what the snapshot captures
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_snapshots_session_id ON snapshots(session_id);
+CREATE INDEX idx_snapshots_user_id ON snapshots(user_id);
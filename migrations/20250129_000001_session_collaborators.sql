@@ -0,0 +1,11 @@
+-- Session collaborators: Users granted access to a session they don't own
+
+CREATE TABLE session_collaborators (
+    session_id TEXT NOT NULL REFERENCES fork_sessions(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    access TEXT NOT NULL,                   -- read | write
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (session_id, user_id)
+);
+
+CREATE INDEX idx_session_collaborators_user_id ON session_collaborators(user_id);
@@ -0,0 +1,3 @@
+-- Optional mainnet slot a fork session was started from (NULL = latest)
+
+ALTER TABLE fork_sessions ADD COLUMN fork_slot INTEGER;
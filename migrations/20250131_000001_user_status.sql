@@ -0,0 +1,3 @@
+-- Account status for suspension and soft deletion
+
+ALTER TABLE users ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
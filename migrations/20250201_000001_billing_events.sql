@@ -0,0 +1,13 @@
+-- Billing events: Append-only audit log of billing mutations
+
+CREATE TABLE billing_events (
+    id TEXT PRIMARY KEY,                    -- UUID v4
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,                     -- tier_changed, payment_failed, refunded, reconciliation_fix
+    before_value TEXT,                      -- JSON snapshot of the affected values before the change
+    after_value TEXT,                       -- JSON snapshot of the affected values after the change
+    stripe_event_id TEXT,                   -- Originating Stripe event (evt_...), if any
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_billing_events_user_id ON billing_events(user_id);
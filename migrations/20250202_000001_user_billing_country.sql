@@ -0,0 +1,3 @@
+-- Billing country (ISO 3166-1 alpha-2) for tax calculation
+
+ALTER TABLE users ADD COLUMN billing_country TEXT;
@@ -0,0 +1,16 @@
+-- Plans: Catalog of subscription tiers, their limits and feature flags
+-- Loaded by the API at startup; edit rows and restart to change limits
+
+CREATE TABLE plans (
+    tier TEXT PRIMARY KEY,                  -- entry, lite, pro
+    name TEXT NOT NULL,                     -- Customer-facing name
+    max_concurrent_sessions INTEGER NOT NULL,
+    max_snapshots_per_session INTEGER NOT NULL,
+    features TEXT NOT NULL DEFAULT '[]',    -- JSON array of feature flags
+    sort_order INTEGER NOT NULL DEFAULT 0   -- Display order, lowest first
+);
+
+INSERT INTO plans (tier, name, max_concurrent_sessions, max_snapshots_per_session, features, sort_order) VALUES
+    ('entry', 'Entry', 1, 3, '[]', 0),
+    ('lite', 'Lite', 3, 20, '["collaborators"]', 1),
+    ('pro', 'Pro', 10, 100, '["collaborators", "priority_support"]', 2);
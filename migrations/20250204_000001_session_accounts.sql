@@ -0,0 +1,12 @@
+-- Session accounts: Mainnet account state captured when a fork is created
+
+CREATE TABLE session_accounts (
+    session_id TEXT NOT NULL REFERENCES fork_sessions(id) ON DELETE CASCADE,
+    pubkey TEXT NOT NULL,                   -- Base58 account address
+    lamports INTEGER NOT NULL,
+    owner TEXT NOT NULL,                    -- Base58 owning program
+    data BLOB NOT NULL,
+    executable BOOLEAN NOT NULL,
+    rent_epoch INTEGER NOT NULL,            -- u64 stored bit-for-bit as i64
+    PRIMARY KEY (session_id, pubkey)
+);
@@ -0,0 +1,8 @@
+-- Session usage: Running totals reported by heartbeats from the client running a session
+
+CREATE TABLE session_usage (
+    session_id TEXT PRIMARY KEY NOT NULL REFERENCES fork_sessions(id) ON DELETE CASCADE,
+    elapsed_seconds INTEGER NOT NULL,
+    peak_memory_bytes INTEGER NOT NULL,     -- u64 stored bit-for-bit as i64
+    last_heartbeat_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Stats indexes: Keep admin dashboard rollups from scanning whole tables
+
+CREATE INDEX idx_users_created_at ON users(created_at);
+CREATE INDEX idx_fork_sessions_status ON fork_sessions(status);
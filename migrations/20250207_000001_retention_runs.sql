@@ -0,0 +1,9 @@
+-- Retention runs: Audit log of data purged by the retention job
+
+CREATE TABLE retention_runs (
+    id TEXT PRIMARY KEY,                    -- UUID v4
+    rule TEXT NOT NULL,                     -- unused_auth_tokens, deleted_users, billing_events
+    cutoff TIMESTAMP NOT NULL,              -- Records older than this were purged
+    affected INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,18 @@
+-- Subscriptions: Each user's paid subscription, kept in step with Stripe by webhooks
+
+CREATE TABLE subscriptions (
+    user_id TEXT PRIMARY KEY NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tier TEXT NOT NULL,                     -- entry, lite, pro
+    status TEXT NOT NULL,                   -- active, past_due, cancelled
+    provider_subscription_id TEXT NOT NULL, -- Stripe subscription (sub_...)
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+-- Processed webhook events: Stripe event IDs already handled, so redeliveries are skipped
+
+CREATE TABLE processed_webhook_events (
+    event_id TEXT PRIMARY KEY NOT NULL,     -- Stripe event (evt_...)
+    event_type TEXT NOT NULL,
+    processed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
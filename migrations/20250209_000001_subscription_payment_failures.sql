@@ -0,0 +1,4 @@
+-- Subscriptions: Failed payment history, reset once a payment succeeds
+
+ALTER TABLE subscriptions ADD COLUMN failed_payment_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE subscriptions ADD COLUMN last_payment_failed_at TIMESTAMP;
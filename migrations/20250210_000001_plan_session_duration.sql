@@ -0,0 +1,6 @@
+-- Plans: Longest a single session may run on each tier
+
+ALTER TABLE plans ADD COLUMN max_session_minutes INTEGER;  -- NULL means unlimited
+
+UPDATE plans SET max_session_minutes = 120 WHERE tier = 'entry';
+UPDATE plans SET max_session_minutes = 720 WHERE tier = 'lite';
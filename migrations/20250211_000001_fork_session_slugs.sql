@@ -0,0 +1,8 @@
+-- Fork sessions: Human-friendly slug, unique among a user's sessions
+
+ALTER TABLE fork_sessions ADD COLUMN slug TEXT;
+
+-- Existing sessions are addressed by a prefix of their ID
+UPDATE fork_sessions SET slug = 'session-' || substr(id, 1, 8);
+
+CREATE UNIQUE INDEX idx_fork_sessions_user_slug ON fork_sessions(user_id, slug);
@@ -0,0 +1,14 @@
+-- Snapshot state: Accounts and fork slot saved with each snapshot, so it can be restored
+
+ALTER TABLE snapshots ADD COLUMN fork_slot INTEGER;  -- Slot of the session when taken (NULL = latest)
+
+CREATE TABLE snapshot_accounts (
+    snapshot_id TEXT NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    pubkey TEXT NOT NULL,                   -- Base58 account address
+    lamports INTEGER NOT NULL,
+    owner TEXT NOT NULL,                    -- Base58 owning program
+    data BLOB NOT NULL,
+    executable BOOLEAN NOT NULL,
+    rent_epoch INTEGER NOT NULL,            -- u64 stored bit-for-bit as i64
+    PRIMARY KEY (snapshot_id, pubkey)
+);
@@ -0,0 +1,10 @@
+-- Provider tokens: Login provider (GitHub) OAuth tokens, kept so expiring tokens can be refreshed
+
+CREATE TABLE provider_tokens (
+    user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT,                     -- NULL when the provider's tokens don't expire
+    expires_at TIMESTAMP,                   -- NULL = access token never expires
+    refresh_token_expires_at TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Plans: API requests each tier may make per minute
+
+ALTER TABLE plans ADD COLUMN api_requests_per_minute INTEGER NOT NULL DEFAULT 60;
+
+UPDATE plans SET api_requests_per_minute = 300 WHERE tier = 'lite';
+UPDATE plans SET api_requests_per_minute = 1200 WHERE tier = 'pro';
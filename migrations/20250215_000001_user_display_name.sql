@@ -0,0 +1,3 @@
+-- Optional name shown instead of the email address
+
+ALTER TABLE users ADD COLUMN display_name TEXT;
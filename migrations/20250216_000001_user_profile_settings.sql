@@ -0,0 +1,5 @@
+-- Profile settings: where to send mail other than the GitHub email, and the
+-- region new sessions are placed in
+
+ALTER TABLE users ADD COLUMN contact_email TEXT;
+ALTER TABLE users ADD COLUMN preferred_region TEXT;
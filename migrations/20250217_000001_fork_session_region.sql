@@ -0,0 +1,3 @@
+-- Region a fork session is placed in (NULL = the server has no regions)
+
+ALTER TABLE fork_sessions ADD COLUMN region TEXT;
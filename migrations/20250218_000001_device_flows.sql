@@ -0,0 +1,12 @@
+-- Device flows: Logins waiting for the user to enter their code, kept so they survive a restart
+
+CREATE TABLE device_flows (
+    device_code TEXT PRIMARY KEY,
+    user_code TEXT NOT NULL,
+    verification_uri TEXT NOT NULL,
+    interval_seconds INTEGER NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_device_flows_expires_at ON device_flows(expires_at);
@@ -0,0 +1,3 @@
+-- User roles: member, or admin (may call the admin endpoints with their own API token)
+
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'member';
@@ -18,6 +18,7 @@ fn run() -> Result<()> {
         "migrate" => migrate(),
         "dev" => dev(),
         "watch" => watch(),
+        "docker" => docker(),
         "help" | "--help" | "-h" => {
             print_help();
             Ok(())
@@ -42,6 +43,7 @@ TASKS:
     migrate    Run database migrations
     dev        Start API server in development mode
     watch      Run API and CLI in watch mode (requires cargo-watch)
+    docker     Build API and dev Docker images and generate docker-compose.yml
     help       Show this help message
 "#
     );
@@ -99,6 +101,48 @@ fn watch() -> Result<()> {
     check_status(status)
 }
 
+/// Compose file for running the API server locally
+///
+/// Only the API is in it: the server stores everything in SQLite, on a
+/// volume, and keeps nothing in object storage, so there is no Postgres or
+/// MinIO to run alongside it yet.
+const COMPOSE_FILE: &str = r#"# Generated by `cargo xtask docker` - do not edit by hand
+services:
+  api:
+    image: forkforge-api:dev
+    ports:
+      - "3000:3000"
+    environment:
+      FORKFORGE_GITHUB_CLIENT_ID: ${FORKFORGE_GITHUB_CLIENT_ID}
+    volumes:
+      - forkforge-data:/data
+
+volumes:
+  forkforge-data:
+"#;
+
+fn docker() -> Result<()> {
+    if !command_exists("docker") {
+        eprintln!("docker is not installed. See https://docs.docker.com/get-docker/");
+        std::process::exit(1);
+    }
+
+    for (dockerfile, tag) in [
+        ("docker/api.Dockerfile", "forkforge-api:dev"),
+        ("docker/dev.Dockerfile", "forkforge-dev:dev"),
+    ] {
+        println!("Building {tag}...");
+        let status = Command::new("docker")
+            .args(["build", "-f", dockerfile, "-t", tag, "."])
+            .status()?;
+        check_status(status)?;
+    }
+
+    std::fs::write("docker-compose.yml", COMPOSE_FILE)?;
+    println!("✅ Images built. Start the API with: docker compose up");
+    Ok(())
+}
+
 fn command_exists(cmd: &str) -> bool {
     Command::new("which")
         .arg(cmd)